@@ -81,8 +81,11 @@ pub fn execute<S: StorageView, Mode: ExecutionMode>(
     // execute commands
     let mut mode_results = Mode::empty_results();
     for (idx, command) in commands.into_iter().enumerate() {
-        execute_command::<_, Mode>(&mut context, &mut mode_results, command)
-            .map_err(|e| e.with_command_index(idx))?
+        let gas_used_before = context.gas_status.gas_used();
+        let result = execute_command::<_, Mode>(&mut context, &mut mode_results, command);
+        let command_gas = context.gas_status.gas_used().saturating_sub(gas_used_before);
+        context.gas_status.record_command_gas(command_gas);
+        result.map_err(|e| e.with_command_index(idx))?
     }
     // apply changes
     let ExecutionResults {
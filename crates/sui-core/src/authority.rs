@@ -16,7 +16,7 @@ use fastcrypto::traits::KeyPair;
 use itertools::Itertools;
 use move_binary_format::compatibility::Compatibility;
 use move_binary_format::CompiledModule;
-use move_core_types::language_storage::ModuleId;
+use move_core_types::language_storage::{ModuleId, TypeTag};
 use parking_lot::Mutex;
 use prometheus::{
     register_histogram_with_registry, register_int_counter_vec_with_registry,
@@ -48,13 +48,16 @@ use sui_config::node::{
 };
 use sui_framework::BuiltInFramework;
 use sui_json_rpc_types::{
-    Checkpoint, DevInspectResults, DryRunTransactionBlockResponse, EventFilter, SuiEvent,
-    SuiMoveValue, SuiObjectDataFilter, SuiTransactionBlockData, SuiTransactionBlockEvents,
+    BalanceChange, Checkpoint, DevInspectResults, DryRunTransactionBlockResponse, EventFilter,
+    ObjectChange, SuiEvent, SuiExecutionStatus, SuiMoveValue, SuiObjectDataFilter,
+    SuiTransactionBlockData, SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI,
+    SuiTransactionBlockEvents,
 };
 use sui_macros::{fail_point, fail_point_async, nondeterministic};
 use sui_protocol_config::SupportedProtocolVersions;
 use sui_storage::indexes::ObjectIndexChanges;
 use sui_storage::IndexStore;
+use sui_types::coin::Coin;
 use sui_types::committee::{EpochId, ProtocolVersion};
 use sui_types::crypto::{
     default_hash, AggregateAuthoritySignature, AuthorityKeyPair, AuthoritySignInfo, NetworkKeyPair,
@@ -64,7 +67,11 @@ use sui_types::digests::TransactionEventsDigest;
 use sui_types::dynamic_field::{DynamicFieldInfo, DynamicFieldName, DynamicFieldType, Field};
 use sui_types::error::UserInputError;
 use sui_types::event::{Event, EventID};
-use sui_types::gas::{GasCostSummary, SuiGasStatus};
+use sui_types::gas::{
+    self, CommandGasBreakdown, GasCostSummary, GasProfile, SuiCostTable, SuiGasStatus,
+    SuiGasStatusAPI,
+};
+use sui_types::gas_coin::GAS;
 use sui_types::message_envelope::Message;
 use sui_types::messages_checkpoint::{
     CheckpointCommitment, CheckpointContents, CheckpointContentsDigest, CheckpointDigest,
@@ -73,7 +80,7 @@ use sui_types::messages_checkpoint::{
 use sui_types::messages_checkpoint::{CheckpointRequest, CheckpointResponse};
 use sui_types::object::{MoveObject, Owner, PastObjectRead, OBJECT_START_VERSION};
 use sui_types::query::TransactionFilter;
-use sui_types::storage::{ObjectKey, ObjectStore, WriteKind};
+use sui_types::storage::{DeleteKind, ObjectKey, ObjectStore, WriteKind};
 use sui_types::sui_system_state::epoch_start_sui_system_state::EpochStartSystemStateTrait;
 use sui_types::sui_system_state::SuiSystemState;
 use sui_types::sui_system_state::SuiSystemStateTrait;
@@ -612,11 +619,17 @@ impl AuthorityState {
         }
 
         // Checks to see if the transaction has expired
-        if match &transaction.inner().data().transaction_data().expiration() {
-            TransactionExpiration::None => false,
-            TransactionExpiration::Epoch(epoch) => *epoch < epoch_store.epoch(),
-        } {
-            return Err(SuiError::TransactionExpired);
+        if let TransactionExpiration::Epoch(expiration) =
+            transaction.inner().data().transaction_data().expiration()
+        {
+            let current_epoch = epoch_store.epoch();
+            if *expiration < current_epoch {
+                return Err(UserInputError::TransactionExpired {
+                    expiration: *expiration,
+                    current_epoch,
+                }
+                .into());
+            }
         }
 
         let signed = self.handle_transaction_impl(transaction, epoch_store).await;
@@ -1015,7 +1028,130 @@ impl AuthorityState {
         anyhow::Error,
     > {
         let epoch_store = self.load_epoch_store_one_call_per_task();
-        if !self.is_fullnode(&epoch_store) {
+        let (response, inner_temp_store, effects) = self
+            .dry_exec_transaction_impl(&epoch_store, transaction, transaction_digest, false)
+            .await?;
+        Ok((response, inner_temp_store.written, effects))
+    }
+
+    /// Execute `transaction` against the current state without committing any of its
+    /// writes and without requiring a signature, e.g. to estimate its gas cost. This goes
+    /// through the same input checks and execution engine as a certified transaction.
+    /// Like every dry run, it is only supported on fullnodes.
+    /// Besides the effects, the response previews the objects the transaction creates,
+    /// mutates, deletes or wraps and the balance it moves per owner and coin type.
+    pub async fn dry_run_transaction(
+        &self,
+        transaction: TransactionData,
+    ) -> Result<DryRunTransactionBlockResponse, anyhow::Error> {
+        let epoch_store = self.load_epoch_store_one_call_per_task();
+        let transaction_digest = TransactionDigest::new(default_hash(&transaction));
+        let (response, _, _) = self
+            .dry_exec_transaction_impl(&epoch_store, transaction, transaction_digest, false)
+            .await?;
+        Ok(response)
+    }
+
+    /// Same as `dry_run_transaction`, additionally returning how the computation cost splits
+    /// across the commands of the programmable transaction. The breakdown is `None` under
+    /// gas models that do not track it.
+    pub async fn dry_run_transaction_with_gas_breakdown(
+        &self,
+        transaction: TransactionData,
+    ) -> Result<(SuiTransactionBlockEffects, Option<CommandGasBreakdown>), anyhow::Error> {
+        let epoch_store = self.load_epoch_store_one_call_per_task();
+        let transaction_digest = TransactionDigest::new(default_hash(&transaction));
+        let (response, inner_temp_store, _) = self
+            .dry_exec_transaction_impl(&epoch_store, transaction, transaction_digest, false)
+            .await?;
+        Ok((response.effects, inner_temp_store.command_gas_breakdown))
+    }
+
+    /// Same as `dry_run_transaction`, additionally returning how the computation cost splits
+    /// across the bytecode instruction categories and native functions the Move VM charged.
+    /// Profiling adds bookkeeping to every charge, so it is only ever enabled for dry runs.
+    /// The profile is `None` under gas models that do not track it.
+    pub async fn dry_run_transaction_with_gas_profile(
+        &self,
+        transaction: TransactionData,
+    ) -> Result<(SuiTransactionBlockEffects, Option<GasProfile>), anyhow::Error> {
+        let epoch_store = self.load_epoch_store_one_call_per_task();
+        let transaction_digest = TransactionDigest::new(default_hash(&transaction));
+        let (response, inner_temp_store, _) = self
+            .dry_exec_transaction_impl(&epoch_store, transaction, transaction_digest, true)
+            .await?;
+        Ok((response.effects, inner_temp_store.gas_profile))
+    }
+
+    /// Return `transaction` with its gas budget replaced by the gas its dry run uses plus
+    /// `margin_bps` basis points, capped at `max_budget` and at least the protocol minimum.
+    /// The dry run itself is given the largest budget the gas payment can afford, up to
+    /// `max_budget`. Fails if `max_budget` is below the protocol minimum, or if the dry run
+    /// does not succeed within that budget.
+    ///
+    /// The budget is reserved out of the gas coin first, so a transaction that also spends
+    /// from the gas coin, e.g. splitting it through `Argument::GasCoin`, only gets what the
+    /// budget leaves of it. Its dry run fails unless `max_budget` leaves room for the amount
+    /// it spends.
+    pub async fn with_auto_gas_budget(
+        &self,
+        transaction: TransactionData,
+        margin_bps: u64,
+        max_budget: u64,
+    ) -> Result<TransactionData, anyhow::Error> {
+        let epoch_store = self.load_epoch_store_one_call_per_task();
+        let min_budget = SuiCostTable::new(epoch_store.protocol_config()).min_gas_budget();
+        if max_budget < min_budget {
+            return Err(anyhow!(
+                "Gas budget ceiling {max_budget} is below the minimum budget {min_budget}"
+            ));
+        }
+        let mut gas_balance: u64 = 0;
+        for (gas_object_id, _, _) in transaction.gas() {
+            let gas_object = self
+                .get_object(gas_object_id)
+                .await?
+                .ok_or_else(|| anyhow!("Gas object {gas_object_id} not found"))?;
+            gas_balance = gas_balance.saturating_add(gas::get_gas_balance(&gas_object)?);
+        }
+
+        let TransactionData::V1(mut data) = transaction;
+        data.gas_data.budget = max_budget.min(gas_balance);
+        let dry_run = TransactionData::V1(data.clone());
+        let transaction_digest = TransactionDigest::new(default_hash(&dry_run));
+        let (response, _, _) = self
+            .dry_exec_transaction_impl(&epoch_store, dry_run, transaction_digest, false)
+            .await?;
+        let effects = response.effects;
+        if let SuiExecutionStatus::Failure { error } = effects.status() {
+            return Err(anyhow!("Dry run failed: {error}"));
+        }
+
+        let gas_used = effects.gas_cost_summary().gas_used();
+        let margin = (gas_used as u128 * margin_bps as u128 / 10_000) as u64;
+        data.gas_data.budget = gas_used
+            .saturating_add(margin)
+            .min(max_budget)
+            .max(min_budget);
+        Ok(TransactionData::V1(data))
+    }
+
+    async fn dry_exec_transaction_impl(
+        &self,
+        epoch_store: &Arc<AuthorityPerEpochStore>,
+        transaction: TransactionData,
+        transaction_digest: TransactionDigest,
+        profile_gas: bool,
+    ) -> Result<
+        (
+            DryRunTransactionBlockResponse,
+            InnerTemporaryStore,
+            TransactionEffects,
+        ),
+        anyhow::Error,
+    > {
+        // every dry run goes through here, validators never execute unsigned transactions
+        if !self.is_fullnode(epoch_store) {
             return Err(anyhow!("dry-exec is only supported on fullnodes"));
         }
         match transaction.kind() {
@@ -1029,7 +1165,7 @@ impl AuthorityState {
 
         // make a gas object if one was not provided
         let mut gas_object_refs = transaction.gas().to_vec();
-        let (gas_status, input_objects) = if transaction.gas().is_empty() {
+        let (mut gas_status, input_objects) = if transaction.gas().is_empty() {
             let sender = transaction.sender();
             // use a 100M sui coin
             const MIST_TO_SUI: u64 = 1_000_000_000;
@@ -1059,6 +1195,10 @@ impl AuthorityState {
             .await?
         };
 
+        if profile_gas {
+            gas_status.enable_gas_profiling();
+        }
+
         let shared_object_refs = input_objects.filter_shared_objects();
 
         let transaction_dependencies = input_objects.transaction_dependencies();
@@ -1098,11 +1238,8 @@ impl AuthorityState {
         let module_cache =
             TemporaryModuleResolver::new(&inner_temp_store, epoch_store.module_cache().clone());
 
-        // Returning empty vector here because we recalculate changes in the rpc layer.
-        let object_changes = Vec::new();
-
-        // Returning empty vector here because we recalculate changes in the rpc layer.
-        let balance_changes = Vec::new();
+        let (object_changes, balance_changes) =
+            self.get_dry_run_changes(signer, &inner_temp_store, &effects)?;
 
         Ok((
             DryRunTransactionBlockResponse {
@@ -1117,11 +1254,149 @@ impl AuthorityState {
                 object_changes,
                 balance_changes,
             },
-            inner_temp_store.written,
+            inner_temp_store,
             effects,
         ))
     }
 
+    /// The object and balance changes of a dry run, the same as the RPC layer reports for an
+    /// executed transaction. Objects are read at their version before the transaction from
+    /// its inputs first, since the gas coin of a dry run may not exist, and from the store
+    /// for children loaded at runtime.
+    fn get_dry_run_changes(
+        &self,
+        sender: SuiAddress,
+        inner_temp_store: &InnerTemporaryStore,
+        effects: &TransactionEffects,
+    ) -> SuiResult<(Vec<ObjectChange>, Vec<BalanceChange>)> {
+        let previous_object =
+            |id: &ObjectID, version: SequenceNumber| match inner_temp_store.objects.get(id) {
+                Some(o) if o.version() == version => Ok(Some(o.clone())),
+                _ => self.database.get_object_by_key(id, version),
+            };
+        let coin_balance = |o: &Object| -> SuiResult<Option<(TypeTag, u64)>> {
+            let coin_type = match o.type_().filter(|type_| type_.is_coin()) {
+                Some(type_) => type_.clone().into_type_params().pop(),
+                None => return Ok(None),
+            };
+            let balance = Coin::extract_balance_if_coin(o).map_err(|e| {
+                SuiError::ObjectDeserializationError {
+                    error: e.to_string(),
+                }
+            })?;
+            Ok(coin_type.zip(balance))
+        };
+        let modified_at_versions = effects
+            .modified_at_versions()
+            .iter()
+            .cloned()
+            .collect::<BTreeMap<_, _>>();
+
+        let mut object_changes = vec![];
+        for ((id, version, digest), owner, kind) in effects.all_changed_objects() {
+            let o = match inner_temp_store.get_written_object(id) {
+                Some(o) => o,
+                None => continue,
+            };
+            if let Some(type_) = o.type_() {
+                let object_type = type_.clone().into();
+                match kind {
+                    WriteKind::Mutate => object_changes.push(ObjectChange::Mutated {
+                        sender,
+                        owner: *owner,
+                        object_type,
+                        object_id: *id,
+                        version: *version,
+                        previous_version: modified_at_versions.get(id).cloned().unwrap_or_default(),
+                        digest: *digest,
+                    }),
+                    WriteKind::Create => object_changes.push(ObjectChange::Created {
+                        sender,
+                        owner: *owner,
+                        object_type,
+                        object_id: *id,
+                        version: *version,
+                        digest: *digest,
+                    }),
+                    WriteKind::Unwrap => {}
+                }
+            } else if let Some(p) = o.data.try_as_package() {
+                if kind == WriteKind::Create {
+                    object_changes.push(ObjectChange::Published {
+                        package_id: p.id(),
+                        version: p.version(),
+                        digest: *digest,
+                        modules: p.serialized_module_map().keys().cloned().collect(),
+                    })
+                }
+            }
+        }
+        for ((id, version, _), kind) in effects.all_deleted() {
+            let o = match modified_at_versions.get(id) {
+                Some(previous_version) => previous_object(id, *previous_version)?,
+                None => None,
+            };
+            let type_ = match o.as_ref().and_then(|o| o.type_()) {
+                Some(type_) if !type_.is_coin() => type_,
+                _ => continue,
+            };
+            let object_type = type_.clone().into();
+            match kind {
+                DeleteKind::Normal => object_changes.push(ObjectChange::Deleted {
+                    sender,
+                    object_type,
+                    object_id: *id,
+                    version: *version,
+                }),
+                DeleteKind::Wrap => object_changes.push(ObjectChange::Wrapped {
+                    sender,
+                    object_type,
+                    object_id: *id,
+                    version: *version,
+                }),
+                DeleteKind::UnwrapThenDelete => {}
+            }
+        }
+
+        // A failed transaction only charges gas, whatever its commands would have moved.
+        if effects.status() != &ExecutionStatus::Success {
+            let (_, gas_owner) = effects.gas_object();
+            let balance_changes = vec![BalanceChange {
+                owner: *gas_owner,
+                coin_type: GAS::type_tag(),
+                amount: -(effects.gas_cost_summary().net_gas_usage() as i128),
+            }];
+            return Ok((object_changes, balance_changes));
+        }
+
+        let mut balances = BTreeMap::<(Owner, TypeTag), i128>::new();
+        for (id, version) in effects.modified_at_versions() {
+            if let Some(o) = previous_object(id, *version)? {
+                if let Some((coin_type, balance)) = coin_balance(&o)? {
+                    *balances.entry((o.owner, coin_type)).or_default() -= balance as i128;
+                }
+            }
+        }
+        for ((id, _, _), _, _) in effects.all_changed_objects() {
+            if let Some(o) = inner_temp_store.get_written_object(id) {
+                if let Some((coin_type, balance)) = coin_balance(o)? {
+                    *balances.entry((o.owner, coin_type)).or_default() += balance as i128;
+                }
+            }
+        }
+        let balance_changes = balances
+            .into_iter()
+            .filter(|(_, amount)| *amount != 0)
+            .map(|((owner, coin_type), amount)| BalanceChange {
+                owner,
+                coin_type,
+                amount,
+            })
+            .collect();
+
+        Ok((object_changes, balance_changes))
+    }
+
     /// The object ID for gas can be any object ID, even for an uncreated object
     pub async fn dev_inspect_transaction_block(
         &self,
@@ -1218,6 +1493,16 @@ impl AuthorityState {
         )
     }
 
+    /// The reference gas price of the current epoch, as loaded in its epoch store. Fails once
+    /// the loaded epoch has been terminated by reconfiguration, its price being stale then.
+    pub async fn reference_gas_price(&self) -> SuiResult<u64> {
+        let epoch_store = self.load_epoch_store_one_call_per_task();
+        epoch_store
+            .within_alive_epoch(async { epoch_store.reference_gas_price() })
+            .await
+            .map_err(|()| SuiError::ValidatorHaltedAtEpochEnd)
+    }
+
     // Only used for testing because of how epoch store is loaded.
     pub fn reference_gas_price_for_testing(&self) -> Result<u64, anyhow::Error> {
         let epoch_store = self.epoch_store_for_testing();
@@ -792,6 +792,8 @@ impl AuthorityStore {
             deleted,
             events,
             max_binary_format_version: _,
+            command_gas_breakdown: _,
+            gas_profile: _,
         } = inner_temporary_store;
         trace!(written =? written.values().map(|((obj_id, ver, _), _, _)| (obj_id, ver)).collect::<Vec<_>>(),
                "batch_update_objects: temp store written");
@@ -16,12 +16,12 @@ use sui_types::{
     base_types::{SequenceNumber, SuiAddress},
     error::SuiResult,
     fp_ensure,
-    gas::{SuiCostTable, SuiGasStatus},
+    gas::{self, SuiCostTable, SuiGasStatus},
     messages::{InputObjectKind, InputObjects, TransactionData, TransactionDataAPI},
     object::{Object, Owner},
 };
 use sui_types::{SUI_CLOCK_OBJECT_ID, SUI_CLOCK_OBJECT_SHARED_VERSION};
-use tracing::instrument;
+use tracing::{debug, instrument};
 
 checked_arithmetic! {
 
@@ -205,9 +205,49 @@ async fn check_gas(
             more_gas_objects.push(obj);
         }
 
-        // check balance and coins consistency
+        // a payment holding no SUI at all cannot pay for anything, whatever the budget
+        let mut total_balance: u128 = 0;
+        let mut all_coins = true;
+        for obj in std::iter::once(gas_object).chain(more_gas_objects.iter().copied()) {
+            match gas::get_gas_balance(obj) {
+                Ok(balance) => {
+                    if balance == 0 {
+                        debug!(gas_coin = ?obj.id(), "Zero balance coin in gas payment");
+                    }
+                    total_balance += balance as u128;
+                }
+                // not a gas coin, left to `check_gas_balance` to report
+                Err(_) => all_coins = false,
+            }
+        }
+        if all_coins && total_balance == 0 {
+            return Err(UserInputError::ZeroGasBalance.into());
+        }
+
+        // check balance and coins consistency, the budget ceiling is the epoch's
+        // `max_tx_gas` protocol config
         let cost_table = SuiCostTable::new(protocol_config);
-        cost_table.check_gas_balance(gas_object, more_gas_objects, gas_budget, gas_price)?;
+        cost_table
+            .check_gas_balance(gas_object, more_gas_objects, gas_budget, gas_price)
+            .map_err(|e| match e {
+                UserInputError::GasBudgetTooLow {
+                    gas_budget,
+                    min_budget,
+                } => match cost_table.estimate_min_budget(
+                    objects.values().copied(),
+                    gas_price,
+                    protocol_config,
+                ) {
+                    // a suggestion under the floor would fail this same check again
+                    Some(suggested_budget) => UserInputError::GasBudgetTooLowWithSuggestion {
+                        gas_budget,
+                        min_budget,
+                        suggested_budget: suggested_budget.max(min_budget),
+                    },
+                    None => e,
+                },
+                e => e,
+            })?;
         Ok(SuiGasStatus::new_with_budget(
             gas_budget,
             gas_price,
@@ -15,6 +15,11 @@ module examples::object_basics {
         value: u64,
     }
 
+    struct Payload has key, store {
+        id: UID,
+        data: vector<u8>,
+    }
+
     struct Wrapper has key {
         id: UID,
         o: Object
@@ -59,6 +64,18 @@ module examples::object_basics {
         object::delete(id);
     }
 
+    public entry fun create_payload(data: vector<u8>, recipient: address, ctx: &mut TxContext) {
+        transfer::public_transfer(
+            Payload { id: object::new(ctx), data },
+            recipient
+        )
+    }
+
+    public entry fun delete_payload(p: Payload) {
+        let Payload { id, data: _ } = p;
+        object::delete(id);
+    }
+
     public entry fun wrap(o: Object, ctx: &mut TxContext) {
         transfer::transfer(wrap_object(o, ctx), tx_context::sender(ctx))
     }
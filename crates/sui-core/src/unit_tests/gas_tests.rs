@@ -3,22 +3,31 @@
 
 use super::*;
 
-use super::authority_tests::{init_state_with_ids, send_and_confirm_transaction};
+use super::authority_tests::{
+    init_state_with_committee, init_state_with_ids, send_and_confirm_transaction,
+};
 use super::move_integration_tests::build_and_try_publish_test_package;
-use crate::authority::authority_tests::{init_state, init_state_with_ids_and_object_basics};
+use crate::authority::authority_tests::{
+    init_state, init_state_with_ids_and_object_basics,
+    init_state_with_ids_and_object_basics_with_fullnode,
+};
 use move_core_types::account_address::AccountAddress;
 use move_core_types::ident_str;
 use once_cell::sync::Lazy;
+use sui_json_rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
 use sui_protocol_config::ProtocolConfig;
 use sui_types::crypto::AccountKeyPair;
-use sui_types::gas::SuiCostTable;
+use sui_types::gas::{SuiCostTable, SuiGasStatusAPI};
 use sui_types::gas_coin::GasCoin;
 use sui_types::object::GAS_VALUE_FOR_TESTING;
 use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
-use sui_types::utils::to_sender_signed_transaction;
+use sui_types::sui_system_state::epoch_start_sui_system_state::EpochStartSystemState;
+use sui_types::utils::{
+    to_sender_signed_transaction, to_sender_signed_transaction_with_multi_signers,
+};
 use sui_types::{base_types::dbg_addr, crypto::get_key_pair};
 
-static MAX_GAS_BUDGET: Lazy<u64> = Lazy::new(|| SuiCostTable::new_for_testing().max_gas_budget());
+static MAX_GAS_BUDGET: Lazy<u64> = Lazy::new(|| ProtocolConfig::get_for_max_version().max_tx_gas());
 static MIN_GAS_BUDGET: Lazy<u64> = Lazy::new(|| SuiCostTable::new_for_testing().min_gas_budget());
 
 #[tokio::test]
@@ -29,13 +38,71 @@ async fn test_tx_less_than_minimum_gas_budget() {
     let budget = *MIN_GAS_BUDGET - 1;
     let result = execute_transfer(*MAX_GAS_BUDGET, budget, false).await;
 
-    assert_eq!(
+    assert!(matches!(
         UserInputError::try_from(result.response.unwrap_err()).unwrap(),
-        UserInputError::GasBudgetTooLow {
-            gas_budget: budget,
-            min_budget: *MIN_GAS_BUDGET
-        }
-    );
+        UserInputError::GasBudgetTooLowWithSuggestion {
+            gas_budget,
+            min_budget,
+            suggested_budget,
+        } if gas_budget == budget
+            && min_budget == *MIN_GAS_BUDGET
+            && suggested_budget >= min_budget
+    ));
+}
+
+#[tokio::test]
+async fn test_gas_budget_too_low_suggestion() {
+    // The suggested budget is a lower bound on what the transfer actually costs, and
+    // more useful than the protocol floor.
+    let result = execute_transfer(*MAX_GAS_BUDGET, *MIN_GAS_BUDGET - 1, false).await;
+    let suggested_budget = match UserInputError::try_from(result.response.unwrap_err()).unwrap() {
+        UserInputError::GasBudgetTooLowWithSuggestion {
+            suggested_budget, ..
+        } => suggested_budget,
+        e => panic!("expected a budget suggestion, got {:?}", e),
+    };
+
+    let result = execute_transfer(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, true).await;
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    assert!(effects.status().is_ok());
+    let gas_used = effects.gas_cost_summary().net_gas_usage() as u64;
+    assert!(suggested_budget > *MIN_GAS_BUDGET);
+    assert!(suggested_budget <= gas_used);
+}
+
+#[tokio::test]
+async fn test_gas_budget_too_low_suggestion_multi_command() {
+    // One TransferObjects command per recipient. The suggestion leaves out the computation
+    // of the commands, so it stays a lower bound however many there are.
+    let recipients = (2..8).map(dbg_addr).collect::<Vec<_>>();
+    let result = execute_transfers(
+        *MAX_GAS_BUDGET,
+        *MIN_GAS_BUDGET - 1,
+        recipients.clone(),
+        false,
+    )
+    .await;
+    let suggested_budget = match UserInputError::try_from(result.response.unwrap_err()).unwrap() {
+        UserInputError::GasBudgetTooLowWithSuggestion {
+            suggested_budget, ..
+        } => suggested_budget,
+        e => panic!("expected a budget suggestion, got {:?}", e),
+    };
+
+    let result = execute_transfers(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, recipients, true).await;
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    assert!(effects.status().is_ok());
+    let gas_used = effects.gas_cost_summary().net_gas_usage() as u64;
+    assert!(suggested_budget > *MIN_GAS_BUDGET);
+    assert!(suggested_budget <= gas_used);
 }
 
 #[tokio::test]
@@ -55,70 +122,199 @@ async fn test_tx_more_than_maximum_gas_budget() {
     );
 }
 
-// #[tokio::test]
-// async fn test_tx_max_computation() -> SuiResult {
-//     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
-//     let gas_object_id = ObjectID::random();
-//     let authority_state = init_state_with_ids(vec![(sender, gas_object_id)]).await;
-//
-//     let response = build_and_try_publish_test_package(
-//         &authority_state,
-//         &sender,
-//         &sender_key,
-//         &gas_object_id,
-//         "move_random",
-//         *MAX_GAS_BUDGET,
-//         /* with_unpublished_deps */ false,
-//     )
-//     .await;
-//     let effects = response.1.into_data();
-//     assert!(effects.status().is_ok());
-//     let package = effects
-//         .created()
-//         .iter()
-//         .find(|(_, owner)| matches!(owner, Owner::Immutable))
-//         .unwrap()
-//         .0
-//         .0;
-//
-//     let gas_object = authority_state.get_object(&gas_object_id).await.unwrap().unwrap();
-//     let initial_value = GasCoin::try_from(&gas_object)?.value();
-//     let gas_object_ref = gas_object.compute_object_reference();
-//     let module = ident_str!("move_random").to_owned();
-//     let function = ident_str!("loopy").to_owned();
-//     let args = vec![];
-//     let budget = 1_500_000_000;
-//     let data = TransactionData::new_move_call(
-//         sender,
-//         package,
-//         module,
-//         function,
-//         vec![],
-//         gas_object_ref,
-//         args,
-//         budget,
-//         300,
-//     )
-//     .unwrap();
-//
-//     let tx = to_sender_signed_transaction(data, &sender_key);
-//     let effects = send_and_confirm_transaction(&authority_state, tx)
-//         .await
-//         .unwrap()
-//         .1
-//         .into_data();
-//     assert_eq!(
-//         effects.status().clone().unwrap_err().0,
-//         ExecutionFailureStatus::InsufficientGas
-//     );
-//     let gas_ref = effects.gas_object().0;
-//     let gas_object = authority_state.get_object(&gas_ref.0).await.unwrap().unwrap();
-//     let final_value = GasCoin::try_from(&gas_object)?.value();
-//     let summary = effects.gas_cost_summary();
-//     assert!(summary.computation_cost == budget);
-//     assert!(initial_value - budget == final_value);
-//     Ok(())
-// }
+#[test]
+fn test_max_gas_budget_follows_protocol_version() {
+    let sender = dbg_addr(1);
+    let gas_price = 1;
+    let mut ceilings = vec![];
+    for version in ProtocolVersion::MIN.as_u64()..=ProtocolVersion::MAX.as_u64() {
+        let config = ProtocolConfig::get_for_version(ProtocolVersion::new(version));
+        let cost_table = SuiCostTable::new(&config);
+        // Gas model 1 expresses the ceiling in gas units, later models in MIST.
+        let ceiling = if config.gas_model_version() == 1 {
+            config.max_tx_gas() * gas_price
+        } else {
+            config.max_tx_gas()
+        };
+        let gas_object =
+            Object::with_id_owner_gas_for_testing(ObjectID::random(), sender, ceiling + 1);
+
+        cost_table
+            .check_gas_balance(&gas_object, vec![], ceiling, gas_price)
+            .unwrap();
+        assert_eq!(
+            cost_table
+                .check_gas_balance(&gas_object, vec![], ceiling + 1, gas_price)
+                .unwrap_err(),
+            UserInputError::GasBudgetTooHigh {
+                gas_budget: ceiling + 1,
+                max_budget: config.max_tx_gas(),
+            }
+        );
+        ceilings.push(ceiling);
+    }
+    ceilings.dedup();
+    assert!(ceilings.len() > 1, "ceiling never changed: {:?}", ceilings);
+}
+
+#[test]
+fn test_cost_table_builder() {
+    let config = ProtocolConfig::get_for_max_version();
+    let cost_table = SuiCostTable::builder(&config)
+        .with_min_gas_budget(10)
+        .with_max_gas_budget(1_000)
+        .with_package_publish_per_byte_cost(2)
+        .with_object_read_per_byte_cost(3)
+        .with_storage_per_byte_cost(4)
+        .build();
+    assert_eq!(cost_table.min_gas_budget(), 10);
+    assert_eq!(cost_table.max_gas_budget(), 1_000);
+    assert_eq!(cost_table.package_publish_per_byte_cost(), 2);
+    assert_eq!(cost_table.object_read_per_byte_cost(), 3);
+    assert_eq!(cost_table.storage_per_byte_cost(), 4);
+
+    // The overrides are what the budget checks use.
+    let gas_object = Object::with_id_owner_gas_for_testing(ObjectID::random(), dbg_addr(1), 2_000);
+    assert_eq!(
+        cost_table
+            .check_gas_balance(&gas_object, vec![], 1_001, 1)
+            .unwrap_err(),
+        UserInputError::GasBudgetTooHigh {
+            gas_budget: 1_001,
+            max_budget: 1_000,
+        }
+    );
+    cost_table
+        .check_gas_balance(&gas_object, vec![], 1_000, 1)
+        .unwrap();
+
+    // Without overrides the builder matches the protocol config.
+    let default_table = SuiCostTable::builder(&config).build();
+    let expected = SuiCostTable::new(&config);
+    assert_eq!(default_table.min_gas_budget(), expected.min_gas_budget());
+    assert_eq!(default_table.max_gas_budget(), config.max_tx_gas());
+    assert_eq!(
+        default_table.storage_per_byte_cost(),
+        config.obj_data_cost_refundable()
+    );
+}
+
+#[test]
+fn test_non_refundable_storage_fee_follows_rebate_rate() {
+    // Storage cost of the object being deleted, chosen so the rebate rate doesn't divide it.
+    let storage_cost = 1_234_567;
+    let split = |config: &ProtocolConfig| {
+        let mut gas_status = SuiGasStatus::new_with_budget(*MAX_GAS_BUDGET, 1, config);
+        gas_status.track_storage_mutation(0, storage_cost);
+        gas_status.summary()
+    };
+    // The sender's share of the rebate is rounded to the nearest MIST.
+    let expected_fee =
+        |rate: u64| storage_cost - ((storage_cost as u128 * rate as u128 + 5_000) / 10_000) as u64;
+
+    let config = ProtocolConfig::get_for_max_version();
+    let summary = split(&config);
+    assert_eq!(
+        summary.non_refundable_storage_fee,
+        expected_fee(config.storage_rebate_rate())
+    );
+    assert_eq!(
+        summary.storage_rebate + summary.non_refundable_storage_fee,
+        storage_cost
+    );
+
+    // Gas model 1 doesn't split the rebate at all.
+    for version in ProtocolVersion::MIN.as_u64()..=ProtocolVersion::MAX.as_u64() {
+        let config = ProtocolConfig::get_for_version(ProtocolVersion::new(version));
+        if config.gas_model_version() < 2 {
+            continue;
+        }
+        assert_eq!(
+            split(&config).non_refundable_storage_fee,
+            expected_fee(config.storage_rebate_rate()),
+            "protocol version {}",
+            version
+        );
+    }
+
+    let mut config = ProtocolConfig::get_for_max_version();
+    for rate in [0, 5_000, 10_000] {
+        config.set_storage_rebate_rate_for_testing(rate);
+        assert_eq!(
+            split(&config).non_refundable_storage_fee,
+            expected_fee(rate)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_tx_max_computation() -> SuiResult {
+    // This test runs an infinite loop in Move. Execution should stop once the
+    // computation budget is exhausted, fail with out of gas and charge the whole budget.
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let authority_state = init_state_with_ids(vec![(sender, gas_object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+
+    let response = build_and_try_publish_test_package(
+        &authority_state,
+        &sender,
+        &sender_key,
+        &gas_object_id,
+        "move_random",
+        TEST_ONLY_GAS_UNIT_FOR_PUBLISH * rgp,
+        rgp,
+        /* with_unpublished_deps */ false,
+    )
+    .await;
+    let effects = response.1.into_data();
+    assert!(effects.status().is_ok());
+    let package = effects
+        .created()
+        .iter()
+        .find(|(_, owner)| matches!(owner, Owner::Immutable))
+        .unwrap()
+        .0
+         .0;
+
+    let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
+    let initial_value = GasCoin::try_from(&gas_object)?.value();
+    let gas_object_ref = gas_object.compute_object_reference();
+    let module = ident_str!("move_random").to_owned();
+    let function = ident_str!("loopy").to_owned();
+    let budget = 1_500_000 * rgp;
+    let data = TransactionData::new_move_call(
+        sender,
+        package,
+        module,
+        function,
+        vec![],
+        gas_object_ref,
+        vec![],
+        budget,
+        rgp,
+    )
+    .unwrap();
+
+    let tx = to_sender_signed_transaction(data, &sender_key);
+    let effects = send_and_confirm_transaction(&authority_state, tx)
+        .await
+        .unwrap()
+        .1
+        .into_data();
+    assert_eq!(
+        effects.status().clone().unwrap_err().0,
+        ExecutionFailureStatus::InsufficientGas
+    );
+    let summary = effects.gas_cost_summary();
+    assert_eq!(summary.computation_cost, budget);
+    assert_eq!(summary.storage_cost, 0);
+    let gas_ref = effects.gas_object().0;
+    let gas_object = authority_state.get_object(&gas_ref.0).await?.unwrap();
+    let final_value = GasCoin::try_from(&gas_object)?.value();
+    assert_eq!(initial_value - budget, final_value);
+    Ok(())
+}
 
 #[tokio::test]
 async fn test_tx_gas_balance_less_than_budget() {
@@ -196,6 +392,58 @@ async fn test_native_transfer_gas_price_is_used() {
     ));
 }
 
+#[tokio::test]
+async fn test_gas_price_overflow_is_rejected() {
+    // Any reference gas price up to 1M puts this price past what computation costs can
+    // be scaled by without overflowing.
+    let rgp_multiple = u64::MAX / 1_000_000;
+
+    // A budget near u64::MAX is rejected for the budget itself.
+    let gas_budget = u64::MAX - 1;
+    let result = execute_transfer_with_price(u64::MAX, gas_budget, rgp_multiple, false).await;
+    assert_eq!(
+        UserInputError::try_from(result.response.unwrap_err()).unwrap(),
+        UserInputError::GasBudgetTooHigh {
+            gas_budget,
+            max_budget: *MAX_GAS_BUDGET,
+        }
+    );
+
+    // Within the budget ceiling, the price alone would overflow the computation charge.
+    let result =
+        execute_transfer_with_price(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, rgp_multiple, true).await;
+    let err = UserInputError::try_from(result.response.unwrap_err()).unwrap();
+    assert!(
+        matches!(
+            err,
+            UserInputError::GasPriceTooHigh {
+                gas_price,
+                max_gas_price,
+            } if gas_price > max_gas_price
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_zero_balance_gas_payment() {
+    let result = execute_transfer(0, *MIN_GAS_BUDGET, false).await;
+    assert_eq!(
+        UserInputError::try_from(result.response.unwrap_err()).unwrap(),
+        UserInputError::ZeroGasBalance
+    );
+    // Rejected while handling the transaction, nothing was executed.
+    let gas_object = result
+        .authority_state
+        .get_object(&result.gas_object_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(gas_object.version(), OBJECT_START_VERSION);
+    assert_eq!(GasCoin::try_from(&gas_object).unwrap().value(), 0);
+}
+
 #[tokio::test]
 async fn test_transfer_sui_insufficient_gas() {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
@@ -231,6 +479,7 @@ async fn test_transfer_sui_insufficient_gas() {
 
 /// - All gas coins should be owned by an address (not shared or immutable)
 /// - All gas coins should be owned by the sender, or the sponsor
+/// - No gas coin should be listed more than once
 #[tokio::test]
 async fn test_invalid_gas_owners() {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
@@ -335,93 +584,504 @@ async fn test_invalid_gas_owners() {
         )
         .await,
         UserInputError::IncorrectUserSignature { .. }
-    ))
+    ));
+    assert_eq!(
+        test(
+            gas_object1,
+            gas_object1,
+            sender,
+            &sender_key,
+            &authority_state
+        )
+        .await,
+        UserInputError::DuplicateGasCoin {
+            object_id: gas_object1.0
+        }
+    );
 }
 
+/// A sponsor can pay for the sender's transaction with its own gas coin, but not with a
+/// coin neither of them owns.
 #[tokio::test]
-async fn test_native_transfer_insufficient_gas_reading_objects() {
-    // This test creates a transfer transaction with a gas budget, that's more than
-    // the minimum budget requirement, but not enough to even read the objects from db.
-    // This will lead to failure in lock check step during handle transaction phase.
-    let balance = *MIN_GAS_BUDGET + 1;
-    let result = execute_transfer(balance, balance, true).await;
-    // The transaction should still execute to effects, but with execution status as failure.
-    let effects = result
-        .response
-        .unwrap()
-        .into_effects_for_testing()
+async fn test_sponsored_gas_owners() -> SuiResult {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let (sponsor, sponsor_key): (_, AccountKeyPair) = get_key_pair();
+    let third_party = dbg_addr(3);
+    let object_id = ObjectID::random();
+    let authority_state = init_state_with_ids(vec![(sender, object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+
+    let sponsor_gas = Object::with_owner_for_testing(sponsor);
+    let sponsor_gas_ref = sponsor_gas.compute_object_reference();
+    authority_state.insert_genesis_object(sponsor_gas).await;
+    let third_party_gas = Object::with_owner_for_testing(third_party);
+    let third_party_gas_ref = third_party_gas.compute_object_reference();
+    authority_state.insert_genesis_object(third_party_gas).await;
+    let object = authority_state.get_object(&object_id).await?.unwrap();
+
+    let kind = {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .transfer_object(dbg_addr(2), object.compute_object_reference())
+            .unwrap();
+        TransactionKind::ProgrammableTransaction(builder.finish())
+    };
+    let sponsored_tx = |payment| {
+        let data = TransactionData::new_with_gas_data(
+            kind.clone(),
+            sender,
+            GasData {
+                payment: vec![payment],
+                owner: sponsor,
+                price: rgp,
+                budget: *MAX_GAS_BUDGET,
+            },
+        );
+        to_sender_signed_transaction_with_multi_signers(data, vec![&sender_key, &sponsor_key])
+    };
+
+    let result =
+        send_and_confirm_transaction(&authority_state, sponsored_tx(third_party_gas_ref)).await;
+    assert!(matches!(
+        UserInputError::try_from(result.unwrap_err()).unwrap(),
+        UserInputError::IncorrectUserSignature { .. }
+    ));
+
+    let effects = send_and_confirm_transaction(&authority_state, sponsored_tx(sponsor_gas_ref))
+        .await?
+        .1
         .into_data();
+    assert!(effects.status().is_ok());
+    // The sponsor paid, and still owns its gas coin.
+    assert_eq!(effects.gas_object().1, Owner::AddressOwner(sponsor));
+    let gas_object = authority_state
+        .get_object(&sponsor_gas_ref.0)
+        .await?
+        .unwrap();
     assert_eq!(
-        effects.into_status().unwrap_err().0,
-        ExecutionFailureStatus::InsufficientGas
+        GasCoin::try_from(&gas_object)?.value(),
+        GAS_VALUE_FOR_TESTING - effects.gas_cost_summary().net_gas_usage() as u64
     );
+    Ok(())
 }
 
+/// Gas coins whose balances are individually below the budget, but cover it together,
+/// should be accepted and smashed into the first coin.
 #[tokio::test]
-async fn test_native_transfer_insufficient_gas_execution() {
-    // This test creates a transfer transaction with a gas budget that's insufficient
-    // to finalize the transfer object mutation effects. It will fail during
-    // execution phase, and hence gas object will still be mutated and all budget
-    // will be charged.
-    let result = execute_transfer(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, true).await;
-    let total_gas = result
-        .response
-        .unwrap()
-        .into_effects_for_testing()
-        .data()
-        .gas_cost_summary()
-        .gas_used();
-    let budget = total_gas - 1;
-    let result = execute_transfer(budget, budget, true).await;
-    let effects = result
+async fn test_gas_smashing_multiple_coins() -> SuiResult {
+    // Find out how much a single-coin transfer costs, and budget for twice that.
+    let reference = execute_transfer(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, true).await;
+    let reference_effects = reference
         .response
         .unwrap()
         .into_effects_for_testing()
         .into_data();
-    // Transaction failed for out of gas so charge is same as budget
-    assert!(effects.gas_cost_summary().gas_used() == budget);
-    let gas_object = result
-        .authority_state
-        .get_object(&result.gas_object_id)
-        .await
-        .unwrap()
-        .unwrap();
-    let gas_coin = GasCoin::try_from(&gas_object).unwrap();
-    assert_eq!(gas_coin.value(), 0);
-    // After a failed transfer, the version should have been incremented,
-    // but the owner of the object should remain the same, unchanged.
-    let ((_, version, _), owner) = effects.mutated_excluding_gas().first().unwrap();
-    assert_eq!(version, &gas_object.version());
-    assert_eq!(owner, &gas_object.owner);
+    let gas_budget = reference_effects.gas_cost_summary().gas_used() * 2;
+    let coin_balance = gas_budget / 3 + 1;
+    assert!(coin_balance < gas_budget);
+
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let object_id = ObjectID::random();
+    let recipient = dbg_addr(2);
+    let authority_state = init_state_with_ids(vec![(sender, object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+
+    let mut gas_coins = vec![];
+    for _ in 0..3 {
+        let gas_object =
+            Object::with_id_owner_gas_for_testing(ObjectID::random(), sender, coin_balance);
+        gas_coins.push(gas_object.compute_object_reference());
+        authority_state.insert_genesis_object(gas_object).await;
+    }
+    let object = authority_state.get_object(&object_id).await?.unwrap();
+
+    let pt = {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .transfer_object(recipient, object.compute_object_reference())
+            .unwrap();
+        builder.finish()
+    };
+    let kind = TransactionKind::ProgrammableTransaction(pt);
+    let data =
+        TransactionData::new_with_gas_coins(kind, sender, gas_coins.clone(), gas_budget, rgp);
+    let tx = to_sender_signed_transaction(data, &sender_key);
+
+    let effects = send_and_confirm_transaction(&authority_state, tx)
+        .await?
+        .1
+        .into_data();
+    assert!(effects.status().is_ok());
+
+    // The first coin pays for gas, and the others are merged into it and deleted.
+    assert_eq!(effects.gas_object().0 .0, gas_coins[0].0);
+    let deleted: Vec<_> = effects.deleted().iter().map(|obj_ref| obj_ref.0).collect();
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted.contains(&gas_coins[1].0));
+    assert!(deleted.contains(&gas_coins[2].0));
 
+    let gas_cost = effects.gas_cost_summary();
+    let gas_object = authority_state.get_object(&gas_coins[0].0).await?.unwrap();
     assert_eq!(
-        effects.into_status().unwrap_err().0,
-        ExecutionFailureStatus::InsufficientGas,
+        GasCoin::try_from(&gas_object)?.value() as i64,
+        (coin_balance * 3) as i64 - gas_cost.net_gas_usage()
     );
+    for (id, _, _) in &gas_coins[1..] {
+        assert!(authority_state.get_object(id).await?.is_none());
+    }
+    Ok(())
 }
 
+/// The order gas coins are listed in must not change what is charged, or how much is
+/// left in the merged coin.
 #[tokio::test]
-async fn test_publish_gas() -> anyhow::Result<()> {
+async fn test_gas_smashing_independent_of_coin_order() -> SuiResult {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
-    let gas_object_id = ObjectID::random();
-    let authority_state = init_state_with_ids(vec![(sender, gas_object_id)]).await;
-    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
-
-    // The successful case.
-    let response = build_and_try_publish_test_package(
-        &authority_state,
-        &sender,
-        &sender_key,
-        &gas_object_id,
-        "object_wrapping",
-        TEST_ONLY_GAS_UNIT_FOR_PUBLISH * rgp * 2,
-        rgp,
-        /* with_unpublished_deps */ false,
-    )
-    .await;
-    let effects = response.1.into_data();
-    let gas_cost = effects.gas_cost_summary();
-    assert!(gas_cost.storage_cost > 0);
+    let object_id = ObjectID::random();
+    let recipient = dbg_addr(2);
+    let coins: Vec<_> = [1_000_000_000, 2_000_000_000, 3_000_000_000]
+        .into_iter()
+        .map(|balance| (ObjectID::random(), balance))
+        .collect();
+
+    async fn run(
+        sender: SuiAddress,
+        sender_key: &AccountKeyPair,
+        object_id: ObjectID,
+        recipient: SuiAddress,
+        coins: &[(ObjectID, u64)],
+    ) -> (GasCostSummary, u64) {
+        let authority_state = init_state_with_ids(vec![(sender, object_id)]).await;
+        let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+        let mut gas_coins = vec![];
+        for (id, balance) in coins {
+            let gas_object = Object::with_id_owner_gas_for_testing(*id, sender, *balance);
+            gas_coins.push(gas_object.compute_object_reference());
+            authority_state.insert_genesis_object(gas_object).await;
+        }
+        let object = authority_state
+            .get_object(&object_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let pt = {
+            let mut builder = ProgrammableTransactionBuilder::new();
+            builder
+                .transfer_object(recipient, object.compute_object_reference())
+                .unwrap();
+            builder.finish()
+        };
+        let kind = TransactionKind::ProgrammableTransaction(pt);
+        let data =
+            TransactionData::new_with_gas_coins(kind, sender, gas_coins, *MAX_GAS_BUDGET, rgp);
+        let tx = to_sender_signed_transaction(data, sender_key);
+
+        let effects = send_and_confirm_transaction(&authority_state, tx)
+            .await
+            .unwrap()
+            .1
+            .into_data();
+        assert!(effects.status().is_ok());
+        let gas_object = authority_state
+            .get_object(&coins[0].0)
+            .await
+            .unwrap()
+            .unwrap();
+        (
+            effects.gas_cost_summary().clone(),
+            GasCoin::try_from(&gas_object).unwrap().value(),
+        )
+    }
+
+    let (summary, balance) = run(sender, &sender_key, object_id, recipient, &coins).await;
+    let mut permuted = coins.clone();
+    permuted.rotate_left(1);
+    let (permuted_summary, permuted_balance) =
+        run(sender, &sender_key, object_id, recipient, &permuted).await;
+    permuted.reverse();
+    let (reversed_summary, reversed_balance) =
+        run(sender, &sender_key, object_id, recipient, &permuted).await;
+
+    assert_eq!(summary, permuted_summary);
+    assert_eq!(summary, reversed_summary);
+    assert_eq!(balance, permuted_balance);
+    assert_eq!(balance, reversed_balance);
+    Ok(())
+}
+
+/// Paying with `max_gas_payment_objects` gas coins or more is rejected before execution.
+#[tokio::test]
+async fn test_too_many_gas_coins() {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let authority_state = init_state().await;
+    let epoch_store = authority_state.load_epoch_store_one_call_per_task();
+    let rgp = epoch_store.reference_gas_price();
+    let max = epoch_store.protocol_config().max_gas_payment_objects() as u64;
+
+    let mut gas_coins = vec![];
+    for _ in 0..max {
+        let gas_object = Object::with_owner_for_testing(sender);
+        gas_coins.push(gas_object.compute_object_reference());
+        authority_state.insert_genesis_object(gas_object).await;
+    }
+
+    let make_tx = |gas_coins: Vec<ObjectRef>| {
+        let pt = {
+            let mut builder = ProgrammableTransactionBuilder::new();
+            builder.transfer_sui(dbg_addr(2), None);
+            builder.finish()
+        };
+        let kind = TransactionKind::ProgrammableTransaction(pt);
+        let data =
+            TransactionData::new_with_gas_coins(kind, sender, gas_coins, *MAX_GAS_BUDGET, rgp);
+        to_sender_signed_transaction(data, &sender_key)
+    };
+
+    let result = authority_state
+        .handle_transaction(&epoch_store, make_tx(gas_coins.clone()))
+        .await;
+    assert_eq!(
+        UserInputError::try_from(result.unwrap_err()).unwrap(),
+        UserInputError::TooManyGasCoins { count: max, max }
+    );
+
+    // One coin fewer than the limit is fine.
+    gas_coins.pop();
+    assert_eq!(gas_coins.len() as u64, max - 1);
+    authority_state
+        .handle_transaction(&epoch_store, make_tx(gas_coins))
+        .await
+        .unwrap();
+}
+
+/// A coin can't pay for gas and be an input to the transaction at the same time.
+#[tokio::test]
+async fn test_gas_coin_used_as_input() {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let authority_state = init_state().await;
+    let epoch_store = authority_state.load_epoch_store_one_call_per_task();
+    let rgp = epoch_store.reference_gas_price();
+    let gas_object = Object::with_owner_for_testing(sender);
+    let gas_object_ref = gas_object.compute_object_reference();
+    authority_state.insert_genesis_object(gas_object).await;
+
+    let pt = {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .transfer_object(dbg_addr(2), gas_object_ref)
+            .unwrap();
+        builder.finish()
+    };
+    let kind = TransactionKind::ProgrammableTransaction(pt);
+    let data = TransactionData::new(kind, sender, gas_object_ref, *MAX_GAS_BUDGET, rgp);
+    let tx = to_sender_signed_transaction(data, &sender_key);
+
+    let result = authority_state.handle_transaction(&epoch_store, tx).await;
+    assert_eq!(
+        UserInputError::try_from(result.unwrap_err()).unwrap(),
+        UserInputError::GasCoinUsedAsInput {
+            object_id: gas_object_ref.0
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_native_transfer_insufficient_gas_reading_objects() {
+    // This test creates a transfer transaction with a gas budget, that's more than
+    // the minimum budget requirement, but not enough to even read the objects from db.
+    // This passes the checks of the handle transaction phase and fails when execution
+    // charges for reading the objects, see `test_gas_charged_before_and_during_execution`.
+    let balance = *MIN_GAS_BUDGET + 1;
+    let result = execute_transfer(balance, balance, true).await;
+    // The transaction should still execute to effects, but with execution status as failure.
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    assert_eq!(
+        effects.into_status().unwrap_err().0,
+        ExecutionFailureStatus::InsufficientGas
+    );
+}
+
+#[tokio::test]
+async fn test_native_transfer_insufficient_gas_execution() {
+    // This test creates a transfer transaction with a gas budget that's insufficient
+    // to finalize the transfer object mutation effects. It will fail during
+    // execution phase, and hence gas object will still be mutated and all budget
+    // will be charged.
+    let result = execute_transfer(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, true).await;
+    let total_gas = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .data()
+        .gas_cost_summary()
+        .gas_used();
+    let budget = total_gas - 1;
+    let result = execute_transfer(budget, budget, true).await;
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    let gas_object = result
+        .authority_state
+        .get_object(&result.gas_object_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let gas_coin = GasCoin::try_from(&gas_object).unwrap();
+    assert_eq!(gas_coin.value(), 0);
+    // After a failed transfer, the owner of the object remains the same, unchanged.
+    assert_gas_invariants(
+        &effects,
+        &GasExpectations {
+            success: false,
+            budget: Some(budget),
+            gas_balance: Some((budget, gas_coin.value())),
+            mutated_owner: Some(gas_object.owner),
+            ..Default::default()
+        },
+    );
+    // The version should still have been incremented.
+    let ((_, version, _), _) = effects.mutated_excluding_gas().first().unwrap();
+    assert_eq!(version, &gas_object.version());
+
+    assert_eq!(
+        effects.into_status().unwrap_err().0,
+        ExecutionFailureStatus::InsufficientGas,
+    );
+}
+
+#[tokio::test]
+async fn test_gas_charged_before_and_during_execution() -> SuiResult {
+    // Rejected before execution: there are no effects and the gas coin is left untouched.
+    let balance = *MAX_GAS_BUDGET;
+    let result = execute_transfer(balance, *MIN_GAS_BUDGET - 1, true).await;
+    assert!(result.response.is_err());
+    let gas_object = result
+        .authority_state
+        .get_object(&result.gas_object_id)
+        .await?
+        .unwrap();
+    assert_eq!(GasCoin::try_from(&gas_object).unwrap().value(), balance);
+    assert_eq!(gas_object.version(), OBJECT_START_VERSION);
+
+    // Out of gas reading the objects, before any command runs: the whole budget is charged
+    // as computation and, the writes being dropped, nothing as storage.
+    let budget = *MIN_GAS_BUDGET + 1;
+    let result = execute_transfer(budget, budget, true).await;
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    assert_eq!(
+        *effects.gas_cost_summary(),
+        GasCostSummary {
+            computation_cost: budget,
+            storage_cost: 0,
+            storage_rebate: 0,
+            non_refundable_storage_fee: 0,
+        }
+    );
+    assert_gas_invariants(
+        &effects,
+        &GasExpectations {
+            success: false,
+            budget: Some(budget),
+            gas_balance: Some((budget, 0)),
+            ..Default::default()
+        },
+    );
+
+    // Failing in a command: the computation performed is charged, and the storage of the
+    // gas coin and inputs written back at their new version, minus their rebate.
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let coin_id = ObjectID::random();
+    let gas_object_id = ObjectID::random();
+    let authority_state =
+        init_state_with_ids(vec![(sender, coin_id), (sender, gas_object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+    let coin = authority_state.get_object(&coin_id).await?.unwrap();
+    let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
+    let data = TransactionData::new_pay(
+        sender,
+        vec![coin.compute_object_reference()],
+        vec![dbg_addr(2)],
+        vec![GAS_VALUE_FOR_TESTING + 1],
+        gas_object.compute_object_reference(),
+        *MAX_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+    let tx = to_sender_signed_transaction(data, &sender_key);
+    let effects = send_and_confirm_transaction(&authority_state, tx)
+        .await?
+        .1
+        .into_data();
+    assert!(
+        matches!(
+            effects.status(),
+            ExecutionStatus::Failure {
+                error: ExecutionFailureStatus::InsufficientCoinBalance,
+                ..
+            }
+        ),
+        "{:?}",
+        effects.status()
+    );
+    let gas_cost = effects.gas_cost_summary();
+    assert!(gas_cost.computation_cost < *MAX_GAS_BUDGET);
+    let coin_after = authority_state.get_object(&coin_id).await?.unwrap();
+    let gas_object_after = authority_state.get_object(&gas_object_id).await?.unwrap();
+    assert_eq!(
+        gas_cost.storage_cost,
+        coin_after.storage_rebate + gas_object_after.storage_rebate
+    );
+    assert_eq!(
+        gas_cost.storage_rebate + gas_cost.non_refundable_storage_fee,
+        coin.storage_rebate + gas_object.storage_rebate
+    );
+    assert_eq!(
+        GAS_VALUE_FOR_TESTING as i64 - GasCoin::try_from(&gas_object_after).unwrap().value() as i64,
+        gas_cost.net_gas_usage()
+    );
+    // The coin failed to split from is written back as it was.
+    assert_eq!(coin_after.version(), gas_object_after.version());
+    assert_eq!(coin_after.owner, Owner::AddressOwner(sender));
+    assert_eq!(
+        GasCoin::try_from(&coin_after).unwrap().value(),
+        GAS_VALUE_FOR_TESTING
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_publish_gas() -> anyhow::Result<()> {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let authority_state = init_state_with_ids(vec![(sender, gas_object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+
+    // The successful case.
+    let response = build_and_try_publish_test_package(
+        &authority_state,
+        &sender,
+        &sender_key,
+        &gas_object_id,
+        "object_wrapping",
+        TEST_ONLY_GAS_UNIT_FOR_PUBLISH * rgp * 2,
+        rgp,
+        /* with_unpublished_deps */ false,
+    )
+    .await;
+    let effects = response.1.into_data();
+    let gas_cost = effects.gas_cost_summary();
+    assert!(gas_cost.storage_cost > 0);
 
     let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
     let gas_size = gas_object.object_size_for_gas_metering();
@@ -475,6 +1135,44 @@ async fn test_publish_gas() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_publish_gas_zero_storage_price() -> anyhow::Result<()> {
+    let _guard = ProtocolConfig::apply_overrides_for_testing(|_, mut config| {
+        config.set_storage_gas_price_for_testing(0);
+        config
+    });
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let authority_state = init_state_with_ids(vec![(sender, gas_object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+
+    let response = build_and_try_publish_test_package(
+        &authority_state,
+        &sender,
+        &sender_key,
+        &gas_object_id,
+        "object_wrapping",
+        TEST_ONLY_GAS_UNIT_FOR_PUBLISH * rgp * 2,
+        rgp,
+        /* with_unpublished_deps */ false,
+    )
+    .await;
+    let effects = response.1.into_data();
+    assert!(effects.status().is_ok());
+    let gas_cost = effects.gas_cost_summary();
+    assert_eq!(gas_cost.storage_cost, 0);
+    assert_eq!(gas_cost.storage_rebate, 0);
+    assert_eq!(gas_cost.non_refundable_storage_fee, 0);
+    assert!(gas_cost.computation_cost > 0);
+
+    let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
+    assert_eq!(
+        GasCoin::try_from(&gas_object)?.value(),
+        GAS_VALUE_FOR_TESTING - gas_cost.computation_cost,
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_move_call_gas() -> SuiResult {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
@@ -507,15 +1205,21 @@ async fn test_move_call_gas() -> SuiResult {
     let response = send_and_confirm_transaction(&authority_state, tx).await?;
     let effects = response.1.into_data();
     let created_object_ref = effects.created()[0].0;
-    assert!(effects.status().is_ok());
     let gas_cost = effects.gas_cost_summary();
     assert!(gas_cost.storage_cost > 0);
     assert_eq!(gas_cost.storage_rebate, 0);
     let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
-    let expected_gas_balance = GAS_VALUE_FOR_TESTING - gas_cost.net_gas_usage() as u64;
-    assert_eq!(
-        GasCoin::try_from(&gas_object)?.value(),
-        expected_gas_balance,
+    assert_gas_invariants(
+        &effects,
+        &GasExpectations {
+            success: true,
+            budget: Some(*MAX_GAS_BUDGET),
+            gas_balance: Some((
+                GAS_VALUE_FOR_TESTING,
+                GasCoin::try_from(&gas_object)?.value(),
+            )),
+            ..Default::default()
+        },
     );
 
     // This is the total amount of storage cost paid. We will use this
@@ -541,19 +1245,592 @@ async fn test_move_call_gas() -> SuiResult {
     let transaction = to_sender_signed_transaction(data, &sender_key);
     let response = send_and_confirm_transaction(&authority_state, transaction).await?;
     let effects = response.1.into_data();
-    assert!(effects.status().is_ok());
     let gas_cost = effects.gas_cost_summary();
     // storage_cost should be less than rebate because for object deletion, we only
     // rebate without charging.
     assert!(gas_cost.storage_cost > 0 && gas_cost.storage_cost < gas_cost.storage_rebate);
-    // Check that we have storage rebate is less or equal to the previous one + non refundable
+    assert_gas_invariants(
+        &effects,
+        &GasExpectations {
+            success: true,
+            budget: Some(*MAX_GAS_BUDGET),
+            prev_storage_cost: Some(prev_storage_cost),
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
+/// Deleting an object rebates exactly what was paid to store it, minus the non-refundable
+/// fee, whatever the size of the object.
+#[tokio::test]
+async fn test_storage_rebate_across_object_sizes() -> SuiResult {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (authority_state, package_object_ref) =
+        init_state_with_ids_and_object_basics(vec![(sender, gas_object_id)]).await;
+    let rgp = authority_state.reference_gas_price_for_testing().unwrap();
+    // The BCS length prefix takes up to 3 bytes of a pure argument.
+    let max_payload_size =
+        ProtocolConfig::get_for_max_version().max_pure_argument_size() as usize - 3;
+
+    // `None` creates the small `Object` struct, otherwise a `Payload` of that many bytes.
+    for payload_size in [None, Some(1024), Some(max_payload_size)] {
+        let (create, delete, mut args) = match payload_size {
+            None => (
+                ident_str!("create"),
+                ident_str!("delete"),
+                vec![CallArg::Pure(16u64.to_le_bytes().to_vec())],
+            ),
+            Some(size) => (
+                ident_str!("create_payload"),
+                ident_str!("delete_payload"),
+                vec![CallArg::Pure(bcs::to_bytes(&vec![7u8; size]).unwrap())],
+            ),
+        };
+        args.push(CallArg::Pure(
+            bcs::to_bytes(&AccountAddress::from(sender)).unwrap(),
+        ));
+
+        let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
+        let data = TransactionData::new_move_call(
+            sender,
+            package_object_ref.0,
+            ident_str!("object_basics").to_owned(),
+            create.to_owned(),
+            vec![],
+            gas_object.compute_object_reference(),
+            args,
+            *MAX_GAS_BUDGET,
+            rgp,
+        )
+        .unwrap();
+        let tx = to_sender_signed_transaction(data, &sender_key);
+        let effects = send_and_confirm_transaction(&authority_state, tx)
+            .await?
+            .1
+            .into_data();
+        assert!(effects.status().is_ok(), "{:?}", effects.status());
+        let created_object_ref = effects.created()[0].0;
+        let prev_storage_cost = effects.gas_cost_summary().storage_cost;
+
+        let gas_object = authority_state.get_object(&gas_object_id).await?.unwrap();
+        let data = TransactionData::new_move_call(
+            sender,
+            package_object_ref.0,
+            ident_str!("object_basics").to_owned(),
+            delete.to_owned(),
+            vec![],
+            gas_object.compute_object_reference(),
+            vec![CallArg::Object(ObjectArg::ImmOrOwnedObject(
+                created_object_ref,
+            ))],
+            *MAX_GAS_BUDGET,
+            rgp,
+        )
+        .unwrap();
+        let tx = to_sender_signed_transaction(data, &sender_key);
+        let effects = send_and_confirm_transaction(&authority_state, tx)
+            .await?
+            .1
+            .into_data();
+        assert!(effects.status().is_ok(), "{:?}", effects.status());
+        let gas_cost = effects.gas_cost_summary();
+        assert!(
+            gas_cost.storage_cost < gas_cost.storage_rebate,
+            "payload size {:?}: {:?}",
+            payload_size,
+            gas_cost
+        );
+        assert_eq!(
+            gas_cost.storage_rebate + gas_cost.non_refundable_storage_fee,
+            prev_storage_cost,
+            "payload size {:?}",
+            payload_size
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dry_run_move_call_gas() -> SuiResult {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (validator, fullnode, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+
+    let data = TransactionData::new_move_call(
+        sender,
+        package_object_ref.0,
+        ident_str!("object_basics").to_owned(),
+        ident_str!("create").to_owned(),
+        Vec::new(),
+        gas_object.compute_object_reference(),
+        vec![
+            CallArg::Pure(16u64.to_le_bytes().to_vec()),
+            CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+        ],
+        *MAX_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+
+    let dry_run = fullnode.dry_run_transaction(data.clone()).await.unwrap();
+    assert_eq!(*dry_run.effects.status(), SuiExecutionStatus::Success);
+    let estimate = dry_run.effects.gas_cost_summary().clone();
+
+    // Dry running must not have touched the gas coin.
+    let gas_object_after_dry_run = fullnode.get_object(&gas_object_id).await?.unwrap();
+    assert_eq!(gas_object_after_dry_run, gas_object);
+
+    let tx = to_sender_signed_transaction(data, &sender_key);
+    let effects = send_and_confirm_transaction(&validator, tx)
+        .await?
+        .1
+        .into_data();
+    assert!(effects.status().is_ok());
+    let gas_cost = effects.gas_cost_summary();
+
+    let margin = gas_cost.gas_used() / 100;
+    assert!(
+        estimate
+            .computation_cost
+            .abs_diff(gas_cost.computation_cost)
+            <= margin
+    );
+    assert!(estimate.storage_cost.abs_diff(gas_cost.storage_cost) <= margin);
+    assert_eq!(estimate.storage_rebate, gas_cost.storage_rebate);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dry_run_coin_split_changes() -> SuiResult {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let recipient = dbg_addr(2);
+    let coin_id = ObjectID::random();
+    let gas_object_id = ObjectID::random();
+    let (_, fullnode, _) = init_state_with_ids_and_object_basics_with_fullnode(vec![
+        (sender, coin_id),
+        (sender, gas_object_id),
+    ])
+    .await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let coin = fullnode.get_object(&coin_id).await?.unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+
+    let amount = 1000;
+    let data = TransactionData::new_pay(
+        sender,
+        vec![coin.compute_object_reference()],
+        vec![recipient],
+        vec![amount],
+        gas_object.compute_object_reference(),
+        *MAX_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+    let response = fullnode.dry_run_transaction(data).await.unwrap();
+    assert_eq!(*response.effects.status(), SuiExecutionStatus::Success);
+
+    // The coin split off goes to the recipient, the coin it comes from and the gas coin
+    // are mutated.
+    let mut created = vec![];
+    let mut mutated = vec![];
+    for change in &response.object_changes {
+        match change {
+            ObjectChange::Created {
+                owner, object_type, ..
+            } => created.push((*owner, object_type.clone())),
+            ObjectChange::Mutated {
+                object_id,
+                previous_version,
+                ..
+            } => mutated.push((*object_id, *previous_version)),
+            change => panic!("Unexpected object change {:?}", change),
+        }
+    }
     assert_eq!(
-        gas_cost.storage_rebate + gas_cost.non_refundable_storage_fee,
-        prev_storage_cost
+        created,
+        vec![(Owner::AddressOwner(recipient), GasCoin::type_())]
+    );
+    mutated.sort();
+    let mut expected_mutated = vec![
+        (coin_id, coin.version()),
+        (gas_object_id, gas_object.version()),
+    ];
+    expected_mutated.sort();
+    assert_eq!(mutated, expected_mutated);
+
+    // The sender spends the amount and the gas, the recipient receives the amount.
+    let net_gas_usage = response.effects.gas_cost_summary().net_gas_usage() as i128;
+    let balance_change = |address| {
+        response
+            .balance_changes
+            .iter()
+            .find(|change| change.owner == Owner::AddressOwner(address))
+            .map(|change| (change.coin_type.clone(), change.amount))
+    };
+    assert_eq!(response.balance_changes.len(), 2);
+    assert_eq!(
+        balance_change(sender),
+        Some((GAS::type_tag(), -(amount as i128) - net_gas_usage))
+    );
+    assert_eq!(
+        balance_change(recipient),
+        Some((GAS::type_tag(), amount as i128))
+    );
+
+    // Nothing the preview shows was committed.
+    assert_eq!(fullnode.get_object(&coin_id).await?.unwrap(), coin);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_command_gas_breakdown() -> SuiResult {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (_, fullnode, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder
+        .move_call(
+            package_object_ref.0,
+            ident_str!("object_basics").to_owned(),
+            ident_str!("create").to_owned(),
+            vec![],
+            vec![
+                CallArg::Pure(16u64.to_le_bytes().to_vec()),
+                CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+            ],
+        )
+        .unwrap();
+    builder
+        .move_call(
+            package_object_ref.0,
+            ident_str!("object_basics").to_owned(),
+            ident_str!("create_payload").to_owned(),
+            vec![],
+            vec![
+                CallArg::Pure(bcs::to_bytes(&vec![7u8; 1024]).unwrap()),
+                CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+            ],
+        )
+        .unwrap();
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![gas_object.compute_object_reference()],
+        builder.finish(),
+        *MAX_GAS_BUDGET,
+        rgp,
     );
+
+    let (effects, breakdown) = fullnode
+        .dry_run_transaction_with_gas_breakdown(data)
+        .await
+        .unwrap();
+    assert_eq!(*effects.status(), SuiExecutionStatus::Success);
+    let breakdown = breakdown.unwrap();
+    assert_eq!(breakdown.commands.len(), 2);
+    assert!(breakdown.commands.iter().all(|cost| *cost > 0));
+    assert_eq!(
+        breakdown.total(),
+        effects.gas_cost_summary().computation_cost
+    );
+    assert_eq!(breakdown.shortfall, None);
+    Ok(())
+}
+
+/// A dry run short of gas to charge for storage tells by how much, while the effects only
+/// say the transaction ran out of gas.
+#[tokio::test]
+async fn test_gas_shortfall_in_breakdown() -> SuiResult {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (_, fullnode, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+    let make_data = |budget| {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        builder
+            .move_call(
+                package_object_ref.0,
+                ident_str!("object_basics").to_owned(),
+                ident_str!("create_payload").to_owned(),
+                vec![],
+                vec![
+                    CallArg::Pure(bcs::to_bytes(&vec![7u8; 1024]).unwrap()),
+                    CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+                ],
+            )
+            .unwrap();
+        TransactionData::new_programmable(
+            sender,
+            vec![gas_object.compute_object_reference()],
+            builder.finish(),
+            budget,
+            rgp,
+        )
+    };
+
+    let (effects, _) = fullnode
+        .dry_run_transaction_with_gas_breakdown(make_data(*MAX_GAS_BUDGET))
+        .await
+        .unwrap();
+    assert_eq!(*effects.status(), SuiExecutionStatus::Success);
+    let net_gas = effects.gas_cost_summary().net_gas_usage() as u64;
+
+    // Enough for computation, one short for the storage of the new object net of rebates.
+    let budget = net_gas - 1;
+    let (effects, breakdown) = fullnode
+        .dry_run_transaction_with_gas_breakdown(make_data(budget))
+        .await
+        .unwrap();
+    assert!(matches!(
+        effects.status(),
+        SuiExecutionStatus::Failure { .. }
+    ));
+    assert_eq!(breakdown.unwrap().shortfall, Some(1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gas_profile() -> SuiResult {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (_, fullnode, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder
+        .move_call(
+            package_object_ref.0,
+            ident_str!("object_basics").to_owned(),
+            ident_str!("create").to_owned(),
+            vec![],
+            vec![
+                CallArg::Pure(16u64.to_le_bytes().to_vec()),
+                CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+            ],
+        )
+        .unwrap();
+    let data = TransactionData::new_programmable(
+        sender,
+        vec![gas_object.compute_object_reference()],
+        builder.finish(),
+        *MAX_GAS_BUDGET,
+        rgp,
+    );
+
+    let (effects, profile) = fullnode
+        .dry_run_transaction_with_gas_profile(data.clone())
+        .await
+        .unwrap();
+    assert_eq!(*effects.status(), SuiExecutionStatus::Success);
+    let profile = profile.unwrap();
+    assert!(profile.operations.contains_key("call"));
+    assert!(profile
+        .operations
+        .keys()
+        .any(|label| label.starts_with("native:")));
+    assert_eq!(
+        profile.total() * rgp,
+        effects.gas_cost_summary().computation_cost
+    );
+
+    // Profiling does not change what is charged.
+    let unprofiled = fullnode.dry_run_transaction(data).await.unwrap();
+    assert_eq!(
+        unprofiled.effects.gas_cost_summary(),
+        effects.gas_cost_summary()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_auto_gas_budget() -> SuiResult {
+    let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (validator, fullnode, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = fullnode.reference_gas_price_for_testing().unwrap();
+    let gas_object = fullnode.get_object(&gas_object_id).await?.unwrap();
+
+    let data = TransactionData::new_move_call(
+        sender,
+        package_object_ref.0,
+        ident_str!("object_basics").to_owned(),
+        ident_str!("create").to_owned(),
+        Vec::new(),
+        gas_object.compute_object_reference(),
+        vec![
+            CallArg::Pure(16u64.to_le_bytes().to_vec()),
+            CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+        ],
+        *MIN_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+    // 10% margin on top of the dry run estimate.
+    let margin_bps = 1_000;
+    let data = fullnode
+        .with_auto_gas_budget(data, margin_bps, *MAX_GAS_BUDGET)
+        .await
+        .unwrap();
+    let budget = data.gas_budget();
+
+    let tx = to_sender_signed_transaction(data, &sender_key);
+    let effects = send_and_confirm_transaction(&validator, tx)
+        .await?
+        .1
+        .into_data();
+    assert!(effects.status().is_ok());
+    let gas_used = effects.gas_cost_summary().gas_used();
+    assert!(budget >= gas_used);
+    assert!(budget <= gas_used + gas_used * margin_bps / 10_000 + gas_used / 100);
+
+    // The ceiling caps the budget, and a ceiling below the cost fails the dry run. The
+    // fullnode has not seen the transaction, its gas coin is still the one of genesis.
+    let data = TransactionData::new_move_call(
+        sender,
+        package_object_ref.0,
+        ident_str!("object_basics").to_owned(),
+        ident_str!("create").to_owned(),
+        Vec::new(),
+        gas_object.compute_object_reference(),
+        vec![
+            CallArg::Pure(16u64.to_le_bytes().to_vec()),
+            CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+        ],
+        *MIN_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+    let capped = fullnode
+        .with_auto_gas_budget(data.clone(), margin_bps, gas_used)
+        .await
+        .unwrap();
+    assert_eq!(capped.gas_budget(), gas_used);
+    assert!(fullnode
+        .with_auto_gas_budget(data.clone(), margin_bps, *MIN_GAS_BUDGET)
+        .await
+        .is_err());
+    // A ceiling below the protocol minimum can't be kept, rather than going over it.
+    let err = fullnode
+        .with_auto_gas_budget(data, margin_bps, *MIN_GAS_BUDGET - 1)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("below the minimum budget"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dry_run_only_on_fullnode() -> SuiResult {
+    let (sender, _): (_, AccountKeyPair) = get_key_pair();
+    let gas_object_id = ObjectID::random();
+    let (validator, _, package_object_ref) =
+        init_state_with_ids_and_object_basics_with_fullnode(vec![(sender, gas_object_id)]).await;
+    let rgp = validator.reference_gas_price_for_testing().unwrap();
+    let gas_object = validator.get_object(&gas_object_id).await?.unwrap();
+    let data = TransactionData::new_move_call(
+        sender,
+        package_object_ref.0,
+        ident_str!("object_basics").to_owned(),
+        ident_str!("create").to_owned(),
+        Vec::new(),
+        gas_object.compute_object_reference(),
+        vec![
+            CallArg::Pure(16u64.to_le_bytes().to_vec()),
+            CallArg::Pure(bcs::to_bytes(&AccountAddress::from(sender)).unwrap()),
+        ],
+        *MAX_GAS_BUDGET,
+        rgp,
+    )
+    .unwrap();
+
+    // A validator never runs an unsigned transaction, whichever way it is asked to.
+    assert!(validator.dry_run_transaction(data.clone()).await.is_err());
+    assert!(validator
+        .dry_run_transaction_with_gas_breakdown(data.clone())
+        .await
+        .is_err());
+    assert!(validator
+        .dry_run_transaction_with_gas_profile(data.clone())
+        .await
+        .is_err());
+    assert!(validator
+        .with_auto_gas_budget(data, 0, *MAX_GAS_BUDGET)
+        .await
+        .is_err());
     Ok(())
 }
 
+#[tokio::test]
+async fn test_reference_gas_price() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let network_config = sui_config::builder::ConfigBuilder::new(&dir).build();
+    let genesis = network_config.genesis;
+    let keypair = network_config.validator_configs[0]
+        .protocol_key_pair()
+        .copy();
+    let authority_state = init_state_with_committee(&genesis, &keypair).await;
+    assert_eq!(
+        authority_state.reference_gas_price().await.unwrap(),
+        genesis.reference_gas_price()
+    );
+
+    // The price follows the epoch store of the next epoch once it is loaded.
+    let epoch_store = authority_state.epoch_store_for_testing().clone();
+    let next_committee = Committee::new(
+        epoch_store.epoch() + 1,
+        epoch_store
+            .committee()
+            .voting_rights
+            .iter()
+            .cloned()
+            .collect(),
+    );
+    let next_reference_gas_price = genesis.reference_gas_price() * 2;
+    let system_state = EpochStartSystemState::new_v1(
+        next_committee.epoch,
+        ProtocolVersion::MAX.as_u64(),
+        next_reference_gas_price,
+        false,
+        0,
+        1000,
+        vec![],
+    );
+    authority_state
+        .reopen_epoch_db(
+            &epoch_store,
+            next_committee,
+            EpochStartConfiguration::new_v1(system_state, CheckpointDigest::default()),
+            &ExpensiveSafetyCheckConfig::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        authority_state.reference_gas_price().await.unwrap(),
+        next_reference_gas_price
+    );
+
+    authority_state
+        .epoch_store_for_testing()
+        .epoch_terminated()
+        .await;
+    assert!(matches!(
+        authority_state.reference_gas_price().await,
+        Err(SuiError::ValidatorHaltedAtEpochEnd)
+    ));
+}
+
 #[tokio::test]
 async fn test_tx_gas_price_less_than_reference_gas_price() {
     let gas_balance = *MAX_GAS_BUDGET;
@@ -565,9 +1842,95 @@ async fn test_tx_gas_price_less_than_reference_gas_price() {
     ));
 }
 
+#[tokio::test]
+async fn test_transfer_to_multiple_recipients() {
+    let recipients = vec![dbg_addr(2), dbg_addr(3), dbg_addr(4)];
+    let result =
+        execute_transfers(*MAX_GAS_BUDGET, *MAX_GAS_BUDGET, recipients.clone(), true).await;
+    let effects = result
+        .response
+        .unwrap()
+        .into_effects_for_testing()
+        .into_data();
+    assert!(effects.status().is_ok());
+    // The gas object and all three transferred objects.
+    assert_eq!(effects.mutated().len(), 4);
+    for (recipient, object_id) in recipients.into_iter().zip(&result.object_ids) {
+        let object = result
+            .authority_state
+            .get_object(object_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(object.owner, Owner::AddressOwner(recipient));
+    }
+}
+
+/// Expected outcome of a transaction for `assert_gas_invariants`, `None` checks skipped.
+#[derive(Default)]
+struct GasExpectations {
+    /// Whether the transaction succeeds, otherwise it must have run out of gas.
+    success: bool,
+    /// The gas budget, never exceeded and charged in full when running out of gas.
+    budget: Option<u64>,
+    /// Balance of the gas coin before and after the transaction, which differ by exactly
+    /// the net gas usage.
+    gas_balance: Option<(u64, u64)>,
+    /// Storage cost paid for the objects the transaction deletes or mutates, all of it
+    /// paid back as the storage rebate plus the non-refundable fee.
+    prev_storage_cost: Option<u64>,
+    /// Owner each object other than the gas coin still has after the transaction, e.g. the
+    /// sender after a failed transfer.
+    mutated_owner: Option<Owner>,
+}
+
+/// Check the invariants the gas charged by a transaction upholds, as `expected` says.
+fn assert_gas_invariants(effects: &TransactionEffects, expected: &GasExpectations) {
+    let gas_cost = effects.gas_cost_summary();
+    if expected.success {
+        assert!(effects.status().is_ok(), "{:?}", effects.status());
+    } else {
+        assert!(
+            matches!(
+                effects.status(),
+                ExecutionStatus::Failure {
+                    error: ExecutionFailureStatus::InsufficientGas,
+                    ..
+                }
+            ),
+            "{:?}",
+            effects.status()
+        );
+    }
+    if let Some(budget) = expected.budget {
+        if expected.success {
+            assert!(gas_cost.gas_used() <= budget);
+        } else {
+            // Out of gas, the whole budget is charged.
+            assert_eq!(gas_cost.gas_used(), budget);
+        }
+    }
+    if let Some((before, after)) = expected.gas_balance {
+        assert_eq!(before as i64 - after as i64, gas_cost.net_gas_usage());
+    }
+    if let Some(prev_storage_cost) = expected.prev_storage_cost {
+        assert_eq!(
+            gas_cost.storage_rebate + gas_cost.non_refundable_storage_fee,
+            prev_storage_cost
+        );
+    }
+    if let Some(owner) = &expected.mutated_owner {
+        for (_, mutated_owner) in effects.mutated_excluding_gas() {
+            assert_eq!(mutated_owner, owner);
+        }
+    }
+}
+
 struct TransferResult {
     pub authority_state: Arc<AuthorityState>,
     pub gas_object_id: ObjectID,
+    // the transferred objects, in the order of their recipients
+    pub object_ids: Vec<ObjectID>,
     pub response: SuiResult<TransactionStatus>,
 }
 
@@ -580,28 +1943,56 @@ async fn execute_transfer_with_price(
     gas_budget: u64,
     rgp_multiple: u64,
     run_confirm: bool,
+) -> TransferResult {
+    execute_transfers_with_price(
+        gas_balance,
+        gas_budget,
+        rgp_multiple,
+        vec![dbg_addr(2)],
+        run_confirm,
+    )
+    .await
+}
+
+/// Transfer a new object to each of `recipients`, all in one transaction.
+async fn execute_transfers(
+    gas_balance: u64,
+    gas_budget: u64,
+    recipients: Vec<SuiAddress>,
+    run_confirm: bool,
+) -> TransferResult {
+    execute_transfers_with_price(gas_balance, gas_budget, 1, recipients, run_confirm).await
+}
+
+async fn execute_transfers_with_price(
+    gas_balance: u64,
+    gas_budget: u64,
+    rgp_multiple: u64,
+    recipients: Vec<SuiAddress>,
+    run_confirm: bool,
 ) -> TransferResult {
     let (sender, sender_key): (_, AccountKeyPair) = get_key_pair();
-    let object_id: ObjectID = ObjectID::random();
-    let recipient = dbg_addr(2);
-    let authority_state = init_state_with_ids(vec![(sender, object_id)]).await;
+    let object_ids: Vec<ObjectID> = recipients.iter().map(|_| ObjectID::random()).collect();
+    let authority_state = init_state_with_ids(object_ids.iter().map(|id| (sender, *id))).await;
     let rgp = authority_state.reference_gas_price_for_testing().unwrap() * rgp_multiple;
     let epoch_store = authority_state.load_epoch_store_one_call_per_task();
     let gas_object_id = ObjectID::random();
     let gas_object = Object::with_id_owner_gas_for_testing(gas_object_id, sender, gas_balance);
     let gas_object_ref = gas_object.compute_object_reference();
     authority_state.insert_genesis_object(gas_object).await;
-    let object = authority_state
-        .get_object(&object_id)
-        .await
-        .unwrap()
-        .unwrap();
+    let mut recipients_and_objects = vec![];
+    for (recipient, object_id) in recipients.into_iter().zip(&object_ids) {
+        let object = authority_state
+            .get_object(object_id)
+            .await
+            .unwrap()
+            .unwrap();
+        recipients_and_objects.push((recipient, object.compute_object_reference()));
+    }
 
     let pt = {
         let mut builder = ProgrammableTransactionBuilder::new();
-        builder
-            .transfer_object(recipient, object.compute_object_reference())
-            .unwrap();
+        builder.transfer_objects_to(recipients_and_objects).unwrap();
         builder.finish()
     };
     let kind = TransactionKind::ProgrammableTransaction(pt);
@@ -627,6 +2018,7 @@ async fn execute_transfer_with_price(
     TransferResult {
         authority_state,
         gas_object_id,
+        object_ids,
         response,
     }
 }
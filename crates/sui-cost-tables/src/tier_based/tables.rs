@@ -69,6 +69,12 @@ pub struct GasStatus<'a> {
     instructions_executed: u64,
     instructions_next_tier_start: Option<u64>,
     instructions_current_tier_mult: u64,
+
+    // Internal gas charged per operation label, only tracked once profiling is enabled.
+    profile: Option<BTreeMap<String, InternalGas>>,
+    // The function of the last call charged while profiling, that native charges are
+    // attributed to.
+    profiled_callee: Option<String>,
 }
 
 impl<'a> GasStatus<'a> {
@@ -104,6 +110,8 @@ impl<'a> GasStatus<'a> {
             stack_height_next_tier_start,
             stack_size_next_tier_start,
             instructions_next_tier_start,
+            profile: None,
+            profiled_callee: None,
         }
     }
 
@@ -131,6 +139,8 @@ impl<'a> GasStatus<'a> {
             stack_height_next_tier_start,
             stack_size_next_tier_start,
             instructions_next_tier_start,
+            profile: None,
+            profiled_callee: None,
         }
     }
 
@@ -156,6 +166,8 @@ impl<'a> GasStatus<'a> {
             stack_height_next_tier_start: None,
             stack_size_next_tier_start: None,
             instructions_next_tier_start: None,
+            profile: None,
+            profiled_callee: None,
         }
     }
 
@@ -335,6 +347,76 @@ impl<'a> GasStatus<'a> {
         let computation_cost = size as u64 * cost_per_byte;
         self.deduct_units(computation_cost)
     }
+
+    /// Attribute the gas charged through the `GasMeter` hooks to the bytecode instruction
+    /// category or native function charging it, at the cost of some bookkeeping on every
+    /// charge. Meant for dry runs, it does not change what is charged.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Gas units charged per operation label when profiling, rounded down per label.
+    /// Natives are labeled `native:<module>::<function>`.
+    pub fn profile(&self) -> Option<BTreeMap<String, u64>> {
+        self.profile.as_ref().map(|profile| {
+            profile
+                .iter()
+                .map(|(label, charged)| {
+                    let gas: Gas = InternalGas::to_unit_round_down(*charged);
+                    (label.clone(), u64::from(gas))
+                })
+                .collect()
+        })
+    }
+
+    /// Run `charge`, recording the gas it deducts under `label` when profiling.
+    fn profiled(
+        &mut self,
+        label: &str,
+        charge: impl FnOnce(&mut Self) -> PartialVMResult<()>,
+    ) -> PartialVMResult<()> {
+        if self.profile.is_none() {
+            return charge(self);
+        }
+        let gas_left = self.gas_left;
+        let result = charge(self);
+        let charged = gas_left
+            .checked_sub(self.gas_left)
+            .unwrap_or_else(InternalGas::zero);
+        if let Some(profile) = &mut self.profile {
+            *profile
+                .entry(label.to_string())
+                .or_insert_with(InternalGas::zero) += charged;
+        }
+        result
+    }
+
+    /// Remember the callee while profiling, a native call is charged right after its `Call`.
+    fn profile_call(&mut self, module_id: &ModuleId, func_name: &str) {
+        if self.profile.is_some() {
+            self.profiled_callee = Some(format!("native:{}::{}", module_id, func_name));
+        }
+    }
+}
+
+/// The category the gas charged for a simple instruction is profiled under.
+fn simple_instruction_category(instr: SimpleInstruction) -> &'static str {
+    use SimpleInstruction::*;
+
+    match instr {
+        Nop | Ret | BrTrue | BrFalse | Branch | Abort => "control_flow",
+        LdU8 | LdU16 | LdU32 | LdU64 | LdU128 | LdU256 | LdTrue | LdFalse => "load_constant",
+        FreezeRef
+        | ImmBorrowLoc
+        | MutBorrowLoc
+        | ImmBorrowField
+        | MutBorrowField
+        | ImmBorrowFieldGeneric
+        | MutBorrowFieldGeneric => "reference",
+        CastU8 | CastU16 | CastU32 | CastU64 | CastU128 | CastU256 => "cast",
+        Add | Sub | Mul | Mod | Div | BitOr | BitAnd | Xor | Shl | Shr => "arithmetic",
+        Or | And | Not | Lt | Gt | Le | Ge => "logic",
+    }
 }
 
 /// Returns a tuple of (<pops>, <pushes>, <stack_size_decrease>, <stack_size_increase>)
@@ -389,11 +471,15 @@ impl<'b> GasMeter for GasStatus<'b> {
     /// Charge an instruction and fail if not enough gas units are left.
     fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
         let (pops, pushes, pop_size, push_size) = get_simple_instruction_stack_change(instr);
-        self.charge(1, pushes, pops, push_size.into(), pop_size.into())
+        self.profiled(simple_instruction_category(instr), |this| {
+            this.charge(1, pushes, pops, push_size.into(), pop_size.into())
+        })
     }
 
     fn charge_pop(&mut self, popped_val: impl ValueView) -> PartialVMResult<()> {
-        self.charge(1, 0, 1, 0, popped_val.legacy_abstract_memory_size().into())
+        self.profiled("stack", |this| {
+            this.charge(1, 0, 1, 0, popped_val.legacy_abstract_memory_size().into())
+        })
     }
 
     fn charge_native_function(
@@ -418,9 +504,12 @@ impl<'b> GasMeter for GasStatus<'b> {
         // Charge for the stack operations. We don't count this as an "instruction" since we
         // already accounted for the `Call` instruction in the
         // `charge_native_function_before_execution` call.
-        self.charge(0, pushes, 0, size_increase.into(), 0)?;
-        // Now charge the gas that the native function told us to charge.
-        self.deduct_gas(amount)
+        let label = self.profiled_callee.clone().unwrap_or_default();
+        self.profiled(&label, |this| {
+            this.charge(0, pushes, 0, size_increase.into(), 0)?;
+            // Now charge the gas that the native function told us to charge.
+            this.deduct_gas(amount)
+        })
     }
 
     fn charge_native_function_before_execution(
@@ -438,16 +527,20 @@ impl<'b> GasMeter for GasStatus<'b> {
         // Track that this is going to be popping from the operand stack. We also increment the
         // instruction count as we need to account for the `Call` bytecode that initiated this
         // native call.
-        self.charge(1, 0, pops, 0, stack_reduction_size.into())
+        let label = self.profiled_callee.clone().unwrap_or_default();
+        self.profiled(&label, |this| {
+            this.charge(1, 0, pops, 0, stack_reduction_size.into())
+        })
     }
 
     fn charge_call(
         &mut self,
-        _module_id: &ModuleId,
-        _func_name: &str,
+        module_id: &ModuleId,
+        func_name: &str,
         args: impl ExactSizeIterator<Item = impl ValueView>,
         _num_locals: NumArgs,
     ) -> PartialVMResult<()> {
+        self.profile_call(module_id, func_name);
         // We will have to perform this many pops for the call.
         let pops = args.len() as u64;
         // Size stays the same -- we're just moving it from the operand stack to the locals. But
@@ -455,17 +548,20 @@ impl<'b> GasMeter for GasStatus<'b> {
         let stack_reduction_size = args.fold(AbstractMemorySize::new(0), |acc, elem| {
             acc + elem.legacy_abstract_memory_size()
         });
-        self.charge(1, 0, pops, 0, stack_reduction_size.into())
+        self.profiled("call", |this| {
+            this.charge(1, 0, pops, 0, stack_reduction_size.into())
+        })
     }
 
     fn charge_call_generic(
         &mut self,
-        _module_id: &ModuleId,
-        _func_name: &str,
+        module_id: &ModuleId,
+        func_name: &str,
         _ty_args: impl ExactSizeIterator<Item = impl TypeView>,
         args: impl ExactSizeIterator<Item = impl ValueView>,
         _num_locals: NumArgs,
     ) -> PartialVMResult<()> {
+        self.profile_call(module_id, func_name);
         // We have to perform this many pops from the operand stack for this function call.
         let pops = args.len() as u64;
         // Calculate the size reduction on the operand stack.
@@ -474,12 +570,16 @@ impl<'b> GasMeter for GasStatus<'b> {
         });
         // Charge for the pops, no pushes, and account for the stack size decrease. Also track the
         // `CallGeneric` instruction we must have encountered for this.
-        self.charge(1, 0, pops, 0, stack_reduction_size.into())
+        self.profiled("call", |this| {
+            this.charge(1, 0, pops, 0, stack_reduction_size.into())
+        })
     }
 
     fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()> {
         // Charge for the load from the locals onto the stack.
-        self.charge(1, 1, 0, u64::from(size), 0)
+        self.profiled("load_constant", |this| {
+            this.charge(1, 1, 0, u64::from(size), 0)
+        })
     }
 
     fn charge_ld_const_after_deserialization(
@@ -492,21 +592,27 @@ impl<'b> GasMeter for GasStatus<'b> {
 
     fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
         // Charge for the copy of the local onto the stack.
-        self.charge(1, 1, 0, val.legacy_abstract_memory_size().into(), 0)
+        self.profiled("local", |this| {
+            this.charge(1, 1, 0, val.legacy_abstract_memory_size().into(), 0)
+        })
     }
 
     fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
         // Charge for the move of the local on to the stack. Note that we charge here since we
         // aren't tracking the local size (at least not yet). If we were, this should be a net-zero
         // operation in terms of memory usage.
-        self.charge(1, 1, 0, val.legacy_abstract_memory_size().into(), 0)
+        self.profiled("local", |this| {
+            this.charge(1, 1, 0, val.legacy_abstract_memory_size().into(), 0)
+        })
     }
 
     fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
         // Charge for the storing of the value on the stack into a local. Note here that if we were
         // also accounting for the size of the locals that this would be a net-zero operation in
         // terms of memory.
-        self.charge(1, 0, 1, 0, val.legacy_abstract_memory_size().into())
+        self.profiled("local", |this| {
+            this.charge(1, 0, 1, 0, val.legacy_abstract_memory_size().into())
+        })
     }
 
     fn charge_pack(
@@ -518,7 +624,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         let num_fields = args.len() as u64;
         // The actual amount of memory on the stack is staying the same with the addition of some
         // extra size for the struct, so the size doesn't really change much.
-        self.charge(1, 1, num_fields, STRUCT_SIZE.into(), 0)
+        self.profiled("struct", |this| {
+            this.charge(1, 1, num_fields, STRUCT_SIZE.into(), 0)
+        })
     }
 
     fn charge_unpack(
@@ -528,20 +636,24 @@ impl<'b> GasMeter for GasStatus<'b> {
     ) -> PartialVMResult<()> {
         // We perform `num_fields` number of pushes.
         let num_fields = args.len() as u64;
-        self.charge(1, num_fields, 1, 0, STRUCT_SIZE.into())
+        self.profiled("struct", |this| {
+            this.charge(1, num_fields, 1, 0, STRUCT_SIZE.into())
+        })
     }
 
     fn charge_read_ref(&mut self, ref_val: impl ValueView) -> PartialVMResult<()> {
         // We read the the reference so we are decreasing the size of the stack by the size of the
         // reference, and adding to it the size of the value that has been read from that
         // reference.
-        self.charge(
-            1,
-            1,
-            1,
-            ref_val.legacy_abstract_memory_size().into(),
-            REFERENCE_SIZE.into(),
-        )
+        self.profiled("reference", |this| {
+            this.charge(
+                1,
+                1,
+                1,
+                ref_val.legacy_abstract_memory_size().into(),
+                REFERENCE_SIZE.into(),
+            )
+        })
     }
 
     fn charge_write_ref(
@@ -552,29 +664,35 @@ impl<'b> GasMeter for GasStatus<'b> {
         // TODO(tzakian): We should account for this elsewhere as the owner of data the the
         // reference points to won't be on the stack. For now though, we treat it as adding to the
         // stack size.
-        self.charge(
-            1,
-            1,
-            2,
-            new_val.legacy_abstract_memory_size().into(),
-            old_val.legacy_abstract_memory_size().into(),
-        )
+        self.profiled("reference", |this| {
+            this.charge(
+                1,
+                1,
+                2,
+                new_val.legacy_abstract_memory_size().into(),
+                old_val.legacy_abstract_memory_size().into(),
+            )
+        })
     }
 
     fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
         let size_reduction = lhs.legacy_abstract_memory_size() + rhs.legacy_abstract_memory_size();
-        self.charge(
-            1,
-            1,
-            2,
-            (Type::Bool.size() + size_reduction).into(),
-            size_reduction.into(),
-        )
+        self.profiled("logic", |this| {
+            this.charge(
+                1,
+                1,
+                2,
+                (Type::Bool.size() + size_reduction).into(),
+                size_reduction.into(),
+            )
+        })
     }
 
     fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
         let size_reduction = lhs.legacy_abstract_memory_size() + rhs.legacy_abstract_memory_size();
-        self.charge(1, 1, 2, Type::Bool.size().into(), size_reduction.into())
+        self.profiled("logic", |this| {
+            this.charge(1, 1, 2, Type::Bool.size().into(), size_reduction.into())
+        })
     }
 
     fn charge_load_resource(
@@ -592,7 +710,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         _ty: impl TypeView,
         _is_success: bool,
     ) -> PartialVMResult<()> {
-        self.charge(1, 1, 1, REFERENCE_SIZE.into(), Type::Address.size().into())
+        self.profiled("global_storage", |this| {
+            this.charge(1, 1, 1, REFERENCE_SIZE.into(), Type::Address.size().into())
+        })
     }
 
     fn charge_exists(
@@ -602,13 +722,15 @@ impl<'b> GasMeter for GasStatus<'b> {
         // TODO(Gas): see if we can get rid of this param
         _exists: bool,
     ) -> PartialVMResult<()> {
-        self.charge(
-            1,
-            1,
-            1,
-            Type::Bool.size().into(),
-            Type::Address.size().into(),
-        )
+        self.profiled("global_storage", |this| {
+            this.charge(
+                1,
+                1,
+                1,
+                Type::Bool.size().into(),
+                Type::Address.size().into(),
+            )
+        })
     }
 
     fn charge_move_from(
@@ -620,7 +742,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         let size = val
             .map(|val| val.legacy_abstract_memory_size())
             .unwrap_or_else(|| ty.to_type_tag().abstract_size_for_gas_metering());
-        self.charge(1, 1, 1, size.into(), Type::Address.size().into())
+        self.profiled("global_storage", |this| {
+            this.charge(1, 1, 1, size.into(), Type::Address.size().into())
+        })
     }
 
     fn charge_move_to(
@@ -630,7 +754,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         _val: impl ValueView,
         _is_success: bool,
     ) -> PartialVMResult<()> {
-        self.charge(1, 0, 2, 0, Type::Address.size().into())
+        self.profiled("global_storage", |this| {
+            this.charge(1, 0, 2, 0, Type::Address.size().into())
+        })
     }
 
     fn charge_vec_pack<'a>(
@@ -642,11 +768,15 @@ impl<'b> GasMeter for GasStatus<'b> {
         let num_args = args.len() as u64;
         // The amount of data on the stack stays constant except we have some extra metadata for
         // the vector to hold the length of the vector.
-        self.charge(1, 1, num_args, VEC_SIZE.into(), 0)
+        self.profiled("vector", |this| {
+            this.charge(1, 1, num_args, VEC_SIZE.into(), 0)
+        })
     }
 
     fn charge_vec_len(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
-        self.charge(1, 1, 1, Type::U64.size().into(), REFERENCE_SIZE.into())
+        self.profiled("vector", |this| {
+            this.charge(1, 1, 1, Type::U64.size().into(), REFERENCE_SIZE.into())
+        })
     }
 
     fn charge_vec_borrow(
@@ -655,13 +785,15 @@ impl<'b> GasMeter for GasStatus<'b> {
         _ty: impl TypeView,
         _is_success: bool,
     ) -> PartialVMResult<()> {
-        self.charge(
-            1,
-            1,
-            2,
-            REFERENCE_SIZE.into(),
-            (REFERENCE_SIZE + Type::U64.size()).into(),
-        )
+        self.profiled("vector", |this| {
+            this.charge(
+                1,
+                1,
+                2,
+                REFERENCE_SIZE.into(),
+                (REFERENCE_SIZE + Type::U64.size()).into(),
+            )
+        })
     }
 
     fn charge_vec_push_back(
@@ -670,7 +802,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         _val: impl ValueView,
     ) -> PartialVMResult<()> {
         // The value was already on the stack, so we aren't increasing the number of bytes on the stack.
-        self.charge(1, 0, 2, 0, REFERENCE_SIZE.into())
+        self.profiled("vector", |this| {
+            this.charge(1, 0, 2, 0, REFERENCE_SIZE.into())
+        })
     }
 
     fn charge_vec_pop_back(
@@ -678,7 +812,9 @@ impl<'b> GasMeter for GasStatus<'b> {
         _ty: impl TypeView,
         _val: Option<impl ValueView>,
     ) -> PartialVMResult<()> {
-        self.charge(1, 1, 1, 0, REFERENCE_SIZE.into())
+        self.profiled("vector", |this| {
+            this.charge(1, 1, 1, 0, REFERENCE_SIZE.into())
+        })
     }
 
     fn charge_vec_unpack(
@@ -690,12 +826,16 @@ impl<'b> GasMeter for GasStatus<'b> {
         // Charge for the pushes
         let pushes = u64::from(expect_num_elements);
         // The stack size stays pretty much the same modulo the additional vector size
-        self.charge(1, pushes, 1, 0, VEC_SIZE.into())
+        self.profiled("vector", |this| {
+            this.charge(1, pushes, 1, 0, VEC_SIZE.into())
+        })
     }
 
     fn charge_vec_swap(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
         let size_decrease = REFERENCE_SIZE + Type::U64.size() + Type::U64.size();
-        self.charge(1, 1, 1, 0, size_decrease.into())
+        self.profiled("vector", |this| {
+            this.charge(1, 1, 1, 0, size_decrease.into())
+        })
     }
 
     fn charge_drop_frame(
@@ -0,0 +1,71 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate criterion;
+
+use std::time::Duration;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+
+use sui_indexer::test_utils::TestCheckpointDataBuilder;
+use sui_indexer::{index_checkpoint_data, index_objects, index_packages, index_transactions};
+
+/// Numbers of transactions of the synthetic checkpoints, each of them a transfer creating a
+/// coin and mutating the gas coin, so that objects grow with transactions.
+const CHECKPOINT_SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn index_checkpoint_benchmark(c: &mut Criterion) {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let genesis = builder.genesis_checkpoint();
+    let checkpoints = CHECKPOINT_SIZES
+        .into_iter()
+        .map(|size| (size, builder.next_checkpoint(size)))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("index_checkpoint");
+    for (size, data) in &checkpoints {
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), data, |b, data| {
+            b.iter(|| index_checkpoint_data(data).unwrap())
+        });
+    }
+    group.finish();
+
+    // The stages of `index_checkpoint_data` on their own, to tell which one a regression of
+    // the whole comes from.
+    let mut group = c.benchmark_group("index_checkpoint_stages");
+    for (size, data) in &checkpoints {
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::new("transactions", size), data, |b, data| {
+            b.iter(|| index_transactions(data.transactions()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("objects", size), data, |b, data| {
+            b.iter(|| {
+                index_objects(
+                    data.checkpoint(),
+                    data.transactions(),
+                    data.changed_objects(),
+                )
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("packages", size), data, |b, data| {
+            b.iter(|| index_packages(data.transactions(), data.changed_objects()).unwrap())
+        });
+    }
+    // Transfers publish nothing, the genesis checkpoint publishes the framework packages.
+    group.throughput(Throughput::Elements(genesis.transactions().len() as u64));
+    group.bench_with_input(
+        BenchmarkId::new("packages", "genesis"),
+        &genesis,
+        |b, data| b.iter(|| index_packages(data.transactions(), data.changed_objects()).unwrap()),
+    );
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20).measurement_time(Duration::from_secs(10));
+    targets = index_checkpoint_benchmark
+}
+criterion_main!(benches);
@@ -8,16 +8,19 @@ use std::env;
 use std::time::Duration;
 
 use chrono::Utc;
-use criterion::Criterion;
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput};
+use prometheus::Registry;
 use tokio::runtime::Runtime;
 
+use sui_indexer::metrics::IndexerCheckpointHandlerMetrics;
 use sui_indexer::models::checkpoints::Checkpoint;
 use sui_indexer::models::objects::{NamedBcsBytes, Object as DBObject, ObjectStatus};
 use sui_indexer::models::owners::OwnerType;
-use sui_indexer::models::transactions::Transaction;
+use sui_indexer::models::transactions::{DBTransactionKind, Transaction};
 use sui_indexer::new_pg_connection_pool;
 use sui_indexer::store::{
-    IndexerStore, PgIndexerStore, TemporaryCheckpointStore, TransactionObjectChanges,
+    IndexerStore, MemoryIndexerStore, PgIndexerStore, TemporaryCheckpointStore,
+    TransactionObjectChanges,
 };
 use sui_indexer::utils::reset_database;
 use sui_json_rpc_types::CheckpointId;
@@ -29,34 +32,147 @@ use sui_types::messages::{TransactionData, TEST_ONLY_GAS_UNIT_FOR_TRANSFER};
 use sui_types::messages_checkpoint::CheckpointDigest;
 use sui_types::object::Object;
 
+/// Numbers of transactions and objects of the checkpoints `persist_checkpoint` throughput
+/// is measured with.
+const CHECKPOINT_SIZES: [(i64, i64); 3] = [(100, 100), (1_000, 1_000), (1_000, 10_000)];
+
 fn indexer_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    bench_persist_checkpoint(c, &rt, "memory", |_| MemoryIndexerStore::new());
+
+    // The Postgres variants need a running DB, set INDEXER_BENCH_POSTGRES to run them.
+    if env::var("INDEXER_BENCH_POSTGRES").is_err() {
+        return;
+    }
     let pg_host = env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".into());
     let pg_port = env::var("POSTGRES_PORT").unwrap_or_else(|_| "32770".into());
     let pw = env::var("POSTGRES_PASSWORD").unwrap_or_else(|_| "postgrespw".into());
     let db_url = format!("postgres://postgres:{pw}@{pg_host}:{pg_port}");
 
-    let rt = Runtime::new().unwrap();
-    let (mut checkpoints, store) = rt.block_on(async {
+    let (mut checkpoints, store, pools) = rt.block_on(async {
         let (blocking_cp, async_cp) = new_pg_connection_pool(&db_url).await.unwrap();
         reset_database(&mut blocking_cp.get().unwrap(), true).unwrap();
-        let store = PgIndexerStore::new(async_cp, blocking_cp).await;
+        let metrics = IndexerCheckpointHandlerMetrics::new(&Registry::default());
+        let store = PgIndexerStore::new(async_cp.clone(), blocking_cp.clone(), metrics).await;
 
-        let checkpoints = (0..150).map(create_checkpoint).collect::<Vec<_>>();
-        (checkpoints, store)
+        let checkpoints = (0..150)
+            .map(|seq| create_checkpoint(seq, 1000, 1000))
+            .collect::<Vec<_>>();
+        (checkpoints, store, (async_cp, blocking_cp))
     });
 
     c.bench_function("persist_checkpoint", |b| {
         b.iter(|| rt.block_on(store.persist_checkpoint(&checkpoints.pop().unwrap())))
     });
 
+    // The same shape of checkpoint over and over, committed with and without reusing the
+    // prepared statements of the inserts.
+    const TRANSACTIONS_PER_CHECKPOINT: i64 = 100;
+    let mut group = c.benchmark_group("persist_checkpoint_statement_cache");
+    group.throughput(Throughput::Elements(TRANSACTIONS_PER_CHECKPOINT as u64));
+    for (cache_statements, first_seq) in [(true, 1_000), (false, 100_000)] {
+        let store = store.clone().with_statement_cache(cache_statements);
+        let mut checkpoints = (first_seq..).map(|seq| {
+            create_checkpoint(
+                seq,
+                TRANSACTIONS_PER_CHECKPOINT,
+                TRANSACTIONS_PER_CHECKPOINT,
+            )
+        });
+        let name = if cache_statements {
+            "cached"
+        } else {
+            "uncached"
+        };
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || checkpoints.next().unwrap(),
+                |checkpoint| rt.block_on(store.persist_checkpoint(&checkpoint)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+
     let mut checkpoints = (20..100).cycle().map(CheckpointId::SequenceNumber);
     c.bench_function("get_checkpoint", |b| {
         b.to_async(Runtime::new().unwrap())
             .iter(|| store.get_checkpoint(checkpoints.next().unwrap()))
     });
+
+    bench_persist_checkpoint(c, &rt, "postgres", |metrics| {
+        let (async_cp, blocking_cp) = pools.clone();
+        rt.block_on(PgIndexerStore::new(async_cp, blocking_cp, metrics))
+    });
+}
+
+/// Measures `persist_checkpoint` throughput for each of `CHECKPOINT_SIZES`, on a store
+/// `new_store` makes for each size with fresh metrics. Prints the mean commit latency of each
+/// table the store records in its metrics once a size is done.
+fn bench_persist_checkpoint<S: IndexerStore>(
+    c: &mut Criterion,
+    rt: &Runtime,
+    store_name: &str,
+    new_store: impl Fn(IndexerCheckpointHandlerMetrics) -> S,
+) {
+    // Far from the sequence numbers of the other benchmarks, so they share the DB.
+    let mut sequence_numbers = 10_000_000..;
+    let mut group = c.benchmark_group(format!("persist_checkpoint_throughput/{store_name}"));
+    for (num_transactions, num_objects) in CHECKPOINT_SIZES {
+        let metrics = IndexerCheckpointHandlerMetrics::new(&Registry::default());
+        let store = new_store(metrics.clone());
+        group.throughput(Throughput::Elements(num_transactions as u64));
+        let id = format!("{num_transactions}_transactions_{num_objects}_objects");
+        group.bench_function(BenchmarkId::from_parameter(&id), |b| {
+            b.iter_batched(
+                || {
+                    let seq = sequence_numbers.next().unwrap();
+                    create_checkpoint(seq, num_transactions, num_objects)
+                },
+                |checkpoint| rt.block_on(store.persist_checkpoint(&checkpoint)).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+        report_table_latencies(&format!("{store_name}/{id}"), &metrics);
+    }
+    group.finish();
 }
 
-fn create_checkpoint(sequence_number: i64) -> TemporaryCheckpointStore {
+fn report_table_latencies(name: &str, metrics: &IndexerCheckpointHandlerMetrics) {
+    let tables = [
+        ("objects", &metrics.checkpoint_db_commit_latency_objects),
+        (
+            "transactions",
+            &metrics.checkpoint_db_commit_latency_transactions,
+        ),
+        ("events", &metrics.checkpoint_db_commit_latency_events),
+        ("packages", &metrics.checkpoint_db_commit_latency_packages),
+        (
+            "move_calls",
+            &metrics.checkpoint_db_commit_latency_move_calls,
+        ),
+        (
+            "recipients",
+            &metrics.checkpoint_db_commit_latency_recipients,
+        ),
+        ("addresses", &metrics.checkpoint_db_commit_latency_addresses),
+    ];
+    for (table, latency) in tables {
+        let count = latency.get_sample_count();
+        if count > 0 {
+            let mean_ms = latency.get_sample_sum() / count as f64 * 1000.0;
+            println!(
+                "{name}: mean commit latency of {table} is {mean_ms:.3} ms over {count} commits"
+            );
+        }
+    }
+}
+
+fn create_checkpoint(
+    sequence_number: i64,
+    num_transactions: i64,
+    num_objects: i64,
+) -> TemporaryCheckpointStore {
     TemporaryCheckpointStore {
         checkpoint: Checkpoint {
             sequence_number,
@@ -70,24 +186,30 @@ fn create_checkpoint(sequence_number: i64) -> TemporaryCheckpointStore {
             total_computation_cost: i64::MAX,
             total_storage_cost: i64::MAX,
             total_storage_rebate: i64::MAX,
-            total_transaction_blocks: 1000,
-            total_transactions: 1000,
+            total_transaction_blocks: num_transactions,
+            total_transactions: num_transactions,
             network_total_transactions: 0,
             timestamp_ms: Utc::now().timestamp_millis(),
         },
-        transactions: (1..1000)
+        transactions: (1..num_transactions)
             .map(|_| create_transaction(sequence_number))
             .collect(),
         events: vec![],
         objects_changes: vec![TransactionObjectChanges {
-            changed_objects: (1..1000).map(|_| create_object(sequence_number)).collect(),
+            changed_objects: (1..num_objects)
+                .map(|_| create_object(sequence_number))
+                .collect(),
             deleted_objects: vec![],
         }],
         addresses: vec![],
+        address_activities: vec![],
         packages: vec![],
         input_objects: vec![],
         move_calls: vec![],
         recipients: vec![],
+        ptb_commands: vec![],
+        dependencies: vec![],
+        consensus_commit: None,
     }
 }
 
@@ -137,6 +259,9 @@ fn create_transaction(sequence_number: i64) -> Transaction {
         transaction_content: serde_json::to_string(&tx).unwrap(),
         transaction_effects_content: "".to_string(),
         confirmed_local_execution: None,
+        kind: DBTransactionKind::ProgrammableTransaction,
+        transaction_signatures: vec![],
+        epoch: 0,
     }
 }
 
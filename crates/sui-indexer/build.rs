@@ -0,0 +1,12 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+use std::io::Result;
+fn main() -> Result<()> {
+    // add this env var to regenerate src/proto from the proto files, you'll need protoc installed locally
+    if option_env!("BUILD_CHECKPOINT_PROTO").is_some() {
+        prost_build::Config::new()
+            .out_dir("src/proto")
+            .compile_protos(&["proto/checkpoint.proto"], &["proto/"])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,3 @@
+ALTER TABLE transactions
+DROP COLUMN kind CASCADE;
+DROP TYPE IF EXISTS transaction_kind_type;
@@ -0,0 +1,24 @@
+DO
+$$
+    BEGIN
+        CREATE TYPE transaction_kind_type AS ENUM ('change_epoch', 'genesis', 'consensus_commit_prologue', 'programmable_transaction');
+    EXCEPTION
+        WHEN duplicate_object THEN
+            -- Type already exists, do nothing
+            NULL;
+    END
+$$;
+
+ALTER TABLE transactions
+    ADD COLUMN kind transaction_kind_type NOT NULL DEFAULT 'programmable_transaction';
+UPDATE transactions
+SET kind = CASE transaction_kind
+               WHEN 'ChangeEpoch' THEN 'change_epoch'::transaction_kind_type
+               WHEN 'Genesis' THEN 'genesis'::transaction_kind_type
+               WHEN 'ConsensusCommitPrologue' THEN 'consensus_commit_prologue'::transaction_kind_type
+               ELSE 'programmable_transaction'::transaction_kind_type
+    END;
+ALTER TABLE transactions
+    ALTER COLUMN kind DROP DEFAULT;
+
+CREATE INDEX transactions_kind ON transactions (kind, id);
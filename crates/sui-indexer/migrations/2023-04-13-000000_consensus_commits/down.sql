@@ -0,0 +1 @@
+DROP TABLE IF EXISTS consensus_commits;
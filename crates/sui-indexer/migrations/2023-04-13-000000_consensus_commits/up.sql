@@ -0,0 +1,11 @@
+CREATE TABLE consensus_commits
+(
+    checkpoint_sequence_number BIGINT       PRIMARY KEY,
+    epoch                      BIGINT       NOT NULL,
+    round                      BIGINT       NOT NULL,
+    -- set by consensus, distinct from the checkpoint timestamp
+    commit_timestamp_ms        BIGINT       NOT NULL,
+    checkpoint_timestamp_ms    BIGINT       NOT NULL,
+    -- digest of the consensus commit prologue transaction
+    transaction_digest         base58digest NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE move_calls
+DROP COLUMN move_type_arguments CASCADE;
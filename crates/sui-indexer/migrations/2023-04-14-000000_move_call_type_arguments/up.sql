@@ -0,0 +1,4 @@
+ALTER TABLE move_calls
+    -- each type argument is a normalized type tag, empty for non-generic calls
+    ADD COLUMN move_type_arguments TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX move_calls_move_type_arguments ON move_calls USING GIN (move_type_arguments);
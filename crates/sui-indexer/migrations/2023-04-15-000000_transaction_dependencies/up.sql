@@ -0,0 +1,9 @@
+-- edges of the causal graph of transactions, from the dependencies of the effects
+CREATE TABLE transaction_dependencies
+(
+    transaction_digest         base58digest NOT NULL,
+    depends_on_digest          base58digest NOT NULL,
+    checkpoint_sequence_number BIGINT       NOT NULL,
+    PRIMARY KEY (transaction_digest, depends_on_digest)
+);
+CREATE INDEX transaction_dependencies_depends_on_digest ON transaction_dependencies (depends_on_digest);
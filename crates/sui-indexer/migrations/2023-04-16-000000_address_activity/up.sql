@@ -0,0 +1,10 @@
+-- window of checkpoints in which an address appeared, maintained with LEAST / GREATEST
+-- so that reindexing checkpoints out of order keeps the window correct.
+CREATE TABLE address_activity
+(
+    account_address             address PRIMARY KEY,
+    first_appearance_checkpoint BIGINT  NOT NULL,
+    first_appearance_timestamp  BIGINT  NOT NULL,
+    last_appearance_checkpoint  BIGINT  NOT NULL,
+    last_appearance_timestamp   BIGINT  NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE transactions
+DROP COLUMN transaction_signatures CASCADE;
@@ -0,0 +1,5 @@
+ALTER TABLE transactions
+    -- base64 encoded `flag || signature || pubkey` of each signature, or the
+    -- multisig flag followed by the BCS bytes of the multisig.
+    -- Sponsored transactions have the signatures of both the sender and the sponsor.
+    ADD COLUMN transaction_signatures TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS ptb_commands;
@@ -0,0 +1,13 @@
+-- the ordered commands of each programmable transaction
+CREATE TABLE ptb_commands
+(
+    transaction_digest         base58digest NOT NULL,
+    command_index              BIGINT       NOT NULL,
+    checkpoint_sequence_number BIGINT       NOT NULL,
+    epoch                      BIGINT       NOT NULL,
+    -- MoveCall, TransferObjects, SplitCoins, MergeCoins, Publish, Upgrade or MakeMoveVec
+    command_kind               TEXT         NOT NULL,
+    -- only non-null for Publish and Upgrade, the resulting package
+    package_id                 address,
+    PRIMARY KEY (transaction_digest, command_index)
+);
@@ -0,0 +1,9 @@
+CREATE TABLE quarantined_checkpoints
+(
+    sequence_number   BIGINT       PRIMARY KEY,
+    epoch             BIGINT       NOT NULL,
+    checkpoint_digest base58digest NOT NULL,
+    -- failed attempts to index the checkpoint before it was skipped
+    attempts          BIGINT       NOT NULL,
+    error             TEXT         NOT NULL
+);
@@ -0,0 +1 @@
+CREATE INDEX checkpoints_checkpoint_digest ON checkpoints (checkpoint_digest);
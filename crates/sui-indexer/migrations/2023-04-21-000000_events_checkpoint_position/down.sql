@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS events_event_type_position;
+ALTER TABLE events
+    DROP COLUMN IF EXISTS checkpoint_sequence_number,
+    DROP COLUMN IF EXISTS event_index;
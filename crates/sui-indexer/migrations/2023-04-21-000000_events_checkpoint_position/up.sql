@@ -0,0 +1,22 @@
+ALTER TABLE events
+    ADD COLUMN checkpoint_sequence_number BIGINT NOT NULL DEFAULT 0,
+    -- position of the event among all events of its checkpoint
+    ADD COLUMN event_index                BIGINT NOT NULL DEFAULT 0;
+
+-- backfill events indexed earlier, which were inserted in checkpoint order
+UPDATE events e
+SET checkpoint_sequence_number = t.checkpoint_sequence_number
+FROM transactions t
+WHERE t.transaction_digest = e.transaction_digest
+  AND t.checkpoint_sequence_number IS NOT NULL;
+UPDATE events e
+SET event_index = ranked.event_index
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY checkpoint_sequence_number ORDER BY id) - 1 AS event_index
+      FROM events) ranked
+WHERE ranked.id = e.id;
+
+ALTER TABLE events
+    ALTER COLUMN checkpoint_sequence_number DROP DEFAULT,
+    ALTER COLUMN event_index DROP DEFAULT;
+
+CREATE INDEX events_event_type_position ON events (event_type, checkpoint_sequence_number, event_index);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS objects_owner_object_type;
@@ -0,0 +1 @@
+CREATE INDEX objects_owner_object_type ON objects (owner_address, object_type, object_id);
@@ -0,0 +1,16 @@
+-- number of live objects owned by each address, maintained on every object change
+CREATE TABLE owner_object_counts
+(
+    owner_address address PRIMARY KEY,
+    object_count  BIGINT  NOT NULL
+);
+
+CREATE INDEX owner_object_counts_object_count ON owner_object_counts (object_count);
+
+INSERT INTO owner_object_counts (owner_address, object_count)
+SELECT owner_address, COUNT(*)
+FROM objects
+WHERE owner_type = 'address_owner'
+  AND owner_address IS NOT NULL
+  AND object_status NOT IN ('deleted', 'wrapped', 'unwrapped_then_deleted')
+GROUP BY owner_address;
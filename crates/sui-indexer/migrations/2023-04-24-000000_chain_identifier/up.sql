@@ -0,0 +1,6 @@
+-- Digest of the genesis checkpoint of the chain the DB is indexing, a single row
+-- recorded on the first run.
+CREATE TABLE chain_identifier
+(
+    checkpoint_digest base58digest PRIMARY KEY
+);
@@ -0,0 +1,59 @@
+-- objects_history, back to the partitions by checkpoint
+ALTER TABLE objects_history
+    RENAME TO objects_history_by_epoch;
+CREATE TABLE objects_history
+(
+    LIKE objects_history_by_epoch
+) PARTITION BY RANGE (checkpoint);
+CREATE TABLE objects_history_fast_path_partition_0 PARTITION OF objects_history FOR VALUES FROM (-1) TO (0);
+CREATE TABLE objects_history_partition_0_by_checkpoint PARTITION OF objects_history FOR VALUES FROM (0) TO (MAXVALUE);
+INSERT INTO objects_history
+SELECT *
+FROM objects_history_by_epoch;
+DROP TABLE objects_history_by_epoch;
+ALTER TABLE objects_history_partition_0_by_checkpoint
+    RENAME TO objects_history_partition_0;
+
+ALTER TABLE objects_history
+    ADD CONSTRAINT objects_history_pk PRIMARY KEY (object_id, version, checkpoint);
+CREATE INDEX objects_history_checkpoint_index ON objects_history (checkpoint);
+CREATE INDEX objects_history_id_version_index ON objects_history (object_id, version);
+CREATE INDEX objects_history_owner_index ON objects_history (owner_type, owner_address);
+CREATE INDEX objects_history_old_owner_index ON objects_history (old_owner_type, old_owner_address);
+
+-- transactions
+DROP VIEW IF EXISTS network_metrics;
+ALTER TABLE transactions
+    RENAME TO transactions_by_epoch;
+CREATE TABLE transactions
+(
+    LIKE transactions_by_epoch INCLUDING DEFAULTS
+);
+INSERT INTO transactions
+SELECT *
+FROM transactions_by_epoch;
+ALTER SEQUENCE transactions_id_seq OWNED BY NONE;
+DROP TABLE transactions_by_epoch;
+ALTER SEQUENCE transactions_id_seq OWNED BY transactions.id;
+ALTER TABLE transactions
+    DROP COLUMN epoch,
+    ADD PRIMARY KEY (id),
+    ADD UNIQUE (transaction_digest);
+CREATE INDEX transactions_transaction_digest ON transactions (transaction_digest);
+CREATE INDEX transactions_timestamp_ms ON transactions (timestamp_ms);
+CREATE INDEX transactions_sender ON transactions (sender);
+CREATE INDEX transactions_checkpoint_sequence_number ON transactions (checkpoint_sequence_number);
+CREATE INDEX transactions_kind ON transactions (kind, id);
+
+CREATE OR REPLACE VIEW network_metrics AS
+SELECT (SELECT COALESCE(SUM(transaction_count)::float8 / 10, 0)
+        FROM transactions
+        WHERE timestamp_ms >
+              (SELECT timestamp_ms FROM checkpoints ORDER BY sequence_number DESC LIMIT 1) - 10000) AS current_tps,
+       (SELECT COALESCE(tps_30_days, 0) FROM epoch_network_metrics)                                 AS tps_30_days,
+       (SELECT COUNT(1) FROM addresses)                                                             AS total_addresses,
+       -- row estimation
+       (SELECT reltuples AS estimate FROM pg_class WHERE relname = 'objects')::BIGINT               AS total_objects,
+       (SELECT COUNT(1) FROM packages)                                                              AS total_packages,
+       (SELECT MAX(epoch) FROM epochs)                                                              AS current_epoch,
+       (SELECT MAX(sequence_number) FROM checkpoints)                                               AS current_checkpoint;
@@ -0,0 +1,110 @@
+-- Partition transactions and objects_history by epoch, with one partition per epoch named
+-- <table>_partition_<epoch>, created when the first checkpoint of the epoch is committed.
+-- Pruning an epoch then drops its partitions.
+-- The objects table is left unpartitioned, its rows move to a new epoch on every mutation
+-- and are upserted by object id, which is not unique across partitions.
+
+-- transactions
+ALTER TABLE transactions
+    ADD COLUMN epoch BIGINT NOT NULL DEFAULT 0;
+UPDATE transactions t
+SET epoch = c.epoch
+FROM checkpoints c
+WHERE c.sequence_number = t.checkpoint_sequence_number;
+ALTER TABLE transactions
+    ALTER COLUMN epoch DROP DEFAULT;
+
+DROP VIEW IF EXISTS network_metrics;
+ALTER TABLE transactions
+    RENAME TO transactions_unpartitioned;
+CREATE TABLE transactions
+(
+    LIKE transactions_unpartitioned INCLUDING DEFAULTS
+) PARTITION BY RANGE (epoch);
+DO
+$$
+    DECLARE
+        e BIGINT;
+    BEGIN
+        FOR e IN SELECT DISTINCT epoch FROM transactions_unpartitioned
+            LOOP
+                EXECUTE format('CREATE TABLE transactions_partition_%s PARTITION OF transactions FOR VALUES FROM (%s) TO (%s)',
+                               e, e, e + 1);
+            END LOOP;
+    END
+$$;
+INSERT INTO transactions
+SELECT *
+FROM transactions_unpartitioned;
+ALTER SEQUENCE transactions_id_seq OWNED BY NONE;
+DROP TABLE transactions_unpartitioned;
+ALTER SEQUENCE transactions_id_seq OWNED BY transactions.id;
+
+-- unique keys of a partitioned table have to include the partition key
+ALTER TABLE transactions
+    ADD PRIMARY KEY (id, epoch),
+    ADD UNIQUE (transaction_digest, epoch);
+CREATE INDEX transactions_transaction_digest ON transactions (transaction_digest);
+CREATE INDEX transactions_timestamp_ms ON transactions (timestamp_ms);
+CREATE INDEX transactions_sender ON transactions (sender);
+CREATE INDEX transactions_checkpoint_sequence_number ON transactions (checkpoint_sequence_number);
+CREATE INDEX transactions_kind ON transactions (kind, id);
+
+CREATE OR REPLACE VIEW network_metrics AS
+SELECT (SELECT COALESCE(SUM(transaction_count)::float8 / 10, 0)
+        FROM transactions
+        WHERE timestamp_ms >
+              (SELECT timestamp_ms FROM checkpoints ORDER BY sequence_number DESC LIMIT 1) - 10000) AS current_tps,
+       (SELECT COALESCE(tps_30_days, 0) FROM epoch_network_metrics)                                 AS tps_30_days,
+       (SELECT COUNT(1) FROM addresses)                                                             AS total_addresses,
+       -- row estimation
+       (SELECT reltuples AS estimate FROM pg_class WHERE relname = 'objects')::BIGINT               AS total_objects,
+       (SELECT COUNT(1) FROM packages)                                                              AS total_packages,
+       (SELECT MAX(epoch) FROM epochs)                                                              AS current_epoch,
+       (SELECT MAX(sequence_number) FROM checkpoints)                                               AS current_checkpoint;
+
+-- objects_history, the partitions by checkpoint are renamed out of the way first
+DO
+$$
+    DECLARE
+        partition_name TEXT;
+    BEGIN
+        FOR partition_name IN SELECT child.relname
+                              FROM pg_inherits
+                                       JOIN pg_class parent ON pg_inherits.inhparent = parent.oid
+                                       JOIN pg_class child ON pg_inherits.inhrelid = child.oid
+                              WHERE parent.relname = 'objects_history'
+            LOOP
+                EXECUTE format('ALTER TABLE %I RENAME TO %I', partition_name, partition_name || '_by_checkpoint');
+            END LOOP;
+    END
+$$;
+ALTER TABLE objects_history
+    RENAME TO objects_history_unpartitioned;
+CREATE TABLE objects_history
+(
+    LIKE objects_history_unpartitioned
+) PARTITION BY RANGE (epoch);
+DO
+$$
+    DECLARE
+        e BIGINT;
+    BEGIN
+        FOR e IN SELECT DISTINCT epoch FROM objects_history_unpartitioned
+            LOOP
+                EXECUTE format('CREATE TABLE objects_history_partition_%s PARTITION OF objects_history FOR VALUES FROM (%s) TO (%s)',
+                               e, e, e + 1);
+            END LOOP;
+    END
+$$;
+INSERT INTO objects_history
+SELECT *
+FROM objects_history_unpartitioned;
+DROP TABLE objects_history_unpartitioned;
+
+ALTER TABLE objects_history
+    ADD CONSTRAINT objects_history_pk PRIMARY KEY (object_id, version, checkpoint, epoch);
+CREATE INDEX objects_history_checkpoint_index ON objects_history (checkpoint);
+CREATE INDEX objects_history_id_version_index ON objects_history (object_id, version);
+CREATE INDEX objects_history_owner_index ON objects_history (owner_type, owner_address);
+CREATE INDEX objects_history_old_owner_index ON objects_history (old_owner_type, old_owner_address);
@@ -0,0 +1,14 @@
+-- The latest checkpoint committed in full, a single row advanced in the same transaction
+-- as the checkpoint, so that resuming doesn't scan the checkpoints table.
+CREATE TABLE watermark
+(
+    -- always true, so that the table holds at most one row
+    id                         BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    checkpoint_sequence_number BIGINT  NOT NULL
+);
+-- checkpoints committed before the table existed
+INSERT INTO watermark (checkpoint_sequence_number)
+SELECT sequence_number
+FROM checkpoints
+ORDER BY sequence_number DESC
+LIMIT 1;
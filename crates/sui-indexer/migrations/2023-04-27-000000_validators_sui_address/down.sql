@@ -0,0 +1 @@
+DROP INDEX IF EXISTS validators_sui_address;
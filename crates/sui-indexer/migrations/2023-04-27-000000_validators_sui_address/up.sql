@@ -0,0 +1,3 @@
+-- Serves the history of a validator across epochs, which the primary key leading with
+-- the epoch can't.
+CREATE INDEX validators_sui_address ON validators (sui_address, epoch);
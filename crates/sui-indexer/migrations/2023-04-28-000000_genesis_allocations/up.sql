@@ -0,0 +1,10 @@
+-- coins created by the genesis checkpoint, the initial token allocation
+CREATE TABLE genesis_allocations
+(
+    object_id     address PRIMARY KEY,
+    owner_address address NOT NULL,
+    coin_type     VARCHAR NOT NULL,
+    amount        BIGINT  NOT NULL
+);
+
+CREATE INDEX genesis_allocations_owner_address ON genesis_allocations (owner_address);
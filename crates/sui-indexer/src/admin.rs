@@ -0,0 +1,81 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::SocketAddr;
+
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Extension, Router};
+use tracing::{error, info, warn};
+
+use mysten_metrics::spawn_monitored_task;
+
+use crate::handlers::dynamic_config::DynamicConfig;
+use crate::handlers::pause::IndexingPause;
+
+pub const HEALTH_ROUTE: &str = "/health";
+pub const PAUSE_ROUTE: &str = "/pause";
+pub const RESUME_ROUTE: &str = "/resume";
+pub const RELOAD_CONFIG_ROUTE: &str = "/reload-config";
+
+/// Starts the HTTP server of the health check, along with the controls pausing and
+/// resuming indexing and reloading the config file.
+pub fn start_admin_server(addr: SocketAddr, pause: IndexingPause, dynamic_config: DynamicConfig) {
+    let app = Router::new()
+        .route(HEALTH_ROUTE, get(health))
+        .route(PAUSE_ROUTE, post(pause_indexing))
+        .route(RESUME_ROUTE, post(resume_indexing))
+        .route(RELOAD_CONFIG_ROUTE, post(reload_config))
+        .layer(Extension(pause))
+        .layer(Extension(dynamic_config));
+
+    spawn_monitored_task!(async move {
+        let server = match axum::Server::try_bind(&addr) {
+            Ok(server) => server,
+            Err(e) => {
+                error!(%addr, error = ?e, "Failed to bind indexer admin server");
+                return;
+            }
+        };
+        info!(%addr, "Indexer admin server started");
+        if let Err(e) = server.serve(app.into_make_service()).await {
+            error!(error = ?e, "Indexer admin server stopped");
+        }
+    });
+}
+
+async fn health(Extension(pause): Extension<IndexingPause>) -> (StatusCode, &'static str) {
+    let status = if pause.is_paused() {
+        "paused"
+    } else {
+        "indexing"
+    };
+    (StatusCode::OK, status)
+}
+
+async fn pause_indexing(Extension(pause): Extension<IndexingPause>) -> StatusCode {
+    info!("Pausing indexing");
+    pause.pause();
+    StatusCode::OK
+}
+
+async fn resume_indexing(Extension(pause): Extension<IndexingPause>) -> StatusCode {
+    info!("Resuming indexing");
+    pause.resume();
+    StatusCode::OK
+}
+
+async fn reload_config(
+    Extension(dynamic_config): Extension<DynamicConfig>,
+) -> (StatusCode, String) {
+    match dynamic_config.reload() {
+        Ok(config) => {
+            info!(?config, "Reloaded indexer config");
+            (StatusCode::OK, format!("{:?}", config))
+        }
+        Err(e) => {
+            warn!(error = ?e, "Rejected indexer config reload");
+            (StatusCode::BAD_REQUEST, e.to_string())
+        }
+    }
+}
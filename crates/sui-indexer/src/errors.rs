@@ -1,12 +1,16 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::fmt;
+
 use fastcrypto::error::FastCryptoError;
 use jsonrpsee::core::Error as RpcError;
+use jsonrpsee::http_client::transport::Error as HttpTransportError;
 use jsonrpsee::types::error::CallError;
 use thiserror::Error;
 
-use sui_types::base_types::ObjectIDParseError;
+use sui_types::base_types::{ObjectID, ObjectIDParseError};
+use sui_types::digests::TransactionDigest;
 use sui_types::error::{SuiError, SuiObjectResponseError, UserInputError};
 
 #[derive(Debug, Error)]
@@ -20,6 +24,12 @@ pub enum IndexerError {
     #[error("Indexer failed to read fullnode with error: `{0}`")]
     FullNodeReadingError(String),
 
+    #[error("Indexer failed to read fullnode with {kind} error: `{message}`")]
+    FullNodeRpcError {
+        kind: FullNodeReadErrorKind,
+        message: String,
+    },
+
     #[error("Indexer failed to convert structs to diesel Insertable with error: `{0}`")]
     InsertableParsingError(String),
 
@@ -29,6 +39,13 @@ pub enum IndexerError {
     #[error("Indexer failed to find object mutations, which should never happen.")]
     ObjectMutationNotAvailable,
 
+    #[error("Indexer refused to overwrite object `{object_id}` of version {stored_version} with older version {version}")]
+    NonMonotonicObjectVersion {
+        object_id: String,
+        version: i64,
+        stored_version: i64,
+    },
+
     #[error("Indexer failed to build PG connection pool with error: `{0}`")]
     PgConnectionPoolInitError(String),
 
@@ -83,8 +100,102 @@ pub enum IndexerError {
     #[error("`{0}`: `{1}`")]
     ErrorWithContext(String, Box<IndexerError>),
 
+    #[error("Indexer failed to load config with error: `{0}`")]
+    InvalidConfigError(String),
+
+    #[error("Indexer failed to write to file with error: `{0}`")]
+    FileWriteError(String),
+
     #[error("Indexer failed to send item to channel with error: `{0}`")]
     MpscChannelError(String),
+
+    #[error(
+        "Indexer refused to index chain `{fullnode}` of the fullnode into DB of chain `{stored}`"
+    )]
+    ChainIdentifierMismatch { stored: String, fullnode: String },
+
+    #[error("Indexer refused to commit checkpoint {sequence_number} out of order, expected checkpoint {expected}")]
+    OutOfOrderCheckpoint { sequence_number: i64, expected: i64 },
+
+    #[error("Indexer failed to index package `{object_id}` fetched without its BCS")]
+    MissingPackageBcs { object_id: ObjectID },
+
+    #[error("Indexer received an incomplete batch of transactions of checkpoint {sequence_number} from the fullnode, missing `{missing:?}` and unexpected `{unexpected:?}`")]
+    IncompleteTransactionBatch {
+        sequence_number: u64,
+        missing: Vec<TransactionDigest>,
+        unexpected: Vec<TransactionDigest>,
+    },
+
+    #[error("Indexer failed to verify signature of checkpoint {sequence_number} with error: `{message}`")]
+    InvalidCheckpointSignature {
+        sequence_number: u64,
+        message: String,
+    },
+}
+
+impl IndexerError {
+    /// Kind of the fullnode read failure behind this error, None if it is not one.
+    pub fn fullnode_read_error_kind(&self) -> Option<FullNodeReadErrorKind> {
+        match self {
+            IndexerError::FullNodeRpcError { kind, .. } => Some(*kind),
+            IndexerError::FullNodeReadingError(_) => Some(FullNodeReadErrorKind::Other),
+            IndexerError::IncompleteTransactionBatch { .. } => Some(FullNodeReadErrorKind::Other),
+            IndexerError::ErrorWithContext(_, e) => e.fullnode_read_error_kind(),
+            _ => None,
+        }
+    }
+}
+
+/// Coarse classification of fullnode read failures, used as the `kind` label of
+/// `fullnode_read_errors_total` so that rate-limiting can be told apart from outages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullNodeReadErrorKind {
+    Timeout,
+    Connection,
+    RateLimited,
+    NotFound,
+    Deserialization,
+    Other,
+}
+
+impl FullNodeReadErrorKind {
+    pub fn classify(e: &RpcError) -> Self {
+        match e {
+            RpcError::RequestTimeout => Self::Timeout,
+            RpcError::Transport(e) => match e.downcast_ref::<HttpTransportError>() {
+                Some(HttpTransportError::Rejected { status_code: 429 }) => Self::RateLimited,
+                Some(HttpTransportError::Rejected { status_code: 404 }) => Self::NotFound,
+                _ => Self::Connection,
+            },
+            RpcError::RestartNeeded(_) => Self::Connection,
+            RpcError::ParseError(_) | RpcError::InvalidResponse(_) => Self::Deserialization,
+            // NOTE: the fullnode reports missing data as a failed call, not as a HTTP 404.
+            RpcError::Call(CallError::Custom(e))
+                if e.message().to_lowercase().contains("not found") =>
+            {
+                Self::NotFound
+            }
+            _ => Self::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Connection => "connection",
+            Self::RateLimited => "rate_limited",
+            Self::NotFound => "not_found",
+            Self::Deserialization => "deserialization",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for FullNodeReadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 pub trait Context<T> {
@@ -102,3 +213,75 @@ impl From<IndexerError> for RpcError {
         RpcError::Call(CallError::Failed(e.into()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use jsonrpsee::core::Error as RpcError;
+    use jsonrpsee::http_client::transport::Error as HttpTransportError;
+    use jsonrpsee::types::error::{CallError, ErrorObject};
+
+    use crate::errors::{FullNodeReadErrorKind, IndexerError};
+
+    #[test]
+    fn test_classify_fullnode_read_errors() {
+        let rejected =
+            |status_code| RpcError::Transport(HttpTransportError::Rejected { status_code }.into());
+        let call_failed = |message: &str| {
+            RpcError::Call(CallError::Custom(ErrorObject::owned(
+                -32000,
+                message.to_string(),
+                None::<()>,
+            )))
+        };
+        let cases = vec![
+            (RpcError::RequestTimeout, FullNodeReadErrorKind::Timeout),
+            (
+                RpcError::Transport(anyhow::anyhow!("Connection refused")),
+                FullNodeReadErrorKind::Connection,
+            ),
+            (
+                RpcError::RestartNeeded("background task closed".to_string()),
+                FullNodeReadErrorKind::Connection,
+            ),
+            (rejected(429), FullNodeReadErrorKind::RateLimited),
+            (rejected(404), FullNodeReadErrorKind::NotFound),
+            (rejected(503), FullNodeReadErrorKind::Connection),
+            (
+                call_failed("Verified checkpoint not found for sequence number: 5"),
+                FullNodeReadErrorKind::NotFound,
+            ),
+            (
+                RpcError::ParseError(serde_json::from_str::<u64>("{").unwrap_err()),
+                FullNodeReadErrorKind::Deserialization,
+            ),
+            (call_failed("Invalid params"), FullNodeReadErrorKind::Other),
+        ];
+        for (error, kind) in cases {
+            assert_eq!(FullNodeReadErrorKind::classify(&error), kind, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn test_fullnode_read_error_kind_through_context() {
+        let error = IndexerError::ErrorWithContext(
+            "Failed to get checkpoint".to_string(),
+            Box::new(IndexerError::FullNodeRpcError {
+                kind: FullNodeReadErrorKind::RateLimited,
+                message: "429".to_string(),
+            }),
+        );
+        assert_eq!(
+            error.fullnode_read_error_kind(),
+            Some(FullNodeReadErrorKind::RateLimited)
+        );
+        assert_eq!(
+            IndexerError::FullNodeReadingError("missing effects".to_string())
+                .fullnode_read_error_kind(),
+            Some(FullNodeReadErrorKind::Other)
+        );
+        assert_eq!(
+            IndexerError::PostgresReadError("timeout".to_string()).fullnode_read_error_kind(),
+            None
+        );
+    }
+}
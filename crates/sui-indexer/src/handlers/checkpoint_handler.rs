@@ -1,15 +1,13 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 
 use fastcrypto::traits::ToFromBytes;
-use futures::future::join_all;
-use futures::FutureExt;
-use jsonrpsee::http_client::HttpClient;
+use futures::{stream, StreamExt};
 use move_core_types::ident_str;
-use prometheus::Registry;
 use tokio::sync::{
     mpsc::{self, Receiver, Sender},
     Mutex,
@@ -18,107 +16,184 @@ use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 
 use mysten_metrics::spawn_monitored_task;
-use sui_core::event_handler::EventHandler;
-use sui_json_rpc::api::ReadApiClient;
 use sui_json_rpc_types::{
-    OwnedObjectRef, SuiGetPastObjectRequest, SuiObjectData, SuiObjectDataOptions, SuiRawData,
-    SuiTransactionBlockDataAPI, SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI,
+    Checkpoint as RpcCheckpoint, OwnedObjectRef, SuiEvent, SuiGetPastObjectRequest, SuiObjectData,
+    SuiObjectDataOptions, SuiRawData, SuiTransactionBlockDataAPI, SuiTransactionBlockEffects,
+    SuiTransactionBlockEffectsAPI,
 };
-use sui_sdk::error::Error;
-use sui_types::base_types::{ObjectID, SequenceNumber};
-use sui_types::committee::EpochId;
-use sui_types::messages_checkpoint::{CheckpointCommitment, CheckpointSequenceNumber};
+use sui_types::base_types::{ObjectID, ObjectType, SequenceNumber, SuiAddress};
+use sui_types::committee::{Committee, EpochId};
+use sui_types::crypto::AuthorityPublicKeyBytes;
+use sui_types::digests::TransactionDigest;
+use sui_types::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointCommitment, CheckpointSequenceNumber,
+};
+use sui_types::object::Owner;
 use sui_types::sui_system_state::sui_system_state_summary::SuiSystemStateSummary;
 use sui_types::sui_system_state::{get_sui_system_state, SuiSystemStateTrait};
-use sui_types::SUI_SYSTEM_ADDRESS;
+use sui_types::{SUI_SYSTEM_ADDRESS, SUI_SYSTEM_STATE_OBJECT_ID};
 
 use crate::errors::IndexerError;
+use crate::handlers::checkpoint_sink::{CheckpointSink, JsonFileSink};
+use crate::handlers::dynamic_config::DynamicConfig;
+use crate::handlers::event_sink::EventSink;
+use crate::handlers::fullnode_client::{FullNodeClient, RateLimitedFullNodeClient};
+use crate::handlers::pause::IndexingPause;
+use crate::handlers::shutdown::IndexingShutdown;
 use crate::metrics::IndexerCheckpointHandlerMetrics;
+use crate::models::addresses::AddressActivity;
 use crate::models::checkpoints::Checkpoint;
+use crate::models::consensus_commits::ConsensusCommit;
 use crate::models::epoch::{DBEpochInfo, SystemEpochInfoEvent};
+use crate::models::events::Event;
+use crate::models::genesis_allocations::GenesisAllocation;
 use crate::models::objects::{DeletedObject, Object, ObjectStatus};
 use crate::models::packages::Package;
+use crate::models::quarantined_checkpoints::QuarantinedCheckpoint;
+use crate::models::system_state::DBValidatorSummary;
 use crate::models::transactions::Transaction;
 use crate::store::{
     CheckpointData, IndexerStore, TemporaryCheckpointStore, TemporaryEpochStore,
     TransactionObjectChanges,
 };
 use crate::types::{CheckpointTransactionBlockResponse, TemporaryTransactionBlockResponseStore};
-use crate::utils::multi_get_full_transactions;
-use crate::IndexerConfig;
+use crate::{IndexerConfig, MalformedEventPolicy};
 
 const DOWNLOAD_RETRY_INTERVAL_IN_SECS: u64 = 10;
 const DB_COMMIT_RETRY_INTERVAL_IN_MILLIS: u64 = 100;
-const MULTI_GET_CHUNK_SIZE: usize = 500;
+// warn on the first retry of a commit and then every 5 seconds of retrying
+const DB_COMMIT_RETRY_WARN_EVERY: u64 = 50;
+const INDEX_RETRY_INTERVAL_IN_MILLIS: u64 = 100;
+const OBJECT_REFETCH_INTERVAL_IN_MILLIS: u64 = 100;
 const CHECKPOINT_QUEUE_LIMIT: usize = 10;
 const EPOCH_QUEUE_LIMIT: usize = 2;
 
+#[cfg(test)]
+#[path = "unit_tests/checkpoint_handler_tests.rs"]
+mod checkpoint_handler_tests;
+
+/// Cloned into each of the tasks `spawn` starts, so that the state they share, caches
+/// included, is kept behind an `Arc` rather than per clone.
 #[derive(Clone)]
-pub struct CheckpointHandler<S> {
+pub struct CheckpointHandler<S, C> {
     state: S,
-    http_client: HttpClient,
-    event_handler: Arc<EventHandler>,
+    fullnode_client: RateLimitedFullNodeClient<C>,
+    event_handler: Arc<dyn EventSink>,
+    checkpoint_sinks: Vec<Arc<dyn CheckpointSink>>,
     metrics: IndexerCheckpointHandlerMetrics,
     config: IndexerConfig,
+    // settings that can change while indexing, read from here rather than from config
+    dynamic_config: DynamicConfig,
     checkpoint_sender: Arc<Mutex<Sender<TemporaryCheckpointStore>>>,
     checkpoint_receiver: Arc<Mutex<Receiver<TemporaryCheckpointStore>>>,
+    // checkpoint received by the commit task and not persisted yet
+    uncommitted_checkpoint: Arc<Mutex<Option<TemporaryCheckpointStore>>>,
     epoch_sender: Arc<Mutex<Sender<TemporaryEpochStore>>>,
     epoch_receiver: Arc<Mutex<Receiver<TemporaryEpochStore>>>,
+    // committees checkpoint signatures are verified against, by epoch, cached for all clones
+    committees: Arc<Mutex<BTreeMap<EpochId, Committee>>>,
+    pause: IndexingPause,
+    shutdown: IndexingShutdown,
+    // sequence numbers of the latest downloaded and committed checkpoints
+    latest_downloaded_seq: Arc<AtomicI64>,
+    latest_committed_seq: Arc<AtomicI64>,
+    // checkpoint the download task has to go back to, -1 if none
+    download_rewind_seq: Arc<AtomicI64>,
 }
 
-impl<S> CheckpointHandler<S>
+impl<S, C> CheckpointHandler<S, C>
 where
     S: IndexerStore + Clone + Sync + Send + 'static,
+    C: FullNodeClient,
 {
     pub fn new(
         state: S,
-        http_client: HttpClient,
-        event_handler: Arc<EventHandler>,
-        prometheus_registry: &Registry,
+        fullnode_client: C,
+        event_handler: Arc<dyn EventSink>,
+        metrics: IndexerCheckpointHandlerMetrics,
         config: &IndexerConfig,
     ) -> Self {
         let (checkpoint_sender, checkpoint_receiver) = mpsc::channel(CHECKPOINT_QUEUE_LIMIT);
         let (epoch_sender, epoch_receiver) = mpsc::channel(EPOCH_QUEUE_LIMIT);
         Self {
             state,
-            http_client,
+            fullnode_client: RateLimitedFullNodeClient::new(
+                fullnode_client,
+                config.max_fullnode_rps,
+            ),
             event_handler,
-            metrics: IndexerCheckpointHandlerMetrics::new(prometheus_registry),
+            checkpoint_sinks: JsonFileSink::from_config(config)
+                .into_iter()
+                .map(|sink| Arc::new(sink) as Arc<dyn CheckpointSink>)
+                .collect(),
+            metrics,
             config: config.clone(),
+            dynamic_config: DynamicConfig::new(config),
             checkpoint_sender: Arc::new(Mutex::new(checkpoint_sender)),
             checkpoint_receiver: Arc::new(Mutex::new(checkpoint_receiver)),
+            uncommitted_checkpoint: Arc::new(Mutex::new(None)),
             epoch_sender: Arc::new(Mutex::new(epoch_sender)),
             epoch_receiver: Arc::new(Mutex::new(epoch_receiver)),
+            committees: Arc::new(Mutex::new(BTreeMap::new())),
+            pause: IndexingPause::new(),
+            shutdown: IndexingShutdown::new(),
+            latest_downloaded_seq: Arc::new(AtomicI64::new(-1)),
+            latest_committed_seq: Arc::new(AtomicI64::new(-1)),
+            download_rewind_seq: Arc::new(AtomicI64::new(-1)),
         }
     }
 
+    /// Pauses the download and commit tasks whenever `pause` is paused, e.g. by a caller
+    /// holding a clone of it during a DB maintenance window.
+    pub fn with_pause(mut self, pause: IndexingPause) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Stops the tasks gracefully once `shutdown` is shut down, see `IndexingShutdown`.
+    pub fn with_shutdown(mut self, shutdown: IndexingShutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Reads the dynamic settings from `dynamic_config`, so that reloading it changes them
+    /// while indexing. Defaults to the settings of the `IndexerConfig` passed to `new`.
+    pub fn with_dynamic_config(mut self, dynamic_config: DynamicConfig) -> Self {
+        self.dynamic_config = dynamic_config;
+        self
+    }
+
+    /// Sends every committed checkpoint to `sink` as well, after the configured ones.
+    pub fn with_checkpoint_sink(mut self, sink: Arc<dyn CheckpointSink>) -> Self {
+        self.checkpoint_sinks.push(sink);
+        self
+    }
+
+    /// Spawns the download, checkpoint commit and epoch commit tasks. The returned handle
+    /// completes once all of them stopped, after a shutdown.
     pub fn spawn(self) -> JoinHandle<()> {
         info!("Indexer checkpoint handler started...");
         let download_handler = self.clone();
-        spawn_monitored_task!(async move {
-            let mut checkpoint_download_index_res =
-                download_handler.start_download_and_index().await;
-            while let Err(e) = &checkpoint_download_index_res {
-                warn!(
-                    "Indexer checkpoint download & index failed with error: {:?}, retrying after {:?} secs...",
-                    e, DOWNLOAD_RETRY_INTERVAL_IN_SECS
-                );
-                tokio::time::sleep(std::time::Duration::from_secs(
-                    DOWNLOAD_RETRY_INTERVAL_IN_SECS,
-                ))
-                .await;
-                checkpoint_download_index_res = download_handler.start_download_and_index().await;
+        let download = spawn_monitored_task!(async move {
+            // NOTE: the download can stop anywhere, nothing it holds is lost, as the next
+            // start downloads again from the committed watermark.
+            tokio::select! {
+                _ = download_handler.shutdown.wait_for_shutdown() => {
+                    info!("Indexer checkpoint download & index task shut down");
+                }
+                _ = download_handler.download_and_index_with_retries() => {}
             }
         });
 
         let checkpoint_commit_handler = self.clone();
-        spawn_monitored_task!(async move {
+        let checkpoint_commit = spawn_monitored_task!(async move {
             let mut checkpoint_commit_res =
                 checkpoint_commit_handler.start_checkpoint_commit().await;
             while let Err(e) = &checkpoint_commit_res {
                 warn!(
-                    "Indexer checkpoint commit failed with error: {:?}, retrying after {:?} secs...",
-                    e, DOWNLOAD_RETRY_INTERVAL_IN_SECS
+                    error = ?e,
+                    retry_after_secs = DOWNLOAD_RETRY_INTERVAL_IN_SECS,
+                    "Indexer checkpoint commit task failed, retrying"
                 );
                 tokio::time::sleep(std::time::Duration::from_secs(
                     DOWNLOAD_RETRY_INTERVAL_IN_SECS,
@@ -128,12 +203,13 @@ where
             }
         });
 
-        spawn_monitored_task!(async move {
+        let epoch_commit = spawn_monitored_task!(async move {
             let mut epoch_commit_res = self.start_epoch_commit().await;
             while let Err(e) = &epoch_commit_res {
                 warn!(
-                    "Indexer epoch commit failed with error: {:?}, retrying after {:?} secs...",
-                    e, DOWNLOAD_RETRY_INTERVAL_IN_SECS
+                    error = ?e,
+                    retry_after_secs = DOWNLOAD_RETRY_INTERVAL_IN_SECS,
+                    "Indexer epoch commit task failed, retrying"
                 );
                 tokio::time::sleep(std::time::Duration::from_secs(
                     DOWNLOAD_RETRY_INTERVAL_IN_SECS,
@@ -141,37 +217,150 @@ where
                 .await;
                 epoch_commit_res = self.start_epoch_commit().await;
             }
+        });
+
+        spawn_monitored_task!(async move {
+            let (download, checkpoint_commit, epoch_commit) =
+                tokio::join!(download, checkpoint_commit, epoch_commit);
+            for result in [download, checkpoint_commit, epoch_commit] {
+                if let Err(e) = result {
+                    if e.is_panic() {
+                        std::panic::resume_unwind(e.into_panic());
+                    }
+                }
+            }
         })
     }
 
+    async fn download_and_index_with_retries(&self) {
+        let mut checkpoint_download_index_res = self.start_download_and_index().await;
+        while let Err(e) = &checkpoint_download_index_res {
+            if matches!(e, IndexerError::ChainIdentifierMismatch { .. }) {
+                // NOTE: retrying can't help, the fullnode or the DB has to be replaced.
+                error!(
+                    error = ?e,
+                    "Indexer is configured with a fullnode of another chain, stopped downloading checkpoints"
+                );
+                break;
+            }
+            warn!(
+                error = ?e,
+                retry_after_secs = DOWNLOAD_RETRY_INTERVAL_IN_SECS,
+                "Indexer checkpoint download & index failed, retrying"
+            );
+            tokio::time::sleep(std::time::Duration::from_secs(
+                DOWNLOAD_RETRY_INTERVAL_IN_SECS,
+            ))
+            .await;
+            checkpoint_download_index_res = self.start_download_and_index().await;
+        }
+    }
+
     async fn start_download_and_index(&self) -> Result<(), IndexerError> {
         info!("Indexer checkpoint download & index task started...");
         // NOTE: important not to cast i64 to u64 here,
         // because -1 will be returned when checkpoints table is empty.
         let last_seq_from_db = self.state.get_latest_checkpoint_sequence_number().await?;
         if last_seq_from_db > 0 {
-            info!("Resuming from checkpoint {last_seq_from_db}");
+            info!(seq = last_seq_from_db, "Resuming from checkpoint");
         }
+        self.latest_downloaded_seq
+            .fetch_max(last_seq_from_db, Ordering::Relaxed);
+        // NOTE: starting over from the watermark already goes back far enough.
+        self.download_rewind_seq.store(-1, Ordering::Relaxed);
         let mut next_cursor_sequence_number = last_seq_from_db + 1;
+        // failed attempts to index the checkpoint at next_cursor_sequence_number
+        let mut index_attempts = 0;
+        let mut chain_identifier_validated = false;
 
         loop {
+            self.wait_if_paused("checkpoint download & index").await;
+            let rewind_seq = self.download_rewind_seq.swap(-1, Ordering::Relaxed);
+            if rewind_seq >= 0 {
+                info!(
+                    seq = rewind_seq,
+                    from_seq = next_cursor_sequence_number,
+                    "Downloading checkpoints again for the checkpoint commit task"
+                );
+                next_cursor_sequence_number = rewind_seq;
+                index_attempts = 0;
+            }
+            self.fullnode_client
+                .set_max_rps(self.dynamic_config.load().max_fullnode_rps)
+                .await;
             // Download checkpoint data
             self.metrics.total_checkpoint_requested.inc();
-            let checkpoint = self
+            let downloaded = self
                 .download_checkpoint_data(next_cursor_sequence_number as u64)
-                .await.map_err(|e| {
-                    error!(
-                        "Failed to download checkpoint data with checkpoint sequence number {} and error {:?}, retrying...",
-                        next_cursor_sequence_number, e
-                    );
-                    e
-                })?;
+                .await;
+            if self.download_rewind_seq.load(Ordering::Relaxed) >= 0 {
+                // NOTE: the commit task would drop this checkpoint, it waits for an earlier one.
+                continue;
+            }
+            let checkpoint = downloaded.map_err(|e| {
+                error!(
+                    seq = next_cursor_sequence_number,
+                    error = ?e,
+                    "Failed to download checkpoint data, retrying"
+                );
+                e
+            })?;
             self.metrics.total_checkpoint_received.inc();
+            self.latest_downloaded_seq
+                .store(next_cursor_sequence_number, Ordering::Relaxed);
+            self.update_download_commit_gap();
+            if !chain_identifier_validated {
+                self.validate_chain_identifier(&checkpoint).await?;
+                chain_identifier_validated = true;
+            }
 
             // Index checkpoint data
             let index_guard = self.metrics.checkpoint_index_latency.start_timer();
-            let (indexed_checkpoint, indexed_epoch) = self.index_checkpoint(&checkpoint)?;
+            let index_res = self.index_checkpoint(&checkpoint);
             index_guard.stop_and_record();
+            let (indexed_checkpoint, indexed_epoch) = match index_res {
+                Ok(indexed) => {
+                    index_attempts = 0;
+                    indexed
+                }
+                Err(e) => {
+                    // NOTE: indexing is deterministic given the downloaded data, so retrying
+                    // forever would stall the pipeline on a poisoned checkpoint.
+                    index_attempts += 1;
+                    if index_attempts < self.config.max_checkpoint_index_attempts {
+                        warn!(
+                            seq = next_cursor_sequence_number,
+                            epoch = checkpoint.checkpoint.epoch,
+                            attempt = index_attempts,
+                            error = ?e,
+                            "Failed to index checkpoint, retrying"
+                        );
+                        tokio::time::sleep(std::time::Duration::from_millis(
+                            INDEX_RETRY_INTERVAL_IN_MILLIS,
+                        ))
+                        .await;
+                        continue;
+                    }
+                    error!(
+                        seq = next_cursor_sequence_number,
+                        epoch = checkpoint.checkpoint.epoch,
+                        attempts = index_attempts,
+                        error = ?e,
+                        "Failed to index checkpoint repeatedly, quarantining and skipping it"
+                    );
+                    self.state
+                        .persist_quarantined_checkpoint(&QuarantinedCheckpoint::new(
+                            &checkpoint.checkpoint,
+                            index_attempts,
+                            &e,
+                        ))
+                        .await?;
+                    self.metrics.total_checkpoint_quarantined.inc();
+                    index_attempts = 0;
+                    next_cursor_sequence_number += 1;
+                    continue;
+                }
+            };
 
             let checkpoint_sender_guard = self.checkpoint_sender.lock().await;
             // NOTE: when the channel is full, checkpoint_sender_guard will wait until the channel has space.
@@ -179,7 +368,10 @@ where
                 .send(indexed_checkpoint)
                 .await
                 .map_err(|e| {
-                    error!("Failed to send indexed checkpoint to checkpoint commit handler with error: {}", e.to_string());
+                    error!(
+                        error = %e,
+                        "Failed to send indexed checkpoint to checkpoint commit handler"
+                    );
                     IndexerError::MpscChannelError(e.to_string())
                 })?;
             drop(checkpoint_sender_guard);
@@ -188,17 +380,16 @@ where
                 // for the first epoch, we need to store the epoch data first,
                 // otherwise send it to channel to be committed later.
                 if epoch.last_epoch.is_none() {
-                    let epoch_db_guard = self.metrics.epoch_db_commit_latency.start_timer();
-                    self.state.persist_epoch(&epoch).await?;
-                    epoch_db_guard.stop_and_record();
-                    self.metrics.total_epoch_committed.inc();
+                    // NOTE: retried here rather than failing the task, which would restart
+                    // the download from genesis.
+                    self.persist_epoch_with_retries(&epoch).await;
                 } else {
                     let epoch_sender_guard = self.epoch_sender.lock().await;
                     // NOTE: when the channel is full, epoch_sender_guard will wait until the channel has space.
                     epoch_sender_guard.send(epoch).await.map_err(|e| {
                         error!(
-                            "Failed to send indexed epoch to epoch commit handler with error {}",
-                            e.to_string()
+                            error = %e,
+                            "Failed to send indexed epoch to epoch commit handler"
                         );
                         IndexerError::MpscChannelError(e.to_string())
                     })?;
@@ -222,53 +413,147 @@ where
 
     async fn start_checkpoint_commit(&self) -> Result<(), IndexerError> {
         info!("Indexer checkpoint commit task started...");
+        // NOTE: the first checkpoint after a restart is the one after the committed watermark.
+        let mut last_committed_seq = self.state.get_latest_checkpoint_sequence_number().await?;
+        self.latest_committed_seq
+            .store(last_committed_seq, Ordering::Relaxed);
+        self.update_download_commit_gap();
         loop {
-            let mut checkpoint_receiver_guard = self.checkpoint_receiver.lock().await;
-            let indexed_checkpoint = checkpoint_receiver_guard.recv().await;
-            drop(checkpoint_receiver_guard);
+            // NOTE: the checkpoint is only taken out of the slot once persisted, so that a
+            // commit cancelled midway, while retrying included, leaves it to the next one.
+            let mut uncommitted = self.uncommitted_checkpoint.lock().await;
+            if uncommitted.is_none() {
+                let mut checkpoint_receiver_guard = self.checkpoint_receiver.lock().await;
+                // NOTE: on shutdown, only the checkpoint in the slot is committed, the ones
+                // left in the channel are downloaded again on the next start.
+                *uncommitted = tokio::select! {
+                    biased;
+                    _ = self.shutdown.wait_for_shutdown() => {
+                        info!(seq = last_committed_seq, "Indexer checkpoint commit task shut down");
+                        return Ok(());
+                    }
+                    received = checkpoint_receiver_guard.recv() => received,
+                };
+                drop(checkpoint_receiver_guard);
+            }
 
-            if let Some(indexed_checkpoint) = indexed_checkpoint {
+            if let Some(pending) = uncommitted.as_ref() {
+                // NOTE: the received checkpoint is held until resumed, so that none is lost.
+                self.wait_if_paused("checkpoint commit").await;
                 if self.config.skip_db_commit {
                     info!(
-                        "Downloaded and indexed checkpoint {} successfully, skipping DB commit...",
-                        indexed_checkpoint.checkpoint.sequence_number,
+                        seq = pending.checkpoint.sequence_number,
+                        epoch = pending.checkpoint.epoch,
+                        "Downloaded and indexed checkpoint, skipping DB commit"
                     );
+                    uncommitted.take();
                     continue;
                 }
+                match self
+                    .check_commit_order(last_committed_seq, pending.checkpoint.sequence_number)
+                    .await
+                {
+                    Ok(()) => {}
+                    Err(IndexerError::OutOfOrderCheckpoint {
+                        sequence_number,
+                        expected,
+                    }) => {
+                        // NOTE: no retry can commit it, so it is dropped rather than held
+                        // forever, and the download task goes back to the expected one,
+                        // unless the checkpoint was committed already.
+                        if sequence_number > expected {
+                            self.download_rewind_seq.store(expected, Ordering::Relaxed);
+                        }
+                        uncommitted.take();
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
 
                 // Write checkpoint to DB
                 let checkpoint_db_guard = self.metrics.checkpoint_db_commit_latency.start_timer();
                 // NOTE: retrials are necessary here, otherwise indexed_checkpoint can be popped and discarded.
-                let mut checkpoint_commit_res =
-                    self.state.persist_checkpoint(&indexed_checkpoint).await;
+                let mut checkpoint_commit_res = self.state.persist_checkpoint(pending).await;
+                let mut retries = 0;
                 while let Err(e) = checkpoint_commit_res {
-                    warn!(
-                        "Indexer checkpoint commit failed with error: {:?}, retrying after {:?} milli-secs...",
-                        e, DB_COMMIT_RETRY_INTERVAL_IN_MILLIS
-                    );
-                    tokio::time::sleep(std::time::Duration::from_millis(
-                        DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
-                    ))
-                    .await;
-                    checkpoint_commit_res =
-                        self.state.persist_checkpoint(&indexed_checkpoint).await;
+                    retries += 1;
+                    self.metrics.db_commit_retries_total.inc();
+                    if should_warn_commit_retry(retries) {
+                        warn!(
+                            seq = pending.checkpoint.sequence_number,
+                            epoch = pending.checkpoint.epoch,
+                            error = ?e,
+                            retries,
+                            retry_after_millis = DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
+                            "Indexer checkpoint commit failed, retrying"
+                        );
+                    }
+                    tokio::select! {
+                        // NOTE: the checkpoint is left in the slot, the next start downloads
+                        // it again from the committed watermark.
+                        _ = self.shutdown.wait_for_shutdown() => {
+                            checkpoint_db_guard.stop_and_discard();
+                            info!(
+                                seq = pending.checkpoint.sequence_number,
+                                retries,
+                                "Indexer checkpoint commit task shut down, the commit kept failing"
+                            );
+                            return Ok(());
+                        }
+                        _ = tokio::time::sleep(std::time::Duration::from_millis(
+                            DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
+                        )) => {}
+                    }
+                    checkpoint_commit_res = self.state.persist_checkpoint(pending).await;
                 }
                 checkpoint_db_guard.stop_and_record();
+                let indexed_checkpoint = uncommitted
+                    .take()
+                    .expect("the slot holds the checkpoint just persisted");
+                drop(uncommitted);
+                last_committed_seq = indexed_checkpoint.checkpoint.sequence_number;
+                self.latest_committed_seq
+                    .store(last_committed_seq, Ordering::Relaxed);
+                self.update_download_commit_gap();
 
                 self.metrics.total_checkpoint_committed.inc();
+                self.metrics
+                    .last_committed_checkpoint_sequence_number
+                    .set(indexed_checkpoint.checkpoint.sequence_number);
+                self.metrics
+                    .last_committed_checkpoint_timestamp_ms
+                    .set(indexed_checkpoint.checkpoint.timestamp_ms);
                 let tx_count = indexed_checkpoint.transactions.len();
                 self.metrics
                     .total_transaction_committed
                     .inc_by(tx_count as u64);
+                let packages = &indexed_checkpoint.packages;
+                self.metrics
+                    .packages_published_total
+                    .inc_by(packages.len() as u64);
+                // NOTE: a package is published at version 1, its upgrades at the versions after.
+                self.metrics
+                    .packages_upgraded_total
+                    .inc_by(packages.iter().filter(|p| p.version > 1).count() as u64);
                 info!(
-                    "Checkpoint {} committed with {} transactions and {} object changes.",
-                    indexed_checkpoint.checkpoint.sequence_number,
+                    seq = indexed_checkpoint.checkpoint.sequence_number,
+                    epoch = indexed_checkpoint.checkpoint.epoch,
                     tx_count,
-                    indexed_checkpoint.objects_changes.len()
+                    object_changes = indexed_checkpoint.objects_changes.len(),
+                    "Checkpoint committed"
                 );
                 self.metrics
                     .transaction_per_checkpoint
                     .observe(tx_count as f64);
+                for sink in &self.checkpoint_sinks {
+                    if let Err(e) = sink.process_checkpoint(&indexed_checkpoint).await {
+                        warn!(
+                            seq = indexed_checkpoint.checkpoint.sequence_number,
+                            error = ?e,
+                            "Failed to send committed checkpoint to checkpoint sink"
+                        );
+                    }
+                }
             } else {
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             }
@@ -279,29 +564,25 @@ where
         info!("Indexer epoch commit task started...");
         loop {
             let mut epoch_receiver_guard = self.epoch_receiver.lock().await;
-            let indexed_epoch = epoch_receiver_guard.recv().await;
+            // NOTE: the epochs received already are committed before shutting down, the
+            // checkpoints they come with may be committed and not downloaded again.
+            let indexed_epoch = tokio::select! {
+                biased;
+                indexed_epoch = epoch_receiver_guard.recv() => indexed_epoch,
+                _ = self.shutdown.wait_for_shutdown() => {
+                    info!("Indexer epoch commit task shut down");
+                    return Ok(());
+                }
+            };
             drop(epoch_receiver_guard);
 
             // Write epoch to DB if needed
-            if let Some(indexed_epoch) = indexed_epoch {
+            if let Some(mut indexed_epoch) = indexed_epoch {
+                self.wait_if_paused("epoch commit").await;
                 if indexed_epoch.last_epoch.is_some() {
-                    let epoch_db_guard = self.metrics.epoch_db_commit_latency.start_timer();
-                    let mut epoch_commit_res = self.state.persist_epoch(&indexed_epoch).await;
+                    self.fill_from_opened_epoch(&mut indexed_epoch).await;
                     // NOTE: retrials are necessary here, otherwise indexed_epoch can be popped and discarded.
-                    while let Err(e) = epoch_commit_res {
-                        warn!(
-                            "Indexer epoch commit failed with error: {:?}, retrying after {:?} milli-secs...",
-                            e, DB_COMMIT_RETRY_INTERVAL_IN_MILLIS
-                        );
-                        tokio::time::sleep(std::time::Duration::from_millis(
-                            DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
-                        ))
-                        .await;
-                        epoch_commit_res = self.state.persist_epoch(&indexed_epoch).await;
-                    }
-                    epoch_db_guard.stop_and_record();
-                    self.metrics.total_epoch_committed.inc();
-                    info!("Epoch {} committed.", indexed_epoch.new_epoch.epoch);
+                    self.persist_epoch_with_retries(&indexed_epoch).await;
                 }
             } else {
                 // sleep for 1 sec to avoid occupying the mutex, as this happens once per epoch / day
@@ -310,53 +591,155 @@ where
         }
     }
 
+    /// Fills the columns of the closing epoch of `epoch` known only when it opened from its
+    /// row committed then, as neither the closing checkpoint nor the handler, which may have
+    /// restarted since, has them.
+    async fn fill_from_opened_epoch(&self, epoch: &mut TemporaryEpochStore) {
+        let last_epoch = match epoch.last_epoch.as_mut() {
+            Some(last_epoch) => last_epoch,
+            None => return,
+        };
+        match self.state.get_epoch_row(last_epoch.epoch as EpochId).await {
+            Ok(Some(opened)) => {
+                last_epoch.first_checkpoint_id = opened.first_checkpoint_id;
+                last_epoch.epoch_start_timestamp = opened.epoch_start_timestamp;
+            }
+            Ok(None) => warn!(
+                epoch = last_epoch.epoch,
+                "Closing an epoch whose opening was not committed, its first checkpoint is unknown"
+            ),
+            Err(e) => warn!(
+                epoch = last_epoch.epoch,
+                error = ?e,
+                "Failed reading the opened epoch, its first checkpoint is left to the store"
+            ),
+        }
+    }
+
+    /// Persists `epoch`, retrying until it succeeds.
+    async fn persist_epoch_with_retries(&self, epoch: &TemporaryEpochStore) {
+        let epoch_db_guard = self.metrics.epoch_db_commit_latency.start_timer();
+        let mut epoch_commit_res = self.state.persist_epoch(epoch).await;
+        let mut retries = 0;
+        while let Err(e) = epoch_commit_res {
+            retries += 1;
+            self.metrics.db_commit_retries_total.inc();
+            if should_warn_commit_retry(retries) {
+                warn!(
+                    epoch = epoch.new_epoch.epoch,
+                    error = ?e,
+                    retries,
+                    retry_after_millis = DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
+                    "Indexer epoch commit failed, retrying"
+                );
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(
+                DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
+            ))
+            .await;
+            epoch_commit_res = self.state.persist_epoch(epoch).await;
+        }
+        epoch_db_guard.stop_and_record();
+        self.metrics.total_epoch_committed.inc();
+        info!(epoch = epoch.new_epoch.epoch, "Epoch committed");
+    }
+
+    /// Checks that checkpoint `seq` is the next one to commit after `last_committed_seq`,
+    /// so that the watermark never skips a checkpoint. Only quarantined checkpoints may
+    /// be left out in between.
+    async fn check_commit_order(
+        &self,
+        last_committed_seq: i64,
+        seq: i64,
+    ) -> Result<(), IndexerError> {
+        let expected = last_committed_seq + 1;
+        if seq == expected {
+            return Ok(());
+        }
+        if seq > expected {
+            let quarantined = self
+                .state
+                .get_quarantined_checkpoints()
+                .await?
+                .into_iter()
+                .map(|cp| cp.sequence_number)
+                .collect::<BTreeSet<_>>();
+            if (expected..seq).all(|s| quarantined.contains(&s)) {
+                return Ok(());
+            }
+        }
+        warn!(seq, expected, "Indexer received checkpoint out of order");
+        Err(IndexerError::OutOfOrderCheckpoint {
+            sequence_number: seq,
+            expected,
+        })
+    }
+
+    fn update_download_commit_gap(&self) {
+        let gap = self.latest_downloaded_seq.load(Ordering::Relaxed)
+            - self.latest_committed_seq.load(Ordering::Relaxed);
+        self.metrics.download_commit_gap.set(gap.max(0));
+    }
+
+    /// Idles at a loop boundary of `task` while indexing is paused.
+    async fn wait_if_paused(&self, task: &str) {
+        if self.pause.is_paused() {
+            info!(task, "Indexer task paused");
+            self.pause.wait_until_resumed().await;
+            info!(task, "Indexer task resumed");
+        }
+    }
+
     /// Download all the data we need for one checkpoint.
     async fn download_checkpoint_data(
         &self,
         seq: CheckpointSequenceNumber,
     ) -> Result<CheckpointData, IndexerError> {
-        let mut checkpoint = self
-            .http_client
-            .get_checkpoint(seq.into())
-            .await
-            .map_err(|e| {
-                IndexerError::FullNodeReadingError(format!(
-                    "Failed to get checkpoint with sequence number {} and error {:?}",
-                    seq, e
-                ))
-            });
+        let mut checkpoint = self.fullnode_client.get_checkpoint(seq).await;
         let fn_checkpoint_guard = self
             .metrics
             .fullnode_checkpoint_wait_and_download_latency
             .start_timer();
-        while checkpoint.is_err() {
+        while let Err(e) = &checkpoint {
+            self.record_fullnode_read_error(e);
+            if self.download_rewind_seq.load(Ordering::Relaxed) >= 0 {
+                // NOTE: no need to wait for this checkpoint, an earlier one is downloaded next.
+                break;
+            }
             // sleep for 0.1 second and retry if latest checkpoint is not available yet
             tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             // TODO(gegaowp): figure how to only measure successful checkpoint download time
-            checkpoint = self
-                .http_client
-                .get_checkpoint(seq.into())
-                .await
-                .map_err(|e| {
-                    IndexerError::FullNodeReadingError(format!(
-                        "Failed to get checkpoint with sequence number {} and error {:?}",
-                        seq, e
-                    ))
-                })
+            checkpoint = self.fullnode_client.get_checkpoint(seq).await;
         }
         fn_checkpoint_guard.stop_and_record();
-        // unwrap here is safe because we checked for error above
-        let checkpoint = checkpoint.unwrap();
+        let checkpoint = checkpoint?;
+        if self.config.verify_checkpoint_signatures {
+            // NOTE: the error makes the download task start over, which refetches the checkpoint.
+            self.verify_checkpoint(&checkpoint).await?;
+        }
 
         let fn_transaction_guard = self
             .metrics
             .fullnode_transaction_download_latency
             .start_timer();
-        let transactions = join_all(checkpoint.transactions.chunks(MULTI_GET_CHUNK_SIZE).map(
-            |digests| multi_get_full_transactions(self.http_client.clone(), digests.to_vec()),
-        ))
+        let dynamic_config = self.dynamic_config.load();
+        let chunk_size = dynamic_config.multi_get_chunk_size.max(1);
+        let transactions = stream::iter(
+            checkpoint
+                .transactions
+                .chunks(chunk_size)
+                .map(|digests| self.download_transaction_batch(seq, digests)),
+        )
+        // NOTE: buffered instead of buffer_unordered to keep transactions in checkpoint order
+        .buffered(dynamic_config.tx_download_concurrency.max(1))
+        .collect::<Vec<_>>()
         .await
         .into_iter()
+        .inspect(|chunk| {
+            if let Err(e) = chunk {
+                self.record_fullnode_read_error(e);
+            }
+        })
         .try_fold(vec![], |mut acc, chunk| {
             acc.extend(chunk?);
             Ok::<_, IndexerError>(acc)
@@ -368,9 +751,28 @@ where
             .iter()
             .flat_map(|tx| get_object_changes(&tx.effects))
             .collect::<Vec<_>>();
-        let changed_objects =
-            fetch_changed_objects(self.http_client.clone(), object_changes).await?;
+        self.metrics
+            .object_fetch_batch_size
+            .observe(object_changes.len() as f64);
+        let object_options = ChangedObjectOptions::default();
+        let changed_objects = fetch_changed_objects(
+            &self.fullnode_client,
+            object_changes,
+            chunk_size,
+            dynamic_config.object_download_concurrency,
+            |owner, status| object_options.for_change(owner, status),
+            dynamic_config.max_object_refetches,
+            &self.metrics,
+        )
+        .await
+        .map_err(|e| {
+            self.record_fullnode_read_error(&e);
+            e
+        })?;
         fn_object_guard.stop_and_record();
+        self.metrics
+            .total_objects_fetched
+            .inc_by(changed_objects.len() as u64);
 
         Ok(CheckpointData {
             checkpoint,
@@ -379,263 +781,846 @@ where
         })
     }
 
-    fn index_checkpoint(
-        &self,
-        data: &CheckpointData,
-    ) -> Result<(TemporaryCheckpointStore, Option<TemporaryEpochStore>), IndexerError> {
-        let CheckpointData {
-            checkpoint,
-            transactions,
-            changed_objects,
-        } = data;
+    /// Verifies the signature of `checkpoint` against the committee of its epoch.
+    /// The genesis checkpoint is not verified, it is pinned by the chain identifier instead.
+    async fn verify_checkpoint(&self, checkpoint: &RpcCheckpoint) -> Result<(), IndexerError> {
+        if checkpoint.sequence_number == 0 {
+            return Ok(());
+        }
+        let committee = self
+            .epoch_committee(checkpoint.epoch)
+            .await?
+            .ok_or_else(|| IndexerError::InvalidCheckpointSignature {
+                sequence_number: checkpoint.sequence_number,
+                message: format!("Committee of epoch {} is unknown", checkpoint.epoch),
+            })?;
+        let certified = self
+            .fullnode_client
+            .get_certified_checkpoint_summary(checkpoint.sequence_number)
+            .await
+            .map_err(|e| {
+                self.record_fullnode_read_error(&e);
+                e
+            })?;
+        verify_checkpoint_signature(checkpoint, &certified, &committee)?;
 
-        // Index transaction
-        let temp_tx_store_iter = transactions
-            .iter()
-            .map(|tx| TemporaryTransactionBlockResponseStore::from(tx.clone()));
-        let db_transactions: Vec<Transaction> = temp_tx_store_iter
-            .map(|tx| tx.try_into())
-            .collect::<Result<Vec<Transaction>, _>>()?;
+        // The committee of the next epoch is signed by the current one.
+        if let Some(end_of_epoch_data) = &checkpoint.end_of_epoch_data {
+            if !end_of_epoch_data.next_epoch_committee.is_empty() {
+                let next_epoch = checkpoint.epoch + 1;
+                let voting_rights = end_of_epoch_data
+                    .next_epoch_committee
+                    .iter()
+                    .cloned()
+                    .collect();
+                self.committees
+                    .lock()
+                    .await
+                    .insert(next_epoch, Committee::new(next_epoch, voting_rights));
+            }
+        }
+        Ok(())
+    }
 
-        // Index events
-        let events = transactions
-            .iter()
-            .flat_map(|tx| tx.events.data.iter().map(move |event| event.clone().into()))
-            .collect::<Vec<_>>();
+    /// Committee of `epoch`, from the end of the previous epoch if the handler has seen it,
+    /// otherwise from the committed validators of the epoch.
+    async fn epoch_committee(&self, epoch: EpochId) -> Result<Option<Committee>, IndexerError> {
+        let mut committees = self.committees.lock().await;
+        if let Some(committee) = committees.get(&epoch) {
+            return Ok(Some(committee.clone()));
+        }
+        let validators = self.state.get_validators(epoch).await?;
+        let committee = committee_of_validators(epoch, &validators)?;
+        if let Some(committee) = &committee {
+            committees.insert(epoch, committee.clone());
+        }
+        Ok(committee)
+    }
+
+    /// Compares the genesis checkpoint digest of the fullnode with the one recorded in the
+    /// store, so that checkpoints of another chain are never committed to the DB.
+    /// The first run records the digest.
+    async fn validate_chain_identifier(
+        &self,
+        checkpoint: &CheckpointData,
+    ) -> Result<(), IndexerError> {
+        let genesis_digest = if checkpoint.checkpoint.sequence_number == 0 {
+            checkpoint.checkpoint.digest
+        } else {
+            self.fullnode_client
+                .get_checkpoint(0)
+                .await
+                .map_err(|e| {
+                    self.record_fullnode_read_error(&e);
+                    e
+                })?
+                .digest
+        };
+        let fullnode = genesis_digest.base58_encode();
+        match self.state.get_chain_identifier().await? {
+            Some(stored) if stored != fullnode => {
+                Err(IndexerError::ChainIdentifierMismatch { stored, fullnode })
+            }
+            Some(_) => Ok(()),
+            None => {
+                info!(chain_identifier = %fullnode, "Recording chain identifier");
+                self.state.persist_chain_identifier(&fullnode).await
+            }
+        }
+    }
 
-        // Index objects
-        let tx_objects = changed_objects
+    /// Downloads the transactions of `digests`, in their order. Matches the returned
+    /// transactions by digest, since a loaded fullnode may leave some out of its response.
+    async fn download_transaction_batch(
+        &self,
+        seq: CheckpointSequenceNumber,
+        digests: &[TransactionDigest],
+    ) -> Result<Vec<CheckpointTransactionBlockResponse>, IndexerError> {
+        let mut returned = self
+            .fullnode_client
+            .multi_get_full_transactions(digests.to_vec())
+            .await?
+            .into_iter()
+            .map(|tx| (tx.digest, tx))
+            .collect::<BTreeMap<_, _>>();
+        let mut missing = vec![];
+        let transactions = digests
             .iter()
-            // Unwrap safe here as we requested previous tx data in the request.
-            .fold(BTreeMap::<_, Vec<_>>::new(), |mut acc, (status, o)| {
-                if let Some(digest) = &o.previous_transaction {
-                    acc.entry(*digest).or_default().push((status, o));
+            .filter_map(|digest| {
+                let tx = returned.remove(digest);
+                if tx.is_none() {
+                    missing.push(*digest);
                 }
-                acc
+                tx
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() || !returned.is_empty() {
+            return Err(IndexerError::IncompleteTransactionBatch {
+                sequence_number: seq,
+                missing,
+                unexpected: returned.into_keys().collect(),
             });
+        }
+        Ok(transactions)
+    }
 
-        let objects_changes = transactions
-            .iter()
-            .map(|tx| {
-                let changed_db_objects = tx_objects
-                    .get(&tx.digest)
-                    .unwrap_or(&vec![])
-                    .iter()
-                    .map(|(status, o)| {
-                        Object::from(
-                            checkpoint.epoch,
-                            Some(checkpoint.sequence_number),
-                            status,
-                            o,
-                        )
-                    })
-                    .collect::<Vec<_>>();
-                let deleted_objects = get_deleted_db_objects(
-                    &tx.effects,
-                    checkpoint.epoch,
-                    Some(checkpoint.sequence_number),
-                );
+    fn record_fullnode_read_error(&self, e: &IndexerError) {
+        if let Some(kind) = e.fullnode_read_error_kind() {
+            self.metrics
+                .fullnode_read_errors_total
+                .with_label_values(&[kind.as_str()])
+                .inc();
+        }
+    }
 
-                TransactionObjectChanges {
-                    changed_objects: changed_db_objects,
-                    deleted_objects,
+    fn index_checkpoint(
+        &self,
+        data: &CheckpointData,
+    ) -> Result<(TemporaryCheckpointStore, Option<TemporaryEpochStore>), IndexerError> {
+        let mut indexed = index_checkpoint_data_with(
+            data,
+            self.config.tolerate_nonstandard_genesis,
+            |event, err| {
+                self.metrics.event_deserialize_errors_total.inc();
+                error!(
+                    seq = data.checkpoint.sequence_number,
+                    epoch = data.checkpoint.epoch,
+                    event_type = %event.type_,
+                    tx_digest = %event.id.tx_digest,
+                    error = ?err,
+                    "Failed to deserialize event"
+                );
+                match self.config.malformed_event_policy {
+                    MalformedEventPolicy::Skip => Ok(()),
+                    MalformedEventPolicy::Quarantine => Err(malformed_event_error(event, err)),
                 }
-            })
-            .collect();
+            },
+        )?;
+        if let Some(epoch) = &indexed.1 {
+            if !is_committee_transition_continuous(epoch) {
+                // NOTE: the epoch is indexed regardless, the mismatch means either corrupted
+                // data from the fullnode or a fork, which needs an operator to look into.
+                self.metrics.epoch_committee_mismatches_total.inc();
+                warn!(
+                    seq = data.checkpoint.sequence_number,
+                    epoch = epoch.new_epoch.epoch,
+                    "Next epoch committee of the closed epoch differs from the active validators of the new epoch"
+                );
+            }
+        }
+        if !self.config.tracked_packages.is_empty() {
+            project_onto_packages(&mut indexed.0, &self.config.tracked_packages);
+        }
+        if let Some(addresses) = &self.config.indexed_addresses {
+            project_onto_addresses(&mut indexed.0, addresses);
+        }
+        // NOTE: skipped objects are still in the checkpoint data, which packages and the
+        // system state are indexed from. Deletions are kept, their type is not known.
+        if !self.config.object_type_allowlist.is_empty()
+            || !self.config.object_type_denylist.is_empty()
+        {
+            for changes in &mut indexed.0.objects_changes {
+                changes
+                    .changed_objects
+                    .retain(|o| self.config.indexes_object_type(&o.object_type));
+            }
+        }
+        if self.config.compress_object_bcs {
+            for object in indexed
+                .0
+                .objects_changes
+                .iter_mut()
+                .flat_map(|changes| changes.changed_objects.iter_mut())
+            {
+                object.compress_bcs()?;
+            }
+        }
+        self.record_deleted_objects(&indexed.0);
+        Ok(indexed)
+    }
 
-        // Index packages
-        let packages = Self::index_packages(transactions, changed_objects)?;
+    /// Observes the deletions and wraps of `indexed` left to commit, zero included so that
+    /// the histograms count every checkpoint.
+    fn record_deleted_objects(&self, indexed: &TemporaryCheckpointStore) {
+        let count = |status: ObjectStatus| {
+            indexed
+                .objects_changes
+                .iter()
+                .flat_map(|changes| changes.deleted_objects.iter())
+                .filter(|o| o.object_status == status)
+                .count() as f64
+        };
+        for status in [ObjectStatus::Deleted, ObjectStatus::UnwrappedThenDeleted] {
+            self.metrics
+                .deleted_objects_per_checkpoint
+                .with_label_values(&[status.as_str()])
+                .observe(count(status));
+        }
+        self.metrics
+            .wrapped_objects_per_checkpoint
+            .observe(count(ObjectStatus::Wrapped));
+    }
+}
 
-        // Store input objects, move calls and recipients separately for transaction query indexing.
-        let input_objects = transactions
-            .iter()
-            .map(|tx| tx.get_input_objects(checkpoint.epoch))
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>();
-        let move_calls = transactions
-            .iter()
-            .flat_map(|tx| tx.get_move_calls(checkpoint.epoch, checkpoint.sequence_number))
-            .collect();
-        let recipients = transactions
-            .iter()
-            .flat_map(|tx| tx.get_recipients(checkpoint.epoch, checkpoint.sequence_number))
-            .collect();
+/// Indexes the data of one checkpoint the same way the checkpoint handler does, into the
+/// rows to persist for the checkpoint and, if it starts or ends an epoch, for the epoch.
+/// This lets other tools reuse the indexing logic without running a `CheckpointHandler`.
+/// Unlike the handler, a malformed epoch event always fails indexing, and so does a
+/// genesis without a readable system state.
+///
+/// ```
+/// use sui_indexer::index_checkpoint_data;
+/// use sui_indexer::store::CheckpointData;
+/// use sui_indexer::test_utils::TestCheckpointDataBuilder;
+///
+/// let mut builder = TestCheckpointDataBuilder::new();
+/// let genesis = builder.genesis_checkpoint();
+/// let data = CheckpointData::new(
+///     genesis.checkpoint().clone(),
+///     genesis.transactions().to_vec(),
+///     genesis.changed_objects().to_vec(),
+/// );
+///
+/// let (checkpoint, epoch) = index_checkpoint_data(&data).unwrap();
+/// assert_eq!(checkpoint.checkpoint.sequence_number, 0);
+/// assert_eq!(checkpoint.transactions.len(), data.transactions().len());
+/// // The genesis checkpoint starts the very first epoch.
+/// assert_eq!(epoch.unwrap().new_epoch.epoch, 0);
+/// ```
+pub fn index_checkpoint_data(
+    data: &CheckpointData,
+) -> Result<(TemporaryCheckpointStore, Option<TemporaryEpochStore>), IndexerError> {
+    index_checkpoint_data_with(data, false, |event, err| {
+        Err(malformed_event_error(event, err))
+    })
+}
 
-        // Index addresses
-        let addresses = transactions
-            .iter()
-            .flat_map(|tx| tx.get_addresses(checkpoint.epoch, checkpoint.sequence_number))
-            .collect();
+/// Indexes a checkpoint, calling `on_malformed_event` for the epoch event if it fails to
+/// deserialize. The event is skipped when it returns `Ok`. With `tolerate_nonstandard_genesis`,
+/// epoch 0 is indexed without system state and validators if the genesis lacks them.
+fn index_checkpoint_data_with(
+    data: &CheckpointData,
+    tolerate_nonstandard_genesis: bool,
+    on_malformed_event: impl Fn(&SuiEvent, &bcs::Error) -> Result<(), IndexerError>,
+) -> Result<(TemporaryCheckpointStore, Option<TemporaryEpochStore>), IndexerError> {
+    let CheckpointData {
+        checkpoint,
+        transactions,
+        changed_objects,
+    } = data;
 
-        // Index epoch
-        let epoch_index = if checkpoint.epoch == 0 && checkpoint.sequence_number == 0 {
-            // very first epoch
-            let system_state = get_sui_system_state(data)?;
-            let system_state: SuiSystemStateSummary = system_state.into_sui_system_state_summary();
-            let validators = system_state
-                .active_validators
-                .iter()
-                .map(|v| (system_state.epoch, v.clone()).into())
-                .collect();
+    // Index transaction
+    let db_transactions = index_transactions(transactions)?;
+
+    // Index events, numbered in the order they appear in the checkpoint
+    let (events, epoch_event) = index_events(
+        transactions.iter().flat_map(|tx| tx.events.data.iter()),
+        checkpoint.sequence_number,
+    );
+
+    // Index packages, first so that a package missing its BCS fails indexing gracefully
+    let packages = index_packages(transactions, changed_objects)?;
+
+    // Index objects
+    let objects_changes = index_objects(checkpoint, transactions, changed_objects);
+
+    // Store input objects, move calls and recipients separately for transaction query indexing.
+    let input_objects = transactions
+        .iter()
+        .map(|tx| tx.get_input_objects(checkpoint.epoch))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let move_calls = transactions
+        .iter()
+        .flat_map(|tx| tx.get_move_calls(checkpoint.epoch, checkpoint.sequence_number))
+        .collect();
+    let recipients = transactions
+        .iter()
+        .flat_map(|tx| tx.get_recipients(checkpoint.epoch, checkpoint.sequence_number))
+        .collect();
+
+    let created_packages = packages
+        .iter()
+        .map(|package| package.package_id.clone())
+        .collect::<BTreeSet<_>>();
+    let ptb_commands = transactions
+        .iter()
+        .flat_map(|tx| {
+            tx.get_ptb_commands(
+                checkpoint.epoch,
+                checkpoint.sequence_number,
+                &created_packages,
+            )
+        })
+        .collect();
+    let dependencies = transactions
+        .iter()
+        .flat_map(|tx| tx.get_dependencies(checkpoint.sequence_number))
+        .collect();
+
+    // Index the consensus commit the checkpoint was built from
+    let consensus_commit = ConsensusCommit::from_transactions(
+        checkpoint.sequence_number,
+        checkpoint.timestamp_ms,
+        transactions,
+    );
 
-            Some(TemporaryEpochStore {
+    // Index addresses
+    let addresses = transactions
+        .iter()
+        .flat_map(|tx| tx.get_addresses(checkpoint.epoch, checkpoint.sequence_number))
+        .collect::<Vec<_>>();
+    let address_activities = AddressActivity::from_checkpoint(
+        &addresses,
+        checkpoint.sequence_number,
+        checkpoint.timestamp_ms,
+    );
+
+    // Index epoch
+    let epoch_index = if checkpoint.epoch == 0 && checkpoint.sequence_number == 0 {
+        // very first epoch
+        let genesis_allocations = genesis_allocations(data)?;
+        match genesis_system_state(data, tolerate_nonstandard_genesis)? {
+            Some(system_state) => {
+                let validators = system_state
+                    .active_validators
+                    .iter()
+                    .map(|v| (system_state.epoch, v.clone()).into())
+                    .collect();
+
+                Some(TemporaryEpochStore {
+                    last_epoch: None,
+                    new_epoch: DBEpochInfo {
+                        epoch: 0,
+                        first_checkpoint_id: 0,
+                        epoch_start_timestamp: system_state.epoch_start_timestamp_ms as i64,
+                        ..Default::default()
+                    },
+                    system_state: Some(system_state.into()),
+                    validators,
+                    genesis_allocations,
+                })
+            }
+            None => Some(TemporaryEpochStore {
                 last_epoch: None,
                 new_epoch: DBEpochInfo {
                     epoch: 0,
                     first_checkpoint_id: 0,
-                    epoch_start_timestamp: system_state.epoch_start_timestamp_ms as i64,
+                    epoch_start_timestamp: checkpoint.timestamp_ms as i64,
                     ..Default::default()
                 },
-                system_state: system_state.into(),
-                validators,
+                system_state: None,
+                validators: vec![],
+                genesis_allocations,
+            }),
+        }
+    } else if let Some(end_of_epoch_data) = &checkpoint.end_of_epoch_data {
+        // Find system state object
+        let system_state = get_sui_system_state(data)?;
+        let system_state: SuiSystemStateSummary = system_state.into_sui_system_state_summary();
+
+        let event = match epoch_event {
+            Some(e) => match bcs::from_bytes::<SystemEpochInfoEvent>(&e.bcs) {
+                Ok(event) => Some(event),
+                Err(err) => {
+                    on_malformed_event(e, &err)?;
+                    None
+                }
+            },
+            None => {
+                warn!(
+                    seq = checkpoint.sequence_number,
+                    epoch = checkpoint.epoch,
+                    "SystemEpochInfoEvent not found in end of epoch checkpoint, deriving what it can from the system state"
+                );
+                None
+            }
+        };
+
+        let validators = system_state
+            .active_validators
+            .iter()
+            .map(|v| (system_state.epoch, v.clone()).into())
+            .collect();
+
+        let epoch_commitments = end_of_epoch_data
+            .epoch_commitments
+            .iter()
+            .map(|c| match c {
+                CheckpointCommitment::ECMHLiveObjectSetDigest(d) => Some(d.digest.digest.to_vec()),
             })
-        } else if let Some(end_of_epoch_data) = &checkpoint.end_of_epoch_data {
-            // Find system state object
-            let system_state = get_sui_system_state(data)?;
-            let system_state: SuiSystemStateSummary = system_state.into_sui_system_state_summary();
-
-            let epoch_event = transactions.iter().find_map(|tx| {
-                tx.events.data.iter().find(|ev| {
-                    ev.type_.address == SUI_SYSTEM_ADDRESS
-                        && ev.type_.module.as_ident_str() == ident_str!("sui_system_state_inner")
-                        && ev.type_.name.as_ident_str() == ident_str!("SystemEpochInfoEvent")
-                })
-            });
+            .collect();
 
-            let event = epoch_event
-                .map(|e| bcs::from_bytes::<SystemEpochInfoEvent>(&e.bcs))
-                .transpose()?;
+        let (next_epoch_committee, next_epoch_committee_stake) =
+            end_of_epoch_data.next_epoch_committee.iter().fold(
+                (vec![], vec![]),
+                |(mut names, mut stakes), (name, stake)| {
+                    names.push(Some(name.as_bytes().to_vec()));
+                    stakes.push(Some(*stake as i64));
+                    (names, stakes)
+                },
+            );
 
-            let validators = system_state
-                .active_validators
-                .iter()
-                .map(|v| (system_state.epoch, v.clone()).into())
-                .collect();
+        let event = event.as_ref();
+        // NOTE: the event is emitted once the system state advanced to the new epoch, the
+        // summary holds the same figures for these fields, the others are only in the event.
+        let protocol_version =
+            event.map_or(system_state.protocol_version as i64, |e| e.protocol_version);
+        let reference_gas_price = event.map_or(system_state.reference_gas_price as i64, |e| {
+            e.reference_gas_price
+        });
+        let total_stake = event.map_or(system_state.total_stake as i64, |e| e.total_stake);
+        let storage_fund_balance = event.map_or(
+            (system_state.storage_fund_total_object_storage_rebates
+                + system_state.storage_fund_non_refundable_balance) as i64,
+            |e| e.storage_fund_balance,
+        );
+
+        Some(TemporaryEpochStore {
+            last_epoch: Some(DBEpochInfo {
+                epoch: system_state.epoch as i64 - 1,
+                first_checkpoint_id: 0,
+                last_checkpoint_id: Some(checkpoint.sequence_number as i64),
+                epoch_start_timestamp: 0,
+                epoch_end_timestamp: Some(checkpoint.timestamp_ms as i64),
+                epoch_total_transactions: 0,
+                next_epoch_version: Some(
+                    end_of_epoch_data.next_epoch_protocol_version.as_u64() as i64
+                ),
+                next_epoch_committee,
+                next_epoch_committee_stake,
+                stake_subsidy_amount: event.map(|e| e.stake_subsidy_amount),
+                reference_gas_price: Some(reference_gas_price),
+                storage_fund_balance: Some(storage_fund_balance),
+                total_gas_fees: event.map(|e| e.total_gas_fees),
+                total_stake_rewards_distributed: event.map(|e| e.total_stake_rewards_distributed),
+                total_stake: Some(total_stake),
+                storage_fund_reinvestment: event.map(|e| e.storage_fund_reinvestment),
+                storage_charge: event.map(|e| e.storage_charge),
+                protocol_version: Some(protocol_version),
+                storage_rebate: event.map(|e| e.storage_rebate),
+                leftover_storage_fund_inflow: event.map(|e| e.leftover_storage_fund_inflow),
+                epoch_commitments,
+            }),
+            new_epoch: DBEpochInfo {
+                epoch: system_state.epoch as i64,
+                first_checkpoint_id: checkpoint.sequence_number as i64 + 1,
+                epoch_start_timestamp: system_state.epoch_start_timestamp_ms as i64,
+                ..Default::default()
+            },
+            system_state: Some(system_state.into()),
+            validators,
+            genesis_allocations: vec![],
+        })
+    } else {
+        None
+    };
+
+    let total_transactions = db_transactions.iter().map(|t| t.transaction_count).sum();
+
+    Ok((
+        TemporaryCheckpointStore {
+            checkpoint: Checkpoint::from(checkpoint, total_transactions)?,
+            transactions: db_transactions,
+            events,
+            objects_changes,
+            addresses,
+            address_activities,
+            packages,
+            input_objects,
+            move_calls,
+            recipients,
+            ptb_commands,
+            dependencies,
+            consensus_commit,
+        },
+        epoch_index,
+    ))
+}
+
+/// Checks that `certified` is the summary of `checkpoint`, signed by a quorum of `committee`.
+/// NOTE: the transactions of the checkpoint are not covered, as the RPC checkpoint lacks
+/// the effects digests to recompute the contents digest from.
+pub fn verify_checkpoint_signature(
+    checkpoint: &RpcCheckpoint,
+    certified: &CertifiedCheckpointSummary,
+    committee: &Committee,
+) -> Result<(), IndexerError> {
+    let invalid = |message: String| IndexerError::InvalidCheckpointSignature {
+        sequence_number: checkpoint.sequence_number,
+        message,
+    };
+    if *certified.digest() != checkpoint.digest {
+        return Err(invalid(format!(
+            "Certified summary has digest {} instead of {}",
+            certified.digest(),
+            checkpoint.digest
+        )));
+    }
+    let summary = certified.data();
+    let matches_summary = summary.epoch == checkpoint.epoch
+        && summary.sequence_number == checkpoint.sequence_number
+        && summary.network_total_transactions == checkpoint.network_total_transactions
+        && summary.previous_digest == checkpoint.previous_digest
+        && summary.epoch_rolling_gas_cost_summary == checkpoint.epoch_rolling_gas_cost_summary
+        && summary.timestamp_ms == checkpoint.timestamp_ms
+        && summary.end_of_epoch_data == checkpoint.end_of_epoch_data;
+    if !matches_summary {
+        return Err(invalid(
+            "Checkpoint does not match its certified summary".to_string(),
+        ));
+    }
+    certified
+        .verify_signature(committee)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// Indexes the events of a checkpoint in order, picking the `SystemEpochInfoEvent` out of
+/// them on the way, so that end of epoch checkpoints don't go over their events again.
+fn index_events<'a>(
+    events: impl Iterator<Item = &'a SuiEvent>,
+    checkpoint_sequence_number: CheckpointSequenceNumber,
+) -> (Vec<Event>, Option<&'a SuiEvent>) {
+    let mut epoch_event = None;
+    let events = events
+        .enumerate()
+        .map(|(i, event)| {
+            if epoch_event.is_none() && is_system_epoch_info_event(event) {
+                epoch_event = Some(event);
+            }
+            Event::new(event.clone(), checkpoint_sequence_number as i64, i as i64)
+        })
+        .collect();
+    (events, epoch_event)
+}
+
+fn is_system_epoch_info_event(event: &SuiEvent) -> bool {
+    event.type_.address == SUI_SYSTEM_ADDRESS
+        && event.type_.module.as_ident_str() == ident_str!("sui_system_state_inner")
+        && event.type_.name.as_ident_str() == ident_str!("SystemEpochInfoEvent")
+}
+
+/// Reads the system state a standard genesis creates, None if it can't be read and a
+/// non-standard genesis is tolerated.
+fn genesis_system_state(
+    data: &CheckpointData,
+    tolerate_nonstandard_genesis: bool,
+) -> Result<Option<SuiSystemStateSummary>, IndexerError> {
+    match get_sui_system_state(data) {
+        Ok(system_state) => Ok(Some(system_state.into_sui_system_state_summary())),
+        Err(e) => {
+            let expected = format!(
+                "expected the genesis checkpoint to create the SuiSystemStateWrapper object {} with an inner system state of a known version",
+                SUI_SYSTEM_STATE_OBJECT_ID
+            );
+            error!(
+                error = ?e,
+                tolerated = tolerate_nonstandard_genesis,
+                "Failed to read the system state of the genesis checkpoint, {expected}"
+            );
+            if tolerate_nonstandard_genesis {
+                Ok(None)
+            } else {
+                Err(IndexerError::ErrorWithContext(
+                    format!("Non-standard genesis, {expected}"),
+                    Box::new(e.into()),
+                ))
+            }
+        }
+    }
+}
+
+/// The coins created by the genesis checkpoint, owned by an address each.
+fn genesis_allocations(data: &CheckpointData) -> Result<Vec<GenesisAllocation>, IndexerError> {
+    data.changed_objects
+        .iter()
+        .filter(|(status, _)| *status == ObjectStatus::Created)
+        .filter_map(|(_, o)| GenesisAllocation::try_from_object(o).transpose())
+        .collect()
+}
+
+/// Whether the next epoch committee recorded when closing the last epoch of `epoch` is
+/// the committee of the active validators of its new epoch, true for the very first epoch.
+pub fn is_committee_transition_continuous(epoch: &TemporaryEpochStore) -> bool {
+    let last_epoch = match &epoch.last_epoch {
+        Some(last_epoch) => last_epoch,
+        None => return true,
+    };
+    let next_epoch_committee = last_epoch
+        .next_epoch_committee
+        .iter()
+        .flatten()
+        .zip(last_epoch.next_epoch_committee_stake.iter().flatten())
+        .map(|(name, stake)| (name.as_slice(), *stake))
+        .collect::<BTreeMap<_, _>>();
+    let active_committee = epoch
+        .validators
+        .iter()
+        .map(|v| (v.protocol_pubkey_bytes.as_slice(), v.voting_power))
+        .collect::<BTreeMap<_, _>>();
+    next_epoch_committee == active_committee
+}
+
+/// Keeps the rows of the transactions with a move call into one of `packages` and of the
+/// packages themselves, the checkpoint is kept whole.
+fn project_onto_packages(indexed: &mut TemporaryCheckpointStore, packages: &[ObjectID]) {
+    let packages = packages
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<BTreeSet<_>>();
+    let txs = indexed
+        .move_calls
+        .iter()
+        .filter(|call| packages.contains(&call.move_package))
+        .map(|call| call.transaction_digest.clone())
+        .collect::<BTreeSet<_>>();
+    project_onto_transactions(indexed, &txs);
+    indexed
+        .packages
+        .retain(|package| packages.contains(&package.package_id));
+}
 
-            let epoch_commitments = end_of_epoch_data
-                .epoch_commitments
+/// Projects the indexed checkpoint onto the transactions with the allowlisted addresses
+/// as sender or recipient, as computed by `get_addresses` and `get_recipients`.
+fn project_onto_addresses(indexed: &mut TemporaryCheckpointStore, addresses: &[SuiAddress]) {
+    let addresses = addresses
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<BTreeSet<_>>();
+    let txs = indexed
+        .addresses
+        .iter()
+        .filter(|address| addresses.contains(&address.account_address))
+        .map(|address| address.first_appearance_tx.clone())
+        .chain(
+            indexed
+                .recipients
                 .iter()
-                .map(|c| match c {
-                    CheckpointCommitment::ECMHLiveObjectSetDigest(d) => {
-                        Some(d.digest.digest.to_vec())
-                    }
-                })
-                .collect();
-
-            let (next_epoch_committee, next_epoch_committee_stake) =
-                end_of_epoch_data.next_epoch_committee.iter().fold(
-                    (vec![], vec![]),
-                    |(mut names, mut stakes), (name, stake)| {
-                        names.push(Some(name.as_bytes().to_vec()));
-                        stakes.push(Some(*stake as i64));
-                        (names, stakes)
-                    },
-                );
+                .filter(|r| addresses.contains(&r.sender) || addresses.contains(&r.recipient))
+                .map(|r| r.transaction_digest.clone()),
+        )
+        .collect::<BTreeSet<_>>();
+    project_onto_transactions(indexed, &txs);
+}
 
-            let event = event.as_ref();
+/// Keeps the rows of the given transactions only, the checkpoint and packages aside.
+fn project_onto_transactions(indexed: &mut TemporaryCheckpointStore, txs: &BTreeSet<String>) {
+    indexed
+        .transactions
+        .retain(|tx| txs.contains(&tx.transaction_digest));
+    indexed
+        .events
+        .retain(|event| txs.contains(&event.transaction_digest));
+    // NOTE: the previous transaction of a changed object is the one which changed it.
+    for changes in &mut indexed.objects_changes {
+        changes
+            .changed_objects
+            .retain(|o| txs.contains(&o.previous_transaction));
+        changes
+            .deleted_objects
+            .retain(|o| txs.contains(&o.previous_transaction));
+    }
+    indexed
+        .addresses
+        .retain(|address| txs.contains(&address.first_appearance_tx));
+    let addresses = indexed
+        .addresses
+        .iter()
+        .map(|address| address.account_address.as_str())
+        .collect::<BTreeSet<_>>();
+    indexed
+        .address_activities
+        .retain(|activity| addresses.contains(activity.account_address.as_str()));
+    indexed
+        .input_objects
+        .retain(|input| txs.contains(&input.transaction_digest));
+    indexed
+        .move_calls
+        .retain(|call| txs.contains(&call.transaction_digest));
+    indexed
+        .recipients
+        .retain(|recipient| txs.contains(&recipient.transaction_digest));
+    indexed
+        .ptb_commands
+        .retain(|command| txs.contains(&command.transaction_digest));
+    indexed
+        .dependencies
+        .retain(|dependency| txs.contains(&dependency.transaction_digest));
+}
 
-            Some(TemporaryEpochStore {
-                last_epoch: Some(DBEpochInfo {
-                    epoch: system_state.epoch as i64 - 1,
-                    first_checkpoint_id: 0,
-                    last_checkpoint_id: Some(checkpoint.sequence_number as i64),
-                    epoch_start_timestamp: 0,
-                    epoch_end_timestamp: Some(checkpoint.timestamp_ms as i64),
-                    epoch_total_transactions: 0,
-                    next_epoch_version: Some(
-                        end_of_epoch_data.next_epoch_protocol_version.as_u64() as i64,
-                    ),
-                    next_epoch_committee,
-                    next_epoch_committee_stake,
-                    stake_subsidy_amount: event.map(|e| e.stake_subsidy_amount),
-                    reference_gas_price: event.map(|e| e.reference_gas_price),
-                    storage_fund_balance: event.map(|e| e.storage_fund_balance),
-                    total_gas_fees: event.map(|e| e.total_gas_fees),
-                    total_stake_rewards_distributed: event
-                        .map(|e| e.total_stake_rewards_distributed),
-                    total_stake: event.map(|e| e.total_stake),
-                    storage_fund_reinvestment: event.map(|e| e.storage_fund_reinvestment),
-                    storage_charge: event.map(|e| e.storage_charge),
-                    protocol_version: event.map(|e| e.protocol_version),
-                    storage_rebate: event.map(|e| e.storage_rebate),
-                    leftover_storage_fund_inflow: event.map(|e| e.leftover_storage_fund_inflow),
-                    epoch_commitments,
-                }),
-                new_epoch: DBEpochInfo {
-                    epoch: system_state.epoch as i64,
-                    first_checkpoint_id: checkpoint.sequence_number as i64 + 1,
-                    epoch_start_timestamp: system_state.epoch_start_timestamp_ms as i64,
-                    ..Default::default()
-                },
-                system_state: system_state.into(),
-                validators,
-            })
-        } else {
-            None
-        };
+/// Throttles the warnings of a commit retried every DB_COMMIT_RETRY_INTERVAL_IN_MILLIS,
+/// `retries` counting from 1, the metric still counts every retry.
+fn should_warn_commit_retry(retries: u64) -> bool {
+    (retries - 1) % DB_COMMIT_RETRY_WARN_EVERY == 0
+}
 
-        let total_transactions = db_transactions.iter().map(|t| t.transaction_count).sum();
-
-        Ok((
-            TemporaryCheckpointStore {
-                checkpoint: Checkpoint::from(checkpoint, total_transactions)?,
-                transactions: db_transactions,
-                events,
-                objects_changes,
-                addresses,
-                packages,
-                input_objects,
-                move_calls,
-                recipients,
-            },
-            epoch_index,
-        ))
+fn committee_of_validators(
+    epoch: EpochId,
+    validators: &[DBValidatorSummary],
+) -> Result<Option<Committee>, IndexerError> {
+    if validators.is_empty() {
+        return Ok(None);
     }
+    let voting_rights = validators
+        .iter()
+        .map(|v| {
+            AuthorityPublicKeyBytes::from_bytes(&v.protocol_pubkey_bytes)
+                .map(|name| (name, v.voting_power as u64))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some(Committee::new(epoch, voting_rights)))
+}
 
-    fn index_packages(
-        transactions: &[CheckpointTransactionBlockResponse],
-        changed_objects: &[(ObjectStatus, SuiObjectData)],
-    ) -> Result<Vec<Package>, IndexerError> {
-        let object_map = changed_objects
-            .iter()
-            .filter_map(|(_, o)| {
-                if let SuiRawData::Package(p) = &o
-                    .bcs
-                    .as_ref()
-                    .expect("Expect the content field to be non-empty from data fetching")
-                {
-                    Some((o.object_id, p))
-                } else {
-                    None
-                }
-            })
-            .collect::<BTreeMap<_, _>>();
+/// Indexes the transactions of a checkpoint, the transaction stage of
+/// `index_checkpoint_data`.
+pub fn index_transactions(
+    transactions: &[CheckpointTransactionBlockResponse],
+) -> Result<Vec<Transaction>, IndexerError> {
+    transactions
+        .iter()
+        .map(|tx| TemporaryTransactionBlockResponseStore::from(tx.clone()).try_into())
+        .collect()
+}
 
-        transactions
-            .iter()
-            .flat_map(|tx| {
-                tx.effects.created().iter().map(|oref| {
-                    object_map
-                        .get(&oref.reference.object_id)
-                        .map(|o| Package::try_from(*tx.transaction.data.sender(), o))
+/// Indexes the objects changed by each transaction of a checkpoint, the object stage of
+/// `index_checkpoint_data`.
+pub fn index_objects(
+    checkpoint: &RpcCheckpoint,
+    transactions: &[CheckpointTransactionBlockResponse],
+    changed_objects: &[(ObjectStatus, SuiObjectData)],
+) -> Vec<TransactionObjectChanges> {
+    let tx_objects = changed_objects
+        .iter()
+        // Unwrap safe here as we requested previous tx data in the request.
+        .fold(BTreeMap::<_, Vec<_>>::new(), |mut acc, (status, o)| {
+            if let Some(digest) = &o.previous_transaction {
+                acc.entry(*digest).or_default().push((status, o));
+            }
+            acc
+        });
+
+    transactions
+        .iter()
+        .map(|tx| {
+            let changed_db_objects = tx_objects
+                .get(&tx.digest)
+                .unwrap_or(&vec![])
+                .iter()
+                .map(|(status, o)| {
+                    Object::from(
+                        checkpoint.epoch,
+                        Some(checkpoint.sequence_number),
+                        status,
+                        o,
+                    )
                 })
+                .collect::<Vec<_>>();
+            let deleted_objects = get_deleted_db_objects(
+                &tx.effects,
+                checkpoint.epoch,
+                Some(checkpoint.sequence_number),
+            );
+
+            TransactionObjectChanges {
+                changed_objects: changed_db_objects,
+                deleted_objects,
+            }
+        })
+        .collect()
+}
+
+/// Indexes the packages published by the transactions of a checkpoint, the package stage
+/// of `index_checkpoint_data`.
+pub fn index_packages(
+    transactions: &[CheckpointTransactionBlockResponse],
+    changed_objects: &[(ObjectStatus, SuiObjectData)],
+) -> Result<Vec<Package>, IndexerError> {
+    let object_map = changed_objects
+        .iter()
+        .filter_map(|(_, o)| match &o.bcs {
+            Some(SuiRawData::Package(p)) => Some(Ok((o.object_id, p))),
+            None if matches!(o.type_, Some(ObjectType::Package)) => {
+                Some(Err(IndexerError::MissingPackageBcs {
+                    object_id: o.object_id,
+                }))
+            }
+            _ => None,
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+    transactions
+        .iter()
+        .flat_map(|tx| {
+            tx.effects.created().iter().map(|oref| {
+                object_map
+                    .get(&oref.reference.object_id)
+                    .map(|o| Package::try_from(*tx.transaction.data.sender(), o))
             })
-            .flatten()
-            .collect()
-    }
+        })
+        .flatten()
+        .collect()
+}
+
+fn malformed_event_error(event: &SuiEvent, err: &bcs::Error) -> IndexerError {
+    IndexerError::EventDeserializationError(format!(
+        "Failed to deserialize event {} of transaction {} with error {:?}",
+        event.type_, event.id.tx_digest, err
+    ))
 }
 
 // TODO(gegaowp): re-orgnize object util functions below
+/// Objects changed by `effects`, along with the owners they have after the transaction.
 pub fn get_object_changes(
     effects: &SuiTransactionBlockEffects,
-) -> Vec<(ObjectID, SequenceNumber, ObjectStatus)> {
+) -> Vec<(ObjectID, SequenceNumber, ObjectStatus, Owner)> {
     let created = effects.created().iter().map(|o: &OwnedObjectRef| {
         (
             o.reference.object_id,
             o.reference.version,
             ObjectStatus::Created,
+            o.owner,
         )
     });
     let mutated = effects.mutated().iter().map(|o: &OwnedObjectRef| {
@@ -643,6 +1628,7 @@ pub fn get_object_changes(
             o.reference.object_id,
             o.reference.version,
             ObjectStatus::Mutated,
+            o.owner,
         )
     });
     let unwrapped = effects.unwrapped().iter().map(|o: &OwnedObjectRef| {
@@ -650,53 +1636,172 @@ pub fn get_object_changes(
             o.reference.object_id,
             o.reference.version,
             ObjectStatus::Unwrapped,
+            o.owner,
         )
     });
     created.chain(mutated).chain(unwrapped).collect()
 }
 
-pub async fn fetch_changed_objects(
-    http_client: HttpClient,
-    object_changes: Vec<(ObjectID, SequenceNumber, ObjectStatus)>,
-) -> Result<Vec<(ObjectStatus, SuiObjectData)>, IndexerError> {
-    join_all(object_changes.chunks(MULTI_GET_CHUNK_SIZE).map(|objects| {
-        let wanted_past_object_statuses: Vec<ObjectStatus> =
-            objects.iter().map(|(_, _, status)| *status).collect();
+/// Whether the object an effect gives `owner` and `status` to can be a package, before it
+/// is fetched. Packages are created immutable, as are the rare objects frozen by the
+/// transaction creating them, which get the options of packages too.
+pub fn is_package_change(owner: &Owner, status: &ObjectStatus) -> bool {
+    matches!((owner, status), (Owner::Immutable, ObjectStatus::Created))
+}
 
-        let wanted_past_object_request = objects
-            .iter()
-            .map(|(id, seq_num, _)| SuiGetPastObjectRequest {
-                object_id: *id,
-                version: *seq_num,
-            })
-            .collect();
-        http_client
-            .try_multi_get_past_objects(
-                wanted_past_object_request,
-                Some(SuiObjectDataOptions::bcs_lossless()),
-            )
-            .map(move |resp| (resp, wanted_past_object_statuses))
+/// Options changed objects are fetched with, by kind.
+#[derive(Clone, Debug)]
+pub struct ChangedObjectOptions {
+    pub packages: SuiObjectDataOptions,
+    pub objects: SuiObjectDataOptions,
+}
+
+impl Default for ChangedObjectOptions {
+    /// Every object is stored along with its BCS, which reads rebuild it from, so plain
+    /// objects need `bcs_lossless` as much as packages, whose modules `index_packages` parses.
+    /// Nothing less converts into a stored `Object`, which takes the BCS, type, owner,
+    /// previous transaction and storage rebate of every object, coins included.
+    fn default() -> Self {
+        Self {
+            packages: SuiObjectDataOptions::bcs_lossless(),
+            objects: SuiObjectDataOptions::bcs_lossless(),
+        }
+    }
+}
+
+impl ChangedObjectOptions {
+    pub fn for_change(&self, owner: &Owner, status: &ObjectStatus) -> SuiObjectDataOptions {
+        if is_package_change(owner, status) {
+            self.packages.clone()
+        } else {
+            self.objects.clone()
+        }
+    }
+}
+
+/// Fetches the changed objects with the options `options_for` picks for each of them, from
+/// its owner and status in the effects. Objects come back grouped by options, in the order
+/// of `object_changes` within a group.
+pub async fn fetch_changed_objects<C, F>(
+    fullnode_client: &C,
+    object_changes: Vec<(ObjectID, SequenceNumber, ObjectStatus, Owner)>,
+    chunk_size: usize,
+    concurrency: usize,
+    options_for: F,
+    max_refetches: usize,
+    metrics: &IndexerCheckpointHandlerMetrics,
+) -> Result<Vec<(ObjectStatus, SuiObjectData)>, IndexerError>
+where
+    C: FullNodeClient,
+    F: Fn(&Owner, &ObjectStatus) -> SuiObjectDataOptions,
+{
+    // NOTE: a multi-get applies the same options to all its objects.
+    let mut groups: Vec<(SuiObjectDataOptions, Vec<_>)> = vec![];
+    for change in object_changes {
+        let options = options_for(&change.3, &change.2);
+        match groups.iter_mut().find(|(o, _)| *o == options) {
+            Some((_, changes)) => changes.push(change),
+            None => groups.push((options, vec![change])),
+        }
+    }
+    let chunks = groups.iter().flat_map(|(options, changes)| {
+        changes
+            .chunks(chunk_size.max(1))
+            .map(move |objects| (options, objects))
+    });
+    stream::iter(chunks.map(|(options, objects)| {
+        fetch_object_chunk(fullnode_client, objects, options, max_refetches, metrics)
     }))
+    .buffered(concurrency.max(1))
+    .collect::<Vec<_>>()
     .await
     .into_iter()
     .try_fold(vec![], |mut acc, chunk| {
-        let object_datas = chunk.0?.into_iter().try_fold(vec![], |mut acc, resp| {
-            let object_data = resp.into_object()?;
-            acc.push(object_data);
-            Ok::<Vec<SuiObjectData>, Error>(acc)
-        })?;
-        let mutated_object_chunk = chunk.1.into_iter().zip(object_datas);
-        acc.extend(mutated_object_chunk);
-        Ok::<_, Error>(acc)
-    })
-    .map_err(|e| {
-        IndexerError::SerdeError(format!(
-            "Failed to generate changed objects of checkpoint with err {:?}",
-            e
-        ))
+        acc.extend(chunk?);
+        Ok::<_, IndexerError>(acc)
     })
 }
 
+/// Fetches `objects` in one multi-get, then re-requests only the objects missing from the
+/// response, up to `max_refetches` times, keeping the ones already fetched.
+async fn fetch_object_chunk<C: FullNodeClient>(
+    fullnode_client: &C,
+    objects: &[(ObjectID, SequenceNumber, ObjectStatus, Owner)],
+    options: &SuiObjectDataOptions,
+    max_refetches: usize,
+    metrics: &IndexerCheckpointHandlerMetrics,
+) -> Result<Vec<(ObjectStatus, SuiObjectData)>, IndexerError> {
+    let mut fetched: Vec<Option<SuiObjectData>> = vec![None; objects.len()];
+    // indexes into objects of the objects to request
+    let mut missing = (0..objects.len()).collect::<Vec<_>>();
+    let mut refetches = 0;
+    loop {
+        let request = missing
+            .iter()
+            .map(|i| SuiGetPastObjectRequest {
+                object_id: objects[*i].0,
+                version: objects[*i].1,
+            })
+            .collect();
+        let error = match fullnode_client
+            .try_multi_get_past_objects(request, Some(options.clone()))
+            .await
+        {
+            Ok(responses) => {
+                let mut responses = responses.into_iter();
+                let mut still_missing = vec![];
+                let mut error = None;
+                for i in missing {
+                    match responses.next().map(|resp| resp.into_object()) {
+                        Some(Ok(object_data)) => fetched[i] = Some(object_data),
+                        Some(Err(e)) => {
+                            still_missing.push(i);
+                            error = Some(IndexerError::SerdeError(format!(
+                                "Failed to generate changed objects of checkpoint with err {:?}",
+                                e
+                            )));
+                        }
+                        None => {
+                            still_missing.push(i);
+                            error = Some(IndexerError::FullNodeReadingError(format!(
+                                "Fullnode did not return object {}",
+                                objects[i].0
+                            )));
+                        }
+                    }
+                }
+                missing = still_missing;
+                error
+            }
+            Err(e) => Some(e),
+        };
+        match error {
+            None => break,
+            Some(e) if refetches >= max_refetches => return Err(e),
+            Some(e) => {
+                refetches += 1;
+                metrics.partial_refetch_total.inc();
+                warn!(
+                    missing = missing.len(),
+                    requested = objects.len(),
+                    attempt = refetches,
+                    error = ?e,
+                    "Failed to fetch changed objects, refetching the missing ones"
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(
+                    OBJECT_REFETCH_INTERVAL_IN_MILLIS,
+                ))
+                .await;
+            }
+        }
+    }
+    Ok(objects
+        .iter()
+        .zip(fetched)
+        .filter_map(|((_, _, status, _), object_data)| object_data.map(|o| (*status, o)))
+        .collect())
+}
+
 // TODO(gegaowp): temp. disable fast-path
 // pub fn to_changed_db_objects(
 //     changed_objects: Vec<(ObjectStatus, SuiObjectData)>,
@@ -0,0 +1,356 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use fastcrypto::encoding::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::errors::IndexerError;
+use crate::models::objects::{NamedBcsBytes, ObjectStatus};
+use crate::models::owners::OwnerType;
+use crate::store::TemporaryCheckpointStore;
+use crate::IndexerConfig;
+
+/// Where the checkpoint handler sends each checkpoint once it is committed, in commit
+/// order. A failing sink only gets the failure logged, the checkpoint is committed already.
+#[async_trait]
+pub trait CheckpointSink: Send + Sync + 'static {
+    async fn process_checkpoint(
+        &self,
+        checkpoint: &TemporaryCheckpointStore,
+    ) -> Result<(), IndexerError>;
+}
+
+/// Writes each committed checkpoint as one line of `JsonCheckpoint` to the files of a
+/// directory, one file per checkpoint or a new file once the current one reaches a size.
+/// Files are named after the first checkpoint they hold.
+pub struct JsonFileSink {
+    dir: PathBuf,
+    max_file_bytes: Option<u64>,
+    include_object_bcs: bool,
+    // file appended to with its size, None until the first checkpoint
+    current: Mutex<Option<(File, u64)>>,
+}
+
+impl JsonFileSink {
+    pub fn new(dir: PathBuf, max_file_bytes: Option<u64>, include_object_bcs: bool) -> Self {
+        Self {
+            dir,
+            max_file_bytes,
+            include_object_bcs,
+            current: Mutex::new(None),
+        }
+    }
+
+    /// The sink exporting to `checkpoint_json_dir`, None if it is not set.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.checkpoint_json_dir.as_ref().map(|dir| {
+            Self::new(
+                dir.clone(),
+                config.checkpoint_json_max_file_bytes,
+                config.checkpoint_json_include_object_bcs,
+            )
+        })
+    }
+
+    pub fn file_name(first_sequence_number: i64) -> String {
+        format!("checkpoint_{:020}.jsonl", first_sequence_number)
+    }
+
+    async fn open(&self, first_sequence_number: i64) -> Result<(File, u64), IndexerError> {
+        let path = self.dir.join(Self::file_name(first_sequence_number));
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| file_write_error(&path, e))?;
+        // NOTE: appending keeps what was exported before a restart from the same checkpoint.
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|e| file_write_error(&path, e))?;
+        let size = file
+            .metadata()
+            .await
+            .map_err(|e| file_write_error(&path, e))?
+            .len();
+        Ok((file, size))
+    }
+}
+
+#[async_trait]
+impl CheckpointSink for JsonFileSink {
+    async fn process_checkpoint(
+        &self,
+        checkpoint: &TemporaryCheckpointStore,
+    ) -> Result<(), IndexerError> {
+        let json = JsonCheckpoint::from_indexed(checkpoint, self.include_object_bcs)?;
+        let mut line = serde_json::to_vec(&json).map_err(|e| {
+            IndexerError::SerdeError(format!(
+                "Failed to serialize checkpoint {} to JSON with error: {:?}",
+                json.sequence_number, e
+            ))
+        })?;
+        line.push(b'\n');
+
+        let mut current = self.current.lock().await;
+        let (mut file, size) = match current.take() {
+            Some((file, size)) if self.max_file_bytes.map_or(false, |max| size < max) => {
+                (file, size)
+            }
+            _ => self.open(json.sequence_number).await?,
+        };
+        let write_error = |e| file_write_error(&self.dir, e);
+        file.write_all(&line).await.map_err(write_error)?;
+        file.flush().await.map_err(write_error)?;
+        *current = Some((file, size + line.len() as u64));
+        Ok(())
+    }
+}
+
+fn file_write_error(path: &std::path::Path, e: std::io::Error) -> IndexerError {
+    IndexerError::FileWriteError(format!("{}: {:?}", path.display(), e))
+}
+
+/// The JSON written by `JsonFileSink` for a checkpoint, independent of the DB schema.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonCheckpoint {
+    pub sequence_number: i64,
+    pub checkpoint_digest: String,
+    pub epoch: i64,
+    pub timestamp_ms: i64,
+    pub transactions: Vec<JsonTransaction>,
+    pub events: Vec<JsonEvent>,
+    pub object_changes: Vec<JsonObjectChange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonTransaction {
+    pub transaction_digest: String,
+    pub sender: String,
+    pub transaction_kind: String,
+    pub recipients: Vec<String>,
+    pub move_calls: Vec<String>,
+    pub gas_price: i64,
+    pub computation_cost: i64,
+    pub storage_cost: i64,
+    pub storage_rebate: i64,
+    pub non_refundable_storage_fee: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonEvent {
+    pub transaction_digest: String,
+    pub event_sequence: i64,
+    pub event_index: i64,
+    pub sender: String,
+    pub event_type: String,
+    // Base64 encoded
+    pub bcs: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonObjectChange {
+    pub object_id: String,
+    pub version: i64,
+    pub object_digest: String,
+    pub object_type: String,
+    pub object_status: ObjectStatus,
+    pub owner_type: OwnerType,
+    pub owner_address: Option<String>,
+    pub previous_transaction: String,
+    // Base64 encoded uncompressed BCS by name, None if not included or deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bcs: Option<BTreeMap<String, String>>,
+}
+
+impl JsonCheckpoint {
+    pub fn from_indexed(
+        indexed: &TemporaryCheckpointStore,
+        include_object_bcs: bool,
+    ) -> Result<Self, IndexerError> {
+        let transactions = indexed
+            .transactions
+            .iter()
+            .map(|tx| JsonTransaction {
+                transaction_digest: tx.transaction_digest.clone(),
+                sender: tx.sender.clone(),
+                transaction_kind: tx.transaction_kind.clone(),
+                recipients: tx.recipients.iter().flatten().cloned().collect(),
+                move_calls: tx.move_calls.iter().flatten().cloned().collect(),
+                gas_price: tx.gas_price,
+                computation_cost: tx.computation_cost,
+                storage_cost: tx.storage_cost,
+                storage_rebate: tx.storage_rebate,
+                non_refundable_storage_fee: tx.non_refundable_storage_fee,
+            })
+            .collect();
+        let events = indexed
+            .events
+            .iter()
+            .map(|event| JsonEvent {
+                transaction_digest: event.transaction_digest.clone(),
+                event_sequence: event.event_sequence,
+                event_index: event.event_index,
+                sender: event.sender.clone(),
+                event_type: event.event_type.clone(),
+                bcs: Base64::encode(&event.event_bcs),
+            })
+            .collect();
+
+        let mut object_changes = vec![];
+        for changes in &indexed.objects_changes {
+            for o in &changes.changed_objects {
+                let bcs = if include_object_bcs {
+                    let bcs = o
+                        .bcs
+                        .iter()
+                        .cloned()
+                        .map(|bcs| -> Result<_, IndexerError> {
+                            let NamedBcsBytes(name, bytes) = bcs.decompress()?;
+                            Ok((name, Base64::encode(bytes)))
+                        })
+                        .collect::<Result<BTreeMap<_, _>, _>>()?;
+                    Some(bcs)
+                } else {
+                    None
+                };
+                object_changes.push(JsonObjectChange {
+                    object_id: o.object_id.clone(),
+                    version: o.version,
+                    object_digest: o.object_digest.clone(),
+                    object_type: o.object_type.clone(),
+                    object_status: o.object_status,
+                    owner_type: o.owner_type.clone(),
+                    owner_address: o.owner_address.clone(),
+                    previous_transaction: o.previous_transaction.clone(),
+                    bcs,
+                });
+            }
+            object_changes.extend(changes.deleted_objects.iter().map(|o| JsonObjectChange {
+                object_id: o.object_id.clone(),
+                version: o.version,
+                object_digest: o.object_digest.clone(),
+                object_type: o.object_type.clone(),
+                object_status: o.object_status,
+                owner_type: o.owner_type.clone(),
+                owner_address: None,
+                previous_transaction: o.previous_transaction.clone(),
+                bcs: None,
+            }));
+        }
+
+        Ok(Self {
+            sequence_number: indexed.checkpoint.sequence_number,
+            checkpoint_digest: indexed.checkpoint.checkpoint_digest.clone(),
+            epoch: indexed.checkpoint.epoch,
+            timestamp_ms: indexed.checkpoint.timestamp_ms,
+            transactions,
+            events,
+            object_changes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_checkpoint_data;
+    use crate::test_utils::TestCheckpointDataBuilder;
+
+    #[tokio::test]
+    async fn test_write_checkpoint_as_json() {
+        let mut builder = TestCheckpointDataBuilder::new();
+        builder.genesis_checkpoint();
+        let data = builder.next_checkpoint(2);
+        let (mut indexed, _) = index_checkpoint_data(&data).unwrap();
+        // Compressed BCS is exported uncompressed.
+        for changes in &mut indexed.objects_changes {
+            for o in &mut changes.changed_objects {
+                o.compress_bcs().unwrap();
+            }
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let sink = JsonFileSink::new(dir.path().to_path_buf(), None, true);
+        sink.process_checkpoint(&indexed).await.unwrap();
+
+        let path = dir
+            .path()
+            .join(JsonFileSink::file_name(indexed.checkpoint.sequence_number));
+        let content = std::fs::read_to_string(path).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+        let json: JsonCheckpoint = serde_json::from_str(lines[0]).unwrap();
+
+        assert_eq!(json.sequence_number, indexed.checkpoint.sequence_number);
+        assert_eq!(json.checkpoint_digest, indexed.checkpoint.checkpoint_digest);
+        let digests = json
+            .transactions
+            .iter()
+            .map(|tx| tx.transaction_digest.clone())
+            .collect::<Vec<_>>();
+        let expected = indexed
+            .transactions
+            .iter()
+            .map(|tx| tx.transaction_digest.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(digests, expected);
+        assert_eq!(json.events.len(), indexed.events.len());
+        let changed = indexed
+            .objects_changes
+            .iter()
+            .flat_map(|changes| changes.changed_objects.iter())
+            .collect::<Vec<_>>();
+        assert!(!changed.is_empty());
+        for o in changed {
+            let exported = json
+                .object_changes
+                .iter()
+                .find(|c| c.object_id == o.object_id)
+                .unwrap();
+            let bcs = exported.bcs.as_ref().unwrap();
+            for named in o.bcs.iter().cloned() {
+                let NamedBcsBytes(name, bytes) = named.decompress().unwrap();
+                assert_eq!(Base64::decode(&bcs[&name]).unwrap(), bytes);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotate_json_files_by_size() {
+        let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+        let dir = tempfile::tempdir().unwrap();
+        // Any checkpoint is larger than a byte, so each file holds a single one.
+        let sink = JsonFileSink::new(dir.path().to_path_buf(), Some(1), false);
+        let unbounded_dir = tempfile::tempdir().unwrap();
+        let unbounded =
+            JsonFileSink::new(unbounded_dir.path().to_path_buf(), Some(u64::MAX), false);
+        for data in &checkpoints {
+            let (indexed, _) = index_checkpoint_data(data).unwrap();
+            sink.process_checkpoint(&indexed).await.unwrap();
+            unbounded.process_checkpoint(&indexed).await.unwrap();
+        }
+
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+        let content =
+            std::fs::read_to_string(unbounded_dir.path().join(JsonFileSink::file_name(0))).unwrap();
+        let json = content
+            .lines()
+            .map(|line| serde_json::from_str::<JsonCheckpoint>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            json.iter().map(|cp| cp.sequence_number).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(json
+            .iter()
+            .flat_map(|cp| cp.object_changes.iter())
+            .all(|o| o.bcs.is_none()));
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use clap::CommandFactory;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::IndexerError;
+use crate::IndexerConfig;
+
+/// The settings of `IndexerConfig` that can change while indexing, under the same names.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicIndexerConfig {
+    pub tx_download_concurrency: usize,
+    pub object_download_concurrency: usize,
+    pub multi_get_chunk_size: usize,
+    pub max_object_refetches: usize,
+    pub max_fullnode_rps: Option<u32>,
+}
+
+impl From<&IndexerConfig> for DynamicIndexerConfig {
+    fn from(config: &IndexerConfig) -> Self {
+        Self {
+            tx_download_concurrency: config.tx_download_concurrency,
+            object_download_concurrency: config.object_download_concurrency,
+            multi_get_chunk_size: config.multi_get_chunk_size,
+            max_object_refetches: config.max_object_refetches,
+            max_fullnode_rps: config.max_fullnode_rps,
+        }
+    }
+}
+
+/// Shared `DynamicIndexerConfig` the checkpoint handler reads at every iteration of its
+/// loops, swapped by reloading the YAML config file. The file holds the settings to
+/// change, the others keep their current value.
+#[derive(Clone)]
+pub struct DynamicConfig {
+    current: Arc<ArcSwap<DynamicIndexerConfig>>,
+    path: Option<PathBuf>,
+}
+
+impl DynamicConfig {
+    pub fn new(config: &IndexerConfig) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(config.into())),
+            path: config.config_file.clone(),
+        }
+    }
+
+    pub fn load(&self) -> Arc<DynamicIndexerConfig> {
+        self.current.load_full()
+    }
+
+    /// Reads the config file again and swaps in its settings.
+    pub fn reload(&self) -> Result<Arc<DynamicIndexerConfig>, IndexerError> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            IndexerError::InvalidConfigError(
+                "Indexer was started without a config file to reload".to_string(),
+            )
+        })?;
+        let yaml = std::fs::read_to_string(path).map_err(|e| {
+            IndexerError::InvalidConfigError(format!(
+                "Failed to read config file {} with error: {:?}",
+                path.display(),
+                e
+            ))
+        })?;
+        self.apply(&yaml)
+    }
+
+    /// Swaps in the settings of `yaml` over the current ones, none of them if any setting
+    /// can't change while indexing.
+    pub fn apply(&self, yaml: &str) -> Result<Arc<DynamicIndexerConfig>, IndexerError> {
+        let invalid = |e: serde_yaml::Error| IndexerError::InvalidConfigError(e.to_string());
+        let changes: Option<serde_yaml::Mapping> = serde_yaml::from_str(yaml).map_err(invalid)?;
+        let mut merged = match serde_yaml::to_value(&*self.load()).map_err(invalid)? {
+            serde_yaml::Value::Mapping(current) => current,
+            _ => unreachable!("DynamicIndexerConfig is serialized to a mapping"),
+        };
+        for (key, value) in changes.unwrap_or_default() {
+            if !merged.contains_key(&key) {
+                let name = key
+                    .as_str()
+                    .map_or_else(|| format!("{:?}", key), String::from);
+                let needs_restart = IndexerConfig::command()
+                    .get_arguments()
+                    .any(|arg| arg.get_id() == name);
+                return Err(IndexerError::InvalidConfigError(if needs_restart {
+                    format!("`{name}` can't be changed while indexing, it needs a restart")
+                } else {
+                    format!("Unknown setting `{name}`")
+                }));
+            }
+            merged.insert(key, value);
+        }
+        let config: DynamicIndexerConfig =
+            serde_yaml::from_value(serde_yaml::Value::Mapping(merged)).map_err(invalid)?;
+        let config = Arc::new(config);
+        self.current.store(config.clone());
+        Ok(config)
+    }
+}
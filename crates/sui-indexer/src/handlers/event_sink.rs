@@ -0,0 +1,32 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+
+use sui_core::event_handler::EventHandler;
+use sui_json_rpc_types::{SuiTransactionBlockEffects, SuiTransactionBlockEvents};
+
+use crate::errors::IndexerError;
+
+/// Where the checkpoint handler sends the events of each indexed transaction, in
+/// checkpoint order. Abstracted so that embedders can route events elsewhere than to
+/// the subscription `EventHandler`.
+#[async_trait]
+pub trait EventSink: Send + Sync + 'static {
+    async fn process_events(
+        &self,
+        effects: &SuiTransactionBlockEffects,
+        events: &SuiTransactionBlockEvents,
+    ) -> Result<(), IndexerError>;
+}
+
+#[async_trait]
+impl EventSink for EventHandler {
+    async fn process_events(
+        &self,
+        effects: &SuiTransactionBlockEffects,
+        events: &SuiTransactionBlockEvents,
+    ) -> Result<(), IndexerError> {
+        Ok(EventHandler::process_events(self, effects, events).await?)
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use jsonrpsee::http_client::HttpClient;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use sui_json_rpc::api::ReadApiClient;
+use sui_json_rpc_types::{
+    Checkpoint as RpcCheckpoint, SuiGetPastObjectRequest, SuiObjectDataOptions,
+    SuiPastObjectResponse,
+};
+use sui_types::digests::TransactionDigest;
+use sui_types::messages_checkpoint::{CertifiedCheckpointSummary, CheckpointSequenceNumber};
+
+use crate::errors::{FullNodeReadErrorKind, IndexerError};
+use crate::types::CheckpointTransactionBlockResponse;
+use crate::utils::multi_get_full_transactions;
+
+/// The fullnode read APIs the checkpoint handler depends on.
+/// Abstracted so that the handler can be driven by a mock fullnode in tests.
+#[async_trait]
+pub trait FullNodeClient: Clone + Send + Sync + 'static {
+    async fn get_checkpoint(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<RpcCheckpoint, IndexerError>;
+
+    /// The checkpoint summary signed by the committee, needed to verify the checkpoint.
+    async fn get_certified_checkpoint_summary(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<CertifiedCheckpointSummary, IndexerError>;
+
+    async fn multi_get_full_transactions(
+        &self,
+        digests: Vec<TransactionDigest>,
+    ) -> Result<Vec<CheckpointTransactionBlockResponse>, IndexerError>;
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<SuiGetPastObjectRequest>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> Result<Vec<SuiPastObjectResponse>, IndexerError>;
+}
+
+#[async_trait]
+impl FullNodeClient for HttpClient {
+    async fn get_checkpoint(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<RpcCheckpoint, IndexerError> {
+        ReadApiClient::get_checkpoint(self, seq.into())
+            .await
+            .map_err(|e| IndexerError::FullNodeRpcError {
+                kind: FullNodeReadErrorKind::classify(&e),
+                message: format!(
+                    "Failed to get checkpoint with sequence number {} and error {:?}",
+                    seq, e
+                ),
+            })
+    }
+
+    async fn get_certified_checkpoint_summary(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<CertifiedCheckpointSummary, IndexerError> {
+        // NOTE: the read API only returns the aggregated signature without its signers,
+        // which is not enough to verify it.
+        Err(IndexerError::NotSupportedError(format!(
+            "Fullnode JSON RPC does not serve the certified summary of checkpoint {seq}"
+        )))
+    }
+
+    async fn multi_get_full_transactions(
+        &self,
+        digests: Vec<TransactionDigest>,
+    ) -> Result<Vec<CheckpointTransactionBlockResponse>, IndexerError> {
+        multi_get_full_transactions(self.clone(), digests).await
+    }
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<SuiGetPastObjectRequest>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> Result<Vec<SuiPastObjectResponse>, IndexerError> {
+        ReadApiClient::try_multi_get_past_objects(self, past_objects, options)
+            .await
+            .map_err(|e| IndexerError::FullNodeRpcError {
+                kind: FullNodeReadErrorKind::classify(&e),
+                message: format!("Failed to get past objects with error {:?}", e),
+            })
+    }
+}
+
+/// A `FullNodeClient` sharing one budget of requests per second across all its clones
+/// and methods, so that the look-ahead and multi-get fan-out can't burst above it.
+#[derive(Clone)]
+pub struct RateLimitedFullNodeClient<C> {
+    inner: C,
+    bucket: Arc<TokenBucket>,
+}
+
+impl<C> RateLimitedFullNodeClient<C> {
+    pub fn new(inner: C, max_rps: Option<u32>) -> Self {
+        Self {
+            inner,
+            bucket: Arc::new(TokenBucket::new(max_rps)),
+        }
+    }
+
+    /// Changes the budget of all the clones, None lifts the limit.
+    pub async fn set_max_rps(&self, max_rps: Option<u32>) {
+        self.bucket.set_rps(max_rps).await;
+    }
+
+    async fn acquire(&self) {
+        self.bucket.acquire().await;
+    }
+}
+
+#[async_trait]
+impl<C: FullNodeClient> FullNodeClient for RateLimitedFullNodeClient<C> {
+    async fn get_checkpoint(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<RpcCheckpoint, IndexerError> {
+        self.acquire().await;
+        self.inner.get_checkpoint(seq).await
+    }
+
+    async fn get_certified_checkpoint_summary(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<CertifiedCheckpointSummary, IndexerError> {
+        self.acquire().await;
+        self.inner.get_certified_checkpoint_summary(seq).await
+    }
+
+    async fn multi_get_full_transactions(
+        &self,
+        digests: Vec<TransactionDigest>,
+    ) -> Result<Vec<CheckpointTransactionBlockResponse>, IndexerError> {
+        self.acquire().await;
+        self.inner.multi_get_full_transactions(digests).await
+    }
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<SuiGetPastObjectRequest>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> Result<Vec<SuiPastObjectResponse>, IndexerError> {
+        self.acquire().await;
+        self.inner
+            .try_multi_get_past_objects(past_objects, options)
+            .await
+    }
+}
+
+/// Refilled with `rps` tokens per second and holding at most `rps` of them, so that a
+/// burst never exceeds one second worth of requests.
+struct TokenBucket {
+    state: Mutex<TokenBucketState>,
+}
+
+struct TokenBucketState {
+    // None when requests are not limited
+    rps: Option<f64>,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rps: Option<u32>) -> Self {
+        let rps = rps.map(|rps| f64::from(rps.max(1)));
+        Self {
+            state: Mutex::new(TokenBucketState {
+                rps,
+                tokens: rps.unwrap_or_default(),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    async fn set_rps(&self, rps: Option<u32>) {
+        let rps = rps.map(|rps| f64::from(rps.max(1)));
+        let mut state = self.state.lock().await;
+        if state.rps == rps {
+            return;
+        }
+        // NOTE: a lower budget cuts the burst left, a newly set one starts full.
+        state.tokens = match (state.rps, rps) {
+            (Some(_), Some(rps)) => state.tokens.min(rps),
+            (_, rps) => rps.unwrap_or_default(),
+        };
+        state.rps = rps;
+        state.last_refill = Instant::now();
+    }
+
+    async fn acquire(&self) {
+        // NOTE: the lock is held while waiting for a token, so that waiters are served
+        // in order and none of them can jump the queue.
+        let mut state = self.state.lock().await;
+        let rps = match state.rps {
+            Some(rps) => rps,
+            None => return,
+        };
+        let now = Instant::now();
+        let refilled = now.duration_since(state.last_refill).as_secs_f64() * rps;
+        state.tokens = (state.tokens + refilled).min(rps);
+        state.last_refill = now;
+        if state.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - state.tokens) / rps);
+            tokio::time::sleep(wait).await;
+            state.tokens = 1.0;
+            state.last_refill = now + wait;
+        }
+        state.tokens -= 1.0;
+    }
+}
@@ -2,3 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod checkpoint_handler;
+pub mod checkpoint_sink;
+pub mod dynamic_config;
+pub mod event_sink;
+pub mod fullnode_client;
+pub mod pause;
+pub mod shutdown;
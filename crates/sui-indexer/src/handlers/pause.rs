@@ -0,0 +1,52 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Shared switch pausing the download and commit tasks of the checkpoint handler, e.g.
+/// during a DB maintenance window. Tasks stop at the boundaries of their loops, where
+/// nothing downloaded or indexed is lost, and idle until indexing is resumed.
+#[derive(Clone)]
+pub struct IndexingPause {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for IndexingPause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexingPause {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.sender.send_replace(false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Returns once indexing is not paused, right away if it isn't.
+    pub async fn wait_until_resumed(&self) {
+        let mut receiver = self.sender.subscribe();
+        while *receiver.borrow_and_update() {
+            // NOTE: the sender lives as long as self, changed() can't fail.
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Shared switch shutting down the tasks of the checkpoint handler gracefully. The download
+/// task stops right away, whatever it has not handed over yet is downloaded again from the
+/// committed watermark on the next start. The commit tasks first finish the commit of what
+/// they received, so that no checkpoint they took off the channel is dropped. A checkpoint
+/// commit that keeps failing stops at its next retry instead, and the checkpoint is
+/// downloaded again on the next start as well.
+#[derive(Clone)]
+pub struct IndexingShutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for IndexingShutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexingShutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Returns once shut down, right away if it is already.
+    pub async fn wait_for_shutdown(&self) {
+        let mut receiver = self.sender.subscribe();
+        while !*receiver.borrow_and_update() {
+            // NOTE: the sender lives as long as self, changed() can't fail.
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
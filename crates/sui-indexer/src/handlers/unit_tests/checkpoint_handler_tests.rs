@@ -0,0 +1,2704 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use fastcrypto::traits::ToFromBytes;
+use move_core_types::identifier::Identifier;
+use prometheus::Registry;
+use proptest::collection;
+use proptest::prelude::*;
+use proptest::test_runner::TestRunner;
+
+use shared_crypto::intent::{Intent, IntentMessage};
+use sui_core::event_handler::EventHandler;
+use sui_json_rpc_types::{
+    Checkpoint as RpcCheckpoint, MoveFunctionName, SuiGetPastObjectRequest, SuiObjectDataOptions,
+    SuiObjectRef, SuiPastObjectResponse, SuiRawData, SuiTransactionBlockEffects,
+    SuiTransactionBlockEffectsAPI, SuiTransactionBlockEvents,
+};
+use sui_types::base_types::{
+    ExecutionDigests, ObjectDigest, ObjectID, ObjectType, SequenceNumber, SuiAddress,
+};
+use sui_types::committee::Committee;
+use sui_types::crypto::{get_key_pair, AccountKeyPair, AuthorityKeyPair, KeypairTraits, Signature};
+use sui_types::digests::TransactionDigest;
+use sui_types::event::EventID;
+use sui_types::gas::GasCostSummary;
+use sui_types::messages::{
+    Argument, Command, SenderSignedData, TransactionEffects, TransactionEffectsV1,
+};
+use sui_types::messages_checkpoint::{
+    CertifiedCheckpointSummary, CheckpointContents, CheckpointSequenceNumber, CheckpointSummary,
+    ECMHLiveObjectSetDigest, SignedCheckpointSummary,
+};
+use sui_types::object::Owner;
+use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_types::sui_system_state::{get_sui_system_state, SuiSystemStateTrait};
+use sui_types::{parse_sui_type_tag, SUI_FRAMEWORK_OBJECT_ID, SUI_SYSTEM_STATE_OBJECT_ID};
+
+use crate::errors::{FullNodeReadErrorKind, IndexerError};
+use crate::handlers::checkpoint_handler::{
+    fetch_changed_objects, get_deleted_db_objects, get_object_changes, index_checkpoint_data,
+    index_events, is_committee_transition_continuous, is_package_change, should_warn_commit_retry,
+    verify_checkpoint_signature, ChangedObjectOptions, CheckpointHandler,
+};
+use crate::handlers::event_sink::EventSink;
+use crate::handlers::fullnode_client::FullNodeClient;
+use crate::handlers::pause::IndexingPause;
+use crate::handlers::shutdown::IndexingShutdown;
+use crate::metrics::IndexerCheckpointHandlerMetrics;
+use crate::models::addresses::AddressActivity;
+use crate::models::checkpoints::Checkpoint;
+use crate::models::consensus_commits::ConsensusCommit;
+use crate::models::genesis_allocations::GenesisAllocation;
+use crate::models::objects::{Object, ObjectStatus};
+use crate::models::owners::OwnerType;
+use crate::models::packages::Package;
+use crate::models::transactions::DBTransactionKind;
+use crate::store::{
+    CheckpointData, IndexerStore, MemoryIndexerStore, TemporaryCheckpointStore,
+    TransactionObjectChanges,
+};
+use crate::test_utils::TestCheckpointDataBuilder;
+use crate::types::CheckpointTransactionBlockResponse;
+use crate::{IndexerConfig, MalformedEventPolicy};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum FullNodeApi {
+    GetCheckpoint,
+    MultiGetFullTransactions,
+    TryMultiGetPastObjects,
+}
+
+/// A fullnode serving checkpoints, transactions and objects out of a fixed set of
+/// `CheckpointData`, with the ability to fail chosen calls.
+#[derive(Clone)]
+pub(crate) struct MockFullNode {
+    checkpoints: Arc<Vec<CheckpointData>>,
+    inner: Arc<Mutex<MockFullNodeInner>>,
+}
+
+#[derive(Default)]
+struct MockFullNodeInner {
+    call_counts: BTreeMap<FullNodeApi, usize>,
+    failing_calls: BTreeSet<(FullNodeApi, usize)>,
+    requested_checkpoints: Vec<CheckpointSequenceNumber>,
+    in_flight: BTreeMap<FullNodeApi, usize>,
+    max_in_flight: BTreeMap<FullNodeApi, usize>,
+    call_delay: Option<Duration>,
+    past_object_options: BTreeMap<ObjectID, SuiObjectDataOptions>,
+    // objects answered with VersionNotFound the next time they are requested
+    failing_objects: BTreeSet<ObjectID>,
+    past_object_requests: BTreeMap<ObjectID, usize>,
+    // transactions left out of the next response they are requested in
+    dropped_transactions: BTreeSet<TransactionDigest>,
+}
+
+impl MockFullNode {
+    pub(crate) fn new(checkpoints: Vec<CheckpointData>) -> Self {
+        Self {
+            checkpoints: Arc::new(checkpoints),
+            inner: Arc::new(Mutex::new(MockFullNodeInner::default())),
+        }
+    }
+
+    /// Makes the `n`th call (starting from 1) to `api` fail with a transient error,
+    /// calls after it are served normally again.
+    pub(crate) fn fail_nth_call(&self, api: FullNodeApi, n: usize) {
+        self.inner.lock().unwrap().failing_calls.insert((api, n));
+    }
+
+    pub(crate) fn call_count(&self, api: FullNodeApi) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .call_counts
+            .get(&api)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sequence numbers of all `get_checkpoint` calls, in the order they were made.
+    pub(crate) fn requested_checkpoints(&self) -> Vec<CheckpointSequenceNumber> {
+        self.inner.lock().unwrap().requested_checkpoints.clone()
+    }
+
+    /// Makes the multi-get calls take `delay` to respond, so that concurrent calls overlap.
+    pub(crate) fn set_call_delay(&self, delay: Duration) {
+        self.inner.lock().unwrap().call_delay = Some(delay);
+    }
+
+    /// Most calls to `api` that were in flight at the same time.
+    pub(crate) fn max_in_flight(&self, api: FullNodeApi) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .max_in_flight
+            .get(&api)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Makes the next request of `object_id` answer that its version is not found, the
+    /// requests after it are served normally again.
+    pub(crate) fn fail_object_once(&self, object_id: ObjectID) {
+        self.inner.lock().unwrap().failing_objects.insert(object_id);
+    }
+
+    /// Leaves `digest` out of the next `multi_get_full_transactions` response requesting
+    /// it, the requests after it are served normally again.
+    pub(crate) fn drop_transaction_once(&self, digest: TransactionDigest) {
+        self.inner
+            .lock()
+            .unwrap()
+            .dropped_transactions
+            .insert(digest);
+    }
+
+    /// Number of `try_multi_get_past_objects` calls which requested `object_id`.
+    pub(crate) fn past_object_requests(&self, object_id: &ObjectID) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .past_object_requests
+            .get(object_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Options of the last `try_multi_get_past_objects` call which requested `object_id`.
+    pub(crate) fn past_object_options(&self, object_id: &ObjectID) -> Option<SuiObjectDataOptions> {
+        self.inner
+            .lock()
+            .unwrap()
+            .past_object_options
+            .get(object_id)
+            .cloned()
+    }
+
+    async fn wait_call_delay(&self, api: FullNodeApi) {
+        let delay = {
+            let mut inner = self.inner.lock().unwrap();
+            let in_flight = inner.in_flight.entry(api).or_default();
+            *in_flight += 1;
+            let in_flight = *in_flight;
+            let max_in_flight = inner.max_in_flight.entry(api).or_default();
+            *max_in_flight = (*max_in_flight).max(in_flight);
+            inner.call_delay
+        };
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        *self.inner.lock().unwrap().in_flight.entry(api).or_default() -= 1;
+    }
+
+    fn record_call(&self, api: FullNodeApi) -> Result<(), IndexerError> {
+        let mut inner = self.inner.lock().unwrap();
+        let count = inner.call_counts.entry(api).or_default();
+        *count += 1;
+        let count = *count;
+        if inner.failing_calls.contains(&(api, count)) {
+            return Err(IndexerError::FullNodeReadingError(format!(
+                "Injected failure on call {count} to {api:?}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FullNodeClient for MockFullNode {
+    async fn get_checkpoint(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<RpcCheckpoint, IndexerError> {
+        self.inner.lock().unwrap().requested_checkpoints.push(seq);
+        self.record_call(FullNodeApi::GetCheckpoint)?;
+        self.checkpoints
+            .iter()
+            .find(|data| data.checkpoint.sequence_number == seq)
+            .map(|data| data.checkpoint.clone())
+            .ok_or_else(|| {
+                IndexerError::FullNodeReadingError(format!("Checkpoint {seq} is not available yet"))
+            })
+    }
+
+    async fn get_certified_checkpoint_summary(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<CertifiedCheckpointSummary, IndexerError> {
+        Err(IndexerError::FullNodeReadingError(format!(
+            "Certified summary of checkpoint {seq} is not served"
+        )))
+    }
+
+    async fn multi_get_full_transactions(
+        &self,
+        digests: Vec<TransactionDigest>,
+    ) -> Result<Vec<CheckpointTransactionBlockResponse>, IndexerError> {
+        self.record_call(FullNodeApi::MultiGetFullTransactions)?;
+        self.wait_call_delay(FullNodeApi::MultiGetFullTransactions)
+            .await;
+        let mut inner = self.inner.lock().unwrap();
+        digests
+            .iter()
+            .filter(|digest| !inner.dropped_transactions.remove(digest))
+            .map(|digest| {
+                self.checkpoints
+                    .iter()
+                    .flat_map(|data| data.transactions.iter())
+                    .find(|tx| &tx.digest == digest)
+                    .cloned()
+                    .ok_or_else(|| {
+                        IndexerError::FullNodeReadingError(format!(
+                            "Transaction {digest} not found"
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<SuiGetPastObjectRequest>,
+        options: Option<SuiObjectDataOptions>,
+    ) -> Result<Vec<SuiPastObjectResponse>, IndexerError> {
+        self.record_call(FullNodeApi::TryMultiGetPastObjects)?;
+        self.wait_call_delay(FullNodeApi::TryMultiGetPastObjects)
+            .await;
+        let options = options.unwrap_or_default();
+        let mut failing = BTreeSet::new();
+        {
+            let mut inner = self.inner.lock().unwrap();
+            for request in &past_objects {
+                inner
+                    .past_object_options
+                    .insert(request.object_id, options.clone());
+                *inner
+                    .past_object_requests
+                    .entry(request.object_id)
+                    .or_default() += 1;
+                if inner.failing_objects.remove(&request.object_id) {
+                    failing.insert(request.object_id);
+                }
+            }
+        }
+        Ok(past_objects
+            .into_iter()
+            .map(|request| {
+                if failing.contains(&request.object_id) {
+                    return SuiPastObjectResponse::VersionNotFound(
+                        request.object_id,
+                        request.version,
+                    );
+                }
+                self.checkpoints
+                    .iter()
+                    .flat_map(|data| data.changed_objects.iter())
+                    .find(|(_, o)| o.object_id == request.object_id && o.version == request.version)
+                    .map(|(_, o)| {
+                        let mut o = o.clone();
+                        if !options.show_bcs {
+                            o.bcs = None;
+                        }
+                        SuiPastObjectResponse::VersionFound(o)
+                    })
+                    .unwrap_or(SuiPastObjectResponse::VersionNotFound(
+                        request.object_id,
+                        request.version,
+                    ))
+            })
+            .collect())
+    }
+}
+
+/// An `EventSink` recording the ids of the events it receives per transaction.
+#[derive(Default)]
+struct RecordingEventSink {
+    received: Mutex<Vec<(TransactionDigest, Vec<EventID>)>>,
+}
+
+#[async_trait]
+impl EventSink for RecordingEventSink {
+    async fn process_events(
+        &self,
+        effects: &SuiTransactionBlockEffects,
+        events: &SuiTransactionBlockEvents,
+    ) -> Result<(), IndexerError> {
+        self.received.lock().unwrap().push((
+            *effects.transaction_digest(),
+            events.data.iter().map(|event| event.id.clone()).collect(),
+        ));
+        Ok(())
+    }
+}
+
+pub(crate) fn new_test_handler<S>(
+    store: S,
+    fullnode: MockFullNode,
+) -> CheckpointHandler<S, MockFullNode>
+where
+    S: IndexerStore + Clone + Sync + Send + 'static,
+{
+    CheckpointHandler::new(
+        store,
+        fullnode,
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &IndexerConfig::default(),
+    )
+}
+
+pub(crate) async fn wait_for_watermark<S: IndexerStore>(store: &S, watermark: i64) {
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.get_latest_checkpoint_sequence_number().await.unwrap() < watermark {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("Timed out waiting for watermark {watermark}"));
+}
+
+async fn wait_for_first_request(fullnode: &MockFullNode) -> CheckpointSequenceNumber {
+    tokio::time::timeout(Duration::from_secs(60), async {
+        loop {
+            if let Some(seq) = fullnode.requested_checkpoints().first() {
+                return *seq;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for the first checkpoint request")
+}
+
+/// A checkpoint with no transactions, enough to move the watermark of a store.
+fn empty_checkpoint_store(sequence_number: i64) -> TemporaryCheckpointStore {
+    TemporaryCheckpointStore {
+        checkpoint: Checkpoint {
+            sequence_number,
+            ..Default::default()
+        },
+        transactions: vec![],
+        events: vec![],
+        objects_changes: vec![],
+        addresses: vec![],
+        address_activities: vec![],
+        packages: vec![],
+        input_objects: vec![],
+        move_calls: vec![],
+        recipients: vec![],
+        ptb_commands: vec![],
+        dependencies: vec![],
+        consensus_commit: None,
+    }
+}
+
+#[tokio::test]
+async fn test_download_index_and_commit_checkpoints() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let fullnode = MockFullNode::new(checkpoints);
+    // A transient error on the second checkpoint download should be retried.
+    fullnode.fail_nth_call(FullNodeApi::GetCheckpoint, 2);
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), fullnode.clone()).spawn();
+    wait_for_watermark(&store, 2).await;
+
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        2
+    );
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 2]);
+    assert_eq!(&fullnode.requested_checkpoints()[..4], &[0, 1, 1, 2]);
+    assert_eq!(
+        fullnode.call_count(FullNodeApi::MultiGetFullTransactions),
+        3
+    );
+    assert_eq!(fullnode.call_count(FullNodeApi::TryMultiGetPastObjects), 3);
+    // The very first epoch is persisted by the download task directly.
+    let epochs = store.epochs();
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0].epoch, 0);
+}
+
+#[tokio::test]
+async fn test_index_epoch_boundary() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let genesis_system_state = builder
+        .genesis()
+        .sui_system_object()
+        .into_sui_system_state_summary();
+    let committee = builder.genesis().committee().unwrap();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(2),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    let end_of_epoch = checkpoints[2].checkpoint.clone();
+    let end_of_epoch_data = end_of_epoch.end_of_epoch_data.clone().unwrap();
+    let epoch_gas_cost = end_of_epoch.epoch_rolling_gas_cost_summary.clone();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 2).await;
+    // The new epoch is committed by the epoch commit task, separately from the checkpoint.
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.epochs().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for epoch 1");
+
+    let epochs = store.epochs();
+    assert_eq!(epochs.len(), 2);
+    let (closed_epoch, new_epoch) = (&epochs[0], &epochs[1]);
+
+    assert_eq!(closed_epoch.epoch, 0);
+    assert_eq!(closed_epoch.first_checkpoint_id, 0);
+    assert_eq!(closed_epoch.last_checkpoint_id, Some(2));
+    assert_eq!(
+        closed_epoch.epoch_start_timestamp,
+        genesis_system_state.epoch_start_timestamp_ms as i64
+    );
+    assert_eq!(
+        closed_epoch.epoch_end_timestamp,
+        Some(end_of_epoch.timestamp_ms as i64)
+    );
+    // Genesis, 2 transfers and the change epoch transaction. The very first epoch is
+    // persisted by the download task before the checkpoint commit task gets to run,
+    // so the count of the genesis checkpoint is included.
+    assert_eq!(closed_epoch.epoch_total_transactions, 4);
+    assert_eq!(
+        closed_epoch.next_epoch_version,
+        Some(end_of_epoch_data.next_epoch_protocol_version.as_u64() as i64)
+    );
+    assert_eq!(
+        closed_epoch.next_epoch_committee,
+        committee
+            .voting_rights
+            .iter()
+            .map(|(name, _)| Some(name.as_bytes().to_vec()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        closed_epoch.next_epoch_committee_stake,
+        committee
+            .voting_rights
+            .iter()
+            .map(|(_, stake)| Some(*stake as i64))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        closed_epoch.epoch_commitments,
+        vec![Some(
+            ECMHLiveObjectSetDigest::default().digest.digest.to_vec()
+        )]
+    );
+    // Fields from the SystemEpochInfoEvent
+    assert_eq!(
+        closed_epoch.protocol_version,
+        Some(genesis_system_state.protocol_version as i64)
+    );
+    assert_eq!(
+        closed_epoch.reference_gas_price,
+        Some(genesis_system_state.reference_gas_price as i64)
+    );
+    assert_eq!(
+        closed_epoch.total_stake,
+        Some(genesis_system_state.total_stake as i64)
+    );
+    assert_eq!(
+        closed_epoch.storage_charge,
+        Some(epoch_gas_cost.storage_cost as i64)
+    );
+    assert_eq!(
+        closed_epoch.storage_rebate,
+        Some(epoch_gas_cost.storage_rebate as i64)
+    );
+    assert_eq!(
+        closed_epoch.total_gas_fees,
+        Some(epoch_gas_cost.computation_cost as i64)
+    );
+    assert_eq!(
+        closed_epoch.total_stake_rewards_distributed,
+        Some(epoch_gas_cost.computation_cost as i64)
+    );
+    assert_eq!(closed_epoch.storage_fund_reinvestment, Some(0));
+    assert_eq!(closed_epoch.stake_subsidy_amount, Some(0));
+    assert_eq!(closed_epoch.leftover_storage_fund_inflow, Some(0));
+
+    assert_eq!(new_epoch.epoch, 1);
+    assert_eq!(new_epoch.first_checkpoint_id, 3);
+    assert_eq!(
+        new_epoch.epoch_start_timestamp,
+        end_of_epoch.timestamp_ms as i64
+    );
+    assert_eq!(new_epoch.last_checkpoint_id, None);
+    assert_eq!(new_epoch.epoch_end_timestamp, None);
+    assert_eq!(new_epoch.epoch_total_transactions, 0);
+    assert!(new_epoch.epoch_commitments.is_empty());
+
+    let system_states = store.system_states();
+    assert_eq!(
+        system_states.iter().map(|s| s.epoch).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    let validators = store.validators();
+    for epoch in [0, 1] {
+        let mut epoch_validators = validators
+            .iter()
+            .filter(|v| v.epoch == epoch)
+            .map(|v| v.sui_address.clone())
+            .collect::<Vec<_>>();
+        epoch_validators.sort();
+        let mut expected = genesis_system_state
+            .active_validators
+            .iter()
+            .map(|v| v.sui_address.to_string())
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(epoch_validators, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_index_transaction_kind() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(2),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    let change_epoch_digest = checkpoints[2].transactions[0].digest.base58_encode();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 2).await;
+
+    let change_epoch = store
+        .get_transactions_by_kind(DBTransactionKind::ChangeEpoch, None, 10)
+        .await
+        .unwrap();
+    assert_eq!(change_epoch.len(), 1);
+    assert_eq!(change_epoch[0].transaction_digest, change_epoch_digest);
+    assert_eq!(change_epoch[0].transaction_kind, "ChangeEpoch");
+    assert!(change_epoch[0].kind.is_system());
+    let genesis = store
+        .get_transactions_by_kind(DBTransactionKind::Genesis, None, 10)
+        .await
+        .unwrap();
+    assert_eq!(genesis.len(), 1);
+
+    // The two transfers, paged one at a time.
+    let first_page = store
+        .get_transactions_by_kind(DBTransactionKind::ProgrammableTransaction, None, 1)
+        .await
+        .unwrap();
+    assert_eq!(first_page.len(), 1);
+    let second_page = store
+        .get_transactions_by_kind(
+            DBTransactionKind::ProgrammableTransaction,
+            first_page[0].id,
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(second_page.len(), 1);
+    assert!(second_page[0].id > first_page[0].id);
+
+    // The zero address sender of the change epoch transaction is not an account, and
+    // the transaction only touches shared system objects.
+    assert!(store
+        .addresses()
+        .iter()
+        .all(|address| address.first_appearance_tx != change_epoch_digest));
+}
+
+#[tokio::test]
+async fn test_index_consensus_commit() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let genesis = builder.genesis_checkpoint();
+    let commit_timestamp_ms = genesis.checkpoint.timestamp_ms + 10;
+    let checkpoints = vec![
+        genesis,
+        builder.next_checkpoint_with_consensus_commit(7, commit_timestamp_ms, 1),
+        builder.next_checkpoint(1),
+    ];
+    let checkpoint = checkpoints[1].checkpoint.clone();
+    let prologue_digest = checkpoints[1].transactions[0].digest.base58_encode();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 2).await;
+
+    // Only the checkpoint starting with a consensus commit prologue has a row.
+    let consensus_commits = store.get_consensus_commits(None, 10).await.unwrap();
+    assert_eq!(
+        consensus_commits,
+        vec![ConsensusCommit {
+            checkpoint_sequence_number: 1,
+            epoch: 0,
+            round: 7,
+            commit_timestamp_ms: commit_timestamp_ms as i64,
+            checkpoint_timestamp_ms: checkpoint.timestamp_ms as i64,
+            transaction_digest: prologue_digest.clone(),
+        }]
+    );
+    assert!(store
+        .get_consensus_commits(Some(1), 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let prologues = store
+        .get_transactions_by_kind(DBTransactionKind::ConsensusCommitPrologue, None, 10)
+        .await
+        .unwrap();
+    assert_eq!(prologues.len(), 1);
+    assert_eq!(prologues[0].transaction_digest, prologue_digest);
+}
+
+#[tokio::test]
+async fn test_index_move_call_type_arguments() {
+    let sui = parse_sui_type_tag("0x2::sui::SUI").unwrap();
+    let mut ptb = ProgrammableTransactionBuilder::new();
+    ptb.programmable_move_call(
+        SUI_FRAMEWORK_OBJECT_ID,
+        Identifier::new("coin").unwrap(),
+        Identifier::new("value").unwrap(),
+        vec![sui.clone()],
+        vec![Argument::GasCoin],
+    );
+    ptb.programmable_move_call(
+        SUI_FRAMEWORK_OBJECT_ID,
+        Identifier::new("coin").unwrap(),
+        Identifier::new("zero").unwrap(),
+        vec![sui.clone()],
+        vec![],
+    );
+    ptb.programmable_move_call(
+        SUI_FRAMEWORK_OBJECT_ID,
+        Identifier::new("tx_context").unwrap(),
+        Identifier::new("epoch").unwrap(),
+        vec![],
+        vec![],
+    );
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint_with_programmable_transaction(ptb.finish()),
+    ];
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 1).await;
+
+    let type_arguments = store
+        .move_calls()
+        .into_iter()
+        .map(|call| (call.move_function, call.move_type_arguments))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        type_arguments,
+        vec![
+            ("value".to_string(), vec![Some(sui.to_string())]),
+            ("zero".to_string(), vec![Some(sui.to_string())]),
+            ("epoch".to_string(), vec![]),
+        ]
+    );
+
+    // The short and the fully expanded forms of a type are the same type argument.
+    for type_argument in ["0x2::sui::SUI".to_string(), sui.to_string()] {
+        let calls = store
+            .get_move_calls_by_type_argument(type_argument, None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(calls.len(), 2);
+    }
+    let first_page = store
+        .get_move_calls_by_type_argument("0x2::sui::SUI".to_string(), None, None, 1)
+        .await
+        .unwrap();
+    assert_eq!(first_page[0].move_function, "value");
+    let second_page = store
+        .get_move_calls_by_type_argument("0x2::sui::SUI".to_string(), None, first_page[0].id, 1)
+        .await
+        .unwrap();
+    assert_eq!(second_page[0].move_function, "zero");
+
+    let coin_zero = MoveFunctionName {
+        package: SUI_FRAMEWORK_OBJECT_ID,
+        module: Identifier::new("coin").unwrap(),
+        function: Identifier::new("zero").unwrap(),
+    };
+    let calls = store
+        .get_move_calls_by_type_argument("0x2::sui::SUI".to_string(), Some(coin_zero), None, 10)
+        .await
+        .unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].move_function, "zero");
+    assert!(store
+        .get_move_calls_by_type_argument(
+            "0x2::coin::Coin<0x2::sui::SUI>".to_string(),
+            None,
+            None,
+            10
+        )
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_index_transaction_dependencies() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    // The second transfer uses the gas coin mutated by the first one.
+    let checkpoints = vec![builder.genesis_checkpoint(), builder.next_checkpoint(2)];
+    let first = checkpoints[1].transactions[0].digest.to_string();
+    let second = checkpoints[1].transactions[1].digest.to_string();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 1).await;
+
+    assert_eq!(
+        store.get_dependencies(&second).await.unwrap(),
+        vec![first.clone()]
+    );
+    assert_eq!(
+        store.get_dependents(&first).await.unwrap(),
+        vec![second.clone()]
+    );
+    assert!(store.get_dependents(&second).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_index_address_activity() {
+    let builder = TestCheckpointDataBuilder::new();
+    let sender = builder.sender().to_string();
+    let checkpoints = builder.build_checkpoints(3);
+    let (first, last) = (
+        checkpoints[1].checkpoint.clone(),
+        checkpoints[2].checkpoint.clone(),
+    );
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 2).await;
+
+    // The sender sends a transfer in both checkpoints 1 and 2.
+    assert_eq!(
+        store.get_address_activity(&sender).await.unwrap(),
+        AddressActivity {
+            account_address: sender.clone(),
+            first_appearance_checkpoint: 1,
+            first_appearance_timestamp: first.timestamp_ms as i64,
+            last_appearance_checkpoint: 2,
+            last_appearance_timestamp: last.timestamp_ms as i64,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_index_transaction_signatures() {
+    let mut checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let single_signed = checkpoints[1].transactions[0].clone();
+    // Have the second transfer also signed by a sponsor.
+    let sponsored = &mut checkpoints[2].transactions[0];
+    let data: SenderSignedData = bcs::from_bytes(&sponsored.raw_transaction).unwrap();
+    let (_, sponsor_key): (_, AccountKeyPair) = get_key_pair();
+    let sponsor_signature = Signature::new_secure(
+        &IntentMessage::new(Intent::sui_transaction(), data.transaction_data().clone()),
+        &sponsor_key,
+    );
+    sponsored
+        .transaction
+        .tx_signatures
+        .push(sponsor_signature.into());
+    let sponsored = sponsored.clone();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 2).await;
+
+    for tx in [single_signed, sponsored] {
+        let signatures = store
+            .get_transaction_signatures(&tx.digest.base58_encode())
+            .await
+            .unwrap();
+        assert_eq!(signatures.len(), tx.transaction.tx_signatures.len());
+        for (stored, expected) in signatures.iter().zip(&tx.transaction.tx_signatures) {
+            assert_eq!(stored.as_ref(), expected.as_ref());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_index_ptb_commands() {
+    let mut ptb = ProgrammableTransactionBuilder::new();
+    let amount = ptb.pure(1_000u64).unwrap();
+    let coin = ptb.command(Command::SplitCoins(Argument::GasCoin, vec![amount]));
+    ptb.transfer_arg(SuiAddress::random_for_testing_only(), coin);
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint_with_programmable_transaction(ptb.finish()),
+    ];
+    let digest = checkpoints[1].transactions[0].digest.to_string();
+    let store = MemoryIndexerStore::new();
+
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 1).await;
+
+    let commands = store.get_ptb_commands(&digest).await.unwrap();
+    assert_eq!(
+        commands
+            .iter()
+            .map(|c| (
+                c.command_index,
+                c.command_kind.as_str(),
+                c.package_id.clone()
+            ))
+            .collect::<Vec<_>>(),
+        vec![(0, "SplitCoins", None), (1, "TransferObjects", None)]
+    );
+    assert!(commands
+        .iter()
+        .all(|c| c.checkpoint_sequence_number == 1 && c.epoch == 0));
+}
+
+#[tokio::test]
+async fn test_index_checkpoint_without_transactions() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(0),
+        builder.next_checkpoint(1),
+    ];
+    let store = MemoryIndexerStore::new();
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 2).await;
+
+    let committed = store.checkpoints();
+    assert_eq!(
+        committed
+            .iter()
+            .map(|cp| cp.sequence_number)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    let empty = &committed[1];
+    assert!(empty.transactions.is_empty());
+    assert_eq!(empty.total_transaction_blocks, 0);
+    assert_eq!(empty.total_transactions, 0);
+    assert_eq!(
+        empty.network_total_transactions,
+        committed[0].network_total_transactions
+    );
+    assert!(store
+        .transactions()
+        .iter()
+        .all(|tx| tx.checkpoint_sequence_number != Some(1)));
+    assert_eq!(metrics.total_checkpoint_committed.get(), 3);
+    assert_eq!(metrics.total_transaction_committed.get(), 2);
+    assert_eq!(metrics.transaction_per_checkpoint.get_sample_count(), 3);
+    // No new epoch is detected from an empty checkpoint.
+    assert_eq!(store.epochs().len(), 1);
+}
+
+#[tokio::test]
+async fn test_quarantine_checkpoint_failing_to_index() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(1),
+        builder.end_of_epoch_checkpoint().unwrap(),
+        builder.next_checkpoint(1),
+    ];
+    // An undecodable SystemEpochInfoEvent makes indexing checkpoint 2 fail every time.
+    for event in &mut checkpoints[2].transactions[0].events.data {
+        event.bcs.clear();
+    }
+    let digest = checkpoints[2].checkpoint.digest.base58_encode();
+    let fullnode = MockFullNode::new(checkpoints);
+    let store = MemoryIndexerStore::new();
+    let config = IndexerConfig {
+        max_checkpoint_index_attempts: 3,
+        ..Default::default()
+    };
+
+    let handler = CheckpointHandler::new(
+        store.clone(),
+        fullnode.clone(),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 3).await;
+
+    let quarantined = store.get_quarantined_checkpoints().await.unwrap();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].sequence_number, 2);
+    assert_eq!(quarantined[0].epoch, 0);
+    assert_eq!(quarantined[0].checkpoint_digest, digest);
+    assert_eq!(quarantined[0].attempts, 3);
+    assert_eq!(
+        fullnode
+            .requested_checkpoints()
+            .iter()
+            .filter(|seq| **seq == 2)
+            .count(),
+        3
+    );
+    assert_eq!(metrics.total_checkpoint_quarantined.get(), 1);
+    // The pipeline advanced past the quarantined checkpoint.
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 3]);
+}
+
+#[tokio::test]
+async fn test_skip_truncated_epoch_event() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(1),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    for event in &mut checkpoints[2].transactions[0].events.data {
+        event.bcs.truncate(event.bcs.len() / 2);
+    }
+    let store = MemoryIndexerStore::new();
+    let config = IndexerConfig {
+        malformed_event_policy: MalformedEventPolicy::Skip,
+        ..Default::default()
+    };
+
+    let handler = CheckpointHandler::new(
+        store.clone(),
+        MockFullNode::new(checkpoints),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 2).await;
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.epochs().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for epoch 1");
+
+    assert_eq!(metrics.event_deserialize_errors_total.get(), 1);
+    assert!(store
+        .get_quarantined_checkpoints()
+        .await
+        .unwrap()
+        .is_empty());
+    // The epoch is closed without the figures only the event carries.
+    let closed_epoch = &store.epochs()[0];
+    assert_eq!(closed_epoch.last_checkpoint_id, Some(2));
+    assert_eq!(closed_epoch.total_gas_fees, None);
+    assert!(closed_epoch.protocol_version.is_some());
+}
+
+#[test]
+fn test_capture_epoch_event_in_single_events_pass() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    builder.genesis_checkpoint();
+    builder.next_checkpoint(1);
+    let data = builder.end_of_epoch_checkpoint().unwrap();
+    let all_events = data
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.events.data.iter());
+    let total = all_events.clone().count();
+
+    let visited = Cell::new(0);
+    let (events, epoch_event) = index_events(
+        all_events.inspect(|_| visited.set(visited.get() + 1)),
+        data.checkpoint.sequence_number,
+    );
+    assert_eq!(visited.get(), total);
+    assert_eq!(events.len(), total);
+    assert_eq!(
+        epoch_event.unwrap().type_.name.as_str(),
+        "SystemEpochInfoEvent"
+    );
+    // The epoch is closed with the figures of the captured event.
+    let (_, epoch) = index_checkpoint_data(&data).unwrap();
+    assert!(epoch.unwrap().last_epoch.unwrap().total_gas_fees.is_some());
+}
+
+#[tokio::test]
+async fn test_derive_epoch_economics_without_epoch_event() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(1),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    for tx in &mut checkpoints[2].transactions {
+        tx.events
+            .data
+            .retain(|ev| ev.type_.name.as_str() != "SystemEpochInfoEvent");
+    }
+    let system_state = get_sui_system_state(&checkpoints[2])
+        .unwrap()
+        .into_sui_system_state_summary();
+
+    let handler = new_test_handler(MemoryIndexerStore::new(), MockFullNode::new(vec![]));
+    let (_, indexed_epoch) = handler.index_checkpoint(&checkpoints[2]).unwrap();
+    let closed_epoch = indexed_epoch.unwrap().last_epoch.unwrap();
+
+    // The fields the system state holds as well are taken from it.
+    assert_eq!(
+        closed_epoch.protocol_version,
+        Some(system_state.protocol_version as i64)
+    );
+    assert_eq!(
+        closed_epoch.reference_gas_price,
+        Some(system_state.reference_gas_price as i64)
+    );
+    assert_eq!(
+        closed_epoch.total_stake,
+        Some(system_state.total_stake as i64)
+    );
+    assert_eq!(
+        closed_epoch.storage_fund_balance,
+        Some(
+            (system_state.storage_fund_total_object_storage_rebates
+                + system_state.storage_fund_non_refundable_balance) as i64
+        )
+    );
+    // Only the event carries these.
+    assert_eq!(closed_epoch.stake_subsidy_amount, None);
+    assert_eq!(closed_epoch.total_gas_fees, None);
+    assert_eq!(closed_epoch.storage_charge, None);
+}
+
+#[tokio::test]
+async fn test_tolerate_genesis_without_system_state() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut checkpoints = vec![builder.genesis_checkpoint(), builder.next_checkpoint(1)];
+    checkpoints[0]
+        .changed_objects
+        .retain(|(_, o)| o.object_id != SUI_SYSTEM_STATE_OBJECT_ID);
+
+    let err = index_checkpoint_data(&checkpoints[0]).unwrap_err();
+    assert!(err.to_string().contains("SuiSystemStateWrapper"), "{err}");
+
+    let store = MemoryIndexerStore::new();
+    let config = IndexerConfig {
+        tolerate_nonstandard_genesis: true,
+        ..Default::default()
+    };
+    let handler = CheckpointHandler::new(
+        store.clone(),
+        MockFullNode::new(checkpoints.clone()),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+    handler.spawn();
+    wait_for_watermark(&store, 1).await;
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.epochs().is_empty() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for epoch 0");
+
+    // Epoch 0 is indexed without what the system state holds.
+    let epochs = store.epochs();
+    assert_eq!(epochs.len(), 1);
+    assert_eq!(epochs[0].epoch, 0);
+    assert_eq!(
+        epochs[0].epoch_start_timestamp,
+        checkpoints[0].checkpoint.timestamp_ms as i64
+    );
+    assert!(store.system_states().is_empty());
+    assert!(store.validators().is_empty());
+    assert!(store
+        .get_quarantined_checkpoints()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_reject_package_without_bcs() {
+    let mut data = TestCheckpointDataBuilder::new().genesis_checkpoint();
+    let (_, package) = data
+        .changed_objects
+        .iter_mut()
+        .find(|(_, o)| o.object_id == SUI_FRAMEWORK_OBJECT_ID)
+        .unwrap();
+    package.bcs = None;
+
+    let err = index_checkpoint_data(&data).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            IndexerError::MissingPackageBcs { object_id } if object_id == SUI_FRAMEWORK_OBJECT_ID
+        ),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_send_events_to_event_sink() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(2),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    let expected = checkpoints
+        .iter()
+        .flat_map(|data| data.transactions.iter())
+        .map(|tx| {
+            (
+                tx.digest,
+                tx.events
+                    .data
+                    .iter()
+                    .map(|event| event.id.clone())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    // The SystemEpochInfoEvent of the end of epoch checkpoint.
+    assert!(expected.iter().any(|(_, events)| !events.is_empty()));
+    let sink = Arc::new(RecordingEventSink::default());
+    let store = MemoryIndexerStore::new();
+
+    CheckpointHandler::new(
+        store.clone(),
+        MockFullNode::new(checkpoints),
+        sink.clone(),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &IndexerConfig::default(),
+    )
+    .spawn();
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while sink.received.lock().unwrap().len() < expected.len() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for the events of all transactions");
+
+    assert_eq!(*sink.received.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_rate_limit_fullnode_requests() {
+    const MAX_RPS: u32 = 20;
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(20);
+    let fullnode = MockFullNode::new(checkpoints);
+    let store = MemoryIndexerStore::new();
+    let config = IndexerConfig {
+        max_fullnode_rps: Some(MAX_RPS),
+        ..Default::default()
+    };
+
+    let start = tokio::time::Instant::now();
+    CheckpointHandler::new(
+        store.clone(),
+        fullnode.clone(),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    )
+    .spawn();
+    wait_for_watermark(&store, 19).await;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let requests = [
+        FullNodeApi::GetCheckpoint,
+        FullNodeApi::MultiGetFullTransactions,
+        FullNodeApi::TryMultiGetPastObjects,
+    ]
+    .into_iter()
+    .map(|api| fullnode.call_count(api))
+    .sum::<usize>();
+    // 3 requests per checkpoint, well beyond the initial burst of one second worth.
+    assert!(requests >= 60);
+    let budget = f64::from(MAX_RPS) * (elapsed + 1.0);
+    assert!(
+        requests as f64 <= budget,
+        "{requests} requests in {elapsed:.2}s exceed the budget of {budget:.0}"
+    );
+}
+
+#[tokio::test]
+async fn test_record_object_fetch_batch_size() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let total = checkpoints
+        .iter()
+        .map(|data| data.changed_objects.len())
+        .sum::<usize>();
+    let store = MemoryIndexerStore::new();
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 2).await;
+
+    assert!(total > 0);
+    assert_eq!(metrics.total_objects_fetched.get(), total as u64);
+    assert_eq!(metrics.object_fetch_batch_size.get_sample_count(), 3);
+    assert_eq!(
+        metrics.object_fetch_batch_size.get_sample_sum(),
+        total as f64
+    );
+}
+
+#[tokio::test]
+async fn test_record_deleted_and_wrapped_objects() {
+    let mut checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let oref = || {
+        SuiObjectRef::from((
+            ObjectID::random(),
+            SequenceNumber::new(),
+            ObjectDigest::random(),
+        ))
+    };
+    let SuiTransactionBlockEffects::V1(effects) = &mut checkpoints[1].transactions[0].effects;
+    effects.deleted.extend([oref(), oref()]);
+    effects.wrapped.push(oref());
+    effects.unwrapped_then_deleted.push(oref());
+    let total = |count: fn(&SuiTransactionBlockEffects) -> usize| {
+        checkpoints
+            .iter()
+            .flat_map(|data| data.transactions.iter())
+            .map(|tx| count(&tx.effects))
+            .sum::<usize>() as f64
+    };
+    let deleted = total(|effects| effects.deleted().len());
+    let wrapped = total(|effects| effects.wrapped().len());
+    let unwrapped_then_deleted = total(|effects| effects.unwrapped_then_deleted().len());
+    let store = MemoryIndexerStore::new();
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 2).await;
+
+    assert!(deleted >= 2.0 && wrapped >= 1.0 && unwrapped_then_deleted >= 1.0);
+    for (status, total) in [
+        ("deleted", deleted),
+        ("unwrapped_then_deleted", unwrapped_then_deleted),
+    ] {
+        let histogram = metrics
+            .deleted_objects_per_checkpoint
+            .with_label_values(&[status]);
+        assert_eq!(histogram.get_sample_count(), 3);
+        assert_eq!(histogram.get_sample_sum(), total);
+    }
+    assert_eq!(metrics.wrapped_objects_per_checkpoint.get_sample_count(), 3);
+    assert_eq!(
+        metrics.wrapped_objects_per_checkpoint.get_sample_sum(),
+        wrapped
+    );
+}
+
+#[tokio::test]
+async fn test_count_published_packages() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(2);
+    let published = checkpoints[0]
+        .changed_objects
+        .iter()
+        .filter(|(_, o)| matches!(o.bcs, Some(SuiRawData::Package(_))))
+        .count();
+    let store = MemoryIndexerStore::new();
+    store.fail_next_checkpoint_commits(10);
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.clone().spawn();
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.persist_checkpoint_calls() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for a retried checkpoint commit");
+    // Nothing is counted before the checkpoint is committed.
+    assert_eq!(metrics.packages_published_total.get(), 0);
+    wait_for_watermark(&store, 1).await;
+
+    // Only the genesis checkpoint publishes packages, the framework ones.
+    assert!(published > 0);
+    assert_eq!(metrics.packages_published_total.get(), published as u64);
+    assert_eq!(metrics.packages_upgraded_total.get(), 0);
+
+    // An upgrade is a package at a version after the first one.
+    let upgraded = Package {
+        package_id: SUI_FRAMEWORK_OBJECT_ID.to_string(),
+        version: 2,
+        author: SuiAddress::ZERO.to_string(),
+        data: vec![],
+    };
+    let mut checkpoint = empty_checkpoint_store(2);
+    checkpoint.packages = vec![upgraded];
+    handler
+        .checkpoint_sender
+        .lock()
+        .await
+        .send(checkpoint)
+        .await
+        .unwrap();
+    wait_for_watermark(&store, 2).await;
+    assert_eq!(metrics.packages_published_total.get(), published as u64 + 1);
+    assert_eq!(metrics.packages_upgraded_total.get(), 1);
+}
+
+#[tokio::test]
+async fn test_fetch_changed_objects_with_options_per_kind() {
+    let genesis = TestCheckpointDataBuilder::new().genesis_checkpoint();
+    // The same changes as the download, from the effects and before any object is fetched.
+    let object_changes = genesis
+        .transactions
+        .iter()
+        .flat_map(|tx| get_object_changes(&tx.effects))
+        .collect::<Vec<_>>();
+    let fullnode = MockFullNode::new(vec![genesis.clone()]);
+    let options = ChangedObjectOptions {
+        objects: SuiObjectDataOptions::new().with_type().with_owner(),
+        ..Default::default()
+    };
+
+    let metrics = IndexerCheckpointHandlerMetrics::new(&Registry::default());
+
+    let fetched = fetch_changed_objects(
+        &fullnode,
+        object_changes.clone(),
+        500,
+        2,
+        |owner, status| options.for_change(owner, status),
+        0,
+        &metrics,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(fetched.len(), object_changes.len());
+    let mut packages = 0;
+    for (status, o) in &fetched {
+        if matches!(o.type_, Some(ObjectType::Package)) {
+            packages += 1;
+            assert!(is_package_change(&o.owner.unwrap(), status));
+            assert_eq!(
+                fullnode.past_object_options(&o.object_id),
+                Some(options.packages.clone())
+            );
+            assert!(matches!(o.bcs, Some(SuiRawData::Package(_))));
+        } else if !is_package_change(&o.owner.unwrap(), status) {
+            assert_eq!(
+                fullnode.past_object_options(&o.object_id),
+                Some(options.objects.clone())
+            );
+            assert!(o.bcs.is_none());
+        }
+    }
+    assert!(packages > 0);
+}
+
+#[tokio::test]
+async fn test_refetch_missing_objects_only() {
+    let genesis = TestCheckpointDataBuilder::new().genesis_checkpoint();
+    let object_changes = genesis
+        .transactions
+        .iter()
+        .flat_map(|tx| get_object_changes(&tx.effects))
+        .collect::<Vec<_>>();
+    let failing = [object_changes[0].0, object_changes[1].0];
+    let fullnode = MockFullNode::new(vec![genesis.clone()]);
+    let metrics = IndexerCheckpointHandlerMetrics::new(&Registry::default());
+
+    for object_id in failing {
+        fullnode.fail_object_once(object_id);
+    }
+    let options = ChangedObjectOptions::default();
+    let fetched = fetch_changed_objects(
+        &fullnode,
+        object_changes.clone(),
+        500,
+        1,
+        |owner, status| options.for_change(owner, status),
+        3,
+        &metrics,
+    )
+    .await
+    .unwrap();
+
+    // Only the missing objects are requested again, the others are kept.
+    let fetched_ids = fetched.iter().map(|(_, o)| o.object_id).collect::<Vec<_>>();
+    let expected_ids = object_changes
+        .iter()
+        .map(|(id, _, _, _)| *id)
+        .collect::<Vec<_>>();
+    assert_eq!(fetched_ids, expected_ids);
+    assert_eq!(metrics.partial_refetch_total.get(), 1);
+    for (object_id, _, _, _) in &object_changes {
+        let expected_requests = if failing.contains(object_id) { 2 } else { 1 };
+        assert_eq!(fullnode.past_object_requests(object_id), expected_requests);
+    }
+
+    // Without refetches the missing objects fail the download.
+    fullnode.fail_object_once(failing[0]);
+    assert!(fetch_changed_objects(
+        &fullnode,
+        object_changes,
+        500,
+        1,
+        |owner, status| options.for_change(owner, status),
+        0,
+        &metrics,
+    )
+    .await
+    .is_err());
+    assert_eq!(metrics.partial_refetch_total.get(), 1);
+}
+
+#[tokio::test]
+async fn test_reload_multi_get_chunk_size() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![builder.genesis_checkpoint(), builder.next_checkpoint(3)];
+    let fullnode = MockFullNode::new(checkpoints);
+    let dir = tempfile::tempdir().unwrap();
+    let config_file = dir.path().join("indexer.yaml");
+    std::fs::write(&config_file, "tx_download_concurrency: 2\n").unwrap();
+    let config = IndexerConfig {
+        config_file: Some(config_file.clone()),
+        ..Default::default()
+    };
+    let handler = CheckpointHandler::new(
+        MemoryIndexerStore::new(),
+        fullnode.clone(),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+    handler.dynamic_config.reload().unwrap();
+    handler.download_checkpoint_data(1).await.unwrap();
+    assert_eq!(
+        fullnode.call_count(FullNodeApi::MultiGetFullTransactions),
+        1
+    );
+
+    std::fs::write(&config_file, "multi_get_chunk_size: 1\n").unwrap();
+    let reloaded = handler.dynamic_config.reload().unwrap();
+    assert_eq!(reloaded.multi_get_chunk_size, 1);
+    // Settings missing from the file keep their value.
+    assert_eq!(reloaded.tx_download_concurrency, 2);
+    handler.download_checkpoint_data(1).await.unwrap();
+    assert_eq!(
+        fullnode.call_count(FullNodeApi::MultiGetFullTransactions),
+        1 + 3
+    );
+
+    // A setting that needs a restart rejects the whole file.
+    std::fs::write(
+        &config_file,
+        "multi_get_chunk_size: 2\ndb_url: postgres://localhost:5432/other\n",
+    )
+    .unwrap();
+    let err = handler.dynamic_config.reload().unwrap_err();
+    assert!(
+        err.to_string().contains("`db_url` can't be changed"),
+        "{err}"
+    );
+    assert_eq!(handler.dynamic_config.load().multi_get_chunk_size, 1);
+}
+
+#[tokio::test]
+async fn test_bound_download_concurrency_per_call_type() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut checkpoints = vec![builder.genesis_checkpoint(), builder.next_checkpoint(1)];
+    // The same transaction over and over, enough for several chunks of transactions
+    // and of objects.
+    let digest = checkpoints[1].checkpoint.transactions[0];
+    checkpoints[1].checkpoint.transactions = vec![digest; 2500];
+    let fullnode = MockFullNode::new(checkpoints);
+    fullnode.set_call_delay(Duration::from_millis(20));
+    let config = IndexerConfig {
+        tx_download_concurrency: 2,
+        object_download_concurrency: 3,
+        ..Default::default()
+    };
+    let handler = CheckpointHandler::new(
+        MemoryIndexerStore::new(),
+        fullnode.clone(),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+
+    let downloaded = handler.download_checkpoint_data(1).await.unwrap();
+
+    assert_eq!(downloaded.transactions.len(), 2500);
+    assert!(fullnode.call_count(FullNodeApi::MultiGetFullTransactions) > 2);
+    assert!(fullnode.call_count(FullNodeApi::TryMultiGetPastObjects) > 3);
+    assert_eq!(
+        fullnode.max_in_flight(FullNodeApi::MultiGetFullTransactions),
+        2
+    );
+    assert_eq!(
+        fullnode.max_in_flight(FullNodeApi::TryMultiGetPastObjects),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_check_epoch_committee_continuity() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    builder.genesis_checkpoint();
+    let end_of_epoch = builder.end_of_epoch_checkpoint().unwrap();
+    let handler = new_test_handler(MemoryIndexerStore::new(), MockFullNode::new(vec![]));
+    let metrics = handler.metrics.clone();
+
+    // The genesis committee is both the next epoch committee and active in epoch 1.
+    let (_, epoch) = handler.index_checkpoint(&end_of_epoch).unwrap();
+    assert!(is_committee_transition_continuous(&epoch.unwrap()));
+    assert_eq!(metrics.epoch_committee_mismatches_total.get(), 0);
+
+    // A member missing from the next epoch committee.
+    let mut missing_member = end_of_epoch.clone();
+    let end_of_epoch_data = missing_member
+        .checkpoint
+        .end_of_epoch_data
+        .as_mut()
+        .unwrap();
+    end_of_epoch_data.next_epoch_committee.pop();
+    let (_, epoch) = handler.index_checkpoint(&missing_member).unwrap();
+    assert!(!is_committee_transition_continuous(&epoch.unwrap()));
+    assert_eq!(metrics.epoch_committee_mismatches_total.get(), 1);
+
+    // A member of the next epoch committee with a different stake.
+    let mut different_stake = end_of_epoch;
+    let end_of_epoch_data = different_stake
+        .checkpoint
+        .end_of_epoch_data
+        .as_mut()
+        .unwrap();
+    end_of_epoch_data.next_epoch_committee[0].1 += 1;
+    let (_, epoch) = handler.index_checkpoint(&different_stake).unwrap();
+    assert!(!is_committee_transition_continuous(&epoch.unwrap()));
+    assert_eq!(metrics.epoch_committee_mismatches_total.get(), 2);
+}
+
+#[tokio::test]
+async fn test_refuse_fullnode_of_another_chain() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(2);
+    let genesis_digest = checkpoints[0].checkpoint.digest.base58_encode();
+
+    // The first run records the digest of the genesis checkpoint.
+    let store = MemoryIndexerStore::new();
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints.clone())).spawn();
+    wait_for_watermark(&store, 1).await;
+    assert_eq!(
+        store.get_chain_identifier().await.unwrap(),
+        Some(genesis_digest.clone())
+    );
+
+    // Resuming a DB of another chain, the genesis checkpoint is fetched for its digest
+    // and nothing gets written.
+    let other_store = MemoryIndexerStore::new();
+    other_store
+        .persist_checkpoint(&empty_checkpoint_store(0))
+        .await
+        .unwrap();
+    other_store
+        .persist_chain_identifier("other_chain")
+        .await
+        .unwrap();
+    let fullnode = MockFullNode::new(checkpoints);
+    let err = new_test_handler(other_store.clone(), fullnode.clone())
+        .start_download_and_index()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        IndexerError::ChainIdentifierMismatch { stored, fullnode }
+            if stored == "other_chain" && fullnode == genesis_digest
+    ));
+    assert_eq!(fullnode.requested_checkpoints(), vec![1, 0]);
+    assert_eq!(
+        other_store.get_chain_identifier().await.unwrap(),
+        Some("other_chain".to_string())
+    );
+    assert!(other_store.epochs().is_empty());
+    assert_eq!(
+        other_store
+            .get_latest_checkpoint_sequence_number()
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_resume_from_committed_watermark() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(7);
+    let fullnode = MockFullNode::new(checkpoints);
+    let store = MemoryIndexerStore::new();
+    for seq in 0..=5 {
+        store
+            .persist_checkpoint(&empty_checkpoint_store(seq))
+            .await
+            .unwrap();
+    }
+
+    new_test_handler(store.clone(), fullnode.clone()).spawn();
+    assert_eq!(wait_for_first_request(&fullnode).await, 6);
+    wait_for_watermark(&store, 6).await;
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        6
+    );
+    assert_eq!(fullnode.requested_checkpoints()[0], 6);
+}
+
+#[tokio::test]
+async fn test_start_from_empty_store() {
+    let fullnode = MockFullNode::new(vec![]);
+    let store = MemoryIndexerStore::new();
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        -1
+    );
+
+    new_test_handler(store.clone(), fullnode.clone()).spawn();
+    assert_eq!(wait_for_first_request(&fullnode).await, 0);
+}
+
+#[tokio::test]
+async fn test_retry_failed_checkpoint_commit() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(1);
+    let timestamp_ms = checkpoints[0].checkpoint.timestamp_ms;
+    let store = MemoryIndexerStore::new();
+    store.fail_next_checkpoint_commits(3);
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 0).await;
+
+    // 3 failed attempts and the successful one, without dropping the checkpoint.
+    assert_eq!(store.persist_checkpoint_calls(), 4);
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0]);
+    assert_eq!(store.transactions().len(), 1);
+    assert_eq!(metrics.db_commit_retries_total.get(), 3);
+    assert_eq!(metrics.total_checkpoint_committed.get(), 1);
+    assert_eq!(metrics.total_transaction_committed.get(), 1);
+    assert_eq!(metrics.last_committed_checkpoint_sequence_number.get(), 0);
+    assert_eq!(
+        metrics.last_committed_checkpoint_timestamp_ms.get(),
+        timestamp_ms as i64
+    );
+}
+
+#[tokio::test]
+async fn test_keep_checkpoint_of_cancelled_commit() {
+    let store = MemoryIndexerStore::new();
+    store.fail_next_checkpoint_commits(usize::MAX);
+    let handler = new_test_handler(store.clone(), MockFullNode::new(vec![]));
+    handler
+        .checkpoint_sender
+        .lock()
+        .await
+        .send(empty_checkpoint_store(0))
+        .await
+        .unwrap();
+
+    let commit_handler = handler.clone();
+    let commit = tokio::spawn(async move { commit_handler.start_checkpoint_commit().await });
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.persist_checkpoint_calls() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for a retried checkpoint commit");
+    commit.abort();
+    assert!(commit.await.unwrap_err().is_cancelled());
+    assert!(store.checkpoints().is_empty());
+
+    // The checkpoint received by the cancelled commit is committed by the restarted one.
+    store.fail_next_checkpoint_commits(0);
+    tokio::spawn(async move { handler.start_checkpoint_commit().await });
+    wait_for_watermark(&store, 0).await;
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0]);
+}
+
+#[tokio::test]
+async fn test_commit_checkpoint_in_flight_on_shutdown() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let store = MemoryIndexerStore::new();
+    // Holds back the commit of the first checkpoint for about a second.
+    store.fail_next_checkpoint_commits(10);
+    let fullnode = MockFullNode::new(checkpoints);
+    let shutdown = IndexingShutdown::new();
+
+    let handle = new_test_handler(store.clone(), fullnode.clone())
+        .with_shutdown(shutdown.clone())
+        .spawn();
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.persist_checkpoint_calls() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for a retried checkpoint commit");
+    shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(60), handle)
+        .await
+        .expect("Timed out waiting for the handler to shut down")
+        .unwrap();
+
+    // The commit in flight is finished, the checkpoints after it are left to the next start.
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0]);
+
+    new_test_handler(store.clone(), fullnode).spawn();
+    wait_for_watermark(&store, 2).await;
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn test_shut_down_while_checkpoint_commit_keeps_failing() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(1);
+    let store = MemoryIndexerStore::new();
+    store.fail_next_checkpoint_commits(usize::MAX);
+    let shutdown = IndexingShutdown::new();
+
+    let handle = new_test_handler(store.clone(), MockFullNode::new(checkpoints))
+        .with_shutdown(shutdown.clone())
+        .spawn();
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while store.persist_checkpoint_calls() < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for a retried checkpoint commit");
+    shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(60), handle)
+        .await
+        .expect("Timed out waiting for the handler to shut down")
+        .unwrap();
+
+    // The commit stopped retrying, nothing is committed.
+    let calls = store.persist_checkpoint_calls();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(store.persist_checkpoint_calls(), calls);
+    assert!(store.checkpoints().is_empty());
+}
+
+#[tokio::test]
+async fn test_skip_stale_object_version_on_commit() {
+    let object = |version: i64, checkpoint: i64| Object {
+        epoch: 0,
+        checkpoint,
+        object_id: "0x1".to_string(),
+        version,
+        object_digest: format!("digest{version}"),
+        owner_type: OwnerType::AddressOwner,
+        owner_address: Some("0xa".to_string()),
+        initial_shared_version: None,
+        previous_transaction: format!("tx{checkpoint}"),
+        object_type: "0x2::coin::Coin<0x2::sui::SUI>".to_string(),
+        object_status: ObjectStatus::Mutated,
+        has_public_transfer: true,
+        storage_rebate: 0,
+        bcs: vec![],
+    };
+    let store = MemoryIndexerStore::new();
+    let handler = new_test_handler(store.clone(), MockFullNode::new(vec![]));
+    {
+        let sender = handler.checkpoint_sender.lock().await;
+        for (seq, version) in [(0, 5), (1, 3), (2, 6)] {
+            let mut checkpoint = empty_checkpoint_store(seq);
+            checkpoint.objects_changes = vec![TransactionObjectChanges {
+                changed_objects: vec![object(version, seq)],
+                deleted_objects: vec![],
+            }];
+            sender.send(checkpoint).await.unwrap();
+        }
+    }
+
+    let metrics = handler.metrics.clone();
+    let commit_handler = handler.clone();
+    let commit = tokio::spawn(async move { commit_handler.start_checkpoint_commit().await });
+    // The stale version doesn't hold back its checkpoint, nor the ones after it.
+    wait_for_watermark(&store, 2).await;
+    assert!(!commit.is_finished());
+    assert_eq!(store.persist_checkpoint_calls(), 3);
+    assert_eq!(metrics.db_commit_retries_total.get(), 0);
+    let objects = store.objects();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].version, 6);
+    assert_eq!(objects[0].checkpoint, 2);
+}
+
+#[tokio::test]
+async fn test_retry_failed_first_epoch_commit() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(2);
+    let store = MemoryIndexerStore::new();
+    store.fail_next_epoch_commits(1);
+    let fullnode = MockFullNode::new(checkpoints);
+
+    let handler = new_test_handler(store.clone(), fullnode.clone());
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    wait_for_watermark(&store, 1).await;
+
+    // The failed attempt and the successful one, without starting over from genesis.
+    assert_eq!(store.persist_epoch_calls(), 2);
+    assert_eq!(store.epochs().len(), 1);
+    assert_eq!(
+        fullnode
+            .requested_checkpoints()
+            .iter()
+            .filter(|seq| **seq == 0)
+            .count(),
+        1
+    );
+    assert_eq!(metrics.db_commit_retries_total.get(), 1);
+    assert_eq!(metrics.total_epoch_committed.get(), 1);
+}
+
+#[tokio::test]
+async fn test_refuse_out_of_order_checkpoint_commit() {
+    let store = MemoryIndexerStore::new();
+    let handler = new_test_handler(store.clone(), MockFullNode::new(vec![]));
+    {
+        let sender = handler.checkpoint_sender.lock().await;
+        for seq in [0, 2] {
+            sender.send(empty_checkpoint_store(seq)).await.unwrap();
+        }
+    }
+
+    let commit_handler = handler.clone();
+    let commit = tokio::spawn(async move { commit_handler.start_checkpoint_commit().await });
+    tokio::time::timeout(Duration::from_secs(60), async {
+        while handler.download_rewind_seq.load(Ordering::Relaxed) < 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for the download to be sent back");
+    // The checkpoint after the gap is dropped, and the download sent back to the missing one.
+    assert_eq!(handler.download_rewind_seq.load(Ordering::Relaxed), 1);
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0]);
+
+    // The commit task keeps running, and commits the checkpoints downloaded again.
+    {
+        let sender = handler.checkpoint_sender.lock().await;
+        for seq in [0, 1, 2] {
+            sender.send(empty_checkpoint_store(seq)).await.unwrap();
+        }
+    }
+    wait_for_watermark(&store, 2).await;
+    assert!(!commit.is_finished());
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn test_download_again_after_lost_checkpoint() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(4);
+    let store = MemoryIndexerStore::new();
+    let fullnode = MockFullNode::new(checkpoints);
+    let handler = new_test_handler(store.clone(), fullnode.clone());
+
+    let download_handler = handler.clone();
+    let download = tokio::spawn(async move { download_handler.start_download_and_index().await });
+    // Checkpoint 0 never reaches the commit task, which then receives 1 first.
+    let lost = handler
+        .checkpoint_receiver
+        .lock()
+        .await
+        .recv()
+        .await
+        .unwrap();
+    assert_eq!(lost.checkpoint.sequence_number, 0);
+
+    let commit_handler = handler.clone();
+    let commit = tokio::spawn(async move { commit_handler.start_checkpoint_commit().await });
+    wait_for_watermark(&store, 3).await;
+    assert!(!download.is_finished());
+    assert!(!commit.is_finished());
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 2, 3]);
+    assert!(
+        fullnode
+            .requested_checkpoints()
+            .iter()
+            .filter(|seq| **seq == 0)
+            .count()
+            > 1
+    );
+}
+
+#[tokio::test]
+async fn test_track_download_commit_gap() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let store = MemoryIndexerStore::new();
+    // Holds back the commit of the first checkpoint for about 2 seconds.
+    store.fail_next_checkpoint_commits(20);
+
+    let handler = new_test_handler(store.clone(), MockFullNode::new(checkpoints));
+    let metrics = handler.metrics.clone();
+    handler.spawn();
+    let wait_for_gap = |gap| {
+        let metrics = metrics.clone();
+        tokio::time::timeout(Duration::from_secs(60), async move {
+            while metrics.download_commit_gap.get() != gap {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+    };
+    // All 3 checkpoints are downloaded while none is committed.
+    wait_for_gap(3)
+        .await
+        .expect("Timed out waiting for the checkpoints to be downloaded");
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        -1
+    );
+
+    wait_for_gap(0)
+        .await
+        .expect("Timed out waiting for the checkpoints to be committed");
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        2
+    );
+}
+
+#[test]
+fn test_index_tracked_packages_only() {
+    let mut ptb = ProgrammableTransactionBuilder::new();
+    ptb.programmable_move_call(
+        SUI_FRAMEWORK_OBJECT_ID,
+        Identifier::new("tx_context").unwrap(),
+        Identifier::new("epoch").unwrap(),
+        vec![],
+        vec![],
+    );
+    let mut builder = TestCheckpointDataBuilder::new();
+    builder.genesis_checkpoint();
+    let move_call = builder.next_checkpoint_with_programmable_transaction(ptb.finish());
+    let transfers = builder.next_checkpoint(2);
+    let index = |tracked_packages: Vec<ObjectID>, data: &CheckpointData| {
+        let config = IndexerConfig {
+            tracked_packages,
+            ..Default::default()
+        };
+        CheckpointHandler::new(
+            MemoryIndexerStore::new(),
+            MockFullNode::new(vec![]),
+            Arc::new(EventHandler::default()),
+            IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+            &config,
+        )
+        .index_checkpoint(data)
+        .unwrap()
+        .0
+    };
+
+    // The transaction calling into the tracked package is indexed with its rows.
+    let indexed = index(vec![SUI_FRAMEWORK_OBJECT_ID], &move_call);
+    let tx_digest = move_call.transactions[0].digest.base58_encode();
+    assert_eq!(indexed.transactions.len(), 1);
+    assert_eq!(indexed.transactions[0].transaction_digest, tx_digest);
+    assert!(!indexed.move_calls.is_empty());
+    assert!(indexed
+        .input_objects
+        .iter()
+        .all(|i| i.transaction_digest == tx_digest));
+    assert!(indexed
+        .objects_changes
+        .iter()
+        .flat_map(|changes| changes.changed_objects.iter())
+        .all(|o| o.previous_transaction == tx_digest));
+    assert!(!indexed.addresses.is_empty());
+
+    // Transactions without a move call into a tracked package are skipped entirely, the
+    // checkpoint itself is still indexed.
+    for (tracked_packages, data) in [
+        (vec![SUI_FRAMEWORK_OBJECT_ID], &transfers),
+        (vec![ObjectID::from_single_byte(0x42)], &move_call),
+    ] {
+        let indexed = index(tracked_packages, data);
+        assert_eq!(
+            indexed.checkpoint.sequence_number,
+            data.checkpoint.sequence_number as i64
+        );
+        assert!(indexed.transactions.is_empty());
+        assert!(indexed.events.is_empty());
+        assert!(indexed.move_calls.is_empty());
+        assert!(indexed.input_objects.is_empty());
+        assert!(indexed.recipients.is_empty());
+        assert!(indexed.addresses.is_empty());
+        assert!(indexed.address_activities.is_empty());
+        assert!(indexed.objects_changes.iter().all(
+            |changes| changes.changed_objects.is_empty() && changes.deleted_objects.is_empty()
+        ));
+    }
+}
+
+#[test]
+fn test_index_allowlisted_addresses_only() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    builder.genesis_checkpoint();
+    let sender = builder.sender().to_string();
+    let data = builder.next_checkpoint(2);
+    let recipient = |tx: &CheckpointTransactionBlockResponse| {
+        tx.get_recipients(0, data.checkpoint.sequence_number)
+            .into_iter()
+            .map(|r| r.recipient)
+            .find(|r| *r != sender)
+            .unwrap()
+    };
+    let allowed = recipient(&data.transactions[0]);
+    let other = recipient(&data.transactions[1]);
+    let config = IndexerConfig {
+        indexed_addresses: Some(vec![SuiAddress::from_str(&allowed).unwrap()]),
+        ..Default::default()
+    };
+    let handler = CheckpointHandler::new(
+        MemoryIndexerStore::new(),
+        MockFullNode::new(vec![]),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    );
+
+    let (indexed, _) = handler.index_checkpoint(&data).unwrap();
+    let tx_digest = data.transactions[0].digest.base58_encode();
+    assert_eq!(indexed.checkpoint.transactions.len(), 2);
+    assert_eq!(indexed.transactions.len(), 1);
+    assert_eq!(indexed.transactions[0].transaction_digest, tx_digest);
+    assert!(indexed
+        .recipients
+        .iter()
+        .all(|r| r.transaction_digest == tx_digest));
+    assert!(indexed.recipients.iter().any(|r| r.recipient == allowed));
+    assert!(indexed.addresses.iter().all(|a| a.account_address != other));
+    assert!(indexed
+        .objects_changes
+        .iter()
+        .flat_map(|changes| changes.changed_objects.iter())
+        .all(|o| o.previous_transaction == tx_digest));
+}
+
+#[tokio::test]
+async fn test_index_allowed_object_types_only() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(2);
+    let coin_type = checkpoints[0]
+        .changed_objects
+        .iter()
+        .filter_map(|(_, o)| o.type_.as_ref().map(|t| t.to_string()))
+        .find(|t| t.contains("::coin::Coin<"))
+        .unwrap();
+    let coin_prefix = &coin_type[..coin_type.find('<').unwrap()];
+    let store = MemoryIndexerStore::new();
+    let config = IndexerConfig {
+        object_type_allowlist: vec![coin_prefix.to_string()],
+        ..Default::default()
+    };
+
+    CheckpointHandler::new(
+        store.clone(),
+        MockFullNode::new(checkpoints),
+        Arc::new(EventHandler::default()),
+        IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        &config,
+    )
+    .spawn();
+    wait_for_watermark(&store, 1).await;
+
+    let objects = store.objects();
+    assert!(!objects.is_empty());
+    assert!(objects
+        .iter()
+        .all(|o| o.object_type.starts_with(coin_prefix)));
+    assert!(store
+        .objects_history()
+        .iter()
+        .all(|o| o.object_type.starts_with(coin_prefix)));
+    // Packages and the epoch are indexed from the fetched objects regardless.
+    assert!(!store.packages().is_empty());
+    assert!(!store.epochs().is_empty());
+
+    let config = IndexerConfig {
+        object_type_allowlist: vec![coin_prefix.to_string()],
+        object_type_denylist: vec![coin_type.clone()],
+        ..Default::default()
+    };
+    assert!(!config.indexes_object_type(&coin_type));
+    assert!(config.indexes_object_type(&format!("{coin_prefix}<0x5::other::OTHER>")));
+    assert!(!config.indexes_object_type("package"));
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_indexing() {
+    let checkpoints = TestCheckpointDataBuilder::new().build_checkpoints(3);
+    let store = MemoryIndexerStore::new();
+    let fullnode = MockFullNode::new(checkpoints);
+    let pause = IndexingPause::new();
+    pause.pause();
+
+    new_test_handler(store.clone(), fullnode.clone())
+        .with_pause(pause.clone())
+        .spawn();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    // Nothing is downloaded nor committed while paused.
+    assert!(fullnode.requested_checkpoints().is_empty());
+    assert!(store.checkpoints().is_empty());
+    assert_eq!(
+        store.get_latest_checkpoint_sequence_number().await.unwrap(),
+        -1
+    );
+
+    pause.resume();
+    wait_for_watermark(&store, 2).await;
+    let committed = store
+        .checkpoints()
+        .iter()
+        .map(|cp| cp.sequence_number)
+        .collect::<Vec<_>>();
+    assert_eq!(committed, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_throttle_commit_retry_warnings() {
+    let warned = (1..=120)
+        .filter(|retries| should_warn_commit_retry(*retries))
+        .collect::<Vec<_>>();
+    assert_eq!(warned, vec![1, 51, 101]);
+}
+
+/// Certifies `summary` with the signatures of `keys`, members of `committee`.
+fn certify(
+    summary: &CheckpointSummary,
+    keys: &[AuthorityKeyPair],
+    committee: &Committee,
+) -> CertifiedCheckpointSummary {
+    let sign_infos = keys
+        .iter()
+        .map(|key| {
+            SignedCheckpointSummary::sign(committee.epoch, summary, key, key.public().into())
+        })
+        .collect();
+    CertifiedCheckpointSummary::new(summary.clone(), sign_infos, committee).unwrap()
+}
+
+#[test]
+fn test_verify_checkpoint_signature() {
+    let (committee, keys) = Committee::new_simple_test_committee();
+    let contents = CheckpointContents::new_with_causally_ordered_transactions(
+        [ExecutionDigests::random()].into_iter(),
+    );
+    let summary = CheckpointSummary::new(
+        committee.epoch,
+        1,
+        2,
+        &contents,
+        None,
+        GasCostSummary::default(),
+        None,
+        1000,
+    );
+    // A quorum of 3 out of 4 validators of equal stake.
+    let certified = certify(&summary, &keys[..3], &committee);
+    let checkpoint = RpcCheckpoint::from((
+        summary.clone(),
+        contents.clone(),
+        certified.auth_sig().signature.clone(),
+    ));
+    verify_checkpoint_signature(&checkpoint, &certified, &committee).unwrap();
+
+    // Signed by another committee of the same epoch.
+    let (other_committee, other_keys) = Committee::new_simple_test_committee();
+    let forged = certify(&summary, &other_keys[..3], &other_committee);
+    let err = verify_checkpoint_signature(&checkpoint, &forged, &committee).unwrap_err();
+    assert!(matches!(
+        err,
+        IndexerError::InvalidCheckpointSignature {
+            sequence_number: 1,
+            ..
+        }
+    ));
+
+    // A fullnode lying about the checkpoint, but serving the genuine summary.
+    let tampered = RpcCheckpoint {
+        timestamp_ms: 2000,
+        ..checkpoint.clone()
+    };
+    assert!(matches!(
+        verify_checkpoint_signature(&tampered, &certified, &committee),
+        Err(IndexerError::InvalidCheckpointSignature { .. })
+    ));
+
+    // The genuine summary of another checkpoint.
+    let other_summary = CheckpointSummary::new(
+        committee.epoch,
+        2,
+        2,
+        &contents,
+        Some(checkpoint.digest),
+        GasCostSummary::default(),
+        None,
+        1000,
+    );
+    let other_certified = certify(&other_summary, &keys[..3], &committee);
+    assert!(matches!(
+        verify_checkpoint_signature(&checkpoint, &other_certified, &committee),
+        Err(IndexerError::InvalidCheckpointSignature { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_retry_incomplete_transaction_batch() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![builder.genesis_checkpoint(), builder.next_checkpoint(3)];
+    let requested = checkpoints[1].checkpoint.transactions.clone();
+    let fullnode = MockFullNode::new(checkpoints);
+    fullnode.drop_transaction_once(requested[1]);
+    let handler = new_test_handler(MemoryIndexerStore::new(), fullnode.clone());
+
+    let err = handler.download_checkpoint_data(1).await.unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            IndexerError::IncompleteTransactionBatch {
+                sequence_number: 1,
+                missing,
+                unexpected,
+            } if missing == &vec![requested[1]] && unexpected.is_empty()
+        ),
+        "{err:?}"
+    );
+    assert_eq!(
+        err.fullnode_read_error_kind(),
+        Some(FullNodeReadErrorKind::Other)
+    );
+
+    // The fullnode serves the whole batch again, in checkpoint order.
+    let downloaded = handler.download_checkpoint_data(1).await.unwrap();
+    let digests = downloaded
+        .transactions
+        .iter()
+        .map(|tx| tx.digest)
+        .collect::<Vec<_>>();
+    assert_eq!(digests, requested);
+
+    // The download task starts over on the incomplete batch.
+    let store = MemoryIndexerStore::new();
+    fullnode.drop_transaction_once(requested[2]);
+    new_test_handler(store.clone(), fullnode.clone()).spawn();
+    wait_for_watermark(&store, 1).await;
+    let committed = store
+        .transactions()
+        .into_iter()
+        .filter(|tx| tx.checkpoint_sequence_number == Some(1))
+        .count();
+    assert_eq!(committed, requested.len());
+}
+
+// Indexing structurally valid checkpoints with random gas, missing objects and missing
+// events never panics, and what it indexes is consistent with the checkpoint.
+#[test]
+fn test_index_arbitrary_checkpoint() {
+    // Building a genesis is slow, so the cases perturb the same checkpoints.
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = [builder.genesis_checkpoint(), builder.next_checkpoint(8)];
+    let gas = || (0..u32::MAX as u64, 0..u32::MAX as u64, 0..u32::MAX as u64);
+    let strategy = (
+        0..checkpoints.len(),
+        0..=8usize,
+        gas(),
+        collection::vec(gas(), 8),
+        collection::vec(any::<bool>(), 8),
+        collection::vec(any::<bool>(), 64),
+        0..64usize,
+    );
+
+    let mut runner = TestRunner::new(ProptestConfig::with_cases(64));
+    runner
+        .run(
+            &strategy,
+            |(
+                index,
+                num_transactions,
+                rolling_gas,
+                tx_gas,
+                kept_events,
+                kept_objects,
+                rotation,
+            )| {
+                let mut data = checkpoints[index].clone();
+                data.transactions.truncate(num_transactions);
+                data.checkpoint.transactions.truncate(num_transactions);
+                let (computation_cost, storage_cost, storage_rebate) = rolling_gas;
+                data.checkpoint.epoch_rolling_gas_cost_summary =
+                    GasCostSummary::new(computation_cost, storage_cost, storage_rebate, 0);
+                for ((tx, (computation_cost, storage_cost, storage_rebate)), keep_events) in
+                    data.transactions.iter_mut().zip(tx_gas).zip(kept_events)
+                {
+                    let SuiTransactionBlockEffects::V1(effects) = &mut tx.effects;
+                    effects.gas_used =
+                        GasCostSummary::new(computation_cost, storage_cost, storage_rebate, 0);
+                    if !keep_events {
+                        tx.events.data.clear();
+                    }
+                }
+                let num_objects = data.changed_objects.len().max(1);
+                data.changed_objects.rotate_left(rotation % num_objects);
+                let mut kept_objects = kept_objects.into_iter().cycle();
+                data.changed_objects
+                    .retain(|_| kept_objects.next().unwrap());
+
+                let (indexed, epoch) = match index_checkpoint_data(&data) {
+                    Ok(indexed) => indexed,
+                    // A genesis missing its system state or its transaction fails to index,
+                    // which is fine as long as it doesn't panic.
+                    Err(_) => return Ok(()),
+                };
+
+                let checkpoint = &indexed.checkpoint;
+                prop_assert!(checkpoint.total_computation_cost >= 0);
+                prop_assert!(checkpoint.total_storage_cost >= 0);
+                prop_assert!(checkpoint.total_storage_rebate >= 0);
+                prop_assert_eq!(
+                    checkpoint.total_gas_cost,
+                    checkpoint.total_computation_cost + checkpoint.total_storage_cost
+                        - checkpoint.total_storage_rebate
+                );
+                prop_assert_eq!(
+                    checkpoint.total_transactions,
+                    indexed
+                        .transactions
+                        .iter()
+                        .map(|tx| tx.transaction_count)
+                        .sum::<i64>()
+                );
+                prop_assert_eq!(epoch.is_some(), data.checkpoint.sequence_number == 0);
+
+                prop_assert_eq!(indexed.transactions.len(), data.transactions.len());
+                prop_assert_eq!(indexed.objects_changes.len(), data.transactions.len());
+                for ((tx, indexed_tx), changes) in data
+                    .transactions
+                    .iter()
+                    .zip(&indexed.transactions)
+                    .zip(&indexed.objects_changes)
+                {
+                    let digest = tx.digest.base58_encode();
+                    prop_assert_eq!(&indexed_tx.transaction_digest, &digest);
+                    prop_assert!(indexed_tx.computation_cost >= 0);
+                    prop_assert!(indexed_tx.storage_cost >= 0);
+                    prop_assert!(indexed_tx.storage_rebate >= 0);
+                    prop_assert_eq!(
+                        indexed_tx.total_gas_cost,
+                        indexed_tx.computation_cost + indexed_tx.storage_cost
+                            - indexed_tx.storage_rebate
+                    );
+                    for o in &changes.changed_objects {
+                        prop_assert!(matches!(
+                            o.object_status,
+                            ObjectStatus::Created | ObjectStatus::Mutated | ObjectStatus::Unwrapped
+                        ));
+                        prop_assert_eq!(&o.previous_transaction, &digest);
+                        prop_assert!(o.version >= 0 && o.storage_rebate >= 0);
+                    }
+                    for o in &changes.deleted_objects {
+                        prop_assert!(matches!(
+                            o.object_status,
+                            ObjectStatus::Deleted
+                                | ObjectStatus::Wrapped
+                                | ObjectStatus::UnwrappedThenDeleted
+                        ));
+                    }
+                }
+                // Changed objects of transactions outside the checkpoint are left out.
+                let expected_objects = data
+                    .changed_objects
+                    .iter()
+                    .filter(|(_, o)| {
+                        data.transactions
+                            .iter()
+                            .any(|tx| o.previous_transaction == Some(tx.digest))
+                    })
+                    .count();
+                let changed_objects = indexed
+                    .objects_changes
+                    .iter()
+                    .map(|changes| changes.changed_objects.len())
+                    .sum::<usize>();
+                prop_assert_eq!(changed_objects, expected_objects);
+
+                let num_events = data
+                    .transactions
+                    .iter()
+                    .map(|tx| tx.events.data.len())
+                    .sum::<usize>();
+                prop_assert_eq!(indexed.events.len(), num_events);
+                for (i, event) in indexed.events.iter().enumerate() {
+                    prop_assert_eq!(event.event_index, i as i64);
+                    prop_assert_eq!(
+                        event.checkpoint_sequence_number,
+                        data.checkpoint.sequence_number as i64
+                    );
+                }
+                Ok(())
+            },
+        )
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_index_genesis_allocations() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let mut genesis = builder.genesis_checkpoint();
+    // Only the two coins below are allocated.
+    genesis
+        .changed_objects
+        .retain(|(_, o)| !o.type_.as_ref().map_or(false, |type_| type_.is_coin()));
+    let (alice, bob) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let coins = vec![
+        builder.genesis_coin(alice, 1_000),
+        builder.genesis_coin(bob, 2_000),
+    ];
+    genesis.changed_objects.extend(coins.clone());
+
+    let (_, epoch) = index_checkpoint_data(&genesis).unwrap();
+    let epoch = epoch.unwrap();
+    let mut expected = coins
+        .iter()
+        .zip([(alice, 1_000), (bob, 2_000)])
+        .map(|((_, o), (owner, amount))| GenesisAllocation {
+            object_id: o.object_id.to_string(),
+            owner_address: owner.to_string(),
+            coin_type: "0x2::sui::SUI".to_string(),
+            amount,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(epoch.genesis_allocations, expected);
+
+    // The store returns them in object ID order.
+    let store = MemoryIndexerStore::new();
+    assert!(store.get_genesis_allocations().await.unwrap().is_empty());
+    store.persist_epoch(&epoch).await.unwrap();
+    expected.sort_by(|a, b| a.object_id.cmp(&b.object_id));
+    assert_eq!(store.get_genesis_allocations().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_close_epoch_opened_before_restart() {
+    let mut builder = TestCheckpointDataBuilder::new();
+    let checkpoints = vec![
+        builder.genesis_checkpoint(),
+        builder.next_checkpoint(1),
+        builder.end_of_epoch_checkpoint().unwrap(),
+        builder.next_checkpoint(1),
+        builder.next_checkpoint(1),
+        builder.end_of_epoch_checkpoint().unwrap(),
+    ];
+    let store = MemoryIndexerStore::new();
+
+    // Indexes up to the middle of epoch 1, the fullnode has nothing past checkpoint 4.
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints[..5].to_vec())).spawn();
+    wait_for_watermark(&store, 4).await;
+    let wait_for_epoch = |epoch: i64, closed: bool| {
+        let store = store.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(60), async {
+                while !store
+                    .epochs()
+                    .iter()
+                    .any(|e| e.epoch == epoch && e.last_checkpoint_id.is_some() == closed)
+                {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+            })
+            .await
+            .unwrap_or_else(|_| panic!("Timed out waiting for epoch {epoch}"))
+        }
+    };
+    wait_for_epoch(1, false).await;
+    let opened = store.epochs().into_iter().find(|e| e.epoch == 1).unwrap();
+    assert_eq!(opened.first_checkpoint_id, 3);
+
+    // A restarted handler knows nothing of epoch 1 but its committed row.
+    new_test_handler(store.clone(), MockFullNode::new(checkpoints)).spawn();
+    wait_for_watermark(&store, 5).await;
+    wait_for_epoch(1, true).await;
+
+    let closed = store.epochs().into_iter().find(|e| e.epoch == 1).unwrap();
+    assert_eq!(closed.first_checkpoint_id, 3);
+    assert_eq!(closed.last_checkpoint_id, Some(5));
+    assert_eq!(closed.epoch_start_timestamp, opened.epoch_start_timestamp);
+    let epoch_ids = store.epochs().iter().map(|e| e.epoch).collect::<Vec<_>>();
+    assert_eq!(epoch_ids, vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn test_share_committee_cache_across_clones() {
+    let handler = new_test_handler(MemoryIndexerStore::new(), MockFullNode::new(vec![]));
+    let clone = handler.clone();
+    let (committee, _) = Committee::new_simple_test_committee();
+
+    handler
+        .committees
+        .lock()
+        .await
+        .insert(committee.epoch, committee.clone());
+    // The store has no validators, the committee can only come from the cache of the other
+    // clone.
+    assert_eq!(
+        clone.epoch_committee(committee.epoch).await.unwrap(),
+        Some(committee)
+    );
+}
+
+const OBJECT_STATUSES: [ObjectStatus; 6] = [
+    ObjectStatus::Created,
+    ObjectStatus::Mutated,
+    ObjectStatus::Unwrapped,
+    ObjectStatus::Deleted,
+    ObjectStatus::Wrapped,
+    ObjectStatus::UnwrappedThenDeleted,
+];
+
+/// Builds effects which put each object into the list of its status.
+fn effects_with_statuses(
+    objects: &BTreeMap<ObjectID, (ObjectStatus, SequenceNumber)>,
+) -> SuiTransactionBlockEffects {
+    let mut effects = TransactionEffectsV1::default();
+    for (id, (status, version)) in objects {
+        let oref = (*id, *version, ObjectDigest::random());
+        let owner = Owner::AddressOwner(SuiAddress::default());
+        match status {
+            ObjectStatus::Created => effects.created.push((oref, owner)),
+            ObjectStatus::Mutated => effects.mutated.push((oref, owner)),
+            ObjectStatus::Unwrapped => effects.unwrapped.push((oref, owner)),
+            ObjectStatus::Deleted => effects.deleted.push(oref),
+            ObjectStatus::Wrapped => effects.wrapped.push(oref),
+            ObjectStatus::UnwrappedThenDeleted => effects.unwrapped_then_deleted.push(oref),
+        }
+    }
+    SuiTransactionBlockEffects::try_from(TransactionEffects::V1(effects)).unwrap()
+}
+
+proptest! {
+    // Every object in the effects lands in exactly one of the outputs of
+    // get_object_changes and get_deleted_db_objects, with the status it has in the effects.
+    #[test]
+    fn test_object_changes_partition_effects(
+        objects in collection::btree_map(
+            any::<[u8; ObjectID::LENGTH]>(),
+            (0..OBJECT_STATUSES.len(), 1..u32::MAX as u64),
+            0..64,
+        )
+    ) {
+        let objects = objects
+            .into_iter()
+            .map(|(id, (status, version))| {
+                (
+                    ObjectID::new(id),
+                    (OBJECT_STATUSES[status], SequenceNumber::from_u64(version)),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let effects = effects_with_statuses(&objects);
+
+        let changed_objects = get_object_changes(&effects)
+            .into_iter()
+            .map(|(id, version, status, _)| (id, status, version));
+        let deleted_objects = get_deleted_db_objects(&effects, 0, Some(0))
+            .into_iter()
+            .map(|o| {
+                (
+                    ObjectID::from_str(&o.object_id).unwrap(),
+                    o.object_status,
+                    SequenceNumber::from_u64(o.version as u64),
+                )
+            });
+        let mut outputs = BTreeMap::new();
+        for (id, status, version) in changed_objects.chain(deleted_objects) {
+            prop_assert!(
+                outputs.insert(id, (status, version)).is_none(),
+                "Object {} is in more than one bucket",
+                id
+            );
+        }
+        prop_assert_eq!(outputs, objects);
+    }
+}
@@ -4,6 +4,7 @@
 
 use std::env;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::SystemTime;
@@ -11,14 +12,14 @@ use std::time::SystemTime;
 use anyhow::Result;
 use backoff::future::retry;
 use backoff::ExponentialBackoff;
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::ConnectionManager;
 use diesel_async::pooled_connection::deadpool::{Object, Pool};
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_async::AsyncPgConnection;
-use futures::future::BoxFuture;
+use futures::future::{join_all, BoxFuture};
 use futures::FutureExt;
 use jsonrpsee::http_client::{HeaderMap, HeaderValue, HttpClient, HttpClientBuilder};
 use prometheus::Registry;
@@ -33,26 +34,41 @@ use apis::{
 };
 use errors::IndexerError;
 use handlers::checkpoint_handler::CheckpointHandler;
+use handlers::pause::IndexingPause;
+use handlers::shutdown::IndexingShutdown;
+use metrics::IndexerCheckpointHandlerMetrics;
 use mysten_metrics::spawn_monitored_task;
 use store::IndexerStore;
 use sui_core::event_handler::EventHandler;
 use sui_json_rpc::{JsonRpcServerBuilder, ServerHandle, CLIENT_SDK_TYPE_HEADER};
 use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_types::base_types::{ObjectID, SuiAddress};
 
 use crate::apis::MoveUtilsApi;
 
+pub mod admin;
 pub mod apis;
 pub mod errors;
 mod handlers;
 pub mod metrics;
 pub mod models;
 pub mod processors;
+pub mod proto;
 pub mod schema;
 pub mod store;
 pub mod test_utils;
 pub mod types;
 pub mod utils;
 
+pub use handlers::checkpoint_handler::{
+    index_checkpoint_data, index_objects, index_packages, index_transactions,
+};
+pub use handlers::checkpoint_sink::{CheckpointSink, JsonFileSink};
+pub use handlers::dynamic_config::{DynamicConfig, DynamicIndexerConfig};
+pub use handlers::event_sink::EventSink;
+pub use handlers::pause::IndexingPause;
+pub use handlers::shutdown::IndexingShutdown;
+
 pub type PgConnectionPool = diesel::r2d2::Pool<ConnectionManager<PgConnection>>;
 pub type PgPoolConnection = diesel::r2d2::PooledConnection<ConnectionManager<PgConnection>>;
 
@@ -105,6 +121,99 @@ pub struct IndexerConfig {
     // NOTE: experimental only, do not use in production.
     #[clap(long)]
     pub skip_db_commit: bool,
+    /// Attempts to index a checkpoint before it is quarantined and skipped.
+    #[clap(long, default_value = "5")]
+    pub max_checkpoint_index_attempts: usize,
+    /// Limit of requests per second sent to the fullnode, unlimited if unset.
+    #[clap(long)]
+    pub max_fullnode_rps: Option<u32>,
+    /// What to do with an event that fails to deserialize.
+    #[clap(long, arg_enum, default_value = "quarantine")]
+    pub malformed_event_policy: MalformedEventPolicy,
+    /// Verify the signature of every checkpoint against the committee of its epoch,
+    /// requires a fullnode serving certified checkpoint summaries.
+    #[clap(long)]
+    pub verify_checkpoint_signatures: bool,
+    /// Index a genesis checkpoint without a readable system state, e.g. of a localnet with
+    /// a modified genesis, skipping the system state and validators of epoch 0.
+    #[clap(long)]
+    pub tolerate_nonstandard_genesis: bool,
+    /// Transaction chunks of a checkpoint fetched from the fullnode at the same time.
+    #[clap(long, default_value = "10")]
+    pub tx_download_concurrency: usize,
+    /// Object chunks of a checkpoint fetched from the fullnode at the same time.
+    #[clap(long, default_value = "10")]
+    pub object_download_concurrency: usize,
+    /// Re-requests of the objects missing from an object chunk before the download of the
+    /// checkpoint fails.
+    #[clap(long, default_value = "3")]
+    pub max_object_refetches: usize,
+    /// Transactions or objects requested from the fullnode in one multi-get.
+    #[clap(long, default_value = "500")]
+    pub multi_get_chunk_size: usize,
+    /// YAML file overriding the settings that can change while indexing, i.e. the download
+    /// concurrencies, `multi_get_chunk_size`, `max_object_refetches` and `max_fullnode_rps`.
+    /// Read at start and again on each reload through the admin server.
+    #[clap(long)]
+    pub config_file: Option<PathBuf>,
+    /// Packages to index the transactions of, all transactions if empty. This is a lossy
+    /// projection: only the transactions with a move call into one of the packages are
+    /// indexed, with their events, objects, addresses and the like, along with the packages
+    /// themselves. Checkpoints are indexed whole, their transaction counts included, so
+    /// that the watermark advances.
+    #[clap(long, multiple_occurrences = false, multiple_values = true)]
+    pub tracked_packages: Vec<ObjectID>,
+    /// Addresses to index the transactions of, all transactions if not set. Like
+    /// `tracked_packages`, a lossy projection onto the transactions with one of the
+    /// addresses as sender or recipient, checkpoints are still indexed whole.
+    #[clap(long, multiple_occurrences = false, multiple_values = true)]
+    pub indexed_addresses: Option<Vec<SuiAddress>>,
+    /// Prefixes of the types of the objects to index, all objects if empty. Prefixes are
+    /// matched against the stored `object_type`, with full-length addresses, `package` for
+    /// packages. Objects of other types are still fetched, but missing from the objects
+    /// tables, which makes owner and type queries partial.
+    #[clap(long, multiple_occurrences = false, multiple_values = true)]
+    pub object_type_allowlist: Vec<String>,
+    /// Prefixes of the types of the objects not to index, even if they are in the allowlist.
+    #[clap(long, multiple_occurrences = false, multiple_values = true)]
+    pub object_type_denylist: Vec<String>,
+    /// Store the BCS of objects compressed with zstd, rows stored either way stay readable.
+    #[clap(long)]
+    pub compress_object_bcs: bool,
+    /// Directory to export each committed checkpoint to as newline-delimited JSON, for
+    /// debugging and analysis, not exported if unset.
+    #[clap(long)]
+    pub checkpoint_json_dir: Option<PathBuf>,
+    /// Size at which the export moves on to a new file, one file per checkpoint if unset.
+    #[clap(long)]
+    pub checkpoint_json_max_file_bytes: Option<u64>,
+    /// Export the BCS of changed objects along with them.
+    #[clap(long)]
+    pub checkpoint_json_include_object_bcs: bool,
+    /// Connections of the pool the store reads and commits with, DB_CONNECTION_SIZE or 187
+    /// if unset. Each checkpoint and epoch commit holds a connection for the whole commit,
+    /// next to the reads of the RPC server, while up to 10 indexed checkpoints queue up for
+    /// their commit. A smaller pool than that makes commits wait for a connection.
+    #[clap(long)]
+    pub db_pool_size: Option<usize>,
+    /// Connections of the pool established up front and kept open while idle.
+    #[clap(long, default_value = "0")]
+    pub db_pool_min_idle: usize,
+    /// Host of the HTTP server of the health check and of the pause and resume controls.
+    #[clap(long, default_value = "127.0.0.1", global = true)]
+    pub admin_host: String,
+    #[clap(long, default_value = "9185", global = true)]
+    pub admin_port: u16,
+}
+
+/// Handling of events the indexer fails to deserialize, e.g. after a protocol upgrade
+/// the indexer hasn't been rebuilt for.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MalformedEventPolicy {
+    /// Index the checkpoint as if the event was not there.
+    Skip,
+    /// Fail indexing the checkpoint, so that it ends up quarantined.
+    Quarantine,
 }
 
 impl IndexerConfig {
@@ -121,6 +230,20 @@ impl IndexerConfig {
         )
     }
 
+    /// Whether changed objects of `object_type` are indexed.
+    pub fn indexes_object_type(&self, object_type: &str) -> bool {
+        let allowed = self.object_type_allowlist.is_empty()
+            || self
+                .object_type_allowlist
+                .iter()
+                .any(|prefix| object_type.starts_with(prefix.as_str()));
+        allowed
+            && !self
+                .object_type_denylist
+                .iter()
+                .any(|prefix| object_type.starts_with(prefix.as_str()))
+    }
+
     pub fn all_implemented_methods() -> Vec<String> {
         IMPLEMENTED_METHODS.iter().map(|&s| s.to_string()).collect()
     }
@@ -140,6 +263,28 @@ impl Default for IndexerConfig {
             fullnode_sync_worker: true,
             rpc_server_worker: true,
             skip_db_commit: false,
+            max_checkpoint_index_attempts: 5,
+            max_fullnode_rps: None,
+            malformed_event_policy: MalformedEventPolicy::Quarantine,
+            verify_checkpoint_signatures: false,
+            tolerate_nonstandard_genesis: false,
+            tx_download_concurrency: 10,
+            object_download_concurrency: 10,
+            max_object_refetches: 3,
+            multi_get_chunk_size: 500,
+            config_file: None,
+            tracked_packages: vec![],
+            indexed_addresses: None,
+            object_type_allowlist: vec![],
+            object_type_denylist: vec![],
+            compress_object_bcs: false,
+            checkpoint_json_dir: None,
+            checkpoint_json_max_file_bytes: None,
+            checkpoint_json_include_object_bcs: false,
+            db_pool_size: None,
+            db_pool_min_idle: 0,
+            admin_host: "127.0.0.1".to_string(),
+            admin_port: 9185,
         }
     }
 }
@@ -151,12 +296,42 @@ impl Indexer {
         config: &IndexerConfig,
         registry: &Registry,
         store: S,
+        metrics: IndexerCheckpointHandlerMetrics,
     ) -> Result<(), IndexerError> {
         info!(
             "Sui indexer of version {:?} started...",
             env!("CARGO_PKG_VERSION")
         );
         let event_handler = Arc::new(EventHandler::default());
+        // NOTE: shared by the handlers of every retry below, so that a pause outlives them.
+        let pause = IndexingPause::new();
+        let shutdown = IndexingShutdown::new();
+        let dynamic_config = DynamicConfig::new(config);
+        if config.config_file.is_some() {
+            dynamic_config.reload()?;
+        }
+        if config.fullnode_sync_worker {
+            let shutdown = shutdown.clone();
+            spawn_monitored_task!(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    info!("Indexer shutting down, after the checkpoint commit in flight");
+                    shutdown.shutdown();
+                    // NOTE: a second interrupt does not wait for the commit.
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        std::process::exit(130);
+                    }
+                }
+            });
+            match format!("{}:{}", config.admin_host, config.admin_port).parse() {
+                Ok(addr) => admin::start_admin_server(addr, pause.clone(), dynamic_config.clone()),
+                Err(e) => warn!(
+                    host = config.admin_host,
+                    port = config.admin_port,
+                    error = ?e,
+                    "Invalid indexer admin server address, not starting it"
+                ),
+            }
+        }
         if config.rpc_server_worker && config.fullnode_sync_worker {
             info!("Starting indexer with both fullnode sync and RPC server");
             let handle =
@@ -173,9 +348,12 @@ impl Indexer {
                     store.clone(),
                     http_client,
                     event_handler_clone,
-                    registry,
+                    metrics.clone(),
                     config,
-                );
+                )
+                .with_pause(pause.clone())
+                .with_shutdown(shutdown.clone())
+                .with_dynamic_config(dynamic_config.clone());
                 cp.spawn()
                     .await
                     .expect("Indexer main should not run into errors.");
@@ -199,9 +377,12 @@ impl Indexer {
                     store.clone(),
                     http_client,
                     event_handler_clone,
-                    registry,
+                    metrics.clone(),
                     config,
-                );
+                )
+                .with_pause(pause.clone())
+                .with_shutdown(shutdown.clone())
+                .with_dynamic_config(dynamic_config.clone());
                 cp.spawn()
                     .await
                     .expect("Indexer main should not run into errors.");
@@ -290,6 +471,16 @@ fn establish_connection(url: &str) -> BoxFuture<ConnectionResult<AsyncPgConnecti
 
 pub async fn new_pg_connection_pool(
     db_url: &str,
+) -> Result<(PgConnectionPool, AsyncPgConnectionPool), IndexerError> {
+    new_pg_connection_pool_with_size(db_url, None, 0).await
+}
+
+/// Creates the connection pools with `max_size` async connections, DB_CONNECTION_SIZE if
+/// unset, `min_idle` of which are established before returning.
+pub async fn new_pg_connection_pool_with_size(
+    db_url: &str,
+    max_size: Option<usize>,
+    min_idle: usize,
 ) -> Result<(PgConnectionPool, AsyncPgConnectionPool), IndexerError> {
     let manager = ConnectionManager::<PgConnection>::new(db_url);
     // default connection pool max size is 10
@@ -306,10 +497,12 @@ pub async fn new_pg_connection_pool(
     );
     // Our vultr instances allow up to 197 concurrent connections,
     // setting the default pool size to 187 for async connections and 10 for blocking connections.
-    let connection_size = env::var("DB_CONNECTION_SIZE")
-        .unwrap_or_else(|_| "187".to_string())
-        .parse::<usize>()
-        .unwrap_or(187);
+    let connection_size = max_size.unwrap_or_else(|| {
+        env::var("DB_CONNECTION_SIZE")
+            .unwrap_or_else(|_| "187".to_string())
+            .parse::<usize>()
+            .unwrap_or(187)
+    });
     info!("Creating connection pool with size: {connection_size}");
     let async_pool = Pool::builder(manager)
         .max_size(connection_size)
@@ -320,6 +513,15 @@ pub async fn new_pg_connection_pool(
                 e
             ))
         })?;
+    // NOTE: the pool keeps the connections it established until they fail, the first ones
+    // are only returned once all of them are checked out.
+    let idle_connections = join_all(
+        (0..min_idle.min(connection_size)).map(|_| get_async_pg_pool_connection(&async_pool)),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+    drop(idle_connections);
     Ok((blocking_cp, async_pool))
 }
 
@@ -5,9 +5,12 @@ use clap::Parser;
 use tracing::{error, info};
 
 use sui_indexer::errors::IndexerError;
+use sui_indexer::metrics::IndexerCheckpointHandlerMetrics;
 use sui_indexer::store::PgIndexerStore;
 use sui_indexer::utils::reset_database;
-use sui_indexer::{get_pg_pool_connection, new_pg_connection_pool, Indexer, IndexerConfig};
+use sui_indexer::{
+    get_pg_pool_connection, new_pg_connection_pool_with_size, Indexer, IndexerConfig,
+};
 use sui_node::metrics::start_prometheus_server;
 
 #[tokio::main]
@@ -31,15 +34,19 @@ async fn main() -> Result<(), IndexerError> {
     );
 
     let registry = registry_service.default_registry();
-    let (blocking_cp, async_cp) = new_pg_connection_pool(&indexer_config.db_url)
-        .await
-        .map_err(|e| {
-            error!(
-                "Failed creating Postgres connection pool with error {:?}",
-                e
-            );
+    let (blocking_cp, async_cp) = new_pg_connection_pool_with_size(
+        &indexer_config.db_url,
+        indexer_config.db_pool_size,
+        indexer_config.db_pool_min_idle,
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            "Failed creating Postgres connection pool with error {:?}",
             e
-        })?;
+        );
+        e
+    })?;
     if indexer_config.reset_db {
         let mut conn = get_pg_pool_connection(&blocking_cp).map_err(|e| {
             error!(
@@ -58,7 +65,8 @@ async fn main() -> Result<(), IndexerError> {
             IndexerError::PostgresResetError(db_err_msg)
         })?;
     }
-    let store = PgIndexerStore::new(async_cp, blocking_cp).await;
+    let metrics = IndexerCheckpointHandlerMetrics::new(&registry);
+    let store = PgIndexerStore::new(async_cp, blocking_cp, metrics.clone()).await;
 
-    Indexer::start(&indexer_config, &registry, store).await
+    Indexer::start(&indexer_config, &registry, store, metrics).await
 }
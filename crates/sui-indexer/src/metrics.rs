@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use prometheus::{
-    register_histogram_with_registry, register_int_counter_with_registry, Histogram, IntCounter,
+    register_histogram_vec_with_registry, register_histogram_with_registry,
+    register_int_counter_vec_with_registry, register_int_counter_with_registry,
+    register_int_gauge_with_registry, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
     Registry,
 };
 
@@ -20,7 +22,22 @@ pub struct IndexerCheckpointHandlerMetrics {
     pub total_checkpoint_received: IntCounter,
     pub total_checkpoint_committed: IntCounter,
     pub total_transaction_committed: IntCounter,
+    pub total_objects_fetched: IntCounter,
+    pub packages_published_total: IntCounter,
+    pub packages_upgraded_total: IntCounter,
+    pub partial_refetch_total: IntCounter,
     pub total_epoch_committed: IntCounter,
+    pub total_checkpoint_quarantined: IntCounter,
+    pub event_deserialize_errors_total: IntCounter,
+    pub epoch_committee_mismatches_total: IntCounter,
+    pub db_commit_retries_total: IntCounter,
+    // labeled by FullNodeReadErrorKind
+    pub fullnode_read_errors_total: IntCounterVec,
+    // absolute position of the commit task, for staleness against wall-clock
+    pub last_committed_checkpoint_sequence_number: IntGauge,
+    pub last_committed_checkpoint_timestamp_ms: IntGauge,
+    // checkpoints downloaded and not committed yet, the DB is the bottleneck when it stays large
+    pub download_commit_gap: IntGauge,
     // checkpoint E2E latency is:
     // fullnode_download_latency + checkpoint_index_latency + db_commit_latency
     pub fullnode_checkpoint_wait_and_download_latency: Histogram,
@@ -29,10 +46,22 @@ pub struct IndexerCheckpointHandlerMetrics {
     pub fullnode_object_download_latency: Histogram,
     pub checkpoint_index_latency: Histogram,
     pub checkpoint_db_commit_latency: Histogram,
+    // breakdown of checkpoint_db_commit_latency per table
+    pub checkpoint_db_commit_latency_objects: Histogram,
+    pub checkpoint_db_commit_latency_transactions: Histogram,
+    pub checkpoint_db_commit_latency_events: Histogram,
+    pub checkpoint_db_commit_latency_packages: Histogram,
+    pub checkpoint_db_commit_latency_move_calls: Histogram,
+    pub checkpoint_db_commit_latency_recipients: Histogram,
+    pub checkpoint_db_commit_latency_addresses: Histogram,
     pub epoch_db_commit_latency: Histogram,
     // latency of event websocket subscription
     pub subscription_process_latency: Histogram,
     pub transaction_per_checkpoint: Histogram,
+    pub object_fetch_batch_size: Histogram,
+    // labeled by ObjectStatus, deleted or unwrapped_then_deleted
+    pub deleted_objects_per_checkpoint: HistogramVec,
+    pub wrapped_objects_per_checkpoint: Histogram,
 }
 
 impl IndexerCheckpointHandlerMetrics {
@@ -62,12 +91,85 @@ impl IndexerCheckpointHandlerMetrics {
                 registry,
             )
             .unwrap(),
+            total_objects_fetched: register_int_counter_with_registry!(
+                "total_objects_fetched",
+                "Total number of changed objects fetched from fullnode",
+                registry,
+            )
+            .unwrap(),
+            packages_published_total: register_int_counter_with_registry!(
+                "packages_published_total",
+                "Total number of packages published in committed checkpoints, upgrades included",
+                registry,
+            )
+            .unwrap(),
+            packages_upgraded_total: register_int_counter_with_registry!(
+                "packages_upgraded_total",
+                "Total number of packages published in committed checkpoints as upgrades",
+                registry,
+            )
+            .unwrap(),
+            partial_refetch_total: register_int_counter_with_registry!(
+                "partial_refetch_total",
+                "Total number of re-requests of the changed objects missing from a fullnode response",
+                registry,
+            )
+            .unwrap(),
             total_epoch_committed: register_int_counter_with_registry!(
                 "total_epoch_committed",
                 "Total number of epoch committed",
                 registry,
             )
             .unwrap(),
+            total_checkpoint_quarantined: register_int_counter_with_registry!(
+                "total_checkpoint_quarantined",
+                "Total number of checkpoint skipped after repeatedly failing to be indexed",
+                registry,
+            )
+            .unwrap(),
+            event_deserialize_errors_total: register_int_counter_with_registry!(
+                "event_deserialize_errors_total",
+                "Total number of events which failed to deserialize",
+                registry,
+            )
+            .unwrap(),
+            epoch_committee_mismatches_total: register_int_counter_with_registry!(
+                "epoch_committee_mismatches_total",
+                "Total number of epoch boundaries whose next epoch committee differs from the active validators of the new epoch",
+                registry,
+            )
+            .unwrap(),
+            db_commit_retries_total: register_int_counter_with_registry!(
+                "db_commit_retries_total",
+                "Total number of retried checkpoint and epoch commits to DB",
+                registry,
+            )
+            .unwrap(),
+            fullnode_read_errors_total: register_int_counter_vec_with_registry!(
+                "fullnode_read_errors_total",
+                "Total number of failed fullnode reads by kind of error",
+                &["kind"],
+                registry,
+            )
+            .unwrap(),
+            last_committed_checkpoint_sequence_number: register_int_gauge_with_registry!(
+                "last_committed_checkpoint_sequence_number",
+                "Sequence number of the last committed checkpoint",
+                registry,
+            )
+            .unwrap(),
+            last_committed_checkpoint_timestamp_ms: register_int_gauge_with_registry!(
+                "last_committed_checkpoint_timestamp_ms",
+                "Timestamp in milli-seconds of the last committed checkpoint",
+                registry,
+            )
+            .unwrap(),
+            download_commit_gap: register_int_gauge_with_registry!(
+                "download_commit_gap",
+                "Sequence number of the latest downloaded checkpoint minus that of the latest committed one",
+                registry,
+            )
+            .unwrap(),
             fullnode_checkpoint_wait_and_download_latency: register_histogram_with_registry!(
                 "fullnode_checkpoint_wait_and_download_latency",
                 "Time spent in waiting for a new checkpoint from the Full Node",
@@ -110,6 +212,55 @@ impl IndexerCheckpointHandlerMetrics {
                 registry,
             )
             .unwrap(),
+            checkpoint_db_commit_latency_objects: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_objects",
+                "Time spent commiting the objects of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_transactions: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_transactions",
+                "Time spent commiting the transactions of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_events: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_events",
+                "Time spent commiting the events of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_packages: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_packages",
+                "Time spent commiting the packages of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_move_calls: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_move_calls",
+                "Time spent commiting the move_calls of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_recipients: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_recipients",
+                "Time spent commiting the recipients of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
+            checkpoint_db_commit_latency_addresses: register_histogram_with_registry!(
+                "checkpoint_db_commit_latency_addresses",
+                "Time spent commiting the addresses of a checkpoint to the db",
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
             epoch_db_commit_latency: register_histogram_with_registry!(
                 "epoch_db_commit_latency",
                 "Time spent commiting a epoch to the db",
@@ -131,6 +282,37 @@ impl IndexerCheckpointHandlerMetrics {
                 registry,
             )
             .unwrap(),
+            object_fetch_batch_size: register_histogram_with_registry!(
+                "object_fetch_batch_size",
+                "Number of changed objects fetched per checkpoint",
+                vec![
+                    1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0,
+                    10000.0, 20000.0, 50000.0
+                ],
+                registry,
+            )
+            .unwrap(),
+            deleted_objects_per_checkpoint: register_histogram_vec_with_registry!(
+                "deleted_objects_per_checkpoint",
+                "Number of objects deleted per checkpoint by status",
+                &["status"],
+                vec![
+                    1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0,
+                    10000.0, 20000.0, 50000.0
+                ],
+                registry,
+            )
+            .unwrap(),
+            wrapped_objects_per_checkpoint: register_histogram_with_registry!(
+                "wrapped_objects_per_checkpoint",
+                "Number of objects wrapped per checkpoint",
+                vec![
+                    1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0,
+                    10000.0, 20000.0, 50000.0
+                ],
+                registry,
+            )
+            .unwrap(),
         }
     }
 }
@@ -1,14 +1,66 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+
 use diesel::prelude::*;
 
-use crate::schema::addresses;
+use crate::schema::{address_activity, addresses};
 
-#[derive(Queryable, Insertable, Debug)]
+#[derive(Queryable, Insertable, Debug, Clone)]
 #[diesel(table_name = addresses, primary_key(account_address))]
 pub struct Address {
     pub account_address: String,
     pub first_appearance_tx: String,
     pub first_appearance_time: i64,
 }
+
+#[derive(Queryable, Insertable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = address_activity, primary_key(account_address))]
+pub struct AddressActivity {
+    pub account_address: String,
+    pub first_appearance_checkpoint: i64,
+    pub first_appearance_timestamp: i64,
+    pub last_appearance_checkpoint: i64,
+    pub last_appearance_timestamp: i64,
+}
+
+impl AddressActivity {
+    /// One activity row per distinct address appearing in a checkpoint.
+    pub fn from_checkpoint(
+        addresses: &[Address],
+        checkpoint: u64,
+        timestamp_ms: u64,
+    ) -> Vec<AddressActivity> {
+        addresses
+            .iter()
+            .map(|address| {
+                (
+                    address.account_address.clone(),
+                    AddressActivity {
+                        account_address: address.account_address.clone(),
+                        first_appearance_checkpoint: checkpoint as i64,
+                        first_appearance_timestamp: timestamp_ms as i64,
+                        last_appearance_checkpoint: checkpoint as i64,
+                        last_appearance_timestamp: timestamp_ms as i64,
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .collect()
+    }
+
+    /// Widens the activity window to also cover `other`, regardless of the order in which
+    /// the two were indexed.
+    pub fn merge(&mut self, other: &AddressActivity) {
+        if other.first_appearance_checkpoint < self.first_appearance_checkpoint {
+            self.first_appearance_checkpoint = other.first_appearance_checkpoint;
+            self.first_appearance_timestamp = other.first_appearance_timestamp;
+        }
+        if other.last_appearance_checkpoint > self.last_appearance_checkpoint {
+            self.last_appearance_checkpoint = other.last_appearance_checkpoint;
+            self.last_appearance_timestamp = other.last_appearance_timestamp;
+        }
+    }
+}
@@ -39,11 +39,11 @@ impl Checkpoint {
         rpc_checkpoint: &RpcCheckpoint,
         total_transactions: i64,
     ) -> Result<Self, IndexerError> {
-        let total_gas_cost = rpc_checkpoint
-            .epoch_rolling_gas_cost_summary
-            .computation_cost
-            + rpc_checkpoint.epoch_rolling_gas_cost_summary.storage_cost
-            - rpc_checkpoint.epoch_rolling_gas_cost_summary.storage_rebate;
+        // NOTE: the storage rebate can exceed the costs, so the total can be negative.
+        let gas_cost_summary = &rpc_checkpoint.epoch_rolling_gas_cost_summary;
+        let total_gas_cost = gas_cost_summary.computation_cost as i64
+            + gas_cost_summary.storage_cost as i64
+            - gas_cost_summary.storage_rebate as i64;
 
         let checkpoint_transactions: Vec<Option<String>> = rpc_checkpoint
             .transactions
@@ -58,7 +58,7 @@ impl Checkpoint {
             transactions: checkpoint_transactions,
             previous_checkpoint_digest: rpc_checkpoint.previous_digest.map(|d| d.base58_encode()),
             end_of_epoch: rpc_checkpoint.end_of_epoch_data.is_some(),
-            total_gas_cost: total_gas_cost as i64,
+            total_gas_cost,
             total_computation_cost: rpc_checkpoint
                 .epoch_rolling_gas_cost_summary
                 .computation_cost as i64,
@@ -140,3 +140,21 @@ impl Checkpoint {
         })
     }
 }
+
+/// Transaction blocks per second over `checkpoints`, in sequence number order.
+/// The transactions of the first checkpoint happened before the measured time span and
+/// are not counted. 0 when the checkpoints span no time, e.g. all share a timestamp.
+pub fn compute_tps(checkpoints: &[Checkpoint]) -> f64 {
+    let elapsed_ms = match (checkpoints.first(), checkpoints.last()) {
+        (Some(first), Some(last)) => last.timestamp_ms - first.timestamp_ms,
+        _ => return 0.0,
+    };
+    if elapsed_ms <= 0 {
+        return 0.0;
+    }
+    let transactions = checkpoints[1..]
+        .iter()
+        .map(|cp| cp.total_transaction_blocks)
+        .sum::<i64>();
+    transactions as f64 * 1000.0 / elapsed_ms as f64
+}
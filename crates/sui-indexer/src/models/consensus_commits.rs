@@ -0,0 +1,46 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use diesel::prelude::*;
+
+use sui_json_rpc_types::{SuiTransactionBlockDataAPI, SuiTransactionBlockKind};
+
+use crate::schema::consensus_commits;
+use crate::types::CheckpointTransactionBlockResponse;
+
+/// The consensus commit a checkpoint was built from. Its commit timestamp is set by
+/// consensus and differs from the checkpoint timestamp.
+#[derive(Queryable, Insertable, Debug, Clone, Default, PartialEq, Eq)]
+#[diesel(table_name = consensus_commits)]
+pub struct ConsensusCommit {
+    pub checkpoint_sequence_number: i64,
+    pub epoch: i64,
+    pub round: i64,
+    pub commit_timestamp_ms: i64,
+    pub checkpoint_timestamp_ms: i64,
+    pub transaction_digest: String,
+}
+
+impl ConsensusCommit {
+    /// Extracts the consensus commit from the first `ConsensusCommitPrologue` transaction
+    /// of a checkpoint, if there is any.
+    pub fn from_transactions(
+        checkpoint_sequence_number: u64,
+        checkpoint_timestamp_ms: u64,
+        transactions: &[CheckpointTransactionBlockResponse],
+    ) -> Option<Self> {
+        transactions
+            .iter()
+            .find_map(|tx| match tx.transaction.data.transaction() {
+                SuiTransactionBlockKind::ConsensusCommitPrologue(prologue) => Some(Self {
+                    checkpoint_sequence_number: checkpoint_sequence_number as i64,
+                    epoch: prologue.epoch as i64,
+                    round: prologue.round as i64,
+                    commit_timestamp_ms: prologue.commit_timestamp_ms as i64,
+                    checkpoint_timestamp_ms: checkpoint_timestamp_ms as i64,
+                    transaction_digest: tx.digest.base58_encode(),
+                }),
+                _ => None,
+            })
+    }
+}
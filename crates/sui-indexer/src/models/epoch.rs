@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+
 use diesel::{Insertable, Queryable};
 use jsonrpsee::core::__reexports::serde::Deserialize;
 
@@ -38,6 +40,119 @@ pub struct DBEpochInfo {
     pub leftover_storage_fund_inflow: Option<i64>,
 }
 
+/// Economics of an epoch taken from its closing `SystemEpochInfoEvent`, the fields are
+/// None while the epoch is ongoing or when the event was not indexed.
+#[derive(Queryable, Debug, Clone, PartialEq, Eq)]
+pub struct EpochEconomics {
+    pub epoch: i64,
+    pub total_gas_fees: Option<i64>,
+    pub storage_charge: Option<i64>,
+    pub storage_rebate: Option<i64>,
+    pub storage_fund_reinvestment: Option<i64>,
+    pub stake_subsidy_amount: Option<i64>,
+    pub total_stake_rewards_distributed: Option<i64>,
+    pub storage_fund_balance: Option<i64>,
+    pub leftover_storage_fund_inflow: Option<i64>,
+}
+
+impl From<&DBEpochInfo> for EpochEconomics {
+    fn from(epoch: &DBEpochInfo) -> Self {
+        Self {
+            epoch: epoch.epoch,
+            total_gas_fees: epoch.total_gas_fees,
+            storage_charge: epoch.storage_charge,
+            storage_rebate: epoch.storage_rebate,
+            storage_fund_reinvestment: epoch.storage_fund_reinvestment,
+            stake_subsidy_amount: epoch.stake_subsidy_amount,
+            total_stake_rewards_distributed: epoch.total_stake_rewards_distributed,
+            storage_fund_balance: epoch.storage_fund_balance,
+            leftover_storage_fund_inflow: epoch.leftover_storage_fund_inflow,
+        }
+    }
+}
+
+const MS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Return of staking over an epoch, annualized by compounding it over a year of epochs
+/// as long as this one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochApy {
+    pub epoch: i64,
+    pub apy: f64,
+}
+
+/// Annualized return of staking over a window of ended epochs. Epochs missing the stake,
+/// rewards or duration their return is computed from are skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StakeApy {
+    /// APY of each epoch of the window with the data for it, in epoch order.
+    pub epochs: Vec<EpochApy>,
+    /// APY of the returns of these epochs compounded over their total duration, None when
+    /// none of them had the data.
+    pub trailing_apy: Option<f64>,
+}
+
+impl StakeApy {
+    /// APY of staking with the whole network over `epochs` but the first one, which is the
+    /// epoch before the window. An epoch earns its `total_stake_rewards_distributed` on the
+    /// stake it starts with, the `total_stake` of the epoch before it.
+    pub fn of_network(epochs: &[DBEpochInfo]) -> Self {
+        Self::from_returns(epochs.windows(2).filter_map(|pair| {
+            let (previous, epoch) = (&pair[0], &pair[1]);
+            if previous.epoch + 1 != epoch.epoch {
+                return None;
+            }
+            let stake = previous.total_stake.filter(|stake| *stake > 0)?;
+            let rewards = epoch.total_stake_rewards_distributed?;
+            Some((epoch, rewards as f64 / stake as f64))
+        }))
+    }
+
+    /// APY of staking with a validator over `epochs`, out of its `states` at the start of
+    /// these epochs and of the epoch after them. The return of an epoch is the growth over
+    /// it of the SUI a token of the staking pool of the validator is worth.
+    pub fn of_validator(epochs: &[DBEpochInfo], states: &[DBValidatorSummary]) -> Self {
+        let exchange_rates = states
+            .iter()
+            .filter(|state| state.pool_token_balance > 0)
+            .map(|state| {
+                let rate = state.staking_pool_sui_balance as f64 / state.pool_token_balance as f64;
+                (state.epoch, rate)
+            })
+            .collect::<BTreeMap<_, _>>();
+        Self::from_returns(epochs.iter().filter_map(|epoch| {
+            let start = exchange_rates.get(&epoch.epoch)?;
+            let end = exchange_rates.get(&(epoch.epoch + 1))?;
+            Some((epoch, end / start - 1.0))
+        }))
+    }
+
+    fn from_returns<'a>(returns: impl Iterator<Item = (&'a DBEpochInfo, f64)>) -> Self {
+        let mut apy = Self::default();
+        let mut growth = 1.0;
+        let mut total_duration_ms = 0;
+        for (epoch, rate) in returns {
+            let duration_ms = match epoch.epoch_end_timestamp {
+                Some(end) if end > epoch.epoch_start_timestamp => end - epoch.epoch_start_timestamp,
+                _ => continue,
+            };
+            apy.epochs.push(EpochApy {
+                epoch: epoch.epoch,
+                apy: annualize(rate, duration_ms),
+            });
+            growth *= 1.0 + rate;
+            total_duration_ms += duration_ms;
+        }
+        apy.trailing_apy =
+            (total_duration_ms > 0).then(|| annualize(growth - 1.0, total_duration_ms));
+        apy
+    }
+}
+
+fn annualize(rate: f64, duration_ms: i64) -> f64 {
+    (1.0 + rate).powf(MS_PER_YEAR / duration_ms as f64) - 1.0
+}
+
 impl DBEpochInfo {
     pub fn to_epoch_info(
         self,
@@ -30,10 +30,31 @@ pub struct Event {
     pub event_type: String,
     pub event_time_ms: Option<i64>,
     pub event_bcs: Vec<u8>,
+    pub checkpoint_sequence_number: i64,
+    pub event_index: i64,
 }
 
-impl From<SuiEvent> for Event {
-    fn from(se: SuiEvent) -> Self {
+/// Position of an event in the chain, which unlike the `id` column doesn't change
+/// when the event is indexed again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventCursor {
+    pub checkpoint_sequence_number: i64,
+    pub event_index: i64,
+}
+
+impl From<&Event> for EventCursor {
+    fn from(event: &Event) -> Self {
+        Self {
+            checkpoint_sequence_number: event.checkpoint_sequence_number,
+            event_index: event.event_index,
+        }
+    }
+}
+
+impl Event {
+    /// `event_index` is the position of the event among all events of checkpoint
+    /// `checkpoint_sequence_number`.
+    pub fn new(se: SuiEvent, checkpoint_sequence_number: i64, event_index: i64) -> Self {
         Self {
             id: None,
             transaction_digest: se.id.tx_digest.base58_encode(),
@@ -44,11 +65,11 @@ impl From<SuiEvent> for Event {
             event_type: se.type_.to_string(),
             event_time_ms: se.timestamp_ms.map(|t| t as i64),
             event_bcs: se.bcs,
+            checkpoint_sequence_number,
+            event_index,
         }
     }
-}
 
-impl Event {
     pub fn try_into(self, module_cache: &impl GetModule) -> Result<SuiEvent, IndexerError> {
         // Event in this table is always MoveEvent
         let package_id = self.package.parse().map_err(|e| {
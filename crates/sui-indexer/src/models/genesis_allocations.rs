@@ -0,0 +1,42 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use diesel::prelude::*;
+
+use sui_json_rpc_types::{SuiObjectData, SuiRawData};
+use sui_types::coin::Coin;
+use sui_types::object::Owner;
+
+use crate::errors::IndexerError;
+use crate::schema::genesis_allocations;
+
+/// A coin created by the genesis checkpoint, part of the initial token allocation.
+#[derive(Queryable, Insertable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = genesis_allocations)]
+pub struct GenesisAllocation {
+    pub object_id: String,
+    pub owner_address: String,
+    pub coin_type: String,
+    pub amount: i64,
+}
+
+impl GenesisAllocation {
+    /// The allocation of `object`, None unless it is a coin owned by an address.
+    pub fn try_from_object(object: &SuiObjectData) -> Result<Option<Self>, IndexerError> {
+        let (owner, move_object) = match (&object.owner, &object.bcs) {
+            (Some(Owner::AddressOwner(owner)), Some(SuiRawData::MoveObject(o))) => (owner, o),
+            _ => return Ok(None),
+        };
+        let coin_type = match move_object.type_.type_params.first() {
+            Some(coin_type) if Coin::is_coin(&move_object.type_) => coin_type,
+            _ => return Ok(None),
+        };
+        let coin = Coin::from_bcs_bytes(&move_object.bcs_bytes)?;
+        Ok(Some(Self {
+            object_id: object.object_id.to_string(),
+            owner_address: owner.to_string(),
+            coin_type: coin_type.to_string(),
+            amount: coin.value() as i64,
+        }))
+    }
+}
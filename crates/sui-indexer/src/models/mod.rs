@@ -3,12 +3,16 @@
 
 pub mod addresses;
 pub mod checkpoints;
+pub mod consensus_commits;
 pub mod epoch;
 pub mod events;
+pub mod genesis_allocations;
 pub mod network_metrics;
 pub mod objects;
+pub mod owner_object_counts;
 pub mod owners;
 pub mod packages;
+pub mod quarantined_checkpoints;
 pub mod system_state;
 pub mod transaction_index;
 pub mod transactions;
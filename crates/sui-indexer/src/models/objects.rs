@@ -10,9 +10,8 @@ use diesel::serialize::{Output, ToSql, WriteTuple};
 use diesel::sql_types::{Bytea, Nullable, Record, VarChar};
 use diesel::SqlType;
 use diesel_derive_enum::DbEnum;
-use fastcrypto::encoding::{Base64, Encoding};
 use serde::{Deserialize, Serialize};
-use serde_json;
+use tracing::warn;
 
 use move_bytecode_utils::module_cache::GetModule;
 use sui_json_rpc_types::{SuiObjectData, SuiObjectRef, SuiRawData};
@@ -27,6 +26,9 @@ use crate::schema::objects;
 use crate::schema::sql_types::BcsBytes;
 
 const OBJECT: &str = "object";
+// Leads the name of a zstd-compressed BCS blob. Names are otherwise `object` or a module
+// name, neither of which can contain a colon, so raw and legacy rows never look compressed.
+const COMPRESSED_BCS_PREFIX: &str = "zstd:";
 
 // NOTE: please add updating statement like below in pg_indexer_store.rs,
 // if new columns are added here:
@@ -70,6 +72,47 @@ impl FromSql<Nullable<BcsBytes>, Pg> for NamedBcsBytes {
     }
 }
 
+impl NamedBcsBytes {
+    pub fn is_compressed(&self) -> bool {
+        self.0.starts_with(COMPRESSED_BCS_PREFIX)
+    }
+
+    /// Compresses the BCS with zstd, kept as-is if already compressed or if it does not
+    /// get any smaller.
+    pub fn compress(self) -> Result<Self, IndexerError> {
+        if self.is_compressed() {
+            return Ok(self);
+        }
+        let compressed =
+            zstd::bulk::compress(&self.1, zstd::DEFAULT_COMPRESSION_LEVEL).map_err(|e| {
+                IndexerError::SerdeError(format!(
+                    "Failed to compress BCS of {} with error: {:?}",
+                    self.0, e
+                ))
+            })?;
+        Ok(if compressed.len() < self.1.len() {
+            NamedBcsBytes(format!("{COMPRESSED_BCS_PREFIX}{}", self.0), compressed)
+        } else {
+            self
+        })
+    }
+
+    /// The raw BCS, for both compressed and legacy uncompressed BCS.
+    pub fn decompress(self) -> Result<Self, IndexerError> {
+        if !self.is_compressed() {
+            return Ok(self);
+        }
+        let name = self.0[COMPRESSED_BCS_PREFIX.len()..].to_string();
+        let bcs = zstd::stream::decode_all(self.1.as_slice()).map_err(|e| {
+            IndexerError::SerdeError(format!(
+                "Failed to decompress BCS of {} with error: {:?}",
+                name, e
+            ))
+        })?;
+        Ok(NamedBcsBytes(name, bcs))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DeletedObject {
     // epoch id in which this object got deleted.
@@ -108,7 +151,7 @@ impl From<DeletedObject> for Object {
     }
 }
 
-#[derive(DbEnum, Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(DbEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[ExistingTypePath = "crate::schema::sql_types::ObjectStatus"]
 #[serde(rename_all = "snake_case")]
 pub enum ObjectStatus {
@@ -120,7 +163,41 @@ pub enum ObjectStatus {
     UnwrappedThenDeleted,
 }
 
+impl ObjectStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Mutated => "mutated",
+            Self::Deleted => "deleted",
+            Self::Wrapped => "wrapped",
+            Self::Unwrapped => "unwrapped",
+            Self::UnwrappedThenDeleted => "unwrapped_then_deleted",
+        }
+    }
+}
+
 impl Object {
+    /// Compresses the BCS to store, reads decompress it transparently.
+    pub fn compress_bcs(&mut self) -> Result<(), IndexerError> {
+        self.bcs = std::mem::take(&mut self.bcs)
+            .into_iter()
+            .map(NamedBcsBytes::compress)
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// The address owning this object, None unless it is a live address-owned object.
+    pub fn live_address_owner(&self) -> Option<&str> {
+        let live = !matches!(
+            self.object_status,
+            ObjectStatus::Deleted | ObjectStatus::Wrapped | ObjectStatus::UnwrappedThenDeleted
+        );
+        match self.owner_type {
+            OwnerType::AddressOwner if live => self.owner_address.as_deref(),
+            _ => None,
+        }
+    }
+
     pub fn from(
         epoch: u64,
         checkpoint: Option<u64>,
@@ -223,11 +300,15 @@ impl TryFrom<Object> for sui_types::object::Object {
             OwnerType::Immutable => Owner::Immutable,
         };
         let previous_transaction = TransactionDigest::from_str(&o.previous_transaction)?;
+        let bcs = o
+            .bcs
+            .into_iter()
+            .map(NamedBcsBytes::decompress)
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(match object_type {
             ObjectType::Package => {
-                let modules = o
-                    .bcs
+                let modules = bcs
                     .into_iter()
                     .map(|NamedBcsBytes(name, bytes)| (name, bytes))
                     .collect();
@@ -254,8 +335,7 @@ impl TryFrom<Object> for sui_types::object::Object {
             }
             // Reconstructing MoveObject form database table, move VM safety concern is irrelevant here.
             ObjectType::Struct(object_type) => unsafe {
-                let content = o
-                    .bcs
+                let content = bcs
                     .first()
                     .expect("BCS content should not be empty")
                     .1
@@ -322,128 +402,6 @@ pub fn owner_to_owner_info(owner: &Owner) -> (OwnerType, Option<String>, Option<
     }
 }
 
-pub fn compose_object_bulk_insert_update_query(objects: &[Object]) -> String {
-    let insert_query = compose_object_bulk_insert_query(objects)
-        .as_str()
-        .trim_matches(';')
-        .to_string();
-    let insert_update_query = format!(
-        "{} ON CONFLICT (object_id) 
-        DO UPDATE SET 
-            epoch = EXCLUDED.epoch,
-            checkpoint = EXCLUDED.checkpoint,
-            version = EXCLUDED.version,
-            object_digest = EXCLUDED.object_digest,
-            owner_type = EXCLUDED.owner_type,
-            owner_address = EXCLUDED.owner_address,
-            initial_shared_version = EXCLUDED.initial_shared_version,
-            previous_transaction = EXCLUDED.previous_transaction,
-            object_type = EXCLUDED.object_type,
-            object_status = EXCLUDED.object_status,
-            has_public_transfer = EXCLUDED.has_public_transfer,
-            storage_rebate = EXCLUDED.storage_rebate,
-            bcs = EXCLUDED.bcs;",
-        insert_query
-    );
-    insert_update_query
-}
-
-pub fn compose_object_bulk_insert_query(objects: &[Object]) -> String {
-    // Construct an array of rows to insert into the `objects` table
-    let rows = objects
-        .iter()
-        .map(|obj| {
-            let bcs_rows = obj
-                .bcs
-                .iter()
-                .map(|bcs| (bcs.0.clone(), bcs.1.clone()))
-                .collect::<Vec<_>>();
-            let owner_type_str = serde_json::to_string(&obj.owner_type)
-                .unwrap()
-                .trim_matches('"')
-                .to_string();
-            let object_status_str = serde_json::to_string(&obj.object_status)
-                .unwrap()
-                .trim_matches('"')
-                .to_string();
-            (
-                obj.epoch,
-                obj.checkpoint,
-                obj.object_id.to_string(),
-                obj.version,
-                obj.object_digest.clone(),
-                owner_type_str,
-                obj.owner_address.clone(),
-                obj.initial_shared_version,
-                obj.previous_transaction.clone(),
-                obj.object_type.clone(),
-                object_status_str,
-                obj.has_public_transfer,
-                obj.storage_rebate,
-                bcs_rows,
-            )
-        })
-        .collect::<Vec<_>>();
-
-    let rows_query = rows
-        .iter()
-        .map(|row| {
-            let (epoch, checkpoint, object_id, version, object_digest, owner_type, owner_address, initial_shared_version, previous_transaction, object_type, object_status, has_public_transfer, storage_rebate, bcs_rows) = row;
-
-            let bcs_rows_query = bcs_rows
-                .iter()
-                .map(|bcs_row| {
-                    let (bcs_key, bcs_value) = bcs_row;
-                    let bytea_str = format!("decode('{}', 'base64')", Base64::encode(bcs_value));
-                    format!(
-                        "ROW('{}', {})",
-                        bcs_key,
-                        bytea_str
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!(
-                "ROW({}::BIGINT, {}::BIGINT, '{}'::address, {}::BIGINT, '{}'::base58digest, '{}'::owner_type, 
-                     '{}'::address, {}::BIGINT, '{}'::base58digest, '{}'::VARCHAR, '{}'::object_status,
-                     {}::BOOLEAN, {}::BIGINT, ARRAY[{}]::bcs_bytes[])",
-                epoch,
-                checkpoint,
-                object_id,
-                version,
-                object_digest,
-                &owner_type,
-                owner_address
-                    .as_ref()
-                    .map(|addr| addr.to_string())
-                    .unwrap_or_else(|| "".to_string()),
-                initial_shared_version
-                    .as_ref()
-                    .map(|v| v.to_string())
-                    .unwrap_or_else(|| "NULL".to_string()),
-                previous_transaction,
-                object_type,
-                &object_status,
-                has_public_transfer,
-                storage_rebate,
-                bcs_rows_query,
-            )
-        })
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    // Construct a prepared statement with placeholders for each row element
-    let bulk_insert_query = format!(
-        "INSERT INTO objects
-            (epoch, checkpoint, object_id, version, object_digest, owner_type, owner_address, initial_shared_version, previous_transaction, object_type, object_status, has_public_transfer, storage_rebate, bcs)
-        SELECT (unnest_arr).*
-        FROM unnest(ARRAY[{}]::record[]) 
-        AS unnest_arr(epoch BIGINT, checkpoint BIGINT, object_id address, version BIGINT, object_digest base58digest, owner_type owner_type, owner_address address, initial_shared_version BIGINT, previous_transaction base58digest, object_type VARCHAR, object_status object_status, has_public_transfer BOOLEAN, storage_rebate BIGINT, bcs bcs_bytes[]);",
-        rows_query
-    );
-    bulk_insert_query
-}
-
 pub fn group_and_sort_objects(objects: Vec<Object>) -> Vec<Vec<Object>> {
     let mut objects_sorted = objects;
     objects_sorted.sort_by(|a, b| a.object_id.cmp(&b.object_id));
@@ -470,3 +428,81 @@ pub fn group_and_sort_objects(objects: Vec<Object>) -> Vec<Vec<Object>> {
     }
     groups
 }
+
+/// Drops, with a warning, any change of `objects` older than the version already stored for
+/// the object, so that a fullnode serving stale data can't roll newer state back. The rest of
+/// the checkpoint still commits, retrying it would only meet the same stored version again.
+pub fn skip_stale_object_versions(
+    objects: Vec<Object>,
+    stored_version: impl Fn(&str) -> Option<i64>,
+) -> Vec<Object> {
+    objects
+        .into_iter()
+        .filter(|object| match stored_version(&object.object_id) {
+            Some(stored_version) if object.version < stored_version => {
+                let err = IndexerError::NonMonotonicObjectVersion {
+                    object_id: object.object_id.clone(),
+                    version: object.version,
+                    stored_version,
+                };
+                warn!(checkpoint = object.checkpoint, "{}, skipping it", err);
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use sui_json_rpc_types::SuiRawData;
+    use sui_types::SUI_FRAMEWORK_OBJECT_ID;
+
+    use crate::models::objects::{NamedBcsBytes, Object};
+    use crate::test_utils::TestCheckpointDataBuilder;
+
+    #[test]
+    fn test_compress_object_bcs_round_trip() {
+        let genesis = TestCheckpointDataBuilder::new().genesis_checkpoint();
+        let (status, framework) = genesis
+            .changed_objects
+            .iter()
+            .find(|(_, o)| o.object_id == SUI_FRAMEWORK_OBJECT_ID)
+            .unwrap();
+        assert!(matches!(framework.bcs, Some(SuiRawData::Package(_))));
+        let object = Object::from(0, Some(0), status, framework);
+
+        let mut compressed = object.clone();
+        compressed.compress_bcs().unwrap();
+        let size = |o: &Object| o.bcs.iter().map(|bcs| bcs.1.len()).sum::<usize>();
+        assert!(size(&compressed) < size(&object));
+        assert!(compressed.bcs.iter().any(NamedBcsBytes::is_compressed));
+        // Compressing again is a no-op.
+        let mut recompressed = compressed.clone();
+        recompressed.compress_bcs().unwrap();
+        assert_eq!(size(&recompressed), size(&compressed));
+
+        let expected: sui_types::object::Object = object.try_into().unwrap();
+        let decompressed: sui_types::object::Object = compressed.try_into().unwrap();
+        assert_eq!(decompressed, expected);
+    }
+
+    #[test]
+    fn test_read_uncompressed_object_bcs() {
+        // Raw BCS holding a whole zstd frame is still read as raw.
+        let mut raw = vec![1];
+        raw.extend(zstd::bulk::compress(&[7u8; 64], zstd::DEFAULT_COMPRESSION_LEVEL).unwrap());
+        let bcs = NamedBcsBytes("object".to_string(), raw);
+        assert!(!bcs.is_compressed());
+        assert_eq!(bcs.clone().decompress().unwrap().1, bcs.1);
+
+        let bcs = NamedBcsBytes("object".to_string(), vec![1, 0x28, 0xB5, 0x2F, 0xFD]);
+        assert!(!bcs.is_compressed());
+        assert_eq!(bcs.clone().decompress().unwrap().1, bcs.1);
+
+        // Too small to get any smaller
+        let compressed = bcs.clone().compress().unwrap();
+        assert!(!compressed.is_compressed());
+        assert_eq!(compressed.1, bcs.1);
+    }
+}
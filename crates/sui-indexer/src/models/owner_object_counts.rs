@@ -0,0 +1,52 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use diesel::prelude::*;
+
+use crate::models::objects::Object;
+use crate::schema::owner_object_counts;
+
+/// Number of live objects owned by an address. Also used for the change of that number
+/// when objects are committed, which is added to the stored count.
+#[derive(Queryable, Insertable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = owner_object_counts)]
+pub struct OwnerObjectCount {
+    pub owner_address: String,
+    pub object_count: i64,
+}
+
+/// Changes of the owner object counts when `objects` replace the stored objects, whose
+/// owners are given by `stored_owner` as `Object::live_address_owner` would.
+/// Only the latest version of each object is applied, and owners whose count does not
+/// change are left out.
+pub fn owner_object_count_changes<'a>(
+    objects: impl IntoIterator<Item = &'a Object>,
+    stored_owner: impl Fn(&str) -> Option<String>,
+) -> Vec<OwnerObjectCount> {
+    let mut latest = BTreeMap::<&str, &Object>::new();
+    for object in objects {
+        let entry = latest.entry(&object.object_id).or_insert(object);
+        if object.version > entry.version {
+            *entry = object;
+        }
+    }
+    let mut changes = BTreeMap::<String, i64>::new();
+    for (object_id, object) in latest {
+        if let Some(owner) = stored_owner(object_id) {
+            *changes.entry(owner).or_default() -= 1;
+        }
+        if let Some(owner) = object.live_address_owner() {
+            *changes.entry(owner.to_string()).or_default() += 1;
+        }
+    }
+    changes
+        .into_iter()
+        .filter(|(_, change)| *change != 0)
+        .map(|(owner_address, object_count)| OwnerObjectCount {
+            owner_address,
+            object_count,
+        })
+        .collect()
+}
@@ -0,0 +1,33 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use diesel::prelude::*;
+
+use sui_json_rpc_types::Checkpoint as RpcCheckpoint;
+
+use crate::errors::IndexerError;
+use crate::schema::quarantined_checkpoints;
+
+/// A checkpoint which repeatedly failed to be indexed and was skipped so that the
+/// checkpoint handler could advance. It is left for an operator to reindex.
+#[derive(Queryable, Insertable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = quarantined_checkpoints)]
+pub struct QuarantinedCheckpoint {
+    pub sequence_number: i64,
+    pub epoch: i64,
+    pub checkpoint_digest: String,
+    pub attempts: i64,
+    pub error: String,
+}
+
+impl QuarantinedCheckpoint {
+    pub fn new(checkpoint: &RpcCheckpoint, attempts: usize, error: &IndexerError) -> Self {
+        Self {
+            sequence_number: checkpoint.sequence_number as i64,
+            epoch: checkpoint.epoch as i64,
+            checkpoint_digest: checkpoint.digest.base58_encode(),
+            attempts: attempts as i64,
+            error: error.to_string(),
+        }
+    }
+}
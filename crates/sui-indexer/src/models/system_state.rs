@@ -115,6 +115,33 @@ pub struct DBValidatorSummary {
     pub exchange_rates_size: i64,
 }
 
+/// Settings of a validator which can change from an epoch to the next, out of its row of an
+/// epoch in the `validators` table.
+#[derive(Queryable, Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorEpoch {
+    pub epoch: i64,
+    pub commission_rate: i64,
+    pub next_epoch_commission_rate: i64,
+    pub gas_price: i64,
+    pub next_epoch_gas_price: i64,
+    pub voting_power: i64,
+    pub next_epoch_stake: i64,
+}
+
+impl From<&DBValidatorSummary> for ValidatorEpoch {
+    fn from(validator: &DBValidatorSummary) -> Self {
+        Self {
+            epoch: validator.epoch,
+            commission_rate: validator.commission_rate,
+            next_epoch_commission_rate: validator.next_epoch_commission_rate,
+            gas_price: validator.gas_price,
+            next_epoch_gas_price: validator.next_epoch_gas_price,
+            voting_power: validator.voting_power,
+            next_epoch_stake: validator.next_epoch_stake,
+        }
+    }
+}
+
 impl From<(EpochId, SuiValidatorSummary)> for DBValidatorSummary {
     fn from((epoch, v): (EpochId, SuiValidatorSummary)) -> Self {
         Self {
@@ -1,8 +1,11 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::schema::{input_objects, move_calls, recipients};
+use crate::schema::{
+    input_objects, move_calls, ptb_commands, recipients, transaction_dependencies,
+};
 use diesel::prelude::*;
+use sui_types::parse_sui_type_tag;
 
 #[derive(Queryable, Insertable, Debug, Clone, Default)]
 #[diesel(table_name = input_objects)]
@@ -26,6 +29,35 @@ pub struct MoveCall {
     pub move_package: String,
     pub move_module: String,
     pub move_function: String,
+    pub move_type_arguments: Vec<Option<String>>,
+}
+
+#[derive(Queryable, Insertable, Debug, Clone, Default, PartialEq, Eq)]
+#[diesel(table_name = transaction_dependencies)]
+pub struct TransactionDependency {
+    pub transaction_digest: String,
+    pub depends_on_digest: String,
+    pub checkpoint_sequence_number: i64,
+}
+
+#[derive(Queryable, Insertable, Debug, Clone, Default, PartialEq, Eq)]
+#[diesel(table_name = ptb_commands)]
+pub struct PtbCommand {
+    pub transaction_digest: String,
+    pub command_index: i64,
+    pub checkpoint_sequence_number: i64,
+    pub epoch: i64,
+    pub command_kind: String,
+    pub package_id: Option<String>,
+}
+
+/// Type arguments are stored in the canonical display form of their type tag, so that
+/// e.g. `0x2::sui::SUI` and its fully expanded form match the same calls.
+/// Strings which don't parse as a type tag are kept as they are.
+pub fn normalize_type_argument(type_argument: &str) -> String {
+    parse_sui_type_tag(type_argument)
+        .map(|tag| tag.to_string())
+        .unwrap_or_else(|_| type_argument.to_string())
 }
 
 #[derive(Queryable, Insertable, Debug, Clone, Default)]
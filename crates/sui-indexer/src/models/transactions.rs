@@ -2,16 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+use fastcrypto::encoding::{Base64, Encoding};
+use fastcrypto::traits::ToFromBytes;
+use serde::{Deserialize, Serialize};
 
 use sui_json_rpc_types::{
     OwnedObjectRef, SuiObjectRef, SuiTransactionBlockDataAPI, SuiTransactionBlockEffectsAPI,
+    SuiTransactionBlockKind,
 };
+use sui_types::signature::GenericSignature;
 
 use crate::errors::IndexerError;
 use crate::schema::transactions;
 use crate::types::TemporaryTransactionBlockResponseStore;
 
-#[derive(Clone, Debug, Queryable, Insertable)]
+#[derive(Clone, Debug, Default, Queryable, Insertable)]
 #[diesel(table_name = transactions)]
 pub struct Transaction {
     #[diesel(deserialize_as = i64)]
@@ -44,6 +50,72 @@ pub struct Transaction {
     pub transaction_content: String,
     pub transaction_effects_content: String,
     pub confirmed_local_execution: Option<bool>,
+    pub kind: DBTransactionKind,
+    // Base64 encoded bytes of each GenericSignature
+    pub transaction_signatures: Vec<Option<String>>,
+    // partition key of the table
+    pub epoch: i64,
+}
+
+impl Transaction {
+    /// Decodes the signatures of the transaction, e.g. to reverify them against
+    /// `raw_transaction`.
+    pub fn signatures(&self) -> Result<Vec<GenericSignature>, IndexerError> {
+        self.transaction_signatures
+            .iter()
+            .flatten()
+            .map(|signature| {
+                let bytes = Base64::decode(signature).map_err(|e| {
+                    IndexerError::SerdeError(format!(
+                        "Failed decoding signature {signature} of transaction {} with error: {:?}",
+                        self.transaction_digest, e
+                    ))
+                })?;
+                GenericSignature::from_bytes(&bytes).map_err(|e| {
+                    IndexerError::SerdeError(format!(
+                        "Failed parsing signature {signature} of transaction {} with error: {:?}",
+                        self.transaction_digest, e
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Discriminant of `SuiTransactionBlockKind`, stored as an indexed enum column so that
+/// user transactions can be told apart from system ones without parsing the content.
+#[derive(DbEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[ExistingTypePath = "crate::schema::sql_types::TransactionKindType"]
+#[serde(rename_all = "snake_case")]
+pub enum DBTransactionKind {
+    ChangeEpoch,
+    Genesis,
+    ConsensusCommitPrologue,
+    #[default]
+    ProgrammableTransaction,
+}
+
+impl DBTransactionKind {
+    /// System transactions are submitted by validators rather than signed by a user;
+    /// their sender is the zero address.
+    pub fn is_system(&self) -> bool {
+        !matches!(self, DBTransactionKind::ProgrammableTransaction)
+    }
+}
+
+impl From<&SuiTransactionBlockKind> for DBTransactionKind {
+    fn from(kind: &SuiTransactionBlockKind) -> Self {
+        match kind {
+            SuiTransactionBlockKind::ChangeEpoch(_) => DBTransactionKind::ChangeEpoch,
+            SuiTransactionBlockKind::Genesis(_) => DBTransactionKind::Genesis,
+            SuiTransactionBlockKind::ConsensusCommitPrologue(_) => {
+                DBTransactionKind::ConsensusCommitPrologue
+            }
+            SuiTransactionBlockKind::ProgrammableTransaction(_) => {
+                DBTransactionKind::ProgrammableTransaction
+            }
+        }
+    }
 }
 
 impl TryFrom<TemporaryTransactionBlockResponseStore> for Transaction {
@@ -149,6 +221,13 @@ impl TryFrom<TemporaryTransactionBlockResponseStore> for Transaction {
             transaction_content: tx_json,
             transaction_effects_content: tx_effect_json,
             confirmed_local_execution,
+            kind: DBTransactionKind::from(transaction.data.transaction()),
+            transaction_signatures: transaction
+                .tx_signatures
+                .iter()
+                .map(|signature| Some(Base64::encode(signature)))
+                .collect(),
+            epoch: effects.executed_epoch() as i64,
         })
     }
 }
@@ -0,0 +1,144 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Protobuf projection of indexed checkpoints for stream consumers, see
+// `proto/checkpoint.proto` for the compatibility rules of its versions.
+
+use crate::models::objects::ObjectStatus;
+use crate::store::TemporaryCheckpointStore;
+
+#[path = "sui.indexer.v1.rs"]
+pub mod v1;
+
+impl From<&TemporaryCheckpointStore> for v1::Checkpoint {
+    fn from(indexed: &TemporaryCheckpointStore) -> Self {
+        let transactions = indexed
+            .transactions
+            .iter()
+            .map(|tx| v1::Transaction {
+                digest: tx.transaction_digest.clone(),
+                sender: tx.sender.clone(),
+                gas: Some(v1::GasSummary {
+                    gas_price: tx.gas_price,
+                    computation_cost: tx.computation_cost,
+                    storage_cost: tx.storage_cost,
+                    storage_rebate: tx.storage_rebate,
+                    non_refundable_storage_fee: tx.non_refundable_storage_fee,
+                }),
+            })
+            .collect();
+        let events = indexed
+            .events
+            .iter()
+            .map(|event| v1::Event {
+                transaction_digest: event.transaction_digest.clone(),
+                event_sequence: event.event_sequence,
+                sender: event.sender.clone(),
+                event_type: event.event_type.clone(),
+                bcs: event.event_bcs.clone(),
+            })
+            .collect();
+        let object_changes = indexed
+            .objects_changes
+            .iter()
+            .flat_map(|changes| {
+                let changed = changes.changed_objects.iter().map(|o| v1::ObjectChange {
+                    object_id: o.object_id.clone(),
+                    version: o.version,
+                    object_digest: o.object_digest.clone(),
+                    object_type: o.object_type.clone(),
+                    status: v1::ObjectStatus::from(o.object_status) as i32,
+                    owner_address: o.live_address_owner().map(str::to_string),
+                });
+                let deleted = changes.deleted_objects.iter().map(|o| v1::ObjectChange {
+                    object_id: o.object_id.clone(),
+                    version: o.version,
+                    object_digest: o.object_digest.clone(),
+                    object_type: o.object_type.clone(),
+                    status: v1::ObjectStatus::from(o.object_status) as i32,
+                    owner_address: None,
+                });
+                changed.chain(deleted)
+            })
+            .collect();
+
+        v1::Checkpoint {
+            sequence_number: indexed.checkpoint.sequence_number,
+            epoch: indexed.checkpoint.epoch,
+            timestamp_ms: indexed.checkpoint.timestamp_ms,
+            digest: indexed.checkpoint.checkpoint_digest.clone(),
+            transactions,
+            events,
+            object_changes,
+        }
+    }
+}
+
+impl From<ObjectStatus> for v1::ObjectStatus {
+    fn from(status: ObjectStatus) -> Self {
+        match status {
+            ObjectStatus::Created => v1::ObjectStatus::Created,
+            ObjectStatus::Mutated => v1::ObjectStatus::Mutated,
+            ObjectStatus::Deleted => v1::ObjectStatus::Deleted,
+            ObjectStatus::Wrapped => v1::ObjectStatus::Wrapped,
+            ObjectStatus::Unwrapped => v1::ObjectStatus::Unwrapped,
+            ObjectStatus::UnwrappedThenDeleted => v1::ObjectStatus::UnwrappedThenDeleted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::index_checkpoint_data;
+    use crate::test_utils::TestCheckpointDataBuilder;
+
+    #[test]
+    fn test_round_trip_checkpoint_projection() {
+        let mut builder = TestCheckpointDataBuilder::new();
+        builder.genesis_checkpoint();
+        let data = builder.next_checkpoint(2);
+        let (indexed, _) = index_checkpoint_data(&data).unwrap();
+
+        let projection = v1::Checkpoint::from(&indexed);
+        let bytes = projection.encode_to_vec();
+        let decoded = v1::Checkpoint::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, projection);
+
+        assert_eq!(decoded.sequence_number, indexed.checkpoint.sequence_number);
+        assert_eq!(decoded.epoch, indexed.checkpoint.epoch);
+        assert_eq!(decoded.timestamp_ms, indexed.checkpoint.timestamp_ms);
+        assert_eq!(decoded.digest, indexed.checkpoint.checkpoint_digest);
+        assert_eq!(decoded.transactions.len(), indexed.transactions.len());
+        for (tx, indexed_tx) in decoded.transactions.iter().zip(&indexed.transactions) {
+            assert_eq!(tx.digest, indexed_tx.transaction_digest);
+            assert_eq!(tx.sender, indexed_tx.sender);
+            let gas = tx.gas.as_ref().unwrap();
+            assert_eq!(gas.gas_price, indexed_tx.gas_price);
+            assert_eq!(gas.computation_cost, indexed_tx.computation_cost);
+            assert_eq!(gas.storage_rebate, indexed_tx.storage_rebate);
+        }
+        for (event, indexed_event) in decoded.events.iter().zip(&indexed.events) {
+            assert_eq!(event.event_type, indexed_event.event_type);
+            assert_eq!(event.bcs, indexed_event.event_bcs);
+        }
+        let changed = indexed
+            .objects_changes
+            .iter()
+            .flat_map(|changes| changes.changed_objects.iter())
+            .collect::<Vec<_>>();
+        assert!(!changed.is_empty());
+        for o in changed {
+            let change = decoded
+                .object_changes
+                .iter()
+                .find(|c| c.object_id == o.object_id)
+                .unwrap();
+            assert_eq!(change.version, o.version);
+            assert_eq!(change.status(), v1::ObjectStatus::from(o.object_status));
+            assert_eq!(change.owner_address.as_deref(), o.live_address_owner());
+        }
+    }
+}
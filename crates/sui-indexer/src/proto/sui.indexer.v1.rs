@@ -0,0 +1,120 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// NOTE: generated by build.rs from proto/checkpoint.proto, regenerate rather than edit.
+
+/// A trimmed projection of an indexed checkpoint.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Checkpoint {
+    #[prost(int64, tag = "1")]
+    pub sequence_number: i64,
+    #[prost(int64, tag = "2")]
+    pub epoch: i64,
+    #[prost(int64, tag = "3")]
+    pub timestamp_ms: i64,
+    #[prost(string, tag = "4")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "5")]
+    pub transactions: ::prost::alloc::vec::Vec<Transaction>,
+    #[prost(message, repeated, tag = "6")]
+    pub events: ::prost::alloc::vec::Vec<Event>,
+    #[prost(message, repeated, tag = "7")]
+    pub object_changes: ::prost::alloc::vec::Vec<ObjectChange>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transaction {
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub gas: ::core::option::Option<GasSummary>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GasSummary {
+    #[prost(int64, tag = "1")]
+    pub gas_price: i64,
+    #[prost(int64, tag = "2")]
+    pub computation_cost: i64,
+    #[prost(int64, tag = "3")]
+    pub storage_cost: i64,
+    #[prost(int64, tag = "4")]
+    pub storage_rebate: i64,
+    #[prost(int64, tag = "5")]
+    pub non_refundable_storage_fee: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Event {
+    #[prost(string, tag = "1")]
+    pub transaction_digest: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub event_sequence: i64,
+    #[prost(string, tag = "3")]
+    pub sender: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub event_type: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub bcs: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ObjectChange {
+    #[prost(string, tag = "1")]
+    pub object_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub version: i64,
+    #[prost(string, tag = "3")]
+    pub object_digest: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub object_type: ::prost::alloc::string::String,
+    #[prost(enumeration = "ObjectStatus", tag = "5")]
+    pub status: i32,
+    /// Only set for live objects owned by an address
+    #[prost(string, optional, tag = "6")]
+    pub owner_address: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ObjectStatus {
+    Unspecified = 0,
+    Created = 1,
+    Mutated = 2,
+    Deleted = 3,
+    Wrapped = 4,
+    Unwrapped = 5,
+    UnwrappedThenDeleted = 6,
+}
+impl ObjectStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ObjectStatus::Unspecified => "OBJECT_STATUS_UNSPECIFIED",
+            ObjectStatus::Created => "OBJECT_STATUS_CREATED",
+            ObjectStatus::Mutated => "OBJECT_STATUS_MUTATED",
+            ObjectStatus::Deleted => "OBJECT_STATUS_DELETED",
+            ObjectStatus::Wrapped => "OBJECT_STATUS_WRAPPED",
+            ObjectStatus::Unwrapped => "OBJECT_STATUS_UNWRAPPED",
+            ObjectStatus::UnwrappedThenDeleted => "OBJECT_STATUS_UNWRAPPED_THEN_DELETED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "OBJECT_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "OBJECT_STATUS_CREATED" => Some(Self::Created),
+            "OBJECT_STATUS_MUTATED" => Some(Self::Mutated),
+            "OBJECT_STATUS_DELETED" => Some(Self::Deleted),
+            "OBJECT_STATUS_WRAPPED" => Some(Self::Wrapped),
+            "OBJECT_STATUS_UNWRAPPED" => Some(Self::Unwrapped),
+            "OBJECT_STATUS_UNWRAPPED_THEN_DELETED" => Some(Self::UnwrappedThenDeleted),
+            _ => None,
+        }
+    }
+}
@@ -14,6 +14,20 @@ pub mod sql_types {
     #[derive(diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "owner_type"))]
     pub struct OwnerType;
+
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "transaction_kind_type"))]
+    pub struct TransactionKindType;
+}
+
+diesel::table! {
+    address_activity (account_address) {
+        account_address -> Varchar,
+        first_appearance_checkpoint -> Int8,
+        first_appearance_timestamp -> Int8,
+        last_appearance_checkpoint -> Int8,
+        last_appearance_timestamp -> Int8,
+    }
 }
 
 diesel::table! {
@@ -33,6 +47,12 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    chain_identifier (checkpoint_digest) {
+        checkpoint_digest -> Varchar,
+    }
+}
+
 diesel::table! {
     checkpoints (sequence_number) {
         sequence_number -> Int8,
@@ -53,6 +73,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    consensus_commits (checkpoint_sequence_number) {
+        checkpoint_sequence_number -> Int8,
+        epoch -> Int8,
+        round -> Int8,
+        commit_timestamp_ms -> Int8,
+        checkpoint_timestamp_ms -> Int8,
+        transaction_digest -> Varchar,
+    }
+}
+
 diesel::table! {
     epochs (epoch) {
         epoch -> Int8,
@@ -90,6 +121,17 @@ diesel::table! {
         event_type -> Text,
         event_time_ms -> Nullable<Int8>,
         event_bcs -> Bytea,
+        checkpoint_sequence_number -> Int8,
+        event_index -> Int8,
+    }
+}
+
+diesel::table! {
+    genesis_allocations (object_id) {
+        object_id -> Varchar,
+        owner_address -> Varchar,
+        coin_type -> Varchar,
+        amount -> Int8,
     }
 }
 
@@ -114,6 +156,7 @@ diesel::table! {
         move_package -> Text,
         move_module -> Text,
         move_function -> Text,
+        move_type_arguments -> Array<Nullable<Text>>,
     }
 }
 
@@ -167,6 +210,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    owner_object_counts (owner_address) {
+        owner_address -> Varchar,
+        object_count -> Int8,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::BcsBytes;
@@ -179,6 +229,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ptb_commands (transaction_digest, command_index) {
+        transaction_digest -> Varchar,
+        command_index -> Int8,
+        checkpoint_sequence_number -> Int8,
+        epoch -> Int8,
+        command_kind -> Text,
+        package_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    quarantined_checkpoints (sequence_number) {
+        sequence_number -> Int8,
+        epoch -> Int8,
+        checkpoint_digest -> Varchar,
+        attempts -> Int8,
+        error -> Text,
+    }
+}
+
 diesel::table! {
     recipients (id) {
         id -> Int8,
@@ -218,6 +289,17 @@ diesel::table! {
 }
 
 diesel::table! {
+    transaction_dependencies (transaction_digest, depends_on_digest) {
+        transaction_digest -> Varchar,
+        depends_on_digest -> Varchar,
+        checkpoint_sequence_number -> Int8,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TransactionKindType;
+
     transactions (id) {
         id -> Int8,
         transaction_digest -> Varchar,
@@ -247,6 +329,9 @@ diesel::table! {
         transaction_content -> Text,
         transaction_effects_content -> Text,
         confirmed_local_execution -> Nullable<Bool>,
+        kind -> TransactionKindType,
+        transaction_signatures -> Array<Nullable<Text>>,
+        epoch -> Int8,
     }
 }
 
@@ -295,19 +380,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    watermark (id) {
+        id -> Bool,
+        checkpoint_sequence_number -> Int8,
+    }
+}
+
 diesel::allow_tables_to_appear_in_same_query!(
+    address_activity,
     addresses,
     at_risk_validators,
+    chain_identifier,
     checkpoints,
+    consensus_commits,
     epochs,
     events,
+    genesis_allocations,
     input_objects,
     move_calls,
     objects,
     objects_history,
+    owner_object_counts,
     packages,
+    ptb_commands,
+    quarantined_checkpoints,
     recipients,
     system_states,
+    transaction_dependencies,
     transactions,
     validators,
+    watermark,
 );
@@ -0,0 +1,132 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::models::epoch::DBEpochInfo;
+
+/// Inconsistencies found in the checkpoints and epochs committed up to `watermark`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub watermark: i64,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl AuditReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// Checkpoints `first` to `last` inclusive are missing below the watermark.
+    MissingCheckpoints { first: i64, last: i64 },
+    /// Epochs `first` to `last` inclusive are missing between committed epochs.
+    MissingEpochs { first: i64, last: i64 },
+    /// An epoch other than the latest one has no last checkpoint.
+    UnclosedEpoch { epoch: i64 },
+    /// An epoch does not start right after the last checkpoint of the previous epoch,
+    /// leaving a gap between them or overlapping it.
+    DiscontiguousEpoch {
+        epoch: i64,
+        expected_first_checkpoint_id: i64,
+        first_checkpoint_id: i64,
+    },
+    /// An epoch ends before it starts.
+    InvalidEpochRange {
+        epoch: i64,
+        first_checkpoint_id: i64,
+        last_checkpoint_id: i64,
+    },
+    /// The transaction count of an epoch differs from the sum over its checkpoints.
+    EpochTransactionsMismatch {
+        epoch: i64,
+        epoch_total_transactions: i64,
+        checkpoint_total_transactions: i64,
+    },
+}
+
+/// Audits `checkpoints`, as (sequence_number, total_transaction_blocks) in sequence order,
+/// against `epochs` in epoch order.
+/// The latest epoch may be ongoing, its range then ends at the watermark.
+pub fn audit_checkpoints_and_epochs(
+    checkpoints: &[(i64, i64)],
+    epochs: &[DBEpochInfo],
+) -> AuditReport {
+    let watermark = checkpoints.last().map_or(-1, |(seq, _)| *seq);
+    let mut inconsistencies = vec![];
+
+    let mut next_seq = 0;
+    for (seq, _) in checkpoints {
+        if *seq > next_seq {
+            inconsistencies.push(Inconsistency::MissingCheckpoints {
+                first: next_seq,
+                last: seq - 1,
+            });
+        }
+        next_seq = seq + 1;
+    }
+
+    let mut previous_epoch: Option<&DBEpochInfo> = None;
+    for epoch in epochs {
+        let expected_first_checkpoint_id = match previous_epoch {
+            Some(previous) => {
+                if epoch.epoch > previous.epoch + 1 {
+                    inconsistencies.push(Inconsistency::MissingEpochs {
+                        first: previous.epoch + 1,
+                        last: epoch.epoch - 1,
+                    });
+                }
+                match previous.last_checkpoint_id {
+                    Some(last) => Some(last + 1),
+                    None => {
+                        inconsistencies.push(Inconsistency::UnclosedEpoch {
+                            epoch: previous.epoch,
+                        });
+                        None
+                    }
+                }
+            }
+            // The range of the first committed epoch is only known if it is the very first.
+            None => (epoch.epoch == 0).then_some(0),
+        };
+        if let Some(expected_first_checkpoint_id) = expected_first_checkpoint_id {
+            if epoch.first_checkpoint_id != expected_first_checkpoint_id {
+                inconsistencies.push(Inconsistency::DiscontiguousEpoch {
+                    epoch: epoch.epoch,
+                    expected_first_checkpoint_id,
+                    first_checkpoint_id: epoch.first_checkpoint_id,
+                });
+            }
+        }
+        let last_checkpoint_id = epoch.last_checkpoint_id.unwrap_or(watermark);
+        if let Some(last) = epoch.last_checkpoint_id {
+            if last < epoch.first_checkpoint_id {
+                inconsistencies.push(Inconsistency::InvalidEpochRange {
+                    epoch: epoch.epoch,
+                    first_checkpoint_id: epoch.first_checkpoint_id,
+                    last_checkpoint_id: last,
+                });
+            }
+        }
+
+        let start = checkpoints.partition_point(|(seq, _)| *seq < epoch.first_checkpoint_id);
+        let end = checkpoints.partition_point(|(seq, _)| *seq <= last_checkpoint_id);
+        let checkpoint_total_transactions = checkpoints[start..end.max(start)]
+            .iter()
+            .map(|(_, transactions)| transactions)
+            .sum();
+        if checkpoint_total_transactions != epoch.epoch_total_transactions {
+            inconsistencies.push(Inconsistency::EpochTransactionsMismatch {
+                epoch: epoch.epoch,
+                epoch_total_transactions: epoch.epoch_total_transactions,
+                checkpoint_total_transactions,
+            });
+        }
+        previous_epoch = Some(epoch);
+    }
+
+    AuditReport {
+        watermark,
+        inconsistencies,
+    }
+}
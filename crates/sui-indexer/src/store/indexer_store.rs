@@ -5,27 +5,35 @@ use async_trait::async_trait;
 
 use sui_json_rpc_types::{
     Checkpoint as RpcCheckpoint, CheckpointId, EpochInfo, EventFilter, EventPage, MoveCallMetrics,
-    NetworkMetrics, SuiObjectData, SuiObjectDataFilter, SuiTransactionBlockResponse,
-    SuiTransactionBlockResponseOptions,
+    MoveFunctionName, NetworkMetrics, SuiObjectData, SuiObjectDataFilter,
+    SuiTransactionBlockResponse, SuiTransactionBlockResponseOptions,
 };
-use sui_types::base_types::{EpochId, ObjectID, SequenceNumber, VersionNumber};
+use sui_types::base_types::{EpochId, ObjectID, SequenceNumber, SuiAddress, VersionNumber};
 use sui_types::digests::CheckpointDigest;
 use sui_types::error::SuiError;
 use sui_types::event::EventID;
 use sui_types::messages_checkpoint::CheckpointSequenceNumber;
 use sui_types::object::ObjectRead;
+use sui_types::signature::GenericSignature;
 use sui_types::storage::ObjectStore;
 
 use crate::errors::IndexerError;
-use crate::models::addresses::Address;
+use crate::models::addresses::{Address, AddressActivity};
 use crate::models::checkpoints::Checkpoint;
-use crate::models::epoch::DBEpochInfo;
-use crate::models::events::Event;
+use crate::models::consensus_commits::ConsensusCommit;
+use crate::models::epoch::{DBEpochInfo, EpochEconomics, StakeApy};
+use crate::models::events::{Event, EventCursor};
+use crate::models::genesis_allocations::GenesisAllocation;
 use crate::models::objects::{DeletedObject, Object, ObjectStatus};
+use crate::models::owner_object_counts::OwnerObjectCount;
 use crate::models::packages::Package;
-use crate::models::system_state::{DBSystemStateSummary, DBValidatorSummary};
-use crate::models::transaction_index::{InputObject, MoveCall, Recipient};
-use crate::models::transactions::Transaction;
+use crate::models::quarantined_checkpoints::QuarantinedCheckpoint;
+use crate::models::system_state::{DBSystemStateSummary, DBValidatorSummary, ValidatorEpoch};
+use crate::models::transaction_index::{
+    InputObject, MoveCall, PtbCommand, Recipient, TransactionDependency,
+};
+use crate::models::transactions::{DBTransactionKind, Transaction};
+use crate::store::AuditReport;
 use crate::types::CheckpointTransactionBlockResponse;
 
 #[async_trait]
@@ -38,6 +46,16 @@ pub trait IndexerStore {
         &self,
         digest: CheckpointDigest,
     ) -> Result<CheckpointSequenceNumber, IndexerError>;
+    async fn get_checkpoint_by_digest(
+        &self,
+        digest: CheckpointDigest,
+    ) -> Result<Checkpoint, IndexerError>;
+    /// The last checkpoint with a timestamp no later than `timestamp_ms`, None if
+    /// all checkpoints are later than it.
+    async fn get_checkpoint_at_or_before(
+        &self,
+        timestamp_ms: i64,
+    ) -> Result<Option<Checkpoint>, IndexerError>;
 
     async fn get_event(&self, id: EventID) -> Result<Event, IndexerError>;
     async fn get_events(
@@ -47,6 +65,15 @@ pub trait IndexerStore {
         limit: Option<usize>,
         descending_order: bool,
     ) -> Result<EventPage, IndexerError>;
+    /// Events of type `event_type` ordered by their position in the chain, starting
+    /// after the event at `cursor` when one is given.
+    async fn get_events_by_type(
+        &self,
+        event_type: &str,
+        cursor: Option<EventCursor>,
+        limit: usize,
+        descending_order: bool,
+    ) -> Result<Vec<Event>, IndexerError>;
 
     async fn get_object(
         &self,
@@ -62,6 +89,20 @@ pub trait IndexerStore {
         limit: usize,
     ) -> Result<Vec<ObjectRead>, IndexerError>;
 
+    /// Live objects owned by address `owner`, optionally only those of type
+    /// `type_filter`, in object id order starting after object `cursor`.
+    async fn get_objects_by_owner(
+        &self,
+        owner: String,
+        type_filter: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: usize,
+    ) -> Result<Vec<Object>, IndexerError>;
+
+    /// The `limit` addresses owning the most live objects, in descending order of object
+    /// count, ties broken by address.
+    async fn get_top_owners(&self, limit: usize) -> Result<Vec<OwnerObjectCount>, IndexerError>;
+
     async fn query_latest_objects(
         &self,
         filter: SuiObjectDataFilter,
@@ -85,6 +126,11 @@ pub trait IndexerStore {
         options: Option<&SuiTransactionBlockResponseOptions>,
     ) -> Result<SuiTransactionBlockResponse, IndexerError>;
 
+    async fn get_transaction_signatures(
+        &self,
+        tx_digest: &str,
+    ) -> Result<Vec<GenericSignature>, IndexerError>;
+
     async fn get_all_transaction_page(
         &self,
         start_sequence: Option<i64>,
@@ -108,6 +154,15 @@ pub trait IndexerStore {
         is_descending: bool,
     ) -> Result<Vec<Transaction>, IndexerError>;
 
+    /// Transactions of the given kind in ascending sequence order, starting after
+    /// the transaction with sequence number `cursor` when one is given.
+    async fn get_transactions_by_kind(
+        &self,
+        kind: DBTransactionKind,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, IndexerError>;
+
     async fn get_transaction_page_by_sender_address(
         &self,
         sender_address: String,
@@ -152,6 +207,27 @@ pub trait IndexerStore {
         is_descending: bool,
     ) -> Result<Vec<Transaction>, IndexerError>;
 
+    /// Move calls instantiated with `type_argument`, optionally only the calls of
+    /// `function`, in ascending sequence order starting after move call `cursor`.
+    async fn get_move_calls_by_type_argument(
+        &self,
+        type_argument: String,
+        function: Option<MoveFunctionName>,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<MoveCall>, IndexerError>;
+
+    async fn get_address_activity(&self, address: &str) -> Result<AddressActivity, IndexerError>;
+
+    /// Commands of the programmable transaction `tx_digest` in order.
+    async fn get_ptb_commands(&self, tx_digest: &str) -> Result<Vec<PtbCommand>, IndexerError>;
+
+    /// Digests of the transactions `tx_digest` depends on.
+    async fn get_dependencies(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError>;
+
+    /// Digests of the transactions depending on `tx_digest`.
+    async fn get_dependents(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError>;
+
     async fn get_transaction_sequence_by_digest(
         &self,
         tx_digest: Option<String>,
@@ -176,7 +252,17 @@ pub trait IndexerStore {
         is_descending: bool,
     ) -> Result<Option<i64>, IndexerError>;
 
+    /// Consensus commits of checkpoints in ascending checkpoint order, starting after
+    /// checkpoint `cursor` when one is given.
+    async fn get_consensus_commits(
+        &self,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ConsensusCommit>, IndexerError>;
+
     async fn get_network_metrics(&self) -> Result<NetworkMetrics, IndexerError>;
+    /// Transaction blocks per second over the last `window` checkpoints.
+    async fn get_tps(&self, window: usize) -> Result<f64, IndexerError>;
     async fn get_move_call_metrics(&self) -> Result<MoveCallMetrics, IndexerError>;
 
     async fn persist_fast_path(
@@ -189,6 +275,21 @@ pub trait IndexerStore {
         data: &TemporaryCheckpointStore,
     ) -> Result<usize, IndexerError>;
     async fn persist_epoch(&self, data: &TemporaryEpochStore) -> Result<(), IndexerError>;
+    /// Records a checkpoint skipped after repeatedly failing to be indexed. Quarantining
+    /// the same checkpoint again overwrites the earlier record.
+    async fn persist_quarantined_checkpoint(
+        &self,
+        checkpoint: &QuarantinedCheckpoint,
+    ) -> Result<(), IndexerError>;
+    /// All quarantined checkpoints in ascending order.
+    async fn get_quarantined_checkpoints(&self)
+        -> Result<Vec<QuarantinedCheckpoint>, IndexerError>;
+    /// Digest of the genesis checkpoint of the indexed chain, None before the first run.
+    async fn get_chain_identifier(&self) -> Result<Option<String>, IndexerError>;
+    async fn persist_chain_identifier(&self, checkpoint_digest: &str) -> Result<(), IndexerError>;
+    /// Checks the committed checkpoints and epochs for gaps, overlaps and transaction
+    /// counts not adding up, see `Inconsistency`.
+    async fn audit(&self) -> Result<AuditReport, IndexerError>;
 
     async fn get_epochs(
         &self,
@@ -198,10 +299,43 @@ pub trait IndexerStore {
     ) -> Result<Vec<EpochInfo>, IndexerError>;
 
     async fn get_current_epoch(&self) -> Result<EpochInfo, IndexerError>;
+    /// The row of epoch `epoch`, None if the epoch has not been committed.
+    async fn get_epoch_row(&self, epoch: EpochId) -> Result<Option<DBEpochInfo>, IndexerError>;
+    /// Validators of epoch `epoch`, empty if the epoch has not been committed.
+    async fn get_validators(&self, epoch: EpochId)
+        -> Result<Vec<DBValidatorSummary>, IndexerError>;
+
+    /// Commission rate and other changeable settings of `validator` in each epoch it was a
+    /// validator of, in epoch order.
+    async fn get_validator_commission_history(
+        &self,
+        validator: SuiAddress,
+    ) -> Result<Vec<ValidatorEpoch>, IndexerError>;
+
+    /// Economics of epochs `start_epoch` to `end_epoch` inclusive, in epoch order.
+    async fn get_epoch_economics(
+        &self,
+        start_epoch: EpochId,
+        end_epoch: EpochId,
+    ) -> Result<Vec<EpochEconomics>, IndexerError>;
+
+    /// APY of staking with the whole network over the last `window_epochs` ended epochs.
+    async fn get_network_apy(&self, window_epochs: usize) -> Result<StakeApy, IndexerError>;
+    /// APY of staking with `validator` over the last `window_epochs` ended epochs.
+    async fn get_validator_apy(
+        &self,
+        validator: SuiAddress,
+        window_epochs: usize,
+    ) -> Result<StakeApy, IndexerError>;
+
+    /// Coins created by the genesis checkpoint, in object ID order, empty until epoch 0 has
+    /// been committed.
+    async fn get_genesis_allocations(&self) -> Result<Vec<GenesisAllocation>, IndexerError>;
 
     fn module_cache(&self) -> &Self::ModuleCache;
 }
 
+/// Everything downloaded from a fullnode to index one checkpoint.
 #[derive(Clone, Debug)]
 pub struct CheckpointData {
     pub checkpoint: RpcCheckpoint,
@@ -209,6 +343,35 @@ pub struct CheckpointData {
     pub changed_objects: Vec<(ObjectStatus, SuiObjectData)>,
 }
 
+impl CheckpointData {
+    /// `transactions` are the full transactions of the checkpoint, in checkpoint order.
+    /// `changed_objects` are the objects created, mutated or unwrapped by them at the
+    /// versions written by the checkpoint, fetched with their BCS content.
+    pub fn new(
+        checkpoint: RpcCheckpoint,
+        transactions: Vec<CheckpointTransactionBlockResponse>,
+        changed_objects: Vec<(ObjectStatus, SuiObjectData)>,
+    ) -> Self {
+        Self {
+            checkpoint,
+            transactions,
+            changed_objects,
+        }
+    }
+
+    pub fn checkpoint(&self) -> &RpcCheckpoint {
+        &self.checkpoint
+    }
+
+    pub fn transactions(&self) -> &[CheckpointTransactionBlockResponse] {
+        &self.transactions
+    }
+
+    pub fn changed_objects(&self) -> &[(ObjectStatus, SuiObjectData)] {
+        &self.changed_objects
+    }
+}
+
 impl ObjectStore for CheckpointData {
     fn get_object(
         &self,
@@ -251,10 +414,14 @@ pub struct TemporaryCheckpointStore {
     pub events: Vec<Event>,
     pub objects_changes: Vec<TransactionObjectChanges>,
     pub addresses: Vec<Address>,
+    pub address_activities: Vec<AddressActivity>,
     pub packages: Vec<Package>,
     pub input_objects: Vec<InputObject>,
     pub move_calls: Vec<MoveCall>,
     pub recipients: Vec<Recipient>,
+    pub ptb_commands: Vec<PtbCommand>,
+    pub dependencies: Vec<TransactionDependency>,
+    pub consensus_commit: Option<ConsensusCommit>,
 }
 
 #[derive(Debug)]
@@ -267,6 +434,9 @@ pub struct TransactionObjectChanges {
 pub struct TemporaryEpochStore {
     pub last_epoch: Option<DBEpochInfo>,
     pub new_epoch: DBEpochInfo,
-    pub system_state: DBSystemStateSummary,
+    // None when a non-standard genesis is tolerated without a system state
+    pub system_state: Option<DBSystemStateSummary>,
     pub validators: Vec<DBValidatorSummary>,
+    // only for epoch 0, the coins the genesis checkpoint created
+    pub genesis_allocations: Vec<GenesisAllocation>,
 }
@@ -0,0 +1,1869 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use sui_json_rpc_types::{
+    Checkpoint as RpcCheckpoint, CheckpointId, EpochInfo, EventFilter, EventPage, MoveCallMetrics,
+    MoveFunctionName, NetworkMetrics, SuiObjectDataFilter, SuiTransactionBlockResponse,
+    SuiTransactionBlockResponseOptions,
+};
+use sui_types::base_types::{EpochId, ObjectID, SequenceNumber, SuiAddress};
+use sui_types::digests::CheckpointDigest;
+use sui_types::event::EventID;
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use sui_types::object::ObjectRead;
+use sui_types::signature::GenericSignature;
+
+use crate::errors::IndexerError;
+use crate::models::addresses::{Address, AddressActivity};
+use crate::models::checkpoints::{compute_tps, Checkpoint};
+use crate::models::consensus_commits::ConsensusCommit;
+use crate::models::epoch::{DBEpochInfo, EpochEconomics, StakeApy};
+use crate::models::events::{Event, EventCursor};
+use crate::models::genesis_allocations::GenesisAllocation;
+use crate::models::objects::{skip_stale_object_versions, Object, ObjectStatus};
+use crate::models::owner_object_counts::{owner_object_count_changes, OwnerObjectCount};
+use crate::models::owners::OwnerType;
+use crate::models::packages::Package;
+use crate::models::quarantined_checkpoints::QuarantinedCheckpoint;
+use crate::models::system_state::{DBSystemStateSummary, DBValidatorSummary, ValidatorEpoch};
+use crate::models::transaction_index::{
+    normalize_type_argument, InputObject, MoveCall, PtbCommand, Recipient, TransactionDependency,
+};
+use crate::models::transactions::{DBTransactionKind, Transaction};
+use crate::store::{
+    audit_checkpoints_and_epochs, AuditReport, IndexerStore, TemporaryCheckpointStore,
+    TemporaryEpochStore, TransactionObjectChanges,
+};
+
+/// An `IndexerStore` that keeps everything in memory, so that the checkpoint handler
+/// can be exercised without a Postgres instance.
+/// Tables are keyed by the same primary / unique keys as the Postgres schema, and
+/// conflicting writes are resolved the same way as the Postgres store resolves them.
+#[derive(Clone, Default)]
+pub struct MemoryIndexerStore {
+    inner: Arc<Mutex<MemoryIndexerStoreInner>>,
+}
+
+#[derive(Default)]
+struct MemoryIndexerStoreInner {
+    checkpoints: BTreeMap<i64, Checkpoint>,
+    // keyed by transaction_digest, id is assigned on first insertion like BIGSERIAL.
+    transactions: BTreeMap<String, Transaction>,
+    // events, move_calls, input_objects and recipients only have a BIGSERIAL primary key.
+    events: Vec<Event>,
+    move_calls: Vec<MoveCall>,
+    input_objects: Vec<InputObject>,
+    recipients: Vec<Recipient>,
+    // keyed by (transaction_digest, command_index)
+    ptb_commands: BTreeMap<(String, i64), PtbCommand>,
+    // keyed by (transaction_digest, depends_on_digest)
+    transaction_dependencies: BTreeMap<(String, String), TransactionDependency>,
+    consensus_commits: BTreeMap<i64, ConsensusCommit>,
+    objects: BTreeMap<String, Object>,
+    // keyed by (object_id, version, checkpoint)
+    objects_history: BTreeMap<(String, i64, i64), Object>,
+    // keyed by owner_address
+    owner_object_counts: BTreeMap<String, i64>,
+    addresses: BTreeMap<String, Address>,
+    address_activity: BTreeMap<String, AddressActivity>,
+    // keyed by (package_id, version)
+    packages: BTreeMap<(String, i64), Package>,
+    epochs: BTreeMap<i64, DBEpochInfo>,
+    system_states: BTreeMap<i64, DBSystemStateSummary>,
+    // keyed by (epoch, sui_address)
+    validators: BTreeMap<(i64, String), DBValidatorSummary>,
+    // keyed by object_id
+    genesis_allocations: BTreeMap<String, GenesisAllocation>,
+    quarantined_checkpoints: BTreeMap<i64, QuarantinedCheckpoint>,
+    chain_identifier: Option<String>,
+    // latest checkpoint committed in full
+    watermark: Option<i64>,
+    persist_checkpoint_calls: usize,
+    // number of upcoming persist_checkpoint calls to fail
+    checkpoint_commit_failures: usize,
+    persist_epoch_calls: usize,
+    // number of upcoming persist_epoch calls to fail
+    epoch_commit_failures: usize,
+}
+
+impl MemoryIndexerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the next `n` calls to `persist_checkpoint` fail without writing anything,
+    /// calls after them succeed again.
+    pub fn fail_next_checkpoint_commits(&self, n: usize) {
+        self.inner.lock().unwrap().checkpoint_commit_failures = n;
+    }
+
+    /// Number of `persist_checkpoint` calls made so far, including failed ones.
+    pub fn persist_checkpoint_calls(&self) -> usize {
+        self.inner.lock().unwrap().persist_checkpoint_calls
+    }
+
+    /// Makes the next `n` calls to `persist_epoch` fail without writing anything, calls
+    /// after them succeed again.
+    pub fn fail_next_epoch_commits(&self, n: usize) {
+        self.inner.lock().unwrap().epoch_commit_failures = n;
+    }
+
+    /// Number of `persist_epoch` calls made so far, including failed ones.
+    pub fn persist_epoch_calls(&self) -> usize {
+        self.inner.lock().unwrap().persist_epoch_calls
+    }
+
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        self.inner
+            .lock()
+            .unwrap()
+            .checkpoints
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn transactions(&self) -> Vec<Transaction> {
+        let mut transactions = self
+            .inner
+            .lock()
+            .unwrap()
+            .transactions
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.id);
+        transactions
+    }
+
+    pub fn events(&self) -> Vec<Event> {
+        self.inner.lock().unwrap().events.clone()
+    }
+
+    pub fn objects(&self) -> Vec<Object> {
+        self.inner
+            .lock()
+            .unwrap()
+            .objects
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn objects_history(&self) -> Vec<Object> {
+        self.inner
+            .lock()
+            .unwrap()
+            .objects_history
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.inner
+            .lock()
+            .unwrap()
+            .addresses
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        self.inner
+            .lock()
+            .unwrap()
+            .packages
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn move_calls(&self) -> Vec<MoveCall> {
+        self.inner.lock().unwrap().move_calls.clone()
+    }
+
+    pub fn input_objects(&self) -> Vec<InputObject> {
+        self.inner.lock().unwrap().input_objects.clone()
+    }
+
+    pub fn recipients(&self) -> Vec<Recipient> {
+        self.inner.lock().unwrap().recipients.clone()
+    }
+
+    pub fn epochs(&self) -> Vec<DBEpochInfo> {
+        self.inner
+            .lock()
+            .unwrap()
+            .epochs
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn system_states(&self) -> Vec<DBSystemStateSummary> {
+        self.inner
+            .lock()
+            .unwrap()
+            .system_states
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn validators(&self) -> Vec<DBValidatorSummary> {
+        self.inner
+            .lock()
+            .unwrap()
+            .validators
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+impl MemoryIndexerStoreInner {
+    fn upsert_object(&mut self, object: Object) {
+        self.objects_history
+            .entry((object.object_id.clone(), object.version, object.checkpoint))
+            .or_insert_with(|| object.clone());
+        self.objects.insert(object.object_id.clone(), object);
+    }
+
+    /// The last `n` epochs up to the latest ended one, in epoch order.
+    fn last_ended_epochs(&self, n: usize) -> Vec<DBEpochInfo> {
+        let last_ended = self
+            .epochs
+            .values()
+            .rev()
+            .find(|epoch| epoch.epoch_end_timestamp.is_some());
+        let mut epochs = match last_ended {
+            Some(last) => self
+                .epochs
+                .range(..=last.epoch)
+                .rev()
+                .take(n)
+                .map(|(_, epoch)| epoch.clone())
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+        epochs.reverse();
+        epochs
+    }
+}
+
+// Rows missing in the in-memory tables are reported the same way diesel reports them,
+// so that callers can't tell the two stores apart.
+fn not_found() -> IndexerError {
+    IndexerError::PostgresError(diesel::result::Error::NotFound)
+}
+
+fn not_supported<T>(method: &str) -> Result<T, IndexerError> {
+    Err(IndexerError::NotSupportedError(format!(
+        "{method} is not supported by MemoryIndexerStore"
+    )))
+}
+
+#[async_trait]
+impl IndexerStore for MemoryIndexerStore {
+    type ModuleCache = ();
+
+    async fn get_latest_checkpoint_sequence_number(&self) -> Result<i64, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        // -1 to differentiate between no checkpoints and the first checkpoint
+        Ok(inner.watermark.unwrap_or(-1))
+    }
+
+    async fn get_checkpoint(&self, _id: CheckpointId) -> Result<RpcCheckpoint, IndexerError> {
+        not_supported("get_checkpoint")
+    }
+
+    async fn get_checkpoint_sequence_number(
+        &self,
+        digest: CheckpointDigest,
+    ) -> Result<CheckpointSequenceNumber, IndexerError> {
+        let digest = digest.base58_encode();
+        let inner = self.inner.lock().unwrap();
+        inner
+            .checkpoints
+            .values()
+            .find(|cp| cp.checkpoint_digest == digest)
+            .map(|cp| cp.sequence_number as u64)
+            .ok_or_else(not_found)
+    }
+
+    async fn get_checkpoint_by_digest(
+        &self,
+        digest: CheckpointDigest,
+    ) -> Result<Checkpoint, IndexerError> {
+        let digest = digest.base58_encode();
+        let inner = self.inner.lock().unwrap();
+        inner
+            .checkpoints
+            .values()
+            .find(|cp| cp.checkpoint_digest == digest)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_checkpoint_at_or_before(
+        &self,
+        timestamp_ms: i64,
+    ) -> Result<Option<Checkpoint>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .checkpoints
+            .values()
+            .rev()
+            .find(|cp| cp.timestamp_ms <= timestamp_ms)
+            .cloned())
+    }
+
+    async fn get_event(&self, _id: EventID) -> Result<Event, IndexerError> {
+        not_supported("get_event")
+    }
+
+    async fn get_events(
+        &self,
+        _query: EventFilter,
+        _cursor: Option<EventID>,
+        _limit: Option<usize>,
+        _descending_order: bool,
+    ) -> Result<EventPage, IndexerError> {
+        not_supported("get_events")
+    }
+
+    async fn get_events_by_type(
+        &self,
+        event_type: &str,
+        cursor: Option<EventCursor>,
+        limit: usize,
+        descending_order: bool,
+    ) -> Result<Vec<Event>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        let mut events = inner
+            .events
+            .iter()
+            .filter(|event| event.event_type == event_type)
+            .filter(|event| {
+                cursor.map_or(true, |cursor| {
+                    let position = EventCursor::from(*event);
+                    if descending_order {
+                        position < cursor
+                    } else {
+                        position > cursor
+                    }
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        events.sort_by_key(EventCursor::from);
+        if descending_order {
+            events.reverse();
+        }
+        events.truncate(limit);
+        Ok(events)
+    }
+
+    async fn get_object(
+        &self,
+        _object_id: ObjectID,
+        _version: Option<SequenceNumber>,
+    ) -> Result<ObjectRead, IndexerError> {
+        not_supported("get_object")
+    }
+
+    async fn query_objects_history(
+        &self,
+        _filter: SuiObjectDataFilter,
+        _at_checkpoint: CheckpointSequenceNumber,
+        _cursor: Option<ObjectID>,
+        _limit: usize,
+    ) -> Result<Vec<ObjectRead>, IndexerError> {
+        not_supported("query_objects_history")
+    }
+
+    async fn get_objects_by_owner(
+        &self,
+        owner: String,
+        type_filter: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: usize,
+    ) -> Result<Vec<Object>, IndexerError> {
+        let cursor = cursor.map(|cursor| cursor.to_string());
+        let inner = self.inner.lock().unwrap();
+        // objects is keyed by object id and only holds the latest version of each one.
+        Ok(inner
+            .objects
+            .values()
+            .filter(|o| matches!(o.owner_type, OwnerType::AddressOwner))
+            .filter(|o| o.owner_address.as_ref() == Some(&owner))
+            .filter(|o| {
+                !matches!(
+                    o.object_status,
+                    ObjectStatus::Deleted
+                        | ObjectStatus::Wrapped
+                        | ObjectStatus::UnwrappedThenDeleted
+                )
+            })
+            .filter(|o| type_filter.as_ref().map_or(true, |t| &o.object_type == t))
+            .filter(|o| cursor.as_ref().map_or(true, |cursor| &o.object_id > cursor))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_top_owners(&self, limit: usize) -> Result<Vec<OwnerObjectCount>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        let mut owners = inner
+            .owner_object_counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(owner_address, object_count)| OwnerObjectCount {
+                owner_address: owner_address.clone(),
+                object_count: *object_count,
+            })
+            .collect::<Vec<_>>();
+        // stable sort, so that owners of equal count stay in address order.
+        owners.sort_by_key(|o| std::cmp::Reverse(o.object_count));
+        owners.truncate(limit);
+        Ok(owners)
+    }
+
+    async fn query_latest_objects(
+        &self,
+        _filter: SuiObjectDataFilter,
+        _cursor: Option<ObjectID>,
+        _limit: usize,
+    ) -> Result<Vec<ObjectRead>, IndexerError> {
+        not_supported("query_latest_objects")
+    }
+
+    async fn get_total_transaction_number_from_checkpoints(&self) -> Result<i64, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .checkpoints
+            .values()
+            .map(|cp| cp.network_total_transactions)
+            .max()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_transaction_by_digest(
+        &self,
+        tx_digest: &str,
+    ) -> Result<Transaction, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .transactions
+            .get(tx_digest)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn multi_get_transactions_by_digests(
+        &self,
+        tx_digests: &[String],
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(tx_digests
+            .iter()
+            .filter_map(|digest| inner.transactions.get(digest).cloned())
+            .collect())
+    }
+
+    async fn compose_sui_transaction_block_response(
+        &self,
+        _tx: Transaction,
+        _options: Option<&SuiTransactionBlockResponseOptions>,
+    ) -> Result<SuiTransactionBlockResponse, IndexerError> {
+        not_supported("compose_sui_transaction_block_response")
+    }
+
+    async fn get_transaction_signatures(
+        &self,
+        tx_digest: &str,
+    ) -> Result<Vec<GenericSignature>, IndexerError> {
+        self.get_transaction_by_digest(tx_digest)
+            .await?
+            .signatures()
+    }
+
+    async fn get_all_transaction_page(
+        &self,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_all_transaction_page")
+    }
+
+    async fn get_transaction_page_by_checkpoint(
+        &self,
+        _checkpoint_sequence_number: i64,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_checkpoint")
+    }
+
+    async fn get_transaction_page_by_transaction_kind(
+        &self,
+        _kind: String,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_transaction_kind")
+    }
+
+    async fn get_transactions_by_kind(
+        &self,
+        kind: DBTransactionKind,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        let mut transactions = self
+            .transactions()
+            .into_iter()
+            .filter(|tx| tx.kind == kind && cursor.map_or(true, |cursor| tx.id > Some(cursor)))
+            .collect::<Vec<_>>();
+        transactions.truncate(limit);
+        Ok(transactions)
+    }
+
+    async fn get_transaction_page_by_sender_address(
+        &self,
+        _sender_address: String,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_sender_address")
+    }
+
+    async fn get_transaction_page_by_mutated_object(
+        &self,
+        _object_id: String,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_mutated_object")
+    }
+
+    async fn get_transaction_page_by_sender_recipient_address(
+        &self,
+        _sender_address: Option<String>,
+        _recipient_address: String,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_sender_recipient_address")
+    }
+
+    async fn get_transaction_page_by_input_object(
+        &self,
+        _object_id: String,
+        _version: Option<i64>,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_input_object")
+    }
+
+    async fn get_transaction_page_by_move_call(
+        &self,
+        _package: String,
+        _module: Option<String>,
+        _function: Option<String>,
+        _start_sequence: Option<i64>,
+        _limit: usize,
+        _is_descending: bool,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        not_supported("get_transaction_page_by_move_call")
+    }
+
+    async fn get_move_calls_by_type_argument(
+        &self,
+        type_argument: String,
+        function: Option<MoveFunctionName>,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<MoveCall>, IndexerError> {
+        let type_argument = Some(normalize_type_argument(&type_argument));
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .move_calls
+            .iter()
+            .filter(|call| cursor.map_or(true, |cursor| call.id > Some(cursor)))
+            .filter(|call| call.move_type_arguments.contains(&type_argument))
+            .filter(|call| {
+                function.as_ref().map_or(true, |f| {
+                    call.move_package == f.package.to_string()
+                        && call.move_module == f.module.as_str()
+                        && call.move_function == f.function.as_str()
+                })
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_address_activity(&self, address: &str) -> Result<AddressActivity, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .address_activity
+            .get(address)
+            .cloned()
+            .ok_or_else(not_found)
+    }
+
+    async fn get_ptb_commands(&self, tx_digest: &str) -> Result<Vec<PtbCommand>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .ptb_commands
+            .values()
+            .filter(|command| command.transaction_digest == tx_digest)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_dependencies(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .transaction_dependencies
+            .values()
+            .filter(|dependency| dependency.transaction_digest == tx_digest)
+            .map(|dependency| dependency.depends_on_digest.clone())
+            .collect())
+    }
+
+    async fn get_dependents(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .transaction_dependencies
+            .values()
+            .filter(|dependency| dependency.depends_on_digest == tx_digest)
+            .map(|dependency| dependency.transaction_digest.clone())
+            .collect())
+    }
+
+    async fn get_transaction_sequence_by_digest(
+        &self,
+        _tx_digest: Option<String>,
+        _is_descending: bool,
+    ) -> Result<Option<i64>, IndexerError> {
+        not_supported("get_transaction_sequence_by_digest")
+    }
+
+    async fn get_move_call_sequence_by_digest(
+        &self,
+        _tx_digest: Option<String>,
+        _is_descending: bool,
+    ) -> Result<Option<i64>, IndexerError> {
+        not_supported("get_move_call_sequence_by_digest")
+    }
+
+    async fn get_input_object_sequence_by_digest(
+        &self,
+        _tx_digest: Option<String>,
+        _is_descending: bool,
+    ) -> Result<Option<i64>, IndexerError> {
+        not_supported("get_input_object_sequence_by_digest")
+    }
+
+    async fn get_recipient_sequence_by_digest(
+        &self,
+        _tx_digest: Option<String>,
+        _is_descending: bool,
+    ) -> Result<Option<i64>, IndexerError> {
+        not_supported("get_recipient_sequence_by_digest")
+    }
+
+    async fn get_consensus_commits(
+        &self,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ConsensusCommit>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .consensus_commits
+            .values()
+            .filter(|commit| {
+                cursor.map_or(true, |cursor| commit.checkpoint_sequence_number > cursor)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_network_metrics(&self) -> Result<NetworkMetrics, IndexerError> {
+        not_supported("get_network_metrics")
+    }
+
+    async fn get_tps(&self, window: usize) -> Result<f64, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        let mut checkpoints = inner
+            .checkpoints
+            .values()
+            .rev()
+            .take(window)
+            .cloned()
+            .collect::<Vec<_>>();
+        checkpoints.reverse();
+        Ok(compute_tps(&checkpoints))
+    }
+
+    async fn get_move_call_metrics(&self) -> Result<MoveCallMetrics, IndexerError> {
+        not_supported("get_move_call_metrics")
+    }
+
+    async fn persist_fast_path(
+        &self,
+        _tx: Transaction,
+        _tx_object_changes: TransactionObjectChanges,
+    ) -> Result<usize, IndexerError> {
+        not_supported("persist_fast_path")
+    }
+
+    async fn persist_checkpoint(
+        &self,
+        data: &TemporaryCheckpointStore,
+    ) -> Result<usize, IndexerError> {
+        let TemporaryCheckpointStore {
+            checkpoint,
+            transactions,
+            events,
+            objects_changes: tx_object_changes,
+            addresses,
+            address_activities,
+            packages,
+            input_objects,
+            move_calls,
+            recipients,
+            ptb_commands,
+            dependencies,
+            consensus_commit,
+        } = data;
+
+        // The lock is held for the whole commit, which makes it atomic like the
+        // Postgres transaction.
+        let mut inner = self.inner.lock().unwrap();
+        inner.persist_checkpoint_calls += 1;
+        if inner.checkpoint_commit_failures > 0 {
+            inner.checkpoint_commit_failures -= 1;
+            return Err(IndexerError::PostgresWriteError(format!(
+                "Injected failure on commit of checkpoint {}",
+                checkpoint.sequence_number
+            )));
+        }
+
+        // Same as the Postgres store, per object the versions are applied in ascending order.
+        let mut mutated_objects = tx_object_changes
+            .iter()
+            .flat_map(|changes| changes.changed_objects.iter().cloned())
+            .collect::<Vec<_>>();
+        mutated_objects.sort_by_key(|o| o.version);
+        let deleted_objects = tx_object_changes
+            .iter()
+            .flat_map(|changes| changes.deleted_objects.iter().cloned())
+            .map(Object::from)
+            .collect::<Vec<_>>();
+        let mutated_objects = skip_stale_object_versions(mutated_objects, |id| {
+            inner.objects.get(id).map(|o| o.version)
+        });
+        let deleted_objects = skip_stale_object_versions(deleted_objects, |id| {
+            inner.objects.get(id).map(|o| o.version)
+        });
+        let owner_count_changes = owner_object_count_changes(
+            mutated_objects.iter().chain(deleted_objects.iter()),
+            |id| {
+                inner
+                    .objects
+                    .get(id)
+                    .and_then(|o| o.live_address_owner().map(str::to_string))
+            },
+        );
+        for change in owner_count_changes {
+            *inner
+                .owner_object_counts
+                .entry(change.owner_address)
+                .or_default() += change.object_count;
+        }
+
+        // on conflict of transaction_digest, only update timestamp & checkpoint
+        for transaction in transactions {
+            let next_id = inner.transactions.len() as i64 + 1;
+            inner
+                .transactions
+                .entry(transaction.transaction_digest.clone())
+                .and_modify(|tx| {
+                    tx.timestamp_ms = transaction.timestamp_ms;
+                    tx.checkpoint_sequence_number = transaction.checkpoint_sequence_number;
+                })
+                .or_insert_with(|| Transaction {
+                    id: Some(next_id),
+                    ..transaction.clone()
+                });
+        }
+
+        let next_id = inner.events.len() as i64 + 1;
+        inner
+            .events
+            .extend(events.iter().cloned().enumerate().map(|(i, event)| Event {
+                id: Some(next_id + i as i64),
+                ..event
+            }));
+
+        for object in mutated_objects {
+            inner.upsert_object(object);
+        }
+        for deleted_object in deleted_objects {
+            let object = match inner.objects.get(&deleted_object.object_id) {
+                Some(existing) => Object {
+                    epoch: deleted_object.epoch,
+                    checkpoint: deleted_object.checkpoint,
+                    version: deleted_object.version,
+                    previous_transaction: deleted_object.previous_transaction,
+                    object_status: deleted_object.object_status,
+                    ..existing.clone()
+                },
+                None => deleted_object,
+            };
+            inner.upsert_object(object);
+        }
+
+        for address in addresses {
+            inner
+                .addresses
+                .entry(address.account_address.clone())
+                .or_insert_with(|| address.clone());
+        }
+        // Same as the Postgres store, the activity window only ever widens.
+        for activity in address_activities {
+            inner
+                .address_activity
+                .entry(activity.account_address.clone())
+                .and_modify(|existing| existing.merge(activity))
+                .or_insert_with(|| activity.clone());
+        }
+        for package in packages {
+            inner
+                .packages
+                .entry((package.package_id.clone(), package.version))
+                .or_insert_with(|| package.clone());
+        }
+        let next_id = inner.move_calls.len() as i64 + 1;
+        inner.move_calls.extend(
+            move_calls
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, move_call)| MoveCall {
+                    id: Some(next_id + i as i64),
+                    ..move_call
+                }),
+        );
+        inner.input_objects.extend(input_objects.iter().cloned());
+        inner.recipients.extend(recipients.iter().cloned());
+        for command in ptb_commands {
+            inner
+                .ptb_commands
+                .entry((command.transaction_digest.clone(), command.command_index))
+                .or_insert_with(|| command.clone());
+        }
+        for dependency in dependencies {
+            inner
+                .transaction_dependencies
+                .entry((
+                    dependency.transaction_digest.clone(),
+                    dependency.depends_on_digest.clone(),
+                ))
+                .or_insert_with(|| dependency.clone());
+        }
+        if let Some(consensus_commit) = consensus_commit {
+            inner
+                .consensus_commits
+                .entry(consensus_commit.checkpoint_sequence_number)
+                .or_insert_with(|| consensus_commit.clone());
+        }
+
+        if let Some(epoch) = inner.epochs.get_mut(&checkpoint.epoch) {
+            epoch.epoch_total_transactions += checkpoint.transactions.len() as i64;
+        }
+        // Same as the Postgres store, the watermark only ever advances.
+        inner.watermark = inner.watermark.max(Some(checkpoint.sequence_number));
+        // Commit the checkpoint last, on conflict do nothing.
+        if inner.checkpoints.contains_key(&checkpoint.sequence_number) {
+            return Ok(0);
+        }
+        inner
+            .checkpoints
+            .insert(checkpoint.sequence_number, checkpoint.clone());
+        Ok(1)
+    }
+
+    async fn persist_epoch(&self, data: &TemporaryEpochStore) -> Result<(), IndexerError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.persist_epoch_calls += 1;
+        if inner.epoch_commit_failures > 0 {
+            inner.epoch_commit_failures -= 1;
+            return Err(IndexerError::PostgresWriteError(format!(
+                "Injected failure on commit of epoch {}",
+                data.new_epoch.epoch
+            )));
+        }
+        if let Some(last_epoch) = &data.last_epoch {
+            // Same as the upsert of the Postgres store, columns written when the epoch
+            // started are kept and only the end-of-epoch columns are overwritten.
+            let epoch = inner
+                .epochs
+                .entry(last_epoch.epoch)
+                .or_insert_with(|| last_epoch.clone());
+            *epoch = DBEpochInfo {
+                epoch: epoch.epoch,
+                first_checkpoint_id: epoch.first_checkpoint_id,
+                epoch_start_timestamp: epoch.epoch_start_timestamp,
+                epoch_total_transactions: epoch.epoch_total_transactions,
+                ..last_epoch.clone()
+            };
+        }
+        inner
+            .epochs
+            .entry(data.new_epoch.epoch)
+            .or_insert_with(|| data.new_epoch.clone());
+        if let Some(system_state) = &data.system_state {
+            inner
+                .system_states
+                .entry(system_state.epoch)
+                .or_insert_with(|| system_state.clone());
+        }
+        for validator in &data.validators {
+            inner
+                .validators
+                .entry((validator.epoch, validator.sui_address.clone()))
+                .or_insert_with(|| validator.clone());
+        }
+        for allocation in &data.genesis_allocations {
+            inner
+                .genesis_allocations
+                .entry(allocation.object_id.clone())
+                .or_insert_with(|| allocation.clone());
+        }
+        Ok(())
+    }
+
+    async fn persist_quarantined_checkpoint(
+        &self,
+        checkpoint: &QuarantinedCheckpoint,
+    ) -> Result<(), IndexerError> {
+        self.inner
+            .lock()
+            .unwrap()
+            .quarantined_checkpoints
+            .insert(checkpoint.sequence_number, checkpoint.clone());
+        Ok(())
+    }
+
+    async fn get_quarantined_checkpoints(
+        &self,
+    ) -> Result<Vec<QuarantinedCheckpoint>, IndexerError> {
+        Ok(self
+            .inner
+            .lock()
+            .unwrap()
+            .quarantined_checkpoints
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_chain_identifier(&self) -> Result<Option<String>, IndexerError> {
+        Ok(self.inner.lock().unwrap().chain_identifier.clone())
+    }
+
+    async fn persist_chain_identifier(&self, checkpoint_digest: &str) -> Result<(), IndexerError> {
+        // Same as the Postgres store, an identifier once recorded is never replaced.
+        self.inner
+            .lock()
+            .unwrap()
+            .chain_identifier
+            .get_or_insert_with(|| checkpoint_digest.to_string());
+        Ok(())
+    }
+
+    async fn audit(&self) -> Result<AuditReport, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        let checkpoints = inner
+            .checkpoints
+            .values()
+            .map(|cp| (cp.sequence_number, cp.total_transaction_blocks))
+            .collect::<Vec<_>>();
+        let epochs = inner.epochs.values().cloned().collect::<Vec<_>>();
+        Ok(audit_checkpoints_and_epochs(&checkpoints, &epochs))
+    }
+
+    async fn get_epochs(
+        &self,
+        _cursor: Option<EpochId>,
+        _limit: usize,
+        _descending_order: Option<bool>,
+    ) -> Result<Vec<EpochInfo>, IndexerError> {
+        not_supported("get_epochs")
+    }
+
+    async fn get_current_epoch(&self) -> Result<EpochInfo, IndexerError> {
+        not_supported("get_current_epoch")
+    }
+
+    async fn get_epoch_row(&self, epoch: EpochId) -> Result<Option<DBEpochInfo>, IndexerError> {
+        Ok(self
+            .inner
+            .lock()
+            .unwrap()
+            .epochs
+            .get(&(epoch as i64))
+            .cloned())
+    }
+
+    async fn get_validators(
+        &self,
+        epoch: EpochId,
+    ) -> Result<Vec<DBValidatorSummary>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .validators
+            .range((epoch as i64, String::new())..(epoch as i64 + 1, String::new()))
+            .map(|(_, validator)| validator.clone())
+            .collect())
+    }
+
+    async fn get_validator_commission_history(
+        &self,
+        validator: SuiAddress,
+    ) -> Result<Vec<ValidatorEpoch>, IndexerError> {
+        let address = validator.to_string();
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .validators
+            .values()
+            .filter(|v| v.sui_address == address)
+            .map(ValidatorEpoch::from)
+            .collect())
+    }
+
+    async fn get_epoch_economics(
+        &self,
+        start_epoch: EpochId,
+        end_epoch: EpochId,
+    ) -> Result<Vec<EpochEconomics>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .epochs
+            .range(start_epoch as i64..=end_epoch as i64)
+            .map(|(_, epoch)| EpochEconomics::from(epoch))
+            .collect())
+    }
+
+    async fn get_network_apy(&self, window_epochs: usize) -> Result<StakeApy, IndexerError> {
+        // From the epoch before the window, which ends with the stake the window starts with.
+        let epochs = self
+            .inner
+            .lock()
+            .unwrap()
+            .last_ended_epochs(window_epochs + 1);
+        Ok(StakeApy::of_network(&epochs))
+    }
+
+    async fn get_validator_apy(
+        &self,
+        validator: SuiAddress,
+        window_epochs: usize,
+    ) -> Result<StakeApy, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        let epochs = inner.last_ended_epochs(window_epochs);
+        let (first, last) = match (epochs.first(), epochs.last()) {
+            (Some(first), Some(last)) => (first.epoch, last.epoch),
+            _ => return Ok(StakeApy::default()),
+        };
+        let address = validator.to_string();
+        let states = (first..=last + 1)
+            .filter_map(|epoch| inner.validators.get(&(epoch, address.clone())).cloned())
+            .collect::<Vec<_>>();
+        Ok(StakeApy::of_validator(&epochs, &states))
+    }
+
+    async fn get_genesis_allocations(&self) -> Result<Vec<GenesisAllocation>, IndexerError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.genesis_allocations.values().cloned().collect())
+    }
+
+    fn module_cache(&self) -> &Self::ModuleCache {
+        &()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sui_types::base_types::{ObjectID, SuiAddress};
+    use sui_types::digests::CheckpointDigest;
+
+    use crate::errors::IndexerError;
+    use crate::models::addresses::AddressActivity;
+    use crate::models::checkpoints::Checkpoint;
+    use crate::models::epoch::{DBEpochInfo, EpochEconomics};
+    use crate::models::events::{Event, EventCursor};
+    use crate::models::objects::{Object, ObjectStatus};
+    use crate::models::owner_object_counts::OwnerObjectCount;
+    use crate::models::owners::OwnerType;
+    use crate::models::system_state::{DBValidatorSummary, ValidatorEpoch};
+    use crate::models::transactions::Transaction;
+    use crate::store::{
+        Inconsistency, IndexerStore, MemoryIndexerStore, TemporaryCheckpointStore,
+        TemporaryEpochStore, TransactionObjectChanges,
+    };
+
+    fn checkpoint_store(sequence_number: i64, tx_digest: &str) -> TemporaryCheckpointStore {
+        TemporaryCheckpointStore {
+            checkpoint: Checkpoint {
+                sequence_number,
+                transactions: vec![Some(tx_digest.to_string())],
+                ..Default::default()
+            },
+            transactions: vec![Transaction {
+                transaction_digest: tx_digest.to_string(),
+                checkpoint_sequence_number: Some(sequence_number),
+                ..Default::default()
+            }],
+            events: vec![],
+            objects_changes: vec![],
+            addresses: vec![],
+            address_activities: vec![],
+            packages: vec![],
+            input_objects: vec![],
+            move_calls: vec![],
+            recipients: vec![],
+            ptb_commands: vec![],
+            dependencies: vec![],
+            consensus_commit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persist_checkpoint_and_read_watermark() {
+        let store = MemoryIndexerStore::new();
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            -1
+        );
+
+        let data = checkpoint_store(0, "tx0");
+        assert_eq!(store.persist_checkpoint(&data).await.unwrap(), 1);
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            0
+        );
+
+        // Re-committing the same checkpoint is a no-op, like on conflict do nothing.
+        assert_eq!(store.persist_checkpoint(&data).await.unwrap(), 0);
+        assert_eq!(store.checkpoints().len(), 1);
+        let transactions = store.transactions();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_advance_watermark_on_successful_commit_only() {
+        let store = MemoryIndexerStore::new();
+        store
+            .persist_checkpoint(&checkpoint_store(0, "tx0"))
+            .await
+            .unwrap();
+
+        let data = checkpoint_store(1, "tx1");
+        store.fail_next_checkpoint_commits(1);
+        store.persist_checkpoint(&data).await.unwrap_err();
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            0
+        );
+
+        store.persist_checkpoint(&data).await.unwrap();
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            1
+        );
+
+        // Reindexing an older checkpoint doesn't move the watermark back.
+        store
+            .persist_checkpoint(&checkpoint_store(0, "tx0"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            1
+        );
+    }
+
+    fn object(object_id: &str, version: i64, checkpoint: i64) -> Object {
+        Object {
+            epoch: 0,
+            checkpoint,
+            object_id: object_id.to_string(),
+            version,
+            object_digest: format!("digest{version}"),
+            owner_type: OwnerType::AddressOwner,
+            owner_address: Some("0xa".to_string()),
+            initial_shared_version: None,
+            previous_transaction: format!("tx{checkpoint}"),
+            object_type: "0x2::coin::Coin<0x2::sui::SUI>".to_string(),
+            object_status: ObjectStatus::Mutated,
+            has_public_transfer: true,
+            storage_rebate: 0,
+            bcs: vec![],
+        }
+    }
+
+    fn object_changes_store(
+        sequence_number: i64,
+        objects: Vec<Object>,
+    ) -> TemporaryCheckpointStore {
+        TemporaryCheckpointStore {
+            objects_changes: vec![TransactionObjectChanges {
+                changed_objects: objects,
+                deleted_objects: vec![],
+            }],
+            ..checkpoint_store(sequence_number, &format!("tx{sequence_number}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_skip_older_object_version() {
+        let store = MemoryIndexerStore::new();
+        store
+            .persist_checkpoint(&object_changes_store(0, vec![object("0x1", 5, 0)]))
+            .await
+            .unwrap();
+
+        store
+            .persist_checkpoint(&object_changes_store(
+                1,
+                vec![object("0x1", 3, 1), object("0x2", 1, 1)],
+            ))
+            .await
+            .unwrap();
+        // The older version is skipped, the rest of the checkpoint is written.
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            1
+        );
+        let objects = store.objects();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].object_id, "0x1");
+        assert_eq!(objects[0].version, 5);
+        assert_eq!(objects[0].checkpoint, 0);
+        assert_eq!(objects[1].object_id, "0x2");
+        assert_eq!(store.transactions().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_address_activity_out_of_order() {
+        let store = MemoryIndexerStore::new();
+        let activity = |checkpoint: i64| AddressActivity {
+            account_address: "0xa".to_string(),
+            first_appearance_checkpoint: checkpoint,
+            first_appearance_timestamp: checkpoint * 1000,
+            last_appearance_checkpoint: checkpoint,
+            last_appearance_timestamp: checkpoint * 1000,
+        };
+        // Checkpoints 5, 3 and 4 are indexed in this order.
+        for (sequence_number, digest) in [(5, "tx5"), (3, "tx3"), (4, "tx4")] {
+            let data = TemporaryCheckpointStore {
+                address_activities: vec![activity(sequence_number)],
+                ..checkpoint_store(sequence_number, digest)
+            };
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+
+        assert_eq!(
+            store.get_address_activity("0xa").await.unwrap(),
+            AddressActivity {
+                account_address: "0xa".to_string(),
+                first_appearance_checkpoint: 3,
+                first_appearance_timestamp: 3000,
+                last_appearance_checkpoint: 5,
+                last_appearance_timestamp: 5000,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_checkpoint_by_digest() {
+        let store = MemoryIndexerStore::new();
+        let digests = [
+            CheckpointDigest::new([1; 32]),
+            CheckpointDigest::new([2; 32]),
+        ];
+        for (sequence_number, digest) in digests.iter().enumerate() {
+            let mut data =
+                checkpoint_store(sequence_number as i64, &format!("tx{sequence_number}"));
+            data.checkpoint.checkpoint_digest = digest.base58_encode();
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+
+        let checkpoint = store.get_checkpoint_by_digest(digests[1]).await.unwrap();
+        assert_eq!(checkpoint.sequence_number, 1);
+        assert_eq!(checkpoint.checkpoint_digest, digests[1].base58_encode());
+        assert!(matches!(
+            store
+                .get_checkpoint_by_digest(CheckpointDigest::new([3; 32]))
+                .await,
+            Err(IndexerError::PostgresError(diesel::result::Error::NotFound))
+        ));
+    }
+
+    fn event(event_type: &str, checkpoint_sequence_number: i64, event_index: i64) -> Event {
+        Event {
+            id: None,
+            transaction_digest: format!("tx{checkpoint_sequence_number}"),
+            event_sequence: event_index,
+            sender: "0xa".to_string(),
+            package: "0x2".to_string(),
+            module: "coin".to_string(),
+            event_type: event_type.to_string(),
+            event_time_ms: None,
+            event_bcs: vec![],
+            checkpoint_sequence_number,
+            event_index,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_events_by_type() {
+        let store = MemoryIndexerStore::new();
+        let coin_created = "0x2::coin::CoinCreated";
+        let other = "0x2::coin::Other";
+        for sequence_number in 0..3 {
+            let data = TemporaryCheckpointStore {
+                events: vec![
+                    event(coin_created, sequence_number, 0),
+                    event(other, sequence_number, 1),
+                    event(coin_created, sequence_number, 2),
+                ],
+                ..checkpoint_store(sequence_number, &format!("tx{sequence_number}"))
+            };
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+        let positions = |events: Vec<Event>| {
+            events
+                .iter()
+                .map(|e| (e.checkpoint_sequence_number, e.event_index))
+                .collect::<Vec<_>>()
+        };
+
+        let page = store
+            .get_events_by_type(coin_created, None, 4, false)
+            .await
+            .unwrap();
+        let cursor = page.last().map(EventCursor::from);
+        assert_eq!(positions(page), vec![(0, 0), (0, 2), (1, 0), (1, 2)]);
+        let page = store
+            .get_events_by_type(coin_created, cursor, 4, false)
+            .await
+            .unwrap();
+        assert_eq!(positions(page), vec![(2, 0), (2, 2)]);
+
+        let page = store
+            .get_events_by_type(coin_created, None, 3, true)
+            .await
+            .unwrap();
+        let cursor = page.last().map(EventCursor::from);
+        assert_eq!(positions(page), vec![(2, 2), (2, 0), (1, 2)]);
+        let page = store
+            .get_events_by_type(coin_created, cursor, 3, true)
+            .await
+            .unwrap();
+        assert_eq!(positions(page), vec![(1, 0), (0, 2), (0, 0)]);
+
+        let page = store
+            .get_events_by_type(other, None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(positions(page), vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_get_objects_by_owner() {
+        let store = MemoryIndexerStore::new();
+        let id = |byte| ObjectID::from_single_byte(byte).to_string();
+        let owned = |byte, version, checkpoint, owner: &str, object_type: &str| Object {
+            owner_address: Some(owner.to_string()),
+            object_type: object_type.to_string(),
+            ..object(&id(byte), version, checkpoint)
+        };
+        let coin = "0x2::coin::Coin<0x2::sui::SUI>";
+        let nft = "0x3::nft::Nft";
+        store
+            .persist_checkpoint(&object_changes_store(
+                0,
+                vec![
+                    owned(1, 1, 0, "0xa", coin),
+                    owned(2, 1, 0, "0xa", nft),
+                    owned(3, 1, 0, "0xa", coin),
+                    owned(4, 1, 0, "0xb", coin),
+                    owned(5, 1, 0, "0xa", coin),
+                ],
+            ))
+            .await
+            .unwrap();
+        store
+            .persist_checkpoint(&object_changes_store(
+                1,
+                vec![
+                    // Object 3 is mutated, object 5 is deleted and object 4 changes hands.
+                    owned(3, 2, 1, "0xa", coin),
+                    Object {
+                        object_status: ObjectStatus::Deleted,
+                        ..owned(5, 2, 1, "0xa", coin)
+                    },
+                    owned(4, 2, 1, "0xa", coin),
+                ],
+            ))
+            .await
+            .unwrap();
+        let ids_and_versions = |objects: Vec<Object>| {
+            objects
+                .into_iter()
+                .map(|o| (o.object_id, o.version))
+                .collect::<Vec<_>>()
+        };
+
+        let objects = store
+            .get_objects_by_owner("0xa".to_string(), None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids_and_versions(objects),
+            vec![(id(1), 1), (id(2), 1), (id(3), 2), (id(4), 2)]
+        );
+
+        let coins = store
+            .get_objects_by_owner("0xa".to_string(), Some(coin.to_string()), None, 2)
+            .await
+            .unwrap();
+        assert_eq!(ids_and_versions(coins), vec![(id(1), 1), (id(3), 2)]);
+        let coins = store
+            .get_objects_by_owner(
+                "0xa".to_string(),
+                Some(coin.to_string()),
+                Some(ObjectID::from_single_byte(3)),
+                2,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids_and_versions(coins), vec![(id(4), 2)]);
+
+        assert!(store
+            .get_objects_by_owner("0xb".to_string(), None, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_checkpoint_at_or_before() {
+        let store = MemoryIndexerStore::new();
+        for (sequence_number, timestamp_ms) in [(0, 1000), (1, 2000), (2, 3000)] {
+            let mut data = checkpoint_store(sequence_number, &format!("tx{sequence_number}"));
+            data.checkpoint.timestamp_ms = timestamp_ms;
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+        let at_or_before = |timestamp_ms| {
+            let store = store.clone();
+            async move {
+                store
+                    .get_checkpoint_at_or_before(timestamp_ms)
+                    .await
+                    .unwrap()
+                    .map(|cp| cp.sequence_number)
+            }
+        };
+
+        assert_eq!(at_or_before(999).await, None);
+        assert_eq!(at_or_before(1000).await, Some(0));
+        assert_eq!(at_or_before(1999).await, Some(0));
+        assert_eq!(at_or_before(2000).await, Some(1));
+        assert_eq!(at_or_before(2500).await, Some(1));
+        assert_eq!(at_or_before(3000).await, Some(2));
+        assert_eq!(at_or_before(i64::MAX).await, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_get_epoch_economics() {
+        let store = MemoryIndexerStore::new();
+        let closed = |epoch: i64| DBEpochInfo {
+            epoch,
+            last_checkpoint_id: Some(epoch * 10 + 9),
+            epoch_end_timestamp: Some(epoch * 1000 + 999),
+            total_gas_fees: Some(100 + epoch),
+            storage_charge: Some(200 + epoch),
+            storage_rebate: Some(300 + epoch),
+            storage_fund_reinvestment: Some(400 + epoch),
+            stake_subsidy_amount: Some(500 + epoch),
+            total_stake_rewards_distributed: Some(600 + epoch),
+            storage_fund_balance: Some(700 + epoch),
+            leftover_storage_fund_inflow: Some(800 + epoch),
+            ..Default::default()
+        };
+        for epoch in 0..3 {
+            let data = TemporaryEpochStore {
+                last_epoch: (epoch > 0).then(|| closed(epoch - 1)),
+                new_epoch: DBEpochInfo {
+                    epoch,
+                    first_checkpoint_id: epoch * 10,
+                    ..Default::default()
+                },
+                system_state: None,
+                validators: vec![],
+                genesis_allocations: vec![],
+            };
+            store.persist_epoch(&data).await.unwrap();
+        }
+
+        let economics = store.get_epoch_economics(0, 2).await.unwrap();
+        let expected = store
+            .epochs()
+            .iter()
+            .map(EpochEconomics::from)
+            .collect::<Vec<_>>();
+        assert_eq!(economics, expected);
+        assert_eq!(economics[1].total_gas_fees, Some(101));
+        assert_eq!(economics[1].storage_fund_balance, Some(701));
+        // The ongoing epoch has no closing event yet.
+        assert_eq!(economics[2].epoch, 2);
+        assert_eq!(economics[2].total_gas_fees, None);
+
+        let economics = store.get_epoch_economics(1, 1).await.unwrap();
+        assert_eq!(economics.len(), 1);
+        assert_eq!(economics[0].stake_subsidy_amount, Some(501));
+    }
+
+    #[tokio::test]
+    async fn test_get_validator_commission_history() {
+        let store = MemoryIndexerStore::new();
+        let validator = SuiAddress::random_for_testing_only();
+        let other = SuiAddress::random_for_testing_only();
+        let summary = |epoch: i64, address: SuiAddress, commission_rate: i64| DBValidatorSummary {
+            epoch,
+            sui_address: address.to_string(),
+            commission_rate,
+            next_epoch_commission_rate: commission_rate + 100,
+            next_epoch_stake: 1_000 * (epoch + 1),
+            ..Default::default()
+        };
+        // `validator` raises its commission rate from 200 to 300 at epoch 1.
+        for (epoch, commission_rate) in [(0, 200), (1, 300)] {
+            let data = TemporaryEpochStore {
+                last_epoch: None,
+                new_epoch: DBEpochInfo {
+                    epoch,
+                    ..Default::default()
+                },
+                system_state: None,
+                validators: vec![
+                    summary(epoch, validator, commission_rate),
+                    summary(epoch, other, 1_000),
+                ],
+                genesis_allocations: vec![],
+            };
+            store.persist_epoch(&data).await.unwrap();
+        }
+
+        let history = store
+            .get_validator_commission_history(validator)
+            .await
+            .unwrap();
+        let expected = [summary(0, validator, 200), summary(1, validator, 300)]
+            .iter()
+            .map(ValidatorEpoch::from)
+            .collect::<Vec<_>>();
+        assert_eq!(history, expected);
+        assert_eq!(history[0].next_epoch_commission_rate, 300);
+        assert_eq!(history[1].next_epoch_stake, 2_000);
+        assert!(store
+            .get_validator_commission_history(SuiAddress::random_for_testing_only())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_stake_apy() {
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+        let store = MemoryIndexerStore::new();
+        assert_eq!(store.get_network_apy(3).await.unwrap(), Default::default());
+
+        let validator = SuiAddress::random_for_testing_only();
+        let other = SuiAddress::random_for_testing_only();
+        // Epoch 2 has no rewards and epoch 4 is ongoing, epoch 3 lasts two days.
+        let ends = [DAY_MS, 2 * DAY_MS, 3 * DAY_MS, 5 * DAY_MS];
+        let stakes = [1_000_000, 1_000_100, 2_000_000, 2_000_400];
+        let rewards = [None, Some(100), None, Some(400)];
+        // SUI of the staking pool of `validator` per pool token at the start of each epoch,
+        // its state at the start of epoch 3 is missing.
+        let exchange_rates = [Some(1.0), Some(1.0), Some(1.01), None, Some(1.03)];
+        for epoch in 0..5 {
+            let closed = (epoch > 0).then(|| DBEpochInfo {
+                epoch: epoch as i64 - 1,
+                epoch_end_timestamp: Some(ends[epoch - 1]),
+                total_stake: Some(stakes[epoch - 1]),
+                total_stake_rewards_distributed: rewards[epoch - 1],
+                ..Default::default()
+            });
+            let state = |address: SuiAddress, rate: f64| DBValidatorSummary {
+                epoch: epoch as i64,
+                sui_address: address.to_string(),
+                staking_pool_sui_balance: (1_000_000.0 * rate) as i64,
+                pool_token_balance: 1_000_000,
+                ..Default::default()
+            };
+            let mut validators = vec![state(other, 2.0 + epoch as f64)];
+            validators.extend(exchange_rates[epoch].map(|rate| state(validator, rate)));
+            let data = TemporaryEpochStore {
+                last_epoch: closed,
+                new_epoch: DBEpochInfo {
+                    epoch: epoch as i64,
+                    epoch_start_timestamp: if epoch == 0 { 0 } else { ends[epoch - 1] },
+                    ..Default::default()
+                },
+                system_state: None,
+                validators,
+                genesis_allocations: vec![],
+            };
+            store.persist_epoch(&data).await.unwrap();
+        }
+        let assert_close = |actual: f64, expected: f64| {
+            assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+        };
+
+        // Epochs 1 to 3, on the stake the epochs before them end with.
+        let network = store.get_network_apy(3).await.unwrap();
+        let epochs = network.epochs.iter().map(|e| e.epoch).collect::<Vec<_>>();
+        assert_eq!(epochs, vec![1, 3]);
+        assert_close(network.epochs[0].apy, 1.0001f64.powf(365.0) - 1.0);
+        assert_close(network.epochs[1].apy, 1.0002f64.powf(365.0 / 2.0) - 1.0);
+        assert_close(
+            network.trailing_apy.unwrap(),
+            (1.0001f64 * 1.0002).powf(365.0 / 3.0) - 1.0,
+        );
+        let network = store.get_network_apy(1).await.unwrap();
+        assert_eq!(network.epochs.len(), 1);
+        assert_close(network.trailing_apy.unwrap(), network.epochs[0].apy);
+
+        // Only epoch 1 has the states of its start and of the start of the epoch after it.
+        let apy = store.get_validator_apy(validator, 3).await.unwrap();
+        assert_eq!(apy.epochs.len(), 1);
+        assert_eq!(apy.epochs[0].epoch, 1);
+        assert_close(apy.epochs[0].apy, 1.01f64.powf(365.0) - 1.0);
+        assert_close(apy.trailing_apy.unwrap(), apy.epochs[0].apy);
+        let apy = store.get_validator_apy(validator, 1).await.unwrap();
+        assert_eq!(apy, Default::default());
+        let apy = store
+            .get_validator_apy(SuiAddress::random_for_testing_only(), 3)
+            .await
+            .unwrap();
+        assert_eq!(apy.trailing_apy, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_tps() {
+        let store = MemoryIndexerStore::new();
+        assert_eq!(store.get_tps(10).await.unwrap(), 0.0);
+        // (sequence number, timestamp, transaction blocks)
+        let checkpoints = [(0, 1000, 7), (1, 2000, 10), (2, 2000, 4), (3, 4000, 16)];
+        for (sequence_number, timestamp_ms, total_transaction_blocks) in checkpoints {
+            let mut data = checkpoint_store(sequence_number, &format!("tx{sequence_number}"));
+            data.checkpoint.timestamp_ms = timestamp_ms;
+            data.checkpoint.total_transaction_blocks = total_transaction_blocks;
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+
+        // 10 + 4 + 16 transactions in 3 seconds, those of checkpoint 0 are not counted.
+        assert_eq!(store.get_tps(4).await.unwrap(), 10.0);
+        assert_eq!(store.get_tps(100).await.unwrap(), 10.0);
+        // 4 + 16 transactions in 2 seconds.
+        assert_eq!(store.get_tps(3).await.unwrap(), 10.0);
+        // 16 transactions in 2 seconds.
+        assert_eq!(store.get_tps(2).await.unwrap(), 8.0);
+        assert_eq!(store.get_tps(1).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_tps_of_equal_timestamps() {
+        let store = MemoryIndexerStore::new();
+        for sequence_number in 0..3 {
+            let mut data = checkpoint_store(sequence_number, &format!("tx{sequence_number}"));
+            data.checkpoint.timestamp_ms = 1000;
+            data.checkpoint.total_transaction_blocks = 5;
+            store.persist_checkpoint(&data).await.unwrap();
+        }
+        assert_eq!(store.get_tps(3).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_owner_object_counts() {
+        let store = MemoryIndexerStore::new();
+        let id = |byte| ObjectID::from_single_byte(byte).to_string();
+        let owned = |byte, version, checkpoint, owner: &str| Object {
+            owner_address: Some(owner.to_string()),
+            ..object(&id(byte), version, checkpoint)
+        };
+        let with_status = |object_status, object| Object {
+            object_status,
+            ..object
+        };
+        let top_owners = |owners: Vec<OwnerObjectCount>| {
+            owners
+                .into_iter()
+                .map(|o| (o.owner_address, o.object_count))
+                .collect::<Vec<_>>()
+        };
+        store
+            .persist_checkpoint(&object_changes_store(
+                0,
+                vec![
+                    owned(1, 1, 0, "0xa"),
+                    owned(2, 1, 0, "0xa"),
+                    owned(3, 1, 0, "0xa"),
+                    owned(4, 1, 0, "0xb"),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            top_owners(store.get_top_owners(10).await.unwrap()),
+            vec![("0xa".to_string(), 3), ("0xb".to_string(), 1)]
+        );
+
+        // Object 1 is transferred twice within the checkpoint, only its latest owner counts.
+        // Object 2 is wrapped, object 3 is deleted.
+        store
+            .persist_checkpoint(&object_changes_store(
+                1,
+                vec![
+                    owned(1, 2, 1, "0xb"),
+                    owned(1, 3, 1, "0xc"),
+                    with_status(ObjectStatus::Wrapped, owned(2, 2, 1, "0xa")),
+                    with_status(ObjectStatus::Deleted, owned(3, 2, 1, "0xa")),
+                ],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            top_owners(store.get_top_owners(10).await.unwrap()),
+            vec![("0xb".to_string(), 1), ("0xc".to_string(), 1)]
+        );
+
+        // Object 2 is unwrapped to 0xb.
+        store
+            .persist_checkpoint(&object_changes_store(
+                2,
+                vec![with_status(ObjectStatus::Unwrapped, owned(2, 3, 2, "0xb"))],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            top_owners(store.get_top_owners(10).await.unwrap()),
+            vec![("0xb".to_string(), 2), ("0xc".to_string(), 1)]
+        );
+        assert_eq!(
+            top_owners(store.get_top_owners(1).await.unwrap()),
+            vec![("0xb".to_string(), 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit() {
+        // A checkpoint of one transaction block.
+        let checkpoint = |sequence_number: i64, epoch: i64| {
+            let data = checkpoint_store(sequence_number, &format!("tx{sequence_number}"));
+            TemporaryCheckpointStore {
+                checkpoint: Checkpoint {
+                    epoch,
+                    total_transaction_blocks: 1,
+                    ..data.checkpoint.clone()
+                },
+                ..data
+            }
+        };
+        let epoch = |epoch: i64, first_checkpoint_id: i64, previous_last: Option<i64>| {
+            TemporaryEpochStore {
+                last_epoch: previous_last.map(|last| DBEpochInfo {
+                    epoch: epoch - 1,
+                    last_checkpoint_id: Some(last),
+                    ..Default::default()
+                }),
+                new_epoch: DBEpochInfo {
+                    epoch,
+                    first_checkpoint_id,
+                    ..Default::default()
+                },
+                system_state: None,
+                validators: vec![],
+                genesis_allocations: vec![],
+            }
+        };
+
+        let clean = MemoryIndexerStore::new();
+        clean.persist_epoch(&epoch(0, 0, None)).await.unwrap();
+        for seq in 0..=2 {
+            clean.persist_checkpoint(&checkpoint(seq, 0)).await.unwrap();
+        }
+        clean.persist_epoch(&epoch(1, 3, Some(2))).await.unwrap();
+        for seq in 3..=4 {
+            clean.persist_checkpoint(&checkpoint(seq, 1)).await.unwrap();
+        }
+        let report = clean.audit().await.unwrap();
+        assert_eq!(report.watermark, 4);
+        assert!(report.is_consistent(), "{report:?}");
+
+        // Checkpoints 1 and 3 are missing, epoch 1 starts a checkpoint late and its
+        // first checkpoint is committed before the epoch, so that it isn't counted.
+        let gappy = MemoryIndexerStore::new();
+        gappy.persist_epoch(&epoch(0, 0, None)).await.unwrap();
+        for seq in [0, 2] {
+            gappy.persist_checkpoint(&checkpoint(seq, 0)).await.unwrap();
+        }
+        gappy.persist_checkpoint(&checkpoint(4, 1)).await.unwrap();
+        gappy.persist_epoch(&epoch(1, 4, Some(2))).await.unwrap();
+        let report = gappy.audit().await.unwrap();
+        assert_eq!(report.watermark, 4);
+        assert_eq!(
+            report.inconsistencies,
+            vec![
+                Inconsistency::MissingCheckpoints { first: 1, last: 1 },
+                Inconsistency::MissingCheckpoints { first: 3, last: 3 },
+                Inconsistency::DiscontiguousEpoch {
+                    epoch: 1,
+                    expected_first_checkpoint_id: 3,
+                    first_checkpoint_id: 4,
+                },
+                Inconsistency::EpochTransactionsMismatch {
+                    epoch: 1,
+                    epoch_total_transactions: 0,
+                    checkpoint_total_transactions: 1,
+                },
+            ]
+        );
+    }
+}
@@ -1,10 +1,14 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub use audit::*;
 pub use indexer_store::*;
+pub use memory_indexer_store::MemoryIndexerStore;
 pub use pg_indexer_store::PgIndexerStore;
 
+mod audit;
 mod indexer_store;
+mod memory_indexer_store;
 mod module_resolver;
 mod pg_indexer_store;
 mod query;
@@ -1,15 +1,14 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use anyhow::anyhow;
 use async_trait::async_trait;
 use cached::proc_macro::once;
-use diesel::dsl::max;
 use diesel::query_builder::AsQuery;
+use diesel::sql_function;
 use diesel::sql_types::{BigInt, VarChar};
 use diesel::upsert::excluded;
 use diesel::QueryDsl;
@@ -42,48 +41,98 @@ use sui_types::messages_checkpoint::{
     CheckpointCommitment, CheckpointSequenceNumber, ECMHLiveObjectSetDigest, EndOfEpochData,
 };
 use sui_types::object::ObjectRead;
+use sui_types::signature::GenericSignature;
 
 use crate::errors::{Context, IndexerError};
-use crate::models::checkpoints::Checkpoint;
-use crate::models::epoch::DBEpochInfo;
-use crate::models::events::Event;
+use crate::metrics::IndexerCheckpointHandlerMetrics;
+use crate::models::addresses::AddressActivity;
+use crate::models::checkpoints::{compute_tps, Checkpoint};
+use crate::models::consensus_commits::ConsensusCommit;
+use crate::models::epoch::{DBEpochInfo, EpochEconomics, StakeApy};
+use crate::models::events::{Event, EventCursor};
+use crate::models::genesis_allocations::GenesisAllocation;
 use crate::models::network_metrics::{DBMoveCallMetrics, DBNetworkMetrics};
 use crate::models::objects::{
-    compose_object_bulk_insert_update_query, group_and_sort_objects, Object,
+    group_and_sort_objects, skip_stale_object_versions, Object, ObjectStatus,
 };
-use crate::models::system_state::DBValidatorSummary;
-use crate::models::transactions::Transaction;
+use crate::models::owner_object_counts::{owner_object_count_changes, OwnerObjectCount};
+use crate::models::owners::OwnerType;
+use crate::models::quarantined_checkpoints::QuarantinedCheckpoint;
+use crate::models::system_state::{DBValidatorSummary, ValidatorEpoch};
+use crate::models::transaction_index::{normalize_type_argument, MoveCall, PtbCommand};
+use crate::models::transactions::{DBTransactionKind, Transaction};
 use crate::schema::{
-    addresses, checkpoints, checkpoints::dsl as checkpoints_dsl, epochs, epochs::dsl as epochs_dsl,
-    events, input_objects, input_objects::dsl as input_objects_dsl, move_calls,
+    address_activity, address_activity::dsl as address_activity_dsl, addresses, chain_identifier,
+    checkpoints, checkpoints::dsl as checkpoints_dsl, consensus_commits,
+    consensus_commits::dsl as consensus_commits_dsl, epochs, epochs::dsl as epochs_dsl, events,
+    genesis_allocations, input_objects, input_objects::dsl as input_objects_dsl, move_calls,
     move_calls::dsl as move_calls_dsl, objects, objects::dsl as objects_dsl, objects_history,
-    packages, recipients, recipients::dsl as recipients_dsl, system_states, transactions,
-    transactions::dsl as transactions_dsl, validators,
-};
-use crate::store::diesel_marco::{
-    read_only, read_only_blocking, transactional, transactional_blocking,
+    owner_object_counts, packages, ptb_commands, ptb_commands::dsl as ptb_commands_dsl,
+    quarantined_checkpoints, quarantined_checkpoints::dsl as quarantined_checkpoints_dsl,
+    recipients, recipients::dsl as recipients_dsl, system_states, transaction_dependencies,
+    transaction_dependencies::dsl as transaction_dependencies_dsl, transactions,
+    transactions::dsl as transactions_dsl, validators, watermark,
 };
+use crate::store::diesel_marco::{read_only, read_only_blocking, transactional};
 use crate::store::indexer_store::TemporaryCheckpointStore;
 use crate::store::module_resolver::IndexerModuleResolver;
 use crate::store::query::DBFilter;
 use crate::store::TransactionObjectChanges;
+use crate::store::{audit_checkpoints_and_epochs, AuditReport};
 use crate::store::{IndexerStore, TemporaryEpochStore};
 use crate::utils::{get_balance_changes_from_effect, get_object_changes};
 use crate::{AsyncPgConnectionPool, PgConnectionPool};
 
 const MAX_EVENT_PAGE_SIZE: usize = 1000;
+// Rows per insert statement. The inserts of a checkpoint are sub-batched by it so that
+// no statement exceeds the 65535 bind parameters Postgres allows. 1000 rows of the
+// widest table written per checkpoint (transactions, 30 columns) stay well below that.
 const PG_COMMIT_CHUNK_SIZE: usize = 1000;
 
+// Rows per insert statement reused across commits, see `insert_in_chunks`.
+const PG_CACHED_CHUNK_SIZE: usize = 256;
+
+/// Runs `$insert` on `$rows` in chunks bound to `$values`. With `$cached`, the chunks are
+/// arrays of `PG_CACHED_CHUNK_SIZE` rows, so that the statements are the same from one
+/// commit to the next and reused from the statement cache of the connection. Diesel does
+/// not cache inserts of slices, as their number of rows varies, so the rows left over go
+/// in one more, uncached, statement: a batch never takes more than one round trip over
+/// the number of full chunks.
+macro_rules! insert_in_chunks {
+    ($cached:expr, $rows:expr, |$values:ident| $insert:expr) => {{
+        let rows = &$rows[..];
+        if $cached {
+            let mut chunks = rows.chunks_exact(PG_CACHED_CHUNK_SIZE);
+            for chunk in &mut chunks {
+                let $values: &[_; PG_CACHED_CHUNK_SIZE] = chunk.try_into().unwrap();
+                $insert;
+            }
+            let $values = chunks.remainder();
+            if !$values.is_empty() {
+                $insert;
+            }
+        } else {
+            for $values in rows.chunks(PG_COMMIT_CHUNK_SIZE) {
+                $insert;
+            }
+        }
+    }};
+}
+
+sql_function!(fn least(a: BigInt, b: BigInt) -> BigInt);
+sql_function!(fn greatest(a: BigInt, b: BigInt) -> BigInt);
+
+// Tables with one partition per epoch, named <table>_partition_<epoch>
+const EPOCH_PARTITIONED_TABLES: [&str; 2] = ["transactions", "objects_history"];
+
 const GET_PARTITION_SQL: &str = r#"
-SELECT parent.relname                           AS table_name,
-       MAX(SUBSTRING(child.relname FROM '\d$')) AS last_partition
+SELECT parent.relname                                                AS table_name,
+       SUBSTRING(child.relname FROM '_partition_(\d+)$')::BIGINT AS epoch
 FROM pg_inherits
          JOIN pg_class parent ON pg_inherits.inhparent = parent.oid
          JOIN pg_class child ON pg_inherits.inhrelid = child.oid
-         JOIN pg_namespace nmsp_parent ON nmsp_parent.oid = parent.relnamespace
-         JOIN pg_namespace nmsp_child ON nmsp_child.oid = child.relnamespace
 WHERE parent.relkind = 'p'
-GROUP BY table_name;
+  AND child.relname ~ '_partition_\d+$';
 "#;
 
 #[derive(QueryableByName, Debug, Clone)]
@@ -95,14 +144,19 @@ struct TempDigestTable {
 #[derive(Clone)]
 pub struct PgIndexerStore {
     cp: AsyncPgConnectionPool,
-    // MUSTFIX(gegaowp): temporarily disable partition management.
-    #[allow(dead_code)]
     partition_manager: PartitionManager,
     module_cache: Arc<SyncModuleCache<IndexerModuleResolver>>,
+    metrics: IndexerCheckpointHandlerMetrics,
+    // reuse the prepared statements of the hot inserts across commits
+    cache_statements: bool,
 }
 
 impl PgIndexerStore {
-    pub async fn new(cp: AsyncPgConnectionPool, blocking_cp: PgConnectionPool) -> Self {
+    pub async fn new(
+        cp: AsyncPgConnectionPool,
+        blocking_cp: PgConnectionPool,
+        metrics: IndexerCheckpointHandlerMetrics,
+    ) -> Self {
         let module_cache = Arc::new(SyncModuleCache::new(IndexerModuleResolver::new(
             blocking_cp.clone(),
         )));
@@ -110,9 +164,28 @@ impl PgIndexerStore {
             cp: cp.clone(),
             partition_manager: PartitionManager::new(blocking_cp).await.unwrap(),
             module_cache,
+            metrics,
+            cache_statements: true,
         }
     }
 
+    /// Turns the reuse of prepared statements for the inserts of objects, transactions and
+    /// events on or off, it is on by default.
+    pub fn with_statement_cache(mut self, enabled: bool) -> Self {
+        self.cache_statements = enabled;
+        self
+    }
+
+    /// Epochs of the partitions of `table`, in ascending order.
+    pub async fn get_epoch_partitions(&self, table: &str) -> Result<Vec<EpochId>, IndexerError> {
+        Ok(self
+            .partition_manager
+            .get_table_partitions()
+            .await?
+            .remove(table)
+            .unwrap_or_default())
+    }
+
     pub async fn get_sui_types_object(
         &self,
         object_id: &ObjectID,
@@ -194,6 +267,31 @@ impl PgIndexerStore {
             Err(e) => Err(e),
         }
     }
+
+    /// The last `n` epochs up to the latest ended one, in epoch order.
+    async fn get_last_ended_epochs(&self, n: usize) -> Result<Vec<DBEpochInfo>, IndexerError> {
+        read_only!(&self.cp, |conn| async {
+            let last_ended = epochs::dsl::epochs
+                .select(epochs::epoch)
+                .filter(epochs::epoch_end_timestamp.is_not_null())
+                .order_by(epochs::epoch.desc())
+                .first::<i64>(conn)
+                .await
+                .optional()?;
+            match last_ended {
+                Some(last) => {
+                    epochs::dsl::epochs
+                        .filter(epochs::epoch.between(last + 1 - n as i64, last))
+                        .order_by(epochs::epoch.asc())
+                        .load::<DBEpochInfo>(conn)
+                        .await
+                }
+                None => Ok(vec![]),
+            }
+        }
+        .scope_boxed())
+        .context(&format!("Failed reading the last {n} ended epochs"))
+    }
 }
 
 #[async_trait]
@@ -202,10 +300,11 @@ impl IndexerStore for PgIndexerStore {
 
     async fn get_latest_checkpoint_sequence_number(&self) -> Result<i64, IndexerError> {
         read_only!(&self.cp, |conn| async {
-            checkpoints_dsl::checkpoints
-                .select(max(checkpoints::sequence_number))
-                .first::<Option<i64>>(conn)
+            watermark::table
+                .select(watermark::checkpoint_sequence_number)
+                .first::<i64>(conn)
                 .await
+                .optional()
                 // -1 to differentiate between no checkpoints and the first checkpoint
                 .map(|o| o.unwrap_or(-1))
         }
@@ -313,6 +412,40 @@ impl IndexerStore for PgIndexerStore {
         .context("Failed reading checkpoint seq number from PostgresDB")? as u64)
     }
 
+    async fn get_checkpoint_by_digest(
+        &self,
+        digest: CheckpointDigest,
+    ) -> Result<Checkpoint, IndexerError> {
+        read_only!(&self.cp, |conn| checkpoints_dsl::checkpoints
+            .filter(checkpoints::checkpoint_digest.eq(digest.base58_encode()))
+            .first::<Checkpoint>(conn)
+            .scope_boxed())
+        .context("Failed reading checkpoint by digest from PostgresDB")
+    }
+
+    async fn get_checkpoint_at_or_before(
+        &self,
+        timestamp_ms: i64,
+    ) -> Result<Option<Checkpoint>, IndexerError> {
+        // checkpoint timestamps never decrease, so that ordering by timestamp first
+        // can use the timestamp index.
+        read_only!(&self.cp, |conn| async {
+            checkpoints_dsl::checkpoints
+                .filter(checkpoints::timestamp_ms.le(timestamp_ms))
+                .order((
+                    checkpoints::timestamp_ms.desc(),
+                    checkpoints::sequence_number.desc(),
+                ))
+                .first::<Checkpoint>(conn)
+                .await
+                .optional()
+        }
+        .scope_boxed())
+        .context(&format!(
+            "Failed reading checkpoint at or before timestamp {timestamp_ms} from PostgresDB"
+        ))
+    }
+
     async fn get_event(&self, id: EventID) -> Result<Event, IndexerError> {
         read_only!(&self.cp, |conn| events::table
             .filter(events::dsl::transaction_digest.eq(id.tx_digest.base58_encode()))
@@ -416,6 +549,57 @@ impl IndexerStore for PgIndexerStore {
         })
     }
 
+    async fn get_events_by_type(
+        &self,
+        event_type: &str,
+        cursor: Option<EventCursor>,
+        limit: usize,
+        descending_order: bool,
+    ) -> Result<Vec<Event>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            let mut boxed_query = events::table
+                .filter(events::dsl::event_type.eq(event_type))
+                .into_boxed();
+            if let Some(cursor) = cursor {
+                let same_checkpoint =
+                    events::dsl::checkpoint_sequence_number.eq(cursor.checkpoint_sequence_number);
+                boxed_query = if descending_order {
+                    boxed_query.filter(
+                        events::dsl::checkpoint_sequence_number
+                            .lt(cursor.checkpoint_sequence_number)
+                            .or(same_checkpoint
+                                .and(events::dsl::event_index.lt(cursor.event_index))),
+                    )
+                } else {
+                    boxed_query.filter(
+                        events::dsl::checkpoint_sequence_number
+                            .gt(cursor.checkpoint_sequence_number)
+                            .or(same_checkpoint
+                                .and(events::dsl::event_index.gt(cursor.event_index))),
+                    )
+                };
+            }
+            boxed_query = if descending_order {
+                boxed_query.order((
+                    events::dsl::checkpoint_sequence_number.desc(),
+                    events::dsl::event_index.desc(),
+                ))
+            } else {
+                boxed_query.order((
+                    events::dsl::checkpoint_sequence_number.asc(),
+                    events::dsl::event_index.asc(),
+                ))
+            };
+            boxed_query
+                .limit(limit as i64)
+                .load::<Event>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading events with type {event_type} and cursor {cursor:?} and limit {limit}"
+        ))
+    }
+
     async fn get_total_transaction_number_from_checkpoints(&self) -> Result<i64, IndexerError> {
         let checkpoint: Checkpoint = read_only!(&self.cp, |conn| {
             checkpoints_dsl::checkpoints
@@ -544,6 +728,87 @@ impl IndexerStore for PgIndexerStore {
         ))
     }
 
+    async fn get_move_calls_by_type_argument(
+        &self,
+        type_argument: String,
+        function: Option<MoveFunctionName>,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<MoveCall>, IndexerError> {
+        let normalized = normalize_type_argument(&type_argument);
+        read_only!(&self.cp, |conn| {
+            let mut boxed_query = move_calls_dsl::move_calls
+                .filter(move_calls_dsl::move_type_arguments.contains(vec![Some(normalized.clone())]))
+                .into_boxed();
+            if let Some(function) = &function {
+                boxed_query = boxed_query
+                    .filter(move_calls_dsl::move_package.eq(function.package.to_string()))
+                    .filter(move_calls_dsl::move_module.eq(function.module.to_string()))
+                    .filter(move_calls_dsl::move_function.eq(function.function.to_string()));
+            }
+            if let Some(cursor) = cursor {
+                boxed_query = boxed_query.filter(move_calls_dsl::id.gt(cursor));
+            }
+            boxed_query
+                .order(move_calls_dsl::id.asc())
+                .limit(limit as i64)
+                .load::<MoveCall>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading move calls with type argument {type_argument} and function {function:?} and cursor {cursor:?} and limit {limit}"
+        ))
+    }
+
+    async fn get_address_activity(&self, address: &str) -> Result<AddressActivity, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            address_activity_dsl::address_activity
+                .filter(address_activity_dsl::account_address.eq(address))
+                .first::<AddressActivity>(conn)
+                .scope_boxed()
+        })
+        .context(&format!("Failed reading activity of address {address}"))
+    }
+
+    async fn get_ptb_commands(&self, tx_digest: &str) -> Result<Vec<PtbCommand>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            ptb_commands_dsl::ptb_commands
+                .filter(ptb_commands_dsl::transaction_digest.eq(tx_digest))
+                .order(ptb_commands_dsl::command_index.asc())
+                .load::<PtbCommand>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading commands of transaction {tx_digest}"
+        ))
+    }
+
+    async fn get_dependencies(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            transaction_dependencies_dsl::transaction_dependencies
+                .select(transaction_dependencies_dsl::depends_on_digest)
+                .filter(transaction_dependencies_dsl::transaction_digest.eq(tx_digest))
+                .load::<String>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading dependencies of transaction {tx_digest}"
+        ))
+    }
+
+    async fn get_dependents(&self, tx_digest: &str) -> Result<Vec<String>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            transaction_dependencies_dsl::transaction_dependencies
+                .select(transaction_dependencies_dsl::transaction_digest)
+                .filter(transaction_dependencies_dsl::depends_on_digest.eq(tx_digest))
+                .load::<String>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading dependents of transaction {tx_digest}"
+        ))
+    }
+
     async fn get_transaction_sequence_by_digest(
         &self,
         tx_digest: Option<String>,
@@ -656,6 +921,57 @@ impl IndexerStore for PgIndexerStore {
     }
 
     // NOTE(gegaowp): now only supports query by address owner
+    async fn get_objects_by_owner(
+        &self,
+        owner: String,
+        type_filter: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: usize,
+    ) -> Result<Vec<Object>, IndexerError> {
+        // NOTE: objects only holds the latest version of each object, older versions
+        // are in objects_history.
+        read_only!(&self.cp, |conn| {
+            let mut boxed_query = objects_dsl::objects
+                .filter(objects_dsl::owner_type.eq(OwnerType::AddressOwner))
+                .filter(objects_dsl::owner_address.eq(owner.clone()))
+                .filter(objects_dsl::object_status.ne(ObjectStatus::Deleted))
+                .filter(objects_dsl::object_status.ne(ObjectStatus::Wrapped))
+                .filter(objects_dsl::object_status.ne(ObjectStatus::UnwrappedThenDeleted))
+                .into_boxed();
+            if let Some(type_filter) = &type_filter {
+                boxed_query = boxed_query.filter(objects_dsl::object_type.eq(type_filter.clone()));
+            }
+            if let Some(cursor) = cursor {
+                boxed_query = boxed_query.filter(objects_dsl::object_id.gt(cursor.to_string()));
+            }
+            boxed_query
+                .order(objects_dsl::object_id.asc())
+                .limit(limit as i64)
+                .load::<Object>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading objects of owner {owner} with type {type_filter:?} and cursor {cursor:?} and limit {limit}"
+        ))
+    }
+
+    async fn get_top_owners(&self, limit: usize) -> Result<Vec<OwnerObjectCount>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            owner_object_counts::table
+                .filter(owner_object_counts::object_count.gt(0))
+                .order((
+                    owner_object_counts::object_count.desc(),
+                    owner_object_counts::owner_address.asc(),
+                ))
+                .limit(limit as i64)
+                .load::<OwnerObjectCount>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading top {limit} owners by object count"
+        ))
+    }
+
     async fn query_latest_objects(
         &self,
         filter: SuiObjectDataFilter,
@@ -775,6 +1091,15 @@ impl IndexerStore for PgIndexerStore {
         ))
     }
 
+    async fn get_transaction_signatures(
+        &self,
+        tx_digest: &str,
+    ) -> Result<Vec<GenericSignature>, IndexerError> {
+        self.get_transaction_by_digest(tx_digest)
+            .await?
+            .signatures()
+    }
+
     async fn get_all_transaction_page(
         &self,
         start_sequence: Option<i64>,
@@ -870,6 +1195,30 @@ impl IndexerStore for PgIndexerStore {
         }).context(&format!("Failed reading transaction digests with kind {kind} and start_sequence {start_sequence:?} and limit {limit}"))
     }
 
+    async fn get_transactions_by_kind(
+        &self,
+        kind: DBTransactionKind,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<Transaction>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            let mut boxed_query = transactions_dsl::transactions
+                .filter(transactions_dsl::kind.eq(kind))
+                .into_boxed();
+            if let Some(cursor) = cursor {
+                boxed_query = boxed_query.filter(transactions_dsl::id.gt(cursor));
+            }
+            boxed_query
+                .order(transactions_dsl::id.asc())
+                .limit(limit as i64)
+                .load::<Transaction>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading transactions with kind {kind:?} and cursor {cursor:?} and limit {limit}"
+        ))
+    }
+
     async fn get_transaction_page_by_mutated_object(
         &self,
         object_id: String,
@@ -1070,10 +1419,45 @@ impl IndexerStore for PgIndexerStore {
         self.multi_get_transactions_by_digests(&tx_digests).await
     }
 
+    async fn get_consensus_commits(
+        &self,
+        cursor: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ConsensusCommit>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            let mut boxed_query = consensus_commits_dsl::consensus_commits.into_boxed();
+            if let Some(cursor) = cursor {
+                boxed_query = boxed_query
+                    .filter(consensus_commits_dsl::checkpoint_sequence_number.gt(cursor));
+            }
+            boxed_query
+                .order(consensus_commits_dsl::checkpoint_sequence_number.asc())
+                .limit(limit as i64)
+                .load::<ConsensusCommit>(conn)
+                .scope_boxed()
+        })
+        .context(&format!(
+            "Failed reading consensus commits with cursor {cursor:?} and limit {limit}"
+        ))
+    }
+
     async fn get_network_metrics(&self) -> Result<NetworkMetrics, IndexerError> {
         get_network_metrics_cached(&self.cp).await
     }
 
+    async fn get_tps(&self, window: usize) -> Result<f64, IndexerError> {
+        let mut checkpoints: Vec<Checkpoint> = read_only!(&self.cp, |conn| {
+            checkpoints_dsl::checkpoints
+                .order(checkpoints::sequence_number.desc())
+                .limit(window as i64)
+                .load::<Checkpoint>(conn)
+                .scope_boxed()
+        })
+        .context(&format!("Failed reading last {window} checkpoints"))?;
+        checkpoints.reverse();
+        Ok(compute_tps(&checkpoints))
+    }
+
     async fn get_move_call_metrics(&self) -> Result<MoveCallMetrics, IndexerError> {
         let metrics = read_only!(&self.cp, |conn| {
             diesel::sql_query("SELECT * FROM epoch_move_call_metrics;")
@@ -1114,7 +1498,12 @@ impl IndexerStore for PgIndexerStore {
         tx: Transaction,
         tx_object_changes: TransactionObjectChanges,
     ) -> Result<usize, IndexerError> {
-        transactional!(&self.cp, |conn| async {
+        let epoch = tx.epoch;
+        let needs_partitions = self.partition_manager.needs_partitions(epoch);
+        let committed = transactional!(&self.cp, |conn| async {
+            if needs_partitions {
+                create_epoch_partitions(conn, epoch).await?;
+            }
             diesel::insert_into(transactions::table)
                 .values(vec![tx])
                 .on_conflict_do_nothing()
@@ -1133,10 +1522,15 @@ impl IndexerStore for PgIndexerStore {
                 conn,
                 tx_object_changes.changed_objects,
                 deleted_objects,
+                self.cache_statements,
             )
             .await
         }
-        .scope_boxed())
+        .scope_boxed())?;
+        if needs_partitions {
+            self.partition_manager.partitions_created(epoch);
+        }
+        Ok(committed)
     }
 
     async fn persist_checkpoint(
@@ -1149,18 +1543,31 @@ impl IndexerStore for PgIndexerStore {
             events,
             objects_changes: tx_object_changes,
             addresses,
+            address_activities,
             packages,
             input_objects,
             move_calls,
             recipients,
+            ptb_commands,
+            dependencies,
+            consensus_commit,
         } = data;
 
-        transactional!(&self.cp, |conn| async {
+        let needs_partitions = self.partition_manager.needs_partitions(checkpoint.epoch);
+        let committed = transactional!(&self.cp, |conn| async {
+            if needs_partitions {
+                create_epoch_partitions(conn, checkpoint.epoch).await?;
+            }
+
             // Commit indexed transactions
-            for transaction_chunk in transactions.chunks(PG_COMMIT_CHUNK_SIZE) {
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_transactions
+                .start_timer();
+            insert_in_chunks!(self.cache_statements, transactions, |transaction_chunk| {
                 diesel::insert_into(transactions::table)
                     .values(transaction_chunk)
-                    .on_conflict(transactions::transaction_digest)
+                    .on_conflict((transactions::transaction_digest, transactions::epoch))
                     .do_update()
                     .set((
                         transactions::timestamp_ms.eq(excluded(transactions::timestamp_ms)),
@@ -1170,21 +1577,31 @@ impl IndexerStore for PgIndexerStore {
                     .execute(conn)
                     .await
                     .map_err(IndexerError::from)
-                    .context("Failed writing transactions to PostgresDB")?;
-            }
+                    .context("Failed writing transactions to PostgresDB")?
+            });
+            guard.stop_and_record();
 
             // Commit indexed events
-            for event_chunk in events.chunks(PG_COMMIT_CHUNK_SIZE) {
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_events
+                .start_timer();
+            insert_in_chunks!(self.cache_statements, events, |event_chunk| {
                 diesel::insert_into(events::table)
                     .values(event_chunk)
                     .on_conflict_do_nothing()
                     .execute(conn)
                     .await
                     .map_err(IndexerError::from)
-                    .context("Failed writing events to PostgresDB")?;
-            }
+                    .context("Failed writing events to PostgresDB")?
+            });
+            guard.stop_and_record();
 
             // Commit indexed objects
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_objects
+                .start_timer();
             let mutated_objects: Vec<Object> = tx_object_changes
                 .iter()
                 .flat_map(|changes| changes.changed_objects.iter().cloned())
@@ -1199,9 +1616,20 @@ impl IndexerStore for PgIndexerStore {
                 .iter()
                 .map(|deleted_object| deleted_object.clone().into())
                 .collect();
-            persist_transaction_object_changes(conn, mutated_objects, deleted_objects).await?;
+            persist_transaction_object_changes(
+                conn,
+                mutated_objects,
+                deleted_objects,
+                self.cache_statements,
+            )
+            .await?;
+            guard.stop_and_record();
 
             // Commit indexed addresses
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_addresses
+                .start_timer();
             for addresses_chunk in addresses.chunks(PG_COMMIT_CHUNK_SIZE) {
                 diesel::insert_into(addresses::table)
                     .values(addresses_chunk)
@@ -1213,7 +1641,43 @@ impl IndexerStore for PgIndexerStore {
                     .context("Failed writing addresses to PostgresDB")?;
             }
 
+            // Commit address activity, the window only ever widens so that checkpoints
+            // can be reindexed in any order.
+            for activities_chunk in address_activities.chunks(PG_COMMIT_CHUNK_SIZE) {
+                diesel::insert_into(address_activity::table)
+                    .values(activities_chunk)
+                    .on_conflict(address_activity::account_address)
+                    .do_update()
+                    .set((
+                        address_activity::first_appearance_checkpoint.eq(least(
+                            address_activity::first_appearance_checkpoint,
+                            excluded(address_activity::first_appearance_checkpoint),
+                        )),
+                        address_activity::first_appearance_timestamp.eq(least(
+                            address_activity::first_appearance_timestamp,
+                            excluded(address_activity::first_appearance_timestamp),
+                        )),
+                        address_activity::last_appearance_checkpoint.eq(greatest(
+                            address_activity::last_appearance_checkpoint,
+                            excluded(address_activity::last_appearance_checkpoint),
+                        )),
+                        address_activity::last_appearance_timestamp.eq(greatest(
+                            address_activity::last_appearance_timestamp,
+                            excluded(address_activity::last_appearance_timestamp),
+                        )),
+                    ))
+                    .execute(conn)
+                    .await
+                    .map_err(IndexerError::from)
+                    .context("Failed writing address activity to PostgresDB")?;
+            }
+            guard.stop_and_record();
+
             // Commit indexed packages
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_packages
+                .start_timer();
             for packages_chunk in packages.chunks(PG_COMMIT_CHUNK_SIZE) {
                 diesel::insert_into(packages::table)
                     .values(packages_chunk)
@@ -1223,8 +1687,13 @@ impl IndexerStore for PgIndexerStore {
                     .map_err(IndexerError::from)
                     .context("Failed writing packages to PostgresDB")?;
             }
+            guard.stop_and_record();
 
             // Commit indexed move calls
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_move_calls
+                .start_timer();
             for move_calls_chunk in move_calls.chunks(PG_COMMIT_CHUNK_SIZE) {
                 diesel::insert_into(move_calls::table)
                     .values(move_calls_chunk)
@@ -1234,6 +1703,7 @@ impl IndexerStore for PgIndexerStore {
                     .map_err(IndexerError::from)
                     .context("Failed writing move_calls to PostgresDB")?;
             }
+            guard.stop_and_record();
 
             // Commit indexed input objects
             for input_objects_chunk in input_objects.chunks(PG_COMMIT_CHUNK_SIZE) {
@@ -1247,6 +1717,10 @@ impl IndexerStore for PgIndexerStore {
             }
 
             // Commit indexed recipients
+            let guard = self
+                .metrics
+                .checkpoint_db_commit_latency_recipients
+                .start_timer();
             for recipients_chunk in recipients.chunks(PG_COMMIT_CHUNK_SIZE) {
                 diesel::insert_into(recipients::table)
                     .values(recipients_chunk)
@@ -1256,6 +1730,40 @@ impl IndexerStore for PgIndexerStore {
                     .map_err(IndexerError::from)
                     .context("Failed writing recipients to PostgresDB")?;
             }
+            guard.stop_and_record();
+
+            // Commit indexed programmable transaction commands
+            for commands_chunk in ptb_commands.chunks(PG_COMMIT_CHUNK_SIZE) {
+                diesel::insert_into(ptb_commands::table)
+                    .values(commands_chunk)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .await
+                    .map_err(IndexerError::from)
+                    .context("Failed writing ptb_commands to PostgresDB")?;
+            }
+
+            // Commit indexed transaction dependencies
+            for dependencies_chunk in dependencies.chunks(PG_COMMIT_CHUNK_SIZE) {
+                diesel::insert_into(transaction_dependencies::table)
+                    .values(dependencies_chunk)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .await
+                    .map_err(IndexerError::from)
+                    .context("Failed writing transaction dependencies to PostgresDB")?;
+            }
+
+            // Commit indexed consensus commit
+            if let Some(consensus_commit) = consensus_commit {
+                diesel::insert_into(consensus_commits::table)
+                    .values(consensus_commit)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .await
+                    .map_err(IndexerError::from)
+                    .context("Failed writing consensus commit to PostgresDB")?;
+            }
 
             // update epoch transaction count
             let sql = "UPDATE epochs e1
@@ -1271,28 +1779,37 @@ WHERE e1.epoch = e2.epoch
                 .await?;
             // Commit indexed checkpoint last, so that if the checkpoint is committed,
             // all related data have been committed as well.
-            diesel::insert_into(checkpoints::table)
+            let committed = diesel::insert_into(checkpoints::table)
                 .values(checkpoint)
                 .on_conflict_do_nothing()
                 .execute(conn)
                 .await
                 .map_err(IndexerError::from)
-                .context("Failed writing checkpoint to PostgresDB")
+                .context("Failed writing checkpoint to PostgresDB")?;
+            // The watermark only ever advances, also when a checkpoint is reindexed.
+            diesel::insert_into(watermark::table)
+                .values(watermark::checkpoint_sequence_number.eq(checkpoint.sequence_number))
+                .on_conflict(watermark::id)
+                .do_update()
+                .set(watermark::checkpoint_sequence_number.eq(greatest(
+                    watermark::checkpoint_sequence_number,
+                    excluded(watermark::checkpoint_sequence_number),
+                )))
+                .execute(conn)
+                .await
+                .map_err(IndexerError::from)
+                .context("Failed writing watermark to PostgresDB")?;
+            Ok::<_, IndexerError>(committed)
         }
-        .scope_boxed())
+        .scope_boxed())?;
+        if needs_partitions {
+            // NOTE: only once committed, a failed commit rolls the partitions back
+            self.partition_manager.partitions_created(checkpoint.epoch);
+        }
+        Ok(committed)
     }
 
     async fn persist_epoch(&self, data: &TemporaryEpochStore) -> Result<(), IndexerError> {
-        // MUSTFIX(gegaowp): temporarily disable the epoch advance logic.
-        // let last_epoch_cp_id = if data.last_epoch.is_none() {
-        //     0
-        // } else {
-        //     self.get_current_epoch().await?.first_checkpoint_id as i64
-        // };
-        // self.partition_manager
-        //     .advance_epoch(&data.new_epoch, last_epoch_cp_id)
-        //     .await?;
-
         transactional!(&self.cp, |conn| async {
             if let Some(last_epoch) = &data.last_epoch {
                 diesel::insert_into(epochs::table)
@@ -1331,14 +1848,22 @@ WHERE e1.epoch = e2.epoch
                 .execute(conn)
                 .await?;
 
-            diesel::insert_into(system_states::table)
-                .values(&data.system_state)
+            if let Some(system_state) = &data.system_state {
+                diesel::insert_into(system_states::table)
+                    .values(system_state)
+                    .on_conflict_do_nothing()
+                    .execute(conn)
+                    .await?;
+            }
+
+            diesel::insert_into(validators::table)
+                .values(&data.validators)
                 .on_conflict_do_nothing()
                 .execute(conn)
                 .await?;
 
-            diesel::insert_into(validators::table)
-                .values(&data.validators)
+            diesel::insert_into(genesis_allocations::table)
+                .values(&data.genesis_allocations)
                 .on_conflict_do_nothing()
                 .execute(conn)
                 .await
@@ -1347,6 +1872,99 @@ WHERE e1.epoch = e2.epoch
         Ok(())
     }
 
+    async fn persist_quarantined_checkpoint(
+        &self,
+        checkpoint: &QuarantinedCheckpoint,
+    ) -> Result<(), IndexerError> {
+        transactional!(&self.cp, |conn| async {
+            diesel::insert_into(quarantined_checkpoints::table)
+                .values(checkpoint)
+                .on_conflict(quarantined_checkpoints::sequence_number)
+                .do_update()
+                .set((
+                    quarantined_checkpoints::attempts
+                        .eq(excluded(quarantined_checkpoints::attempts)),
+                    quarantined_checkpoints::error.eq(excluded(quarantined_checkpoints::error)),
+                ))
+                .execute(conn)
+                .await
+                .map_err(IndexerError::from)
+                .context("Failed writing quarantined checkpoint to PostgresDB")
+        }
+        .scope_boxed())?;
+        Ok(())
+    }
+
+    async fn get_quarantined_checkpoints(
+        &self,
+    ) -> Result<Vec<QuarantinedCheckpoint>, IndexerError> {
+        read_only!(&self.cp, |conn| {
+            quarantined_checkpoints_dsl::quarantined_checkpoints
+                .order(quarantined_checkpoints_dsl::sequence_number.asc())
+                .load::<QuarantinedCheckpoint>(conn)
+                .scope_boxed()
+        })
+        .context("Failed reading quarantined checkpoints")
+    }
+
+    async fn get_chain_identifier(&self) -> Result<Option<String>, IndexerError> {
+        read_only!(&self.cp, |conn| async {
+            chain_identifier::table
+                .select(chain_identifier::checkpoint_digest)
+                .first::<String>(conn)
+                .await
+                .optional()
+        }
+        .scope_boxed())
+        .context("Failed reading chain identifier")
+    }
+
+    async fn persist_chain_identifier(&self, checkpoint_digest: &str) -> Result<(), IndexerError> {
+        transactional!(&self.cp, |conn| async {
+            diesel::insert_into(chain_identifier::table)
+                .values(chain_identifier::checkpoint_digest.eq(checkpoint_digest))
+                .on_conflict_do_nothing()
+                .execute(conn)
+                .await
+                .map_err(IndexerError::from)
+                .context("Failed writing chain identifier to PostgresDB")
+        }
+        .scope_boxed())?;
+        Ok(())
+    }
+
+    async fn audit(&self) -> Result<AuditReport, IndexerError> {
+        // NOTE: checkpoints and epochs are read from the same snapshot, otherwise checkpoints
+        // committed in between would show up as transaction count mismatches.
+        let mut pg_pool_conn = crate::get_async_pg_pool_connection(&self.cp).await?;
+        let (checkpoints, epochs) = pg_pool_conn
+            .build_transaction()
+            .repeatable_read()
+            .read_only()
+            .run(|conn| {
+                async {
+                    let checkpoints = checkpoints_dsl::checkpoints
+                        .select((
+                            checkpoints::sequence_number,
+                            checkpoints::total_transaction_blocks,
+                        ))
+                        .order(checkpoints::sequence_number.asc())
+                        .load::<(i64, i64)>(conn)
+                        .await?;
+                    let epochs = epochs_dsl::epochs
+                        .order(epochs::epoch.asc())
+                        .load::<DBEpochInfo>(conn)
+                        .await?;
+                    Ok::<_, diesel::result::Error>((checkpoints, epochs))
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(|e| IndexerError::PostgresReadError(e.to_string()))
+            .context("Failed reading checkpoints and epochs to audit")?;
+        Ok(audit_checkpoints_and_epochs(&checkpoints, &epochs))
+    }
+
     fn module_cache(&self) -> &Self::ModuleCache {
         &self.module_cache
     }
@@ -1433,13 +2051,159 @@ WHERE e1.epoch = e2.epoch
 
         epoch_info.to_epoch_info(validators)
     }
+
+    async fn get_epoch_row(&self, epoch: EpochId) -> Result<Option<DBEpochInfo>, IndexerError> {
+        read_only!(&self.cp, |conn| epochs::dsl::epochs
+            .filter(epochs::epoch.eq(epoch as i64))
+            .first::<DBEpochInfo>(conn)
+            .optional()
+            .scope_boxed())
+        .context(&format!("Failed reading epoch {epoch}"))
+    }
+
+    async fn get_validators(
+        &self,
+        epoch: EpochId,
+    ) -> Result<Vec<DBValidatorSummary>, IndexerError> {
+        read_only!(&self.cp, |conn| validators::dsl::validators
+            .filter(validators::epoch.eq(epoch as i64))
+            .load::<DBValidatorSummary>(conn)
+            .scope_boxed())
+        .context(&format!("Failed reading validators of epoch {epoch}"))
+    }
+
+    async fn get_validator_commission_history(
+        &self,
+        validator: SuiAddress,
+    ) -> Result<Vec<ValidatorEpoch>, IndexerError> {
+        read_only!(&self.cp, |conn| validators::dsl::validators
+            .select((
+                validators::epoch,
+                validators::commission_rate,
+                validators::next_epoch_commission_rate,
+                validators::gas_price,
+                validators::next_epoch_gas_price,
+                validators::voting_power,
+                validators::next_epoch_stake,
+            ))
+            .filter(validators::sui_address.eq(validator.to_string()))
+            .order_by(validators::epoch.asc())
+            .load::<ValidatorEpoch>(conn)
+            .scope_boxed())
+        .context(&format!(
+            "Failed reading commission history of validator {validator}"
+        ))
+    }
+
+    async fn get_epoch_economics(
+        &self,
+        start_epoch: EpochId,
+        end_epoch: EpochId,
+    ) -> Result<Vec<EpochEconomics>, IndexerError> {
+        read_only!(&self.cp, |conn| epochs::dsl::epochs
+            .select((
+                epochs::epoch,
+                epochs::total_gas_fees,
+                epochs::storage_charge,
+                epochs::storage_rebate,
+                epochs::storage_fund_reinvestment,
+                epochs::stake_subsidy_amount,
+                epochs::total_stake_rewards_distributed,
+                epochs::storage_fund_balance,
+                epochs::leftover_storage_fund_inflow,
+            ))
+            .filter(epochs::epoch.between(start_epoch as i64, end_epoch as i64))
+            .order_by(epochs::epoch.asc())
+            .load::<EpochEconomics>(conn)
+            .scope_boxed())
+        .context(&format!(
+            "Failed reading economics of epochs {start_epoch} to {end_epoch}"
+        ))
+    }
+
+    async fn get_network_apy(&self, window_epochs: usize) -> Result<StakeApy, IndexerError> {
+        // From the epoch before the window, which ends with the stake the window starts with.
+        let epochs = self.get_last_ended_epochs(window_epochs + 1).await?;
+        Ok(StakeApy::of_network(&epochs))
+    }
+
+    async fn get_validator_apy(
+        &self,
+        validator: SuiAddress,
+        window_epochs: usize,
+    ) -> Result<StakeApy, IndexerError> {
+        let epochs = self.get_last_ended_epochs(window_epochs).await?;
+        let (first, last) = match (epochs.first(), epochs.last()) {
+            (Some(first), Some(last)) => (first.epoch, last.epoch),
+            _ => return Ok(StakeApy::default()),
+        };
+        let states = read_only!(&self.cp, |conn| validators::dsl::validators
+            .filter(validators::sui_address.eq(validator.to_string()))
+            .filter(validators::epoch.between(first, last + 1))
+            .order_by(validators::epoch.asc())
+            .load::<DBValidatorSummary>(conn)
+            .scope_boxed())
+        .context(&format!(
+            "Failed reading validator {validator} from epoch {first} to {}",
+            last + 1
+        ))?;
+        Ok(StakeApy::of_validator(&epochs, &states))
+    }
+
+    async fn get_genesis_allocations(&self) -> Result<Vec<GenesisAllocation>, IndexerError> {
+        read_only!(&self.cp, |conn| genesis_allocations::table
+            .order_by(genesis_allocations::object_id.asc())
+            .load::<GenesisAllocation>(conn)
+            .scope_boxed())
+        .context("Failed reading genesis allocations")
+    }
 }
 
 async fn persist_transaction_object_changes(
     conn: &mut AsyncPgConnection,
     mutated_objects: Vec<Object>,
     deleted_objects: Vec<Object>,
+    cache_statements: bool,
 ) -> Result<usize, IndexerError> {
+    let object_ids = mutated_objects
+        .iter()
+        .chain(deleted_objects.iter())
+        .map(|o| o.object_id.clone())
+        .collect::<Vec<_>>();
+    let stored_objects = objects::table
+        .select((
+            objects::object_id,
+            objects::version,
+            objects::owner_type,
+            objects::owner_address,
+            objects::object_status,
+        ))
+        .filter(objects::object_id.eq_any(object_ids))
+        .load::<(String, i64, OwnerType, Option<String>, ObjectStatus)>(conn)
+        .await
+        .map_err(IndexerError::from)
+        .context("Failed reading stored object versions from PostgresDB")?;
+    let mut stored_versions = BTreeMap::new();
+    let mut stored_owners = BTreeMap::new();
+    for (object_id, version, owner_type, owner_address, object_status) in stored_objects {
+        let live = !matches!(
+            object_status,
+            ObjectStatus::Deleted | ObjectStatus::Wrapped | ObjectStatus::UnwrappedThenDeleted
+        );
+        if let (OwnerType::AddressOwner, true, Some(owner)) = (owner_type, live, owner_address) {
+            stored_owners.insert(object_id.clone(), owner);
+        }
+        stored_versions.insert(object_id, version);
+    }
+    let mutated_objects =
+        skip_stale_object_versions(mutated_objects, |id| stored_versions.get(id).copied());
+    let deleted_objects =
+        skip_stale_object_versions(deleted_objects, |id| stored_versions.get(id).copied());
+    let owner_count_changes =
+        owner_object_count_changes(mutated_objects.iter().chain(deleted_objects.iter()), |id| {
+            stored_owners.get(id).cloned()
+        });
+
     // TODO(gegaowp): tx object changes from one tx do not need group_and_sort_objects, will optimize soon after this PR.
     // NOTE: to avoid error of `ON CONFLICT DO UPDATE command cannot affect row a second time`,
     // we have to limit update of one object once in a query.
@@ -1452,122 +2216,179 @@ async fn persist_transaction_object_changes(
         if mutated_object_group.is_empty() {
             break;
         }
-        // bulk insert/update via UNNEST trick
-        let insert_update_query = compose_object_bulk_insert_update_query(&mutated_object_group);
-        diesel::sql_query(insert_update_query)
-            .execute(conn)
-            .await
-            .map_err(|e| {
-                IndexerError::PostgresWriteError(format!(
-                    "Failed writing mutated objects to PostgresDB with error: {:?}",
-                    e
-                ))
-            })?;
+        // NOTE: the insert is a prepared statement that connections can reuse.
+        // One checkpoint can change tens of thousands of objects, so the group is split
+        // to keep each statement bounded.
+        insert_in_chunks!(
+            cache_statements,
+            mutated_object_group,
+            |mutated_object_chunk| {
+                diesel::insert_into(objects::table)
+                    .values(mutated_object_chunk)
+                    .on_conflict(objects::object_id)
+                    .do_update()
+                    .set((
+                        objects::epoch.eq(excluded(objects::epoch)),
+                        objects::checkpoint.eq(excluded(objects::checkpoint)),
+                        objects::version.eq(excluded(objects::version)),
+                        objects::object_digest.eq(excluded(objects::object_digest)),
+                        objects::owner_type.eq(excluded(objects::owner_type)),
+                        objects::owner_address.eq(excluded(objects::owner_address)),
+                        objects::initial_shared_version
+                            .eq(excluded(objects::initial_shared_version)),
+                        objects::previous_transaction.eq(excluded(objects::previous_transaction)),
+                        objects::object_type.eq(excluded(objects::object_type)),
+                        objects::object_status.eq(excluded(objects::object_status)),
+                        objects::has_public_transfer.eq(excluded(objects::has_public_transfer)),
+                        objects::storage_rebate.eq(excluded(objects::storage_rebate)),
+                        objects::bcs.eq(excluded(objects::bcs)),
+                    ))
+                    .execute(conn)
+                    .await
+                    .map_err(|e| {
+                        IndexerError::PostgresWriteError(format!(
+                            "Failed writing mutated objects to PostgresDB with error: {:?}",
+                            e
+                        ))
+                    })?
+            }
+        );
     }
 
-    for deleted_object_change_chunk in deleted_objects.chunks(PG_COMMIT_CHUNK_SIZE) {
-        diesel::insert_into(objects::table)
-            .values(deleted_object_change_chunk)
-            .on_conflict(objects::object_id)
+    insert_in_chunks!(
+        cache_statements,
+        deleted_objects,
+        |deleted_object_change_chunk| {
+            diesel::insert_into(objects::table)
+                .values(deleted_object_change_chunk)
+                .on_conflict(objects::object_id)
+                .do_update()
+                .set((
+                    objects::epoch.eq(excluded(objects::epoch)),
+                    objects::checkpoint.eq(excluded(objects::checkpoint)),
+                    objects::version.eq(excluded(objects::version)),
+                    objects::previous_transaction.eq(excluded(objects::previous_transaction)),
+                    objects::object_status.eq(excluded(objects::object_status)),
+                ))
+                .execute(conn)
+                .await
+                .map_err(|e| {
+                    IndexerError::PostgresWriteError(format!(
+                        "Failed writing deleted objects to PostgresDB with error: {:?}",
+                        e
+                    ))
+                })?
+        }
+    );
+
+    for owner_count_chunk in owner_count_changes.chunks(PG_COMMIT_CHUNK_SIZE) {
+        diesel::insert_into(owner_object_counts::table)
+            .values(owner_count_chunk)
+            .on_conflict(owner_object_counts::owner_address)
             .do_update()
-            .set((
-                objects::epoch.eq(excluded(objects::epoch)),
-                objects::checkpoint.eq(excluded(objects::checkpoint)),
-                objects::version.eq(excluded(objects::version)),
-                objects::previous_transaction.eq(excluded(objects::previous_transaction)),
-                objects::object_status.eq(excluded(objects::object_status)),
-            ))
+            .set(
+                owner_object_counts::object_count
+                    .eq(owner_object_counts::object_count
+                        + excluded(owner_object_counts::object_count)),
+            )
             .execute(conn)
             .await
-            .map_err(|e| {
-                IndexerError::PostgresWriteError(format!(
-                    "Failed writing deleted objects to PostgresDB with error: {:?}",
-                    e
-                ))
-            })?;
+            .map_err(IndexerError::from)
+            .context("Failed writing owner object counts to PostgresDB")?;
     }
     Ok(0)
 }
 
+/// Creates the partitions of the epoch partitioned tables on the first checkpoint of
+/// each epoch.
+async fn create_epoch_partitions(
+    conn: &mut AsyncPgConnection,
+    epoch: i64,
+) -> Result<(), IndexerError> {
+    for table in EPOCH_PARTITIONED_TABLES {
+        diesel::sql_query(format!(
+            "CREATE TABLE IF NOT EXISTS {table}_partition_{epoch} PARTITION OF {table} FOR VALUES FROM ({epoch}) TO ({});",
+            epoch + 1
+        ))
+        .execute(conn)
+        .await
+        .map_err(IndexerError::from)
+        .context(&format!("Failed creating partition of epoch {epoch} for {table}"))?;
+    }
+    info!("Created epoch partition {epoch} for {EPOCH_PARTITIONED_TABLES:?}");
+    Ok(())
+}
+
 #[derive(Clone)]
 struct PartitionManager {
     cp: PgConnectionPool,
+    // epochs all epoch partitioned tables have a partition for
+    partitioned_epochs: Arc<Mutex<BTreeSet<i64>>>,
 }
 
 impl PartitionManager {
     async fn new(cp: PgConnectionPool) -> Result<Self, IndexerError> {
         // Find all tables with partition
-        let manager = Self { cp };
+        let manager = Self {
+            cp,
+            partitioned_epochs: Arc::new(Mutex::new(BTreeSet::new())),
+        };
         let tables = manager.get_table_partitions().await?;
         info!(
             "Found {} tables with partitions : [{:?}]",
             tables.len(),
             tables
         );
+        let mut partitioned_epochs: Option<BTreeSet<i64>> = None;
+        for table in EPOCH_PARTITIONED_TABLES {
+            let epochs = tables
+                .get(table)
+                .into_iter()
+                .flatten()
+                .map(|epoch| *epoch as i64)
+                .collect::<BTreeSet<_>>();
+            partitioned_epochs = Some(match partitioned_epochs {
+                Some(partitioned) => partitioned.intersection(&epochs).copied().collect(),
+                None => epochs,
+            });
+        }
+        *manager.partitioned_epochs.lock().unwrap() = partitioned_epochs.unwrap_or_default();
         Ok(manager)
     }
 
-    // MUSTFIX(gegaowp): temporarily disable partition management.
-    #[allow(dead_code)]
-    async fn advance_epoch(
-        &self,
-        new_epoch: &DBEpochInfo,
-        last_epoch_start_cp: i64,
-    ) -> Result<(), IndexerError> {
-        let next_epoch_id = new_epoch.epoch;
-        let last_epoch_id = new_epoch.epoch - 1;
-        let next_epoch_start_cp = new_epoch.first_checkpoint_id;
-
-        let tables = self.get_table_partitions().await?;
-
-        let table_updated = transactional_blocking!(&self.cp, |conn| {
-            let mut updated_table = vec![];
-            for (table, last_partition) in &tables {
-                if last_partition < &(next_epoch_id as u64) {
-                    let detach_partition = format!(
-                        "ALTER TABLE {table} DETACH PARTITION {table}_partition_{last_epoch_id};"
-                    );
-                    let attach_partition_with_new_range = format!("ALTER TABLE {table} ATTACH PARTITION {table}_partition_{last_epoch_id} FOR VALUES FROM ('{last_epoch_start_cp}') TO ('{next_epoch_start_cp}');");
-                    info! {"Changed last epoch partition {last_epoch_id} for {table}, with new range {last_epoch_start_cp} to {next_epoch_start_cp}"};
-                    let new_partition = format!("CREATE TABLE {table}_partition_{next_epoch_id} PARTITION OF {table} FOR VALUES FROM ({next_epoch_start_cp}) TO (MAXVALUE);");
-                    info! {"Created epoch partition {next_epoch_id} for {table}, with new range {next_epoch_start_cp} to MAXVALUE"};
-                    diesel::RunQueryDsl::execute(diesel::sql_query(detach_partition), conn)?;
-                    diesel::RunQueryDsl::execute(
-                        diesel::sql_query(attach_partition_with_new_range),
-                        conn,
-                    )?;
-                    diesel::RunQueryDsl::execute(diesel::sql_query(new_partition), conn)?;
-                    updated_table.push(table.clone());
-                }
-            }
-            Ok::<_, diesel::result::Error>(updated_table)
-        })?;
-        info! {"Created epoch partition {next_epoch_id} for {table_updated:?}"};
-        Ok(())
+    fn needs_partitions(&self, epoch: i64) -> bool {
+        !self.partitioned_epochs.lock().unwrap().contains(&epoch)
+    }
+
+    fn partitions_created(&self, epoch: i64) {
+        self.partitioned_epochs.lock().unwrap().insert(epoch);
     }
 
-    async fn get_table_partitions(&self) -> Result<BTreeMap<String, u64>, IndexerError> {
+    async fn get_table_partitions(&self) -> Result<BTreeMap<String, Vec<EpochId>>, IndexerError> {
         #[derive(QueryableByName, Debug, Clone)]
-        struct PartitionedTable {
+        struct TablePartition {
             #[diesel(sql_type = VarChar)]
             table_name: String,
-            #[diesel(sql_type = VarChar)]
-            last_partition: String,
+            #[diesel(sql_type = BigInt)]
+            epoch: i64,
         }
 
-        Ok(
+        let partitions: Vec<TablePartition> =
             read_only_blocking!(&self.cp, |conn| diesel::RunQueryDsl::load(
                 diesel::sql_query(GET_PARTITION_SQL),
                 conn
-            ))?
-            .into_iter()
-            .map(|table: PartitionedTable| {
-                u64::from_str(&table.last_partition)
-                    .map(|last_partition| (table.table_name, last_partition))
-                    .map_err(|e| anyhow!(e))
-            })
-            .collect::<Result<_, _>>()?,
-        )
+            ))?;
+        let mut tables = BTreeMap::<_, Vec<_>>::new();
+        for partition in partitions {
+            tables
+                .entry(partition.table_name)
+                .or_default()
+                .push(partition.epoch as EpochId);
+        }
+        for epochs in tables.values_mut() {
+            epochs.sort_unstable();
+        }
+        Ok(tables)
     }
 }
 
@@ -2,23 +2,66 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::anyhow;
+use move_core_types::ident_str;
+use move_core_types::language_storage::StructTag;
 use prometheus::Registry;
 use tokio::task::JoinHandle;
 
-use sui_json_rpc_types::SuiTransactionBlockResponse;
+use shared_crypto::intent::Intent;
+use sui_config::builder::ConfigBuilder;
+use sui_config::genesis::Genesis;
+use sui_json_rpc_types::{
+    Checkpoint as RpcCheckpoint, SuiEvent, SuiObjectData, SuiObjectDataOptions,
+    SuiTransactionBlock, SuiTransactionBlockEffects, SuiTransactionBlockEvents,
+    SuiTransactionBlockResponse,
+};
+use sui_protocol_config::ProtocolConfig;
+use sui_types::base_types::{ObjectDigest, ObjectID, SequenceNumber, SuiAddress};
+use sui_types::committee::{EpochId, ProtocolVersion};
+use sui_types::crypto::{
+    deterministic_random_account_key, get_key_pair, AccountKeyPair, AggregateAuthoritySignature,
+};
+use sui_types::digests::CheckpointDigest;
+use sui_types::dynamic_field::{get_dynamic_field_object_from_store, Field};
+use sui_types::event::EventID;
+use sui_types::gas::GasCostSummary;
+use sui_types::gas_coin::GasCoin;
+use sui_types::id::UID;
+use sui_types::in_memory_storage::InMemoryStorage;
+use sui_types::messages::{
+    ExecutionStatus, ProgrammableTransaction, SenderSignedData, Transaction, TransactionData,
+    TransactionEffects, TransactionEffectsAPI, TransactionEffectsV1, VerifiedTransaction,
+};
+use sui_types::messages_checkpoint::{
+    CheckpointContents, CheckpointSequenceNumber, CheckpointSummary, ECMHLiveObjectSetDigest,
+    EndOfEpochData,
+};
+use sui_types::object::{MoveObject, Object, ObjectFormatOptions, Owner};
+use sui_types::sui_system_state::{
+    get_sui_system_state, get_sui_system_state_wrapper, SuiSystemState, SuiSystemStateTrait,
+};
+use sui_types::{SUI_SYSTEM_ADDRESS, SUI_SYSTEM_PACKAGE_ID, SUI_SYSTEM_STATE_OBJECT_ID};
 
 use crate::errors::IndexerError;
-use crate::store::PgIndexerStore;
+use crate::handlers::checkpoint_handler::get_object_changes;
+use crate::metrics::IndexerCheckpointHandlerMetrics;
+use crate::models::objects::ObjectStatus;
+use crate::store::{CheckpointData, PgIndexerStore};
+use crate::types::CheckpointTransactionBlockResponse;
 use crate::utils::reset_database;
-use crate::{new_pg_connection_pool, Indexer, IndexerConfig};
+use crate::{new_pg_connection_pool_with_size, Indexer, IndexerConfig};
 
 /// Spawns an indexer thread with provided Postgres DB url
 pub async fn start_test_indexer(
     config: IndexerConfig,
 ) -> Result<(PgIndexerStore, JoinHandle<Result<(), IndexerError>>), anyhow::Error> {
-    let (blocking_pool, async_pool) = new_pg_connection_pool(&config.base_connection_url())
-        .await
-        .map_err(|e| anyhow!("unable to connect to Postgres, is it running? {e}"))?;
+    let (blocking_pool, async_pool) = new_pg_connection_pool_with_size(
+        &config.base_connection_url(),
+        config.db_pool_size,
+        config.db_pool_min_idle,
+    )
+    .await
+    .map_err(|e| anyhow!("unable to connect to Postgres, is it running? {e}"))?;
     if config.reset_db {
         reset_database(
             &mut blocking_pool
@@ -27,11 +70,13 @@ pub async fn start_test_indexer(
             true,
         )?;
     }
-    let store = PgIndexerStore::new(async_pool, blocking_pool).await;
-
     let registry = Registry::default();
+    let metrics = IndexerCheckpointHandlerMetrics::new(&registry);
+    let store = PgIndexerStore::new(async_pool, blocking_pool, metrics.clone()).await;
+
     let store_clone = store.clone();
-    let handle = tokio::spawn(async move { Indexer::start(&config, &registry, store_clone).await });
+    let handle =
+        tokio::spawn(async move { Indexer::start(&config, &registry, store_clone, metrics).await });
     Ok((store, handle))
 }
 
@@ -120,3 +165,684 @@ impl<'a> SuiTransactionBlockResponseBuilder<'a> {
         }
     }
 }
+
+const TEST_GAS_BALANCE: u64 = 1_000_000_000_000;
+const TEST_TRANSFER_AMOUNT: u64 = 1_000;
+const TEST_GAS_BUDGET: u64 = 10_000_000;
+const TEST_GAS_PRICE: u64 = 1_000;
+const TEST_CHECKPOINT_INTERVAL_MS: u64 = 1_000;
+
+/// Builds a chain of `CheckpointData` shaped like what the checkpoint handler downloads
+/// from a fullnode. The chain starts from a real genesis, every following checkpoint
+/// contains SUI transfers from a single test sender to fresh recipients.
+pub struct TestCheckpointDataBuilder {
+    genesis: Genesis,
+    module_cache: InMemoryStorage,
+    sender: SuiAddress,
+    keypair: AccountKeyPair,
+    gas_coin: Object,
+    epoch: EpochId,
+    next_sequence_number: CheckpointSequenceNumber,
+    network_total_transactions: u64,
+    epoch_rolling_gas_cost_summary: GasCostSummary,
+    previous_digest: Option<CheckpointDigest>,
+    timestamp_ms: u64,
+}
+
+impl Default for TestCheckpointDataBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestCheckpointDataBuilder {
+    pub fn new() -> Self {
+        let genesis = ConfigBuilder::new_with_temp_dir().build().genesis;
+        let module_cache = InMemoryStorage::new(genesis.objects().to_vec());
+        let (sender, keypair) = deterministic_random_account_key();
+        let gas_coin =
+            Object::with_id_owner_gas_for_testing(ObjectID::random(), sender, TEST_GAS_BALANCE);
+        Self {
+            genesis,
+            module_cache,
+            sender,
+            keypair,
+            gas_coin,
+            epoch: 0,
+            next_sequence_number: 0,
+            network_total_transactions: 0,
+            epoch_rolling_gas_cost_summary: GasCostSummary::default(),
+            previous_digest: None,
+            timestamp_ms: 0,
+        }
+    }
+
+    pub fn genesis(&self) -> &Genesis {
+        &self.genesis
+    }
+
+    pub fn sender(&self) -> SuiAddress {
+        self.sender
+    }
+
+    /// A SUI coin of `balance` owned by `owner` as if created by the genesis transaction,
+    /// to add to the changed objects of the genesis checkpoint.
+    pub fn genesis_coin(&self, owner: SuiAddress, balance: u64) -> (ObjectStatus, SuiObjectData) {
+        let mut coin = Object::with_id_owner_gas_for_testing(ObjectID::random(), owner, balance);
+        coin.previous_transaction = *self.genesis.transaction().digest();
+        (ObjectStatus::Created, self.to_sui_object_data(&coin))
+    }
+
+    /// Builds the genesis checkpoint followed by `num_checkpoints - 1` checkpoints
+    /// with one transaction each.
+    pub fn build_checkpoints(mut self, num_checkpoints: usize) -> Vec<CheckpointData> {
+        (0..num_checkpoints)
+            .map(|i| {
+                if i == 0 {
+                    self.genesis_checkpoint()
+                } else {
+                    self.next_checkpoint(1)
+                }
+            })
+            .collect()
+    }
+
+    /// Builds checkpoint 0 out of the genesis transaction and objects.
+    pub fn genesis_checkpoint(&mut self) -> CheckpointData {
+        assert_eq!(
+            self.next_sequence_number, 0,
+            "Genesis checkpoint has to be the first checkpoint"
+        );
+        let verified_checkpoint = self.genesis.checkpoint();
+        let summary = verified_checkpoint.data().clone();
+        let signature = verified_checkpoint.auth_sig().signature.clone();
+        let contents = self.genesis.checkpoint_contents().clone();
+
+        let genesis_transaction = self.genesis.transaction();
+        let events = SuiTransactionBlockEvents::try_from(
+            self.genesis.events().clone(),
+            *genesis_transaction.digest(),
+            Some(summary.timestamp_ms),
+            &self.module_cache,
+        )
+        .unwrap();
+        let transaction = self.transaction_response(
+            genesis_transaction.data(),
+            self.genesis.effects().clone(),
+            events,
+            0,
+            summary.timestamp_ms,
+        );
+        let changed_objects = get_object_changes(&transaction.effects)
+            .into_iter()
+            .map(|(id, _, status, _)| {
+                let object = self
+                    .genesis
+                    .object(id)
+                    .expect("Genesis effects should only refer to genesis objects");
+                (status, self.to_sui_object_data(&object))
+            })
+            .collect();
+
+        self.network_total_transactions = summary.network_total_transactions;
+        self.timestamp_ms = summary.timestamp_ms;
+        let checkpoint = RpcCheckpoint::from((summary, contents, signature));
+        self.previous_digest = Some(checkpoint.digest);
+        self.next_sequence_number = 1;
+
+        CheckpointData {
+            checkpoint,
+            transactions: vec![transaction],
+            changed_objects,
+        }
+    }
+
+    /// Builds the next checkpoint of the current epoch with `num_transactions` transfers.
+    pub fn next_checkpoint(&mut self, num_transactions: usize) -> CheckpointData {
+        self.build_next_checkpoint(None, num_transactions)
+    }
+
+    /// Builds the next checkpoint of the current epoch, starting with a
+    /// `ConsensusCommitPrologue` transaction of the given consensus round and commit
+    /// timestamp, followed by `num_transactions` transfers.
+    pub fn next_checkpoint_with_consensus_commit(
+        &mut self,
+        round: u64,
+        commit_timestamp_ms: u64,
+        num_transactions: usize,
+    ) -> CheckpointData {
+        self.build_next_checkpoint(Some((round, commit_timestamp_ms)), num_transactions)
+    }
+
+    fn build_next_checkpoint(
+        &mut self,
+        consensus_commit: Option<(u64, u64)>,
+        num_transactions: usize,
+    ) -> CheckpointData {
+        assert!(
+            self.next_sequence_number > 0,
+            "Genesis checkpoint has to be built first"
+        );
+        let sequence_number = self.next_sequence_number;
+        let timestamp_ms = self.timestamp_ms + TEST_CHECKPOINT_INTERVAL_MS;
+
+        let mut transactions = vec![];
+        let mut changed_objects = vec![];
+        let mut execution_digests = vec![];
+        if let Some((round, commit_timestamp_ms)) = consensus_commit {
+            let (transaction, effects) = self.consensus_commit_prologue_transaction(
+                round,
+                commit_timestamp_ms,
+                sequence_number,
+                timestamp_ms,
+            );
+            execution_digests.push(effects.execution_digests());
+            transactions.push(transaction);
+        }
+        for _ in 0..num_transactions {
+            let (transaction, effects, objects) =
+                self.transfer_transaction(sequence_number, timestamp_ms);
+            execution_digests.push(effects.execution_digests());
+            transactions.push(transaction);
+            changed_objects.extend(objects);
+        }
+
+        let contents =
+            CheckpointContents::new_with_causally_ordered_transactions(execution_digests);
+        self.network_total_transactions += transactions.len() as u64;
+        let summary = CheckpointSummary::new(
+            self.epoch,
+            sequence_number,
+            self.network_total_transactions,
+            &contents,
+            self.previous_digest,
+            self.epoch_rolling_gas_cost_summary.clone(),
+            None,
+            timestamp_ms,
+        );
+        let checkpoint =
+            RpcCheckpoint::from((summary, contents, AggregateAuthoritySignature::default()));
+
+        self.previous_digest = Some(checkpoint.digest);
+        self.next_sequence_number += 1;
+        self.timestamp_ms = timestamp_ms;
+
+        CheckpointData {
+            checkpoint,
+            transactions,
+            changed_objects,
+        }
+    }
+
+    /// Builds the next checkpoint of the current epoch with a single transaction executing
+    /// `pt`. Its effects only charge gas, the commands themselves change no objects.
+    pub fn next_checkpoint_with_programmable_transaction(
+        &mut self,
+        pt: ProgrammableTransaction,
+    ) -> CheckpointData {
+        assert!(
+            self.next_sequence_number > 0,
+            "Genesis checkpoint has to be built first"
+        );
+        let sequence_number = self.next_sequence_number;
+        let timestamp_ms = self.timestamp_ms + TEST_CHECKPOINT_INTERVAL_MS;
+
+        let (transaction, effects, changed_objects) =
+            self.programmable_transaction(pt, sequence_number, timestamp_ms);
+        let contents = CheckpointContents::new_with_causally_ordered_transactions(vec![
+            effects.execution_digests()
+        ]);
+        self.network_total_transactions += 1;
+        let summary = CheckpointSummary::new(
+            self.epoch,
+            sequence_number,
+            self.network_total_transactions,
+            &contents,
+            self.previous_digest,
+            self.epoch_rolling_gas_cost_summary.clone(),
+            None,
+            timestamp_ms,
+        );
+        let checkpoint =
+            RpcCheckpoint::from((summary, contents, AggregateAuthoritySignature::default()));
+
+        self.previous_digest = Some(checkpoint.digest);
+        self.next_sequence_number += 1;
+        self.timestamp_ms = timestamp_ms;
+
+        CheckpointData {
+            checkpoint,
+            transactions: vec![transaction],
+            changed_objects,
+        }
+    }
+
+    /// Builds the last checkpoint of the current epoch. It contains a single `ChangeEpoch`
+    /// transaction, which moves the system state to the next epoch and emits the
+    /// `SystemEpochInfoEvent` of the closed epoch. Fails, leaving the builder as it was, for
+    /// the system states only simtests have.
+    pub fn end_of_epoch_checkpoint(&mut self) -> Result<CheckpointData, IndexerError> {
+        assert!(
+            self.next_sequence_number > 0,
+            "Genesis checkpoint has to be built first"
+        );
+        let sequence_number = self.next_sequence_number;
+        let timestamp_ms = self.timestamp_ms + TEST_CHECKPOINT_INTERVAL_MS;
+        let next_epoch = self.epoch + 1;
+
+        // Advance the epoch of the system state inner object, which is a dynamic field of
+        // the system state wrapper keyed by the system state version.
+        let wrapper = get_sui_system_state_wrapper(&self.module_cache).unwrap();
+        let mut wrapper_object = self
+            .module_cache
+            .get_object(&SUI_SYSTEM_STATE_OBJECT_ID)
+            .unwrap()
+            .clone();
+        let mut inner_object = get_dynamic_field_object_from_store(
+            &self.module_cache,
+            wrapper.id.id.bytes,
+            &wrapper.version,
+        )
+        .unwrap();
+        let field_id = UID::new(inner_object.id());
+        let inner_contents = match get_sui_system_state(&self.module_cache).unwrap() {
+            SuiSystemState::V1(mut inner) => {
+                inner.epoch = next_epoch;
+                inner.epoch_start_timestamp_ms = timestamp_ms;
+                bcs::to_bytes(&Field {
+                    id: field_id,
+                    name: wrapper.version,
+                    value: inner,
+                })
+            }
+            SuiSystemState::V2(mut inner) => {
+                inner.epoch = next_epoch;
+                inner.epoch_start_timestamp_ms = timestamp_ms;
+                bcs::to_bytes(&Field {
+                    id: field_id,
+                    name: wrapper.version,
+                    value: inner,
+                })
+            }
+            #[cfg(msim)]
+            _ => {
+                return Err(IndexerError::NotSupportedError(
+                    "Simtest system states are not supported".to_string(),
+                ))
+            }
+        }?;
+
+        let gas_cost_summary = std::mem::take(&mut self.epoch_rolling_gas_cost_summary);
+        let system_state = get_sui_system_state(&self.module_cache)
+            .unwrap()
+            .into_sui_system_state_summary();
+        let protocol_version = ProtocolVersion::new(system_state.protocol_version);
+
+        let transaction = VerifiedTransaction::new_change_epoch(
+            next_epoch,
+            protocol_version,
+            gas_cost_summary.storage_cost,
+            gas_cost_summary.computation_cost,
+            gas_cost_summary.storage_rebate,
+            gas_cost_summary.non_refundable_storage_fee,
+            timestamp_ms,
+            vec![],
+        );
+        let digest = *transaction.digest();
+
+        let shared_objects = vec![wrapper_object.compute_object_reference()];
+        let modified_at_versions = vec![
+            (wrapper_object.id(), wrapper_object.version()),
+            (inner_object.id(), inner_object.version()),
+        ];
+        let dependencies = vec![
+            wrapper_object.previous_transaction,
+            inner_object.previous_transaction,
+        ];
+        // Lamport version of all the objects written by the transaction.
+        let version = SequenceNumber::from_u64(
+            wrapper_object
+                .version()
+                .value()
+                .max(inner_object.version().value())
+                + 1,
+        );
+        let wrapper_move_object = wrapper_object.data.try_as_move_mut().unwrap();
+        wrapper_move_object.increment_version_to(version);
+        wrapper_object.previous_transaction = digest;
+        let inner_move_object = inner_object.data.try_as_move_mut().unwrap();
+        inner_move_object
+            .update_contents(inner_contents, &ProtocolConfig::get_for_max_version())
+            .unwrap();
+        inner_move_object.increment_version_to(version);
+        inner_object.previous_transaction = digest;
+
+        let effects = TransactionEffects::V1(TransactionEffectsV1 {
+            status: ExecutionStatus::Success,
+            executed_epoch: self.epoch,
+            modified_at_versions,
+            shared_objects,
+            transaction_digest: digest,
+            mutated: vec![
+                (
+                    wrapper_object.compute_object_reference(),
+                    wrapper_object.owner,
+                ),
+                (inner_object.compute_object_reference(), inner_object.owner),
+            ],
+            // System transactions don't pay for gas.
+            gas_object: (
+                (ObjectID::ZERO, SequenceNumber::default(), ObjectDigest::MIN),
+                Owner::AddressOwner(SuiAddress::default()),
+            ),
+            dependencies,
+            ..Default::default()
+        });
+
+        // Same field layout as `SystemEpochInfoEvent`.
+        let event_bcs = bcs::to_bytes(&(
+            self.epoch,
+            system_state.protocol_version,
+            system_state.reference_gas_price,
+            system_state.total_stake,
+            // storage_fund_reinvestment
+            0u64,
+            gas_cost_summary.storage_cost,
+            gas_cost_summary.storage_rebate,
+            system_state.storage_fund_total_object_storage_rebates
+                + system_state.storage_fund_non_refundable_balance,
+            // stake_subsidy_amount
+            0u64,
+            gas_cost_summary.computation_cost,
+            // total_stake_rewards_distributed
+            gas_cost_summary.computation_cost,
+            // leftover_storage_fund_inflow
+            0u64,
+        ))
+        .unwrap();
+        let event = SuiEvent {
+            id: EventID {
+                tx_digest: digest,
+                event_seq: 0,
+            },
+            package_id: SUI_SYSTEM_PACKAGE_ID,
+            transaction_module: ident_str!("sui_system").to_owned(),
+            sender: SuiAddress::default(),
+            type_: StructTag {
+                address: SUI_SYSTEM_ADDRESS,
+                module: ident_str!("sui_system_state_inner").to_owned(),
+                name: ident_str!("SystemEpochInfoEvent").to_owned(),
+                type_params: vec![],
+            },
+            parsed_json: serde_json::Value::Null,
+            bcs: event_bcs,
+            timestamp_ms: Some(timestamp_ms),
+        };
+
+        let changed_objects = vec![
+            (
+                ObjectStatus::Mutated,
+                self.to_sui_object_data(&wrapper_object),
+            ),
+            (
+                ObjectStatus::Mutated,
+                self.to_sui_object_data(&inner_object),
+            ),
+        ];
+        self.module_cache.insert_object(wrapper_object);
+        self.module_cache.insert_object(inner_object);
+
+        let execution_digests = effects.execution_digests();
+        let response = self.transaction_response(
+            transaction.data(),
+            effects,
+            SuiTransactionBlockEvents { data: vec![event] },
+            sequence_number,
+            timestamp_ms,
+        );
+
+        let contents =
+            CheckpointContents::new_with_causally_ordered_transactions(vec![execution_digests]);
+        self.network_total_transactions += 1;
+        let end_of_epoch_data = EndOfEpochData {
+            next_epoch_committee: self.genesis.committee().unwrap().voting_rights,
+            next_epoch_protocol_version: protocol_version,
+            epoch_commitments: vec![ECMHLiveObjectSetDigest::default().into()],
+        };
+        let summary = CheckpointSummary::new(
+            self.epoch,
+            sequence_number,
+            self.network_total_transactions,
+            &contents,
+            self.previous_digest,
+            gas_cost_summary,
+            Some(end_of_epoch_data),
+            timestamp_ms,
+        );
+        let checkpoint =
+            RpcCheckpoint::from((summary, contents, AggregateAuthoritySignature::default()));
+
+        self.epoch = next_epoch;
+        self.previous_digest = Some(checkpoint.digest);
+        self.next_sequence_number += 1;
+        self.timestamp_ms = timestamp_ms;
+
+        Ok(CheckpointData {
+            checkpoint,
+            transactions: vec![response],
+            changed_objects,
+        })
+    }
+
+    fn consensus_commit_prologue_transaction(
+        &self,
+        round: u64,
+        commit_timestamp_ms: u64,
+        checkpoint: CheckpointSequenceNumber,
+        timestamp_ms: u64,
+    ) -> (CheckpointTransactionBlockResponse, TransactionEffects) {
+        let transaction = VerifiedTransaction::new_consensus_commit_prologue(
+            self.epoch,
+            round,
+            commit_timestamp_ms,
+        );
+        let effects = TransactionEffects::V1(TransactionEffectsV1 {
+            status: ExecutionStatus::Success,
+            executed_epoch: self.epoch,
+            transaction_digest: *transaction.digest(),
+            // System transactions don't pay for gas.
+            gas_object: (
+                (ObjectID::ZERO, SequenceNumber::default(), ObjectDigest::MIN),
+                Owner::AddressOwner(SuiAddress::default()),
+            ),
+            ..Default::default()
+        });
+        let response = self.transaction_response(
+            transaction.data(),
+            effects.clone(),
+            SuiTransactionBlockEvents::default(),
+            checkpoint,
+            timestamp_ms,
+        );
+        (response, effects)
+    }
+
+    fn transfer_transaction(
+        &mut self,
+        checkpoint: CheckpointSequenceNumber,
+        timestamp_ms: u64,
+    ) -> (
+        CheckpointTransactionBlockResponse,
+        TransactionEffects,
+        Vec<(ObjectStatus, SuiObjectData)>,
+    ) {
+        let (recipient, _): (_, AccountKeyPair) = get_key_pair();
+        let gas_ref = self.gas_coin.compute_object_reference();
+        let data = TransactionData::new_transfer_sui(
+            recipient,
+            self.sender,
+            Some(TEST_TRANSFER_AMOUNT),
+            gas_ref,
+            TEST_GAS_BUDGET,
+            TEST_GAS_PRICE,
+        );
+        let transaction =
+            Transaction::from_data_and_signer(data, Intent::sui_transaction(), vec![&self.keypair]);
+        let digest = *transaction.digest();
+
+        let gas_used = GasCostSummary::new(TEST_GAS_PRICE, 2 * TEST_GAS_PRICE, TEST_GAS_PRICE, 0);
+        let gas_balance =
+            self.gas_coin_balance() - TEST_TRANSFER_AMOUNT - gas_used.net_gas_usage() as u64;
+        // Lamport version of all the objects written by the transaction.
+        let version = SequenceNumber::from_u64(gas_ref.1.value() + 1);
+        let new_gas_coin = Object::new_move(
+            MoveObject::new_gas_coin(version, gas_ref.0, gas_balance),
+            Owner::AddressOwner(self.sender),
+            digest,
+        );
+        let new_coin = Object::new_move(
+            MoveObject::new_gas_coin(version, ObjectID::random(), TEST_TRANSFER_AMOUNT),
+            Owner::AddressOwner(recipient),
+            digest,
+        );
+
+        let gas_object = (new_gas_coin.compute_object_reference(), new_gas_coin.owner);
+        let effects = TransactionEffects::V1(TransactionEffectsV1 {
+            status: ExecutionStatus::Success,
+            executed_epoch: self.epoch,
+            gas_used: gas_used.clone(),
+            modified_at_versions: vec![(gas_ref.0, gas_ref.1)],
+            transaction_digest: digest,
+            created: vec![(new_coin.compute_object_reference(), new_coin.owner)],
+            mutated: vec![gas_object],
+            gas_object,
+            dependencies: vec![self.gas_coin.previous_transaction],
+            ..Default::default()
+        });
+
+        self.epoch_rolling_gas_cost_summary.computation_cost += gas_used.computation_cost;
+        self.epoch_rolling_gas_cost_summary.storage_cost += gas_used.storage_cost;
+        self.epoch_rolling_gas_cost_summary.storage_rebate += gas_used.storage_rebate;
+        self.epoch_rolling_gas_cost_summary
+            .non_refundable_storage_fee += gas_used.non_refundable_storage_fee;
+
+        let changed_objects = vec![
+            (ObjectStatus::Created, self.to_sui_object_data(&new_coin)),
+            (
+                ObjectStatus::Mutated,
+                self.to_sui_object_data(&new_gas_coin),
+            ),
+        ];
+        self.gas_coin = new_gas_coin;
+
+        let response = self.transaction_response(
+            transaction.data(),
+            effects.clone(),
+            SuiTransactionBlockEvents::default(),
+            checkpoint,
+            timestamp_ms,
+        );
+        (response, effects, changed_objects)
+    }
+
+    fn programmable_transaction(
+        &mut self,
+        pt: ProgrammableTransaction,
+        checkpoint: CheckpointSequenceNumber,
+        timestamp_ms: u64,
+    ) -> (
+        CheckpointTransactionBlockResponse,
+        TransactionEffects,
+        Vec<(ObjectStatus, SuiObjectData)>,
+    ) {
+        let gas_ref = self.gas_coin.compute_object_reference();
+        let data = TransactionData::new_programmable(
+            self.sender,
+            vec![gas_ref],
+            pt,
+            TEST_GAS_BUDGET,
+            TEST_GAS_PRICE,
+        );
+        let transaction =
+            Transaction::from_data_and_signer(data, Intent::sui_transaction(), vec![&self.keypair]);
+        let digest = *transaction.digest();
+
+        let gas_used = GasCostSummary::new(TEST_GAS_PRICE, TEST_GAS_PRICE, TEST_GAS_PRICE, 0);
+        let gas_balance = self.gas_coin_balance() - gas_used.net_gas_usage() as u64;
+        let version = SequenceNumber::from_u64(gas_ref.1.value() + 1);
+        let new_gas_coin = Object::new_move(
+            MoveObject::new_gas_coin(version, gas_ref.0, gas_balance),
+            Owner::AddressOwner(self.sender),
+            digest,
+        );
+
+        let gas_object = (new_gas_coin.compute_object_reference(), new_gas_coin.owner);
+        let effects = TransactionEffects::V1(TransactionEffectsV1 {
+            status: ExecutionStatus::Success,
+            executed_epoch: self.epoch,
+            gas_used: gas_used.clone(),
+            modified_at_versions: vec![(gas_ref.0, gas_ref.1)],
+            transaction_digest: digest,
+            mutated: vec![gas_object],
+            gas_object,
+            dependencies: vec![self.gas_coin.previous_transaction],
+            ..Default::default()
+        });
+
+        self.epoch_rolling_gas_cost_summary.computation_cost += gas_used.computation_cost;
+        self.epoch_rolling_gas_cost_summary.storage_cost += gas_used.storage_cost;
+        self.epoch_rolling_gas_cost_summary.storage_rebate += gas_used.storage_rebate;
+
+        let changed_objects = vec![(
+            ObjectStatus::Mutated,
+            self.to_sui_object_data(&new_gas_coin),
+        )];
+        self.gas_coin = new_gas_coin;
+
+        let response = self.transaction_response(
+            transaction.data(),
+            effects.clone(),
+            SuiTransactionBlockEvents::default(),
+            checkpoint,
+            timestamp_ms,
+        );
+        (response, effects, changed_objects)
+    }
+
+    fn transaction_response(
+        &self,
+        data: &SenderSignedData,
+        effects: TransactionEffects,
+        events: SuiTransactionBlockEvents,
+        checkpoint: CheckpointSequenceNumber,
+        timestamp_ms: u64,
+    ) -> CheckpointTransactionBlockResponse {
+        CheckpointTransactionBlockResponse {
+            digest: *effects.transaction_digest(),
+            transaction: SuiTransactionBlock::try_from(data.clone(), &self.module_cache).unwrap(),
+            raw_transaction: bcs::to_bytes(data).unwrap(),
+            effects: SuiTransactionBlockEffects::try_from(effects).unwrap(),
+            events,
+            timestamp_ms,
+            confirmed_local_execution: None,
+            checkpoint,
+        }
+    }
+
+    fn gas_coin_balance(&self) -> u64 {
+        GasCoin::try_from(&self.gas_coin).unwrap().value()
+    }
+
+    fn to_sui_object_data(&self, object: &Object) -> SuiObjectData {
+        let layout = object
+            .get_layout(ObjectFormatOptions::default(), &self.module_cache)
+            .unwrap();
+        SuiObjectData::try_from((
+            object.compute_object_reference(),
+            object.clone(),
+            layout,
+            SuiObjectDataOptions::bcs_lossless(),
+        ))
+        .unwrap()
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeSet;
+
 use sui_json_rpc_types::{
     BalanceChange, ObjectChange, SuiCommand, SuiTransactionBlock, SuiTransactionBlockDataAPI,
     SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI, SuiTransactionBlockEvents,
@@ -13,7 +15,10 @@ use sui_types::object::Owner;
 
 use crate::errors::IndexerError;
 use crate::models::addresses::Address;
-use crate::models::transaction_index::{InputObject, MoveCall, Recipient};
+use crate::models::transaction_index::{
+    normalize_type_argument, InputObject, MoveCall, PtbCommand, Recipient, TransactionDependency,
+};
+use crate::models::transactions::DBTransactionKind;
 
 pub struct FastPathTransactionBlockResponse {
     pub digest: TransactionDigest,
@@ -234,6 +239,11 @@ impl CheckpointTransactionBlockResponse {
                             move_package: m.package.to_string(),
                             move_module: m.module,
                             move_function: m.function,
+                            move_type_arguments: m
+                                .type_arguments
+                                .iter()
+                                .map(|t| Some(normalize_type_argument(t)))
+                                .collect(),
                         }),
                         _ => None,
                     })
@@ -245,6 +255,50 @@ impl CheckpointTransactionBlockResponse {
         .unwrap_or_default()
     }
 
+    /// The commands of a programmable transaction in order. Publish and Upgrade commands
+    /// are matched in order with the packages created by the transaction, out of
+    /// `packages`, the ids of all packages created in the checkpoint.
+    pub fn get_ptb_commands(
+        &self,
+        epoch: u64,
+        checkpoint: u64,
+        packages: &BTreeSet<String>,
+    ) -> Vec<PtbCommand> {
+        let commands = match self.transaction.data.transaction() {
+            SuiTransactionBlockKind::ProgrammableTransaction(pt) => &pt.commands,
+            _ => return vec![],
+        };
+        let mut created_packages = self
+            .effects
+            .created()
+            .iter()
+            .map(|oref| oref.reference.object_id.to_string())
+            .filter(|id| packages.contains(id));
+        commands
+            .iter()
+            .enumerate()
+            .map(|(i, command)| {
+                let (command_kind, package_id) = match command {
+                    SuiCommand::MoveCall(_) => ("MoveCall", None),
+                    SuiCommand::TransferObjects(..) => ("TransferObjects", None),
+                    SuiCommand::SplitCoins(..) => ("SplitCoins", None),
+                    SuiCommand::MergeCoins(..) => ("MergeCoins", None),
+                    SuiCommand::Publish(..) => ("Publish", created_packages.next()),
+                    SuiCommand::Upgrade(..) => ("Upgrade", created_packages.next()),
+                    SuiCommand::MakeMoveVec(..) => ("MakeMoveVec", None),
+                };
+                PtbCommand {
+                    transaction_digest: self.digest.to_string(),
+                    command_index: i as i64,
+                    checkpoint_sequence_number: checkpoint as i64,
+                    epoch: epoch as i64,
+                    command_kind: command_kind.to_string(),
+                    package_id,
+                }
+            })
+            .collect()
+    }
+
     pub fn get_recipients(&self, epoch: u64, checkpoint: u64) -> Vec<Recipient> {
         let created = self.effects.created().iter();
         let mutated = self.effects.mutated().iter();
@@ -266,13 +320,28 @@ impl CheckpointTransactionBlockResponse {
             .collect()
     }
 
+    pub fn get_dependencies(&self, checkpoint: u64) -> Vec<TransactionDependency> {
+        self.effects
+            .dependencies()
+            .iter()
+            .map(|dependency| TransactionDependency {
+                transaction_digest: self.digest.to_string(),
+                depends_on_digest: dependency.to_string(),
+                checkpoint_sequence_number: checkpoint as i64,
+            })
+            .collect()
+    }
+
     pub fn get_addresses(&self, epoch: u64, checkpoint: u64) -> Vec<Address> {
         let mut addresses = self
             .get_recipients(epoch, checkpoint)
             .into_iter()
             .map(|r| r.recipient)
             .collect::<Vec<String>>();
-        addresses.push(self.transaction.data.sender().to_string());
+        // System transactions are sent from the zero address, which is not an account.
+        if !DBTransactionKind::from(self.transaction.data.transaction()).is_system() {
+            addresses.push(self.transaction.data.sender().to_string());
+        }
         addresses
             .into_iter()
             .map(|r| Address {
@@ -25,7 +25,7 @@ use sui_types::gas_coin::GAS;
 use sui_types::object::Owner;
 use sui_types::storage::{DeleteKind, WriteKind};
 
-use crate::errors::IndexerError;
+use crate::errors::{FullNodeReadErrorKind, IndexerError};
 use crate::types::CheckpointTransactionBlockResponse;
 use crate::PgPoolConnection;
 
@@ -97,12 +97,13 @@ pub async fn multi_get_full_transactions(
             ),
         )
         .await
-        .map_err(|e| {
-            IndexerError::FullNodeReadingError(format!(
+        .map_err(|e| IndexerError::FullNodeRpcError {
+            kind: FullNodeReadErrorKind::classify(&e),
+            message: format!(
                 "Failed to get transactions {:?} with error: {:?}",
                 digests.clone(),
                 e
-            ))
+            ),
         })?;
     let sui_full_transactions: Vec<CheckpointTransactionBlockResponse> = sui_transactions
         .into_iter()
@@ -4,7 +4,7 @@
 // integration test with standalone postgresql database
 #[cfg(feature = "pg_integration")]
 pub mod pg_integration_test {
-    use diesel::RunQueryDsl;
+    use diesel::{QueryDsl, RunQueryDsl};
     use futures::future::join_all;
     use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
     use move_core_types::ident_str;
@@ -12,20 +12,23 @@ pub mod pg_integration_test {
     use move_core_types::language_storage::StructTag;
     use move_core_types::parser::parse_struct_tag;
     use ntest::timeout;
+    use prometheus::Registry;
     use std::env;
     use std::str::FromStr;
     use tokio::task::JoinHandle;
 
     use sui_config::SUI_KEYSTORE_FILENAME;
     use sui_indexer::errors::IndexerError;
-    use sui_indexer::models::objects::{
-        compose_object_bulk_insert_query, compose_object_bulk_insert_update_query,
-        group_and_sort_objects, NamedBcsBytes, Object, ObjectStatus,
-    };
+    use sui_indexer::metrics::IndexerCheckpointHandlerMetrics;
+    use sui_indexer::models::checkpoints::Checkpoint;
+    use sui_indexer::models::objects::{Object, ObjectStatus};
     use sui_indexer::models::owners::OwnerType;
     use sui_indexer::schema::objects;
-    use sui_indexer::store::{IndexerStore, PgIndexerStore};
+    use sui_indexer::store::{
+        IndexerStore, PgIndexerStore, TemporaryCheckpointStore, TransactionObjectChanges,
+    };
     use sui_indexer::test_utils::{start_test_indexer, SuiTransactionBlockResponseBuilder};
+    use sui_indexer::utils::reset_database;
     use sui_indexer::{get_pg_pool_connection, new_pg_connection_pool, IndexerConfig};
     use sui_json_rpc::api::ExtendedApiClient;
     use sui_json_rpc::api::IndexerApiClient;
@@ -38,7 +41,6 @@ pub mod pg_integration_test {
     };
     use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, Keystore};
     use sui_types::base_types::{ObjectID, SuiAddress};
-    use sui_types::digests::{ObjectDigest, TransactionDigest};
     use sui_types::error::SuiObjectResponseError;
     use sui_types::gas_coin::GasCoin;
     use sui_types::messages::{ExecuteTransactionRequestType, TEST_ONLY_GAS_UNIT_FOR_TRANSFER};
@@ -843,6 +845,13 @@ pub mod pg_integration_test {
         .unwrap();
 
         assert_eq!(GasCoin::type_(), parsed_coin.type_);
+
+        // Clones of the store, as every task of the checkpoint handler holds, share the cache
+        // and see the modules another clone resolved.
+        let clone = store.clone();
+        assert!(std::ptr::eq(store.module_cache(), clone.module_cache()));
+        assert!(clone.module_cache().len() > 0);
+        assert_eq!(clone.module_cache().len(), store.module_cache().len());
         drop(handle);
     }
 
@@ -875,6 +884,48 @@ pub mod pg_integration_test {
         drop(test_cluster);
     }
 
+    #[tokio::test]
+    #[timeout(60000)]
+    async fn test_close_first_epoch() {
+        let (test_cluster, _, store, handle) = start_test_cluster(Some(10000)).await;
+        wait_until_next_checkpoint(&store).await;
+        let first_epoch = store.get_current_epoch().await.unwrap();
+        assert_eq!(first_epoch.epoch, 0);
+        assert!(first_epoch.end_of_epoch_info.is_none());
+
+        wait_until_next_epoch(&store).await;
+        let current_epoch = store.get_current_epoch().await.unwrap();
+        let last_checkpoint_id = current_epoch.first_checkpoint_id - 1;
+        wait_for_checkpoint(&store, last_checkpoint_id as i64).await;
+        let last_checkpoint = store
+            .get_checkpoint(CheckpointId::SequenceNumber(last_checkpoint_id))
+            .await
+            .unwrap();
+
+        // The row stored when bootstrapping epoch 0 is closed in place, not duplicated.
+        let epoch_page = store.get_epochs(None, 100, None).await.unwrap();
+        let first_epochs = epoch_page
+            .iter()
+            .filter(|epoch| epoch.epoch == 0)
+            .collect::<Vec<_>>();
+        assert_eq!(first_epochs.len(), 1);
+        let closed_epoch = first_epochs[0];
+        assert_eq!(closed_epoch.first_checkpoint_id, 0);
+        assert_eq!(
+            closed_epoch.epoch_start_timestamp,
+            first_epoch.epoch_start_timestamp
+        );
+        let end_of_epoch_info = closed_epoch.end_of_epoch_info.as_ref().unwrap();
+        assert_eq!(end_of_epoch_info.last_checkpoint_id, last_checkpoint_id);
+        assert_eq!(
+            end_of_epoch_info.epoch_end_timestamp,
+            last_checkpoint.timestamp_ms
+        );
+
+        drop(handle);
+        drop(test_cluster);
+    }
+
     #[tokio::test]
     #[timeout(60000)]
     async fn test_get_last_checkpoint_of_epoch() {
@@ -910,6 +961,56 @@ pub mod pg_integration_test {
         drop(test_cluster);
     }
 
+    #[tokio::test]
+    #[timeout(60000)]
+    async fn test_partition_tables_by_epoch() {
+        let (test_cluster, _, store, handle) = start_test_cluster(Some(10000)).await;
+        wait_until_next_checkpoint(&store).await;
+        assert_eq!(
+            store.get_epoch_partitions("transactions").await.unwrap(),
+            vec![0]
+        );
+
+        wait_until_next_epoch(&store).await;
+        let current_epoch = store.get_current_epoch().await.unwrap();
+        wait_for_checkpoint(&store, current_epoch.first_checkpoint_id as i64).await;
+
+        // The partitions of epoch 1 are created with its first checkpoint.
+        for table in ["transactions", "objects_history"] {
+            let partitions = store.get_epoch_partitions(table).await.unwrap();
+            assert_eq!(partitions[..2], [0, 1], "Unexpected partitions of {table}");
+        }
+
+        drop(handle);
+        drop(test_cluster);
+    }
+
+    #[tokio::test]
+    async fn test_commit_with_tiny_connection_pool() -> Result<(), anyhow::Error> {
+        let (mut test_cluster, indexer_rpc_client, store, _handle) =
+            start_test_cluster_with_config(None, |config| {
+                config.db_pool_size = Some(2);
+                config.db_pool_min_idle = 1;
+            })
+            .await;
+        // Allow indexer to sync genesis
+        wait_until_next_checkpoint(&store).await;
+        let (tx_response, _, _, _) =
+            execute_simple_transfer(&mut test_cluster, &indexer_rpc_client).await?;
+        let tx_digest = tx_response.digest.base58_encode();
+        wait_until_transaction_synced_in_checkpoint(&store, &tx_digest).await;
+
+        // Commits only wait for the connections, none of the checkpoints is lost.
+        let tx = store.get_transaction_by_digest(&tx_digest).await?;
+        let checkpoint_seq = tx.checkpoint_sequence_number.unwrap();
+        for seq in 0..=checkpoint_seq {
+            store
+                .get_checkpoint(CheckpointId::SequenceNumber(seq as u64))
+                .await?;
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     #[timeout(60000)]
     async fn test_query_objects_cross_check() -> Result<(), anyhow::Error> {
@@ -1022,89 +1123,74 @@ pub mod pg_integration_test {
     }
 
     #[tokio::test]
-    #[timeout(60000)]
-    async fn pg_unnest_bulk_insert_update_test() {
-        start_test_cluster(None).await;
+    #[timeout(120000)]
+    async fn test_persist_checkpoint_with_many_object_changes() {
+        // Far more bind parameters than one statement takes, the objects upsert has to be
+        // split for the commit to go through.
+        const NUM_OBJECTS: usize = 50_000;
         let pg_host = env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".into());
         let pg_port = env::var("POSTGRES_PORT").unwrap_or_else(|_| "32770".into());
         let pw = env::var("POSTGRES_PASSWORD").unwrap_or_else(|_| "postgrespw".into());
         let db_url = format!("postgres://postgres:{pw}@{pg_host}:{pg_port}");
-        let (pg_connection_pool, _) = new_pg_connection_pool(&db_url).await.unwrap();
-        let mut pg_pool_conn = get_pg_pool_connection(&pg_connection_pool).unwrap();
+        let (blocking_pool, async_pool) = new_pg_connection_pool(&db_url).await.unwrap();
+        reset_database(&mut get_pg_pool_connection(&blocking_pool).unwrap(), true).unwrap();
+        let store = PgIndexerStore::new(
+            async_pool,
+            blocking_pool.clone(),
+            IndexerCheckpointHandlerMetrics::new(&Registry::default()),
+        )
+        .await;
 
-        let bulk_data = (1..=10000)
-            .into_iter()
+        let changed_objects = (0..NUM_OBJECTS)
             .map(|_| Object {
                 epoch: 0,
                 checkpoint: 0,
                 object_id: ObjectID::random().to_string(),
-                version: 0,
-                object_digest: ObjectDigest::random().to_string(),
+                version: 1,
+                object_digest: "".to_string(),
                 owner_type: OwnerType::AddressOwner,
-                owner_address: Some(SuiAddress::random_for_testing_only().to_string()),
+                owner_address: Some(SuiAddress::ZERO.to_string()),
                 initial_shared_version: None,
-                previous_transaction: TransactionDigest::random().to_string(),
-                object_type: "0x2::coin::Coin<0x2::sui::SUI>".to_string(),
+                previous_transaction: "".to_string(),
+                object_type: "".to_string(),
                 object_status: ObjectStatus::Created,
                 has_public_transfer: false,
                 storage_rebate: 0,
-                bcs: vec![NamedBcsBytes("object".to_string(), vec![1u8, 2u8, 3u8])],
+                bcs: vec![],
             })
-            .collect::<Vec<_>>();
-
-        let insert_query = compose_object_bulk_insert_query(&bulk_data);
-        let result: Result<usize, IndexerError> = pg_pool_conn
-            .build_transaction()
-            .serializable()
-            .read_write()
-            .run(|conn| diesel::sql_query(insert_query).execute(conn))
-            .map_err(IndexerError::PostgresError);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 10000);
+            .collect();
+        let data = TemporaryCheckpointStore {
+            checkpoint: Checkpoint {
+                sequence_number: 0,
+                ..Default::default()
+            },
+            transactions: vec![],
+            events: vec![],
+            objects_changes: vec![TransactionObjectChanges {
+                changed_objects,
+                deleted_objects: vec![],
+            }],
+            addresses: vec![],
+            address_activities: vec![],
+            packages: vec![],
+            input_objects: vec![],
+            move_calls: vec![],
+            recipients: vec![],
+            ptb_commands: vec![],
+            dependencies: vec![],
+            consensus_commit: None,
+        };
 
-        let mutated_bulk_data = bulk_data
-            .clone()
-            .into_iter()
-            .map(|mut object| {
-                object.object_status = ObjectStatus::Mutated;
-                object.version = 1;
-                object
-            })
-            .collect::<Vec<_>>();
-        let mutated_bulk_data_same_checkpoint = bulk_data
-            .into_iter()
-            .map(|mut object| {
-                object.object_status = ObjectStatus::Mutated;
-                object.version = 2;
-                object
-            })
-            .chain(mutated_bulk_data)
-            .collect::<Vec<_>>();
-        let mut pg_pool_conn = get_pg_pool_connection(&pg_connection_pool).unwrap();
-        let mut mutated_object_groups = group_and_sort_objects(mutated_bulk_data_same_checkpoint);
-        let mut counter = 0;
-        loop {
-            let mutated_object_group = mutated_object_groups
-                .iter_mut()
-                .filter_map(|group| group.pop())
-                .collect::<Vec<_>>();
-            if mutated_object_group.is_empty() {
-                break;
-            }
-            // bulk insert/update via UNNEST trick
-            let insert_update_query =
-                compose_object_bulk_insert_update_query(&mutated_object_group);
-            let result: Result<usize, IndexerError> = pg_pool_conn
-                .build_transaction()
-                .serializable()
-                .read_write()
-                .run(|conn| diesel::sql_query(insert_update_query).execute(conn))
-                .map_err(IndexerError::PostgresError);
-            assert!(result.is_ok());
-            assert_eq!(result.unwrap(), 10000);
-            counter += 1;
-        }
-        assert_eq!(counter, 2);
+        store.persist_checkpoint(&data).await.unwrap();
+        assert_eq!(
+            store.get_latest_checkpoint_sequence_number().await.unwrap(),
+            0
+        );
+        let stored_objects: i64 = objects::table
+            .count()
+            .get_result(&mut get_pg_pool_connection(&blocking_pool).unwrap())
+            .unwrap();
+        assert_eq!(stored_objects, NUM_OBJECTS as i64);
     }
 
     #[tokio::test]
@@ -1268,6 +1354,18 @@ pub mod pg_integration_test {
         HttpClient,
         PgIndexerStore,
         JoinHandle<Result<(), IndexerError>>,
+    ) {
+        start_test_cluster_with_config(epoch_duration_ms, |_| {}).await
+    }
+
+    async fn start_test_cluster_with_config(
+        epoch_duration_ms: Option<u64>,
+        customize_config: impl FnOnce(&mut IndexerConfig),
+    ) -> (
+        TestCluster,
+        HttpClient,
+        PgIndexerStore,
+        JoinHandle<Result<(), IndexerError>>,
     ) {
         let pg_host = env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".into());
         let pg_port = env::var("POSTGRES_PORT").unwrap_or_else(|_| "32770".into());
@@ -1284,13 +1382,14 @@ pub mod pg_integration_test {
             TestClusterBuilder::new().build().await.unwrap()
         };
 
-        let config = IndexerConfig {
+        let mut config = IndexerConfig {
             db_url,
             rpc_client_url: test_cluster.rpc_url().to_string(),
             migrated_methods: IndexerConfig::all_implemented_methods(),
             reset_db: true,
             ..Default::default()
         };
+        customize_config(&mut config);
 
         let http_addr_port = format!(
             "http://{}:{}",
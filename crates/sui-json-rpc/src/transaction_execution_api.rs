@@ -173,32 +173,12 @@ impl TransactionExecutionApi {
         tx_bytes: Base64,
     ) -> Result<DryRunTransactionBlockResponse, Error> {
         let (txn_data, txn_digest) = get_transaction_data_and_digest(tx_bytes)?;
-        let input_objs = txn_data.input_objects()?;
-        let sender = txn_data.sender();
-        let (resp, written_objects, transaction_effects) = self
+        // The object and balance changes are computed by the dry run itself.
+        let (resp, _, _) = self
             .state
-            .dry_exec_transaction(txn_data.clone(), txn_digest)
+            .dry_exec_transaction(txn_data, txn_digest)
             .await?;
-        let object_cache = ObjectProviderCache::new_with_cache(self.state.clone(), written_objects);
-        let balance_changes =
-            get_balance_changes_from_effect(&object_cache, &transaction_effects, input_objs)
-                .await?;
-        let object_changes = get_object_changes(
-            &object_cache,
-            sender,
-            transaction_effects.modified_at_versions(),
-            transaction_effects.all_changed_objects(),
-            transaction_effects.all_deleted(),
-        )
-        .await?;
-
-        Ok(DryRunTransactionBlockResponse {
-            effects: resp.effects,
-            events: resp.events,
-            object_changes,
-            balance_changes,
-            input: resp.input,
-        })
+        Ok(resp)
     }
 }
 
@@ -1016,6 +1016,12 @@ impl ProtocolConfig {
     pub fn set_package_upgrades_for_testing(&mut self, val: bool) {
         self.feature_flags.package_upgrades = val
     }
+    pub fn set_storage_rebate_rate_for_testing(&mut self, rate: u64) {
+        self.storage_rebate_rate = Some(rate)
+    }
+    pub fn set_storage_gas_price_for_testing(&mut self, price: u64) {
+        self.storage_gas_price = Some(price)
+    }
 }
 
 type OverrideFn = dyn Fn(ProtocolVersion, ProtocolConfig) -> ProtocolConfig + Send;
@@ -184,6 +184,42 @@ pub enum UserInputError {
 
     #[error("Feature is not yet supported: {0}")]
     Unsupported(String),
+
+    // NOTE: new variants go last, the discriminants of the ones above are on the wire.
+    #[error("Transaction pays for gas with {count} coins, it has to be fewer than {max}.")]
+    TooManyGasCoins { count: u64, max: u64 },
+    #[error("Gas coin {object_id} is listed more than once in the gas payment.")]
+    DuplicateGasCoin { object_id: ObjectID },
+    #[error("Gas coin {object_id} is also used as an input to the transaction.")]
+    GasCoinUsedAsInput { object_id: ObjectID },
+    #[error(
+        "Gas price: {:?} is higher than max: {:?}, computation cost would overflow.",
+        gas_price,
+        max_gas_price
+    )]
+    GasPriceTooHigh { gas_price: u64, max_gas_price: u64 },
+    #[error("Gas payment has a total balance of zero.")]
+    ZeroGasBalance,
+    #[error(
+        "Transaction expired at epoch {}, current epoch is {}",
+        expiration,
+        current_epoch
+    )]
+    TransactionExpired {
+        expiration: EpochId,
+        current_epoch: EpochId,
+    },
+    #[error(
+        "Gas budget: {:?} is lower than min: {:?}, suggested budget: {:?}.",
+        gas_budget,
+        min_budget,
+        suggested_budget
+    )]
+    GasBudgetTooLowWithSuggestion {
+        gas_budget: u64,
+        min_budget: u64,
+        suggested_budget: u64,
+    },
 }
 
 #[derive(
@@ -450,6 +486,8 @@ pub enum SuiError {
     #[error("Error when advancing epoch: {:?}", error)]
     AdvanceEpochError { error: String },
 
+    /// No longer returned, expired transactions are rejected with
+    /// `UserInputError::TransactionExpired`. Kept for the discriminants of the variants after it.
     #[error("Transaction Expired")]
     TransactionExpired,
 
@@ -2,6 +2,8 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+
 use crate::sui_serde::BigInt;
 use crate::sui_serde::Readable;
 use crate::{
@@ -44,6 +46,10 @@ pub trait SuiGasStatusAPI<'a> {
     fn track_storage_mutation(&mut self, new_size: usize, storage_rebate: u64) -> u64;
     fn charge_storage_and_rebate(&mut self) -> Result<(), ExecutionError>;
     fn adjust_computation_on_out_of_gas(&mut self);
+    fn record_command_gas(&mut self, gas_used: u64);
+    fn command_gas_breakdown(&self) -> Option<CommandGasBreakdown>;
+    fn enable_gas_profiling(&mut self);
+    fn gas_profile(&self) -> Option<GasProfile>;
 }
 
 #[enum_dispatch(SuiGasStatusAPI)]
@@ -83,14 +89,78 @@ pub enum SuiCostTable {
     V2(SuiCostTableV2),
 }
 
-impl SuiCostTable {
+/// Builds a `SuiCostTable` for the gas model of a protocol config, with individual
+/// parameters overridden. Values are in the units of the protocol config parameter
+/// they override.
+#[derive(Clone, Debug)]
+pub struct SuiCostTableBuilder {
+    gas_model_version: u64,
+    pub(crate) min_gas_budget: u64,
+    pub(crate) max_gas_budget: u64,
+    pub(crate) package_publish_per_byte_cost: u64,
+    pub(crate) object_read_per_byte_cost: u64,
+    pub(crate) storage_per_byte_cost: u64,
+}
+
+impl SuiCostTableBuilder {
     pub fn new(config: &ProtocolConfig) -> Self {
-        match config.gas_model_version() {
-            1 => Self::V1(SuiCostTableV1::new(config)),
-            2 | 3 => Self::V2(SuiCostTableV2::new(config)),
+        Self {
+            gas_model_version: config.gas_model_version(),
+            min_gas_budget: config.base_tx_cost_fixed(),
+            max_gas_budget: config.max_tx_gas(),
+            package_publish_per_byte_cost: config.package_publish_cost_per_byte(),
+            object_read_per_byte_cost: config.obj_access_cost_read_per_byte(),
+            storage_per_byte_cost: config.obj_data_cost_refundable(),
+        }
+    }
+
+    /// Overrides `base_tx_cost_fixed`.
+    pub fn with_min_gas_budget(mut self, min_gas_budget: u64) -> Self {
+        self.min_gas_budget = min_gas_budget;
+        self
+    }
+
+    /// Overrides `max_tx_gas`.
+    pub fn with_max_gas_budget(mut self, max_gas_budget: u64) -> Self {
+        self.max_gas_budget = max_gas_budget;
+        self
+    }
+
+    /// Overrides `package_publish_cost_per_byte`.
+    pub fn with_package_publish_per_byte_cost(mut self, cost: u64) -> Self {
+        self.package_publish_per_byte_cost = cost;
+        self
+    }
+
+    /// Overrides `obj_access_cost_read_per_byte`.
+    pub fn with_object_read_per_byte_cost(mut self, cost: u64) -> Self {
+        self.object_read_per_byte_cost = cost;
+        self
+    }
+
+    /// Overrides `obj_data_cost_refundable`.
+    pub fn with_storage_per_byte_cost(mut self, cost: u64) -> Self {
+        self.storage_per_byte_cost = cost;
+        self
+    }
+
+    pub fn build(self) -> SuiCostTable {
+        match self.gas_model_version {
+            1 => SuiCostTable::V1(SuiCostTableV1::from_builder(&self)),
+            2 | 3 => SuiCostTable::V2(SuiCostTableV2::from_builder(&self)),
             _ => panic!("unknown gas model version"),
         }
     }
+}
+
+impl SuiCostTable {
+    pub fn new(config: &ProtocolConfig) -> Self {
+        SuiCostTableBuilder::new(config).build()
+    }
+
+    pub fn builder(config: &ProtocolConfig) -> SuiCostTableBuilder {
+        SuiCostTableBuilder::new(config)
+    }
 
     pub fn new_for_testing() -> Self {
         Self::new(&ProtocolConfig::get_for_max_version())
@@ -118,6 +188,27 @@ impl SuiCostTable {
         }
     }
 
+    pub fn package_publish_per_byte_cost(&self) -> u64 {
+        match self {
+            Self::V1(cost_table) => cost_table.package_publish_per_byte_cost(),
+            Self::V2(cost_table) => cost_table.package_publish_per_byte_cost,
+        }
+    }
+
+    pub fn object_read_per_byte_cost(&self) -> u64 {
+        match self {
+            Self::V1(cost_table) => cost_table.object_read_per_byte_cost(),
+            Self::V2(cost_table) => cost_table.object_read_per_byte_cost,
+        }
+    }
+
+    pub fn storage_per_byte_cost(&self) -> u64 {
+        match self {
+            Self::V1(cost_table) => cost_table.storage_per_byte_cost(),
+            Self::V2(cost_table) => cost_table.storage_per_byte_cost,
+        }
+    }
+
     // Check whether gas arguments are legit:
     // 1. Gas object has an address owner.
     // 2. Gas budget is between min and max budget allowed
@@ -140,11 +231,36 @@ impl SuiCostTable {
                 gas_object,
                 more_gas_objs,
                 gas_budget,
+                gas_price,
                 cost_table,
             ),
         }
     }
 
+    /// A lower bound on the budget a transaction with these input objects needs, when
+    /// the gas model can estimate one up front. Executing the commands is not priced, so
+    /// the actual cost of a transaction grows past it with its commands and the objects
+    /// they create; a dry run gives the actual cost. The command count is not an input,
+    /// a command can cost nothing beyond the objects it reads, so counting commands would
+    /// only push the estimate over the actual cost of some transactions.
+    pub fn estimate_min_budget<'b>(
+        &self,
+        objects: impl IntoIterator<Item = &'b Object>,
+        gas_price: u64,
+        config: &ProtocolConfig,
+    ) -> Option<u64> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2(cost_table) => Some(gas_v2::estimate_min_budget(
+                objects,
+                gas_price,
+                config.storage_gas_price(),
+                config.storage_rebate_rate(),
+                cost_table,
+            )),
+        }
+    }
+
     pub fn into_gas_status_for_testing<'a>(
         self,
         gas_budget: u64,
@@ -191,6 +307,17 @@ impl SuiCostTable {
 /// by the "nonrefundable rate" such that:
 /// `potential_rebate(storage cost of deleted/mutated objects) =
 /// storage_rebate + non_refundable_storage_fee`
+///
+/// A transaction that fails has its writes dropped, only the gas coin and the mutable inputs
+/// are written back at their new version. What is charged depends on where it failed:
+/// - Rejected before execution, e.g. for a budget below the minimum or a stale object
+///   reference: there are no effects and nothing is charged.
+/// - Out of gas, including while reading the objects before any command runs: the whole
+///   budget is charged. When not even the storage of the objects written back fits, it is all
+///   computation and neither storage cost nor rebate is charged.
+/// - Failed in a command otherwise: the computation performed up to the failure is charged,
+///   rounded up to its bucket, plus the storage cost of the objects written back minus their
+///   rebate, as for a successful transaction.
 
 #[serde_as]
 #[derive(Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -273,6 +400,47 @@ impl GasCostSummary {
     }
 }
 
+/// Computation cost of a programmable transaction split by command.
+/// Only tracked by gas models that price computation after execution (version 2 and later).
+#[derive(Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommandGasBreakdown {
+    /// Computation cost charged while executing each command, in command order.
+    pub commands: Vec<u64>,
+    /// Computation cost not attributable to a single command: reading the input objects
+    /// and rounding the total up to its computation bucket.
+    pub overhead: u64,
+    /// How much more gas the transaction needed, if it ran out of gas rounding its
+    /// computation up to a bucket or charging storage. Running out while executing a
+    /// command stops the VM before the full cost is known, which leaves this None.
+    pub shortfall: Option<u64>,
+}
+
+impl CommandGasBreakdown {
+    /// Total computation cost, which matches `GasCostSummary::computation_cost`.
+    pub fn total(&self) -> u64 {
+        self.commands.iter().sum::<u64>() + self.overhead
+    }
+}
+
+/// Computation gas units of a transaction split by the Move VM operation charging them,
+/// only tracked when profiling a dry run under gas model version 2 and later.
+#[derive(Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GasProfile {
+    /// Gas units charged per bytecode instruction category, e.g. `arithmetic` or `vector`,
+    /// and per native function, as `native:<module>::<function>`.
+    pub operations: BTreeMap<String, u64>,
+    /// Gas units not charged by the VM: reading the input objects, publishing packages,
+    /// rounding the total up to its computation bucket and to whole gas units per operation.
+    pub overhead: u64,
+}
+
+impl GasProfile {
+    /// Total computation gas units, `GasCostSummary::computation_cost` over the gas price.
+    pub fn total(&self) -> u64 {
+        self.operations.values().sum::<u64>() + self.overhead
+    }
+}
+
 impl std::fmt::Display for GasCostSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
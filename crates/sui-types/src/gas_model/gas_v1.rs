@@ -5,7 +5,10 @@
 use crate::error::{UserInputError, UserInputResult};
 use crate::{
     error::{ExecutionError, ExecutionErrorKind},
-    gas::{get_gas_balance, GasCostSummary, SuiGasStatusAPI},
+    gas::{
+        get_gas_balance, CommandGasBreakdown, GasCostSummary, GasProfile, SuiCostTableBuilder,
+        SuiGasStatusAPI,
+    },
     object::{Object, Owner},
 };
 use move_core_types::{
@@ -177,16 +180,18 @@ impl std::fmt::Debug for SuiCostTable {
 
 impl SuiCostTable {
     pub(crate) fn new(c: &ProtocolConfig) -> Self {
+        Self::from_builder(&SuiCostTableBuilder::new(c))
+    }
+
+    pub(crate) fn from_builder(b: &SuiCostTableBuilder) -> Self {
         Self {
-            min_transaction_cost: FixedCost::new(c.base_tx_cost_fixed()),
-            max_gas_budget: c.max_tx_gas(),
+            min_transaction_cost: FixedCost::new(b.min_gas_budget),
+            max_gas_budget: b.max_gas_budget,
             package_publish_per_byte_cost: ComputationCostPerByte::new(
-                c.package_publish_cost_per_byte(),
+                b.package_publish_per_byte_cost,
             ),
-            object_read_per_byte_cost: ComputationCostPerByte::new(
-                c.obj_access_cost_read_per_byte(),
-            ),
-            storage_per_byte_cost: StorageCostPerByte::new(c.obj_data_cost_refundable()),
+            object_read_per_byte_cost: ComputationCostPerByte::new(b.object_read_per_byte_cost),
+            storage_per_byte_cost: StorageCostPerByte::new(b.storage_per_byte_cost),
         }
     }
 
@@ -203,6 +208,18 @@ impl SuiCostTable {
     pub(crate) fn min_gas_budget_external(&self) -> u64 {
         u64::from(to_external(*self.min_transaction_cost))
     }
+
+    pub(crate) fn package_publish_per_byte_cost(&self) -> u64 {
+        u64::from(self.package_publish_per_byte_cost.0)
+    }
+
+    pub(crate) fn object_read_per_byte_cost(&self) -> u64 {
+        u64::from(self.object_read_per_byte_cost.0)
+    }
+
+    pub(crate) fn storage_per_byte_cost(&self) -> u64 {
+        u64::from(self.storage_per_byte_cost.0)
+    }
 }
 
 fn to_external(internal_units: InternalGas) -> GasUnits {
@@ -458,6 +475,18 @@ impl<'a> SuiGasStatusAPI<'a> for SuiGasStatus<'a> {
     fn adjust_computation_on_out_of_gas(&mut self) {
         unreachable!("adjust_computation_on_out_of_gas should not be called in v1 gas model");
     }
+
+    fn record_command_gas(&mut self, _gas_used: u64) {}
+
+    fn command_gas_breakdown(&self) -> Option<CommandGasBreakdown> {
+        None
+    }
+
+    fn enable_gas_profiling(&mut self) {}
+
+    fn gas_profile(&self) -> Option<GasProfile> {
+        None
+    }
 }
 
 // Check whether gas arguments are legit:
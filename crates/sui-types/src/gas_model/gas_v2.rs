@@ -3,10 +3,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::{UserInputError, UserInputResult};
-use crate::gas::{GasCostSummary, SuiGasStatusAPI};
+use crate::gas::{
+    CommandGasBreakdown, GasCostSummary, GasProfile, SuiCostTableBuilder, SuiGasStatusAPI,
+};
 use crate::{
     error::{ExecutionError, ExecutionErrorKind},
     gas_coin::GasCoin,
+    is_system_package,
     object::{Object, Owner},
 };
 use move_core_types::vm_status::StatusCode;
@@ -61,6 +64,9 @@ pub(crate) fn get_bucket_cost(table: &[ComputationBucket], computation_cost: u64
 // for a RPG of 1000 this amounts to about 5 SUI
 pub(crate) const MAX_BUCKET_COST: u64 = 5_000_000;
 
+// highest gas price at which the most expensive computation bucket still fits in a u64
+pub(crate) const MAX_GAS_PRICE: u64 = u64::MAX / MAX_BUCKET_COST;
+
 // define the bucket table for computation charging
 pub(crate) static COMPUTATION_BUCKETS: Lazy<Vec<ComputationBucket>> = Lazy::new(|| {
     vec![
@@ -96,14 +102,14 @@ pub struct SuiCostTable {
     /// Computation cost per byte charged for package publish. This cost is primarily
     /// determined by the cost to verify and link a package. Note that this does not
     /// include the cost of writing the package to the store.
-    package_publish_per_byte_cost: u64,
+    pub(crate) package_publish_per_byte_cost: u64,
     /// Per byte cost to read objects from the store. This is computation cost instead of
     /// storage cost because it does not change the amount of data stored on the db.
-    object_read_per_byte_cost: u64,
+    pub(crate) object_read_per_byte_cost: u64,
     /// Unit cost of a byte in the storage. This will be used both for charging for
     /// new storage as well as rebating for deleting storage. That is, we expect users to
     /// get full refund on the object storage when it's deleted.
-    storage_per_byte_cost: u64,
+    pub(crate) storage_per_byte_cost: u64,
 }
 
 impl std::fmt::Debug for SuiCostTable {
@@ -115,12 +121,16 @@ impl std::fmt::Debug for SuiCostTable {
 
 impl SuiCostTable {
     pub(crate) fn new(c: &ProtocolConfig) -> Self {
+        Self::from_builder(&SuiCostTableBuilder::new(c))
+    }
+
+    pub(crate) fn from_builder(b: &SuiCostTableBuilder) -> Self {
         Self {
-            min_transaction_cost: c.base_tx_cost_fixed(),
-            max_gas_budget: c.max_tx_gas(),
-            package_publish_per_byte_cost: c.package_publish_cost_per_byte(),
-            object_read_per_byte_cost: c.obj_access_cost_read_per_byte(),
-            storage_per_byte_cost: c.obj_data_cost_refundable(),
+            min_transaction_cost: b.min_gas_budget,
+            max_gas_budget: b.max_gas_budget,
+            package_publish_per_byte_cost: b.package_publish_per_byte_cost,
+            object_read_per_byte_cost: b.object_read_per_byte_cost,
+            storage_per_byte_cost: b.storage_per_byte_cost,
         }
     }
 
@@ -180,6 +190,12 @@ pub struct SuiGasStatus<'a> {
     /// Amount of storage rebate accumulated when we are running in unmetered mode (i.e. system transaction).
     /// This allows us to track how much storage rebate we need to retain in system transactions.
     unmetered_storage_rebate: u64,
+    /// Gas units (before applying the gas price) used by each command of a programmable
+    /// transaction, recorded as the commands execute.
+    command_gas: Vec<u64>,
+    /// How much more gas the transaction needed when bucketing computation or charging
+    /// storage ran out of budget. Unknown, and None, when the VM itself ran out.
+    shortfall: Option<u64>,
 }
 
 impl<'a> SuiGasStatus<'a> {
@@ -203,6 +219,8 @@ impl<'a> SuiGasStatus<'a> {
             storage_rebate: 0,
             rebate_rate,
             unmetered_storage_rebate: 0,
+            command_gas: vec![],
+            shortfall: None,
             cost_table,
         }
     }
@@ -213,7 +231,7 @@ impl<'a> SuiGasStatus<'a> {
         config: &ProtocolConfig,
     ) -> SuiGasStatus<'a> {
         let storage_gas_price = config.storage_gas_price();
-        let max_computation_budget = MAX_BUCKET_COST * gas_price;
+        let max_computation_budget = MAX_BUCKET_COST.saturating_mul(gas_price);
         let computation_budget = if gas_budget > max_computation_budget {
             max_computation_budget
         } else {
@@ -281,8 +299,11 @@ impl<'a> SuiGasStatusAPI<'a> for SuiGasStatus<'a> {
         let bucket_cost = get_bucket_cost(&COMPUTATION_BUCKETS, gas_used);
         // charge extra on top of `computation_cost` to make the total computation
         // cost a bucket value
-        let gas_used = bucket_cost * self.gas_price;
+        let gas_used = bucket_cost.saturating_mul(self.gas_price);
         if self.gas_budget <= gas_used {
+            if u64::from(self.gas_status.remaining_gas()) > 0 {
+                self.shortfall = Some(gas_used - self.gas_budget + 1);
+            }
             self.computation_cost = self.gas_budget;
             Err(ExecutionErrorKind::InsufficientGas.into())
         } else {
@@ -392,6 +413,10 @@ impl<'a> SuiGasStatusAPI<'a> for SuiGasStatus<'a> {
             let gas_left = self.gas_budget - self.computation_cost;
             // we have to charge for storage and may go out of gas, check
             if gas_left < self.storage_cost - sender_rebate {
+                // NOTE: after running out on computation, the shortfall found then stays.
+                if self.computation_cost < self.gas_budget {
+                    self.shortfall = Some(self.storage_cost - sender_rebate - gas_left);
+                }
                 // Running out of gas would cause the temporary store to reset
                 // and zero storage and rebate.
                 // The remaining_gas will be 0 and we will charge all in computation
@@ -407,6 +432,50 @@ impl<'a> SuiGasStatusAPI<'a> for SuiGasStatus<'a> {
         self.storage_cost = 0;
         self.computation_cost = self.gas_budget;
     }
+
+    fn record_command_gas(&mut self, gas_used: u64) {
+        if self.charge {
+            self.command_gas.push(gas_used);
+        }
+    }
+
+    /// Price the gas recorded for each command and attribute whatever is left of
+    /// `computation_cost` (object reads, bucket rounding) to the overhead.
+    fn command_gas_breakdown(&self) -> Option<CommandGasBreakdown> {
+        if !self.charge {
+            return None;
+        }
+        let commands: Vec<u64> = self
+            .command_gas
+            .iter()
+            .map(|gas_used| gas_used.saturating_mul(self.gas_price))
+            .collect();
+        let overhead = self
+            .computation_cost
+            .saturating_sub(commands.iter().sum::<u64>());
+        Some(CommandGasBreakdown {
+            commands,
+            overhead,
+            shortfall: self.shortfall,
+        })
+    }
+
+    fn enable_gas_profiling(&mut self) {
+        if self.charge {
+            self.gas_status.enable_profiling();
+        }
+    }
+
+    /// Attribute whatever is left of `computation_cost`, in gas units, to the overhead.
+    fn gas_profile(&self) -> Option<GasProfile> {
+        let operations = self.gas_status.profile()?;
+        let computation_units = self.computation_cost / self.gas_price;
+        let overhead = computation_units.saturating_sub(operations.values().sum::<u64>());
+        Some(GasProfile {
+            operations,
+            overhead,
+        })
+    }
 }
 
 // Check whether gas arguments are legit:
@@ -417,6 +486,7 @@ pub(crate) fn check_gas_balance(
     gas_object: &Object,
     more_gas_objs: Vec<&Object>,
     gas_budget: u64,
+    gas_price: u64,
     cost_table: &SuiCostTable,
 ) -> UserInputResult {
     // 1. All gas objects have an address owner
@@ -442,7 +512,15 @@ pub(crate) fn check_gas_balance(
         });
     }
 
-    // 3. Gas balance (all gas coins together) is bigger or equal to budget
+    // 3. Gas price keeps every computation charge within a u64
+    if gas_price > MAX_GAS_PRICE {
+        return Err(UserInputError::GasPriceTooHigh {
+            gas_price,
+            max_gas_price: MAX_GAS_PRICE,
+        });
+    }
+
+    // 4. Gas balance (all gas coins together) is bigger or equal to budget
     let mut gas_balance = get_gas_balance(gas_object)? as u128;
     for extra_obj in more_gas_objs {
         gas_balance += get_gas_balance(extra_obj)? as u128;
@@ -450,6 +528,43 @@ pub(crate) fn check_gas_balance(
     ok_or_gas_balance_error!(gas_balance, gas_budget as u128)
 }
 
+/// A lower bound on the budget needed by a transaction with these input objects, assuming it
+/// only rewrites its mutable inputs in place. The computation of its commands is left out,
+/// there is no floor to the cost of a command to count per command.
+pub(crate) fn estimate_min_budget<'b>(
+    objects: impl IntoIterator<Item = &'b Object>,
+    gas_price: u64,
+    storage_gas_price: u64,
+    rebate_rate: u64,
+    cost_table: &SuiCostTable,
+) -> u64 {
+    let mut read_bytes = 0;
+    let mut storage_cost = 0;
+    let mut storage_rebate = 0;
+    for object in objects {
+        if is_system_package(object.id()) {
+            continue;
+        }
+        let size = object.object_size_for_gas_metering() as u64;
+        read_bytes = read_bytes.saturating_add(size);
+        if !object.is_immutable() {
+            storage_cost = size
+                .saturating_mul(cost_table.storage_per_byte_cost)
+                .saturating_mul(storage_gas_price)
+                .saturating_add(storage_cost);
+            storage_rebate = storage_rebate.saturating_add(object.storage_rebate);
+        }
+    }
+    let computation_cost = get_bucket_cost(
+        &COMPUTATION_BUCKETS,
+        read_bytes.saturating_mul(cost_table.object_read_per_byte_cost),
+    )
+    .saturating_mul(gas_price);
+    let storage_charge = storage_cost.saturating_sub(sender_rebate(storage_rebate, rebate_rate));
+    // the budget has to be strictly above the computation cost, see `bucketize_computation`
+    computation_cost.saturating_add(storage_charge.max(1))
+}
+
 /// Subtract the gas balance of \p gas_object by \p amount.
 /// This function should never fail, since we checked that the budget is always
 /// less than balance, and the amount is capped at the budget.
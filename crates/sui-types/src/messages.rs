@@ -1480,11 +1480,28 @@ impl TransactionDataAPI for TransactionDataV1 {
         fp_ensure!(!self.gas().is_empty(), UserInputError::MissingGasPayment);
         fp_ensure!(
             self.gas().len() < config.max_gas_payment_objects() as usize,
-            UserInputError::SizeLimitExceeded {
-                limit: "maximum number of gas payment objects".to_string(),
-                value: config.max_gas_payment_objects().to_string()
+            UserInputError::TooManyGasCoins {
+                count: self.gas().len() as u64,
+                max: config.max_gas_payment_objects() as u64,
             }
         );
+        let mut used = HashSet::new();
+        if let Some((object_id, _, _)) = self.gas().iter().find(|o| !used.insert(o.0)) {
+            return Err(UserInputError::DuplicateGasCoin {
+                object_id: *object_id,
+            });
+        }
+        let input_ids: HashSet<_> = self
+            .kind
+            .input_objects()?
+            .iter()
+            .map(|o| o.object_id())
+            .collect();
+        if let Some((object_id, _, _)) = self.gas().iter().find(|o| input_ids.contains(&o.0)) {
+            return Err(UserInputError::GasCoinUsedAsInput {
+                object_id: *object_id,
+            });
+        }
         self.validity_check_no_gas_check(config)
     }
 
@@ -4,6 +4,8 @@
 //! Utility for generating programmable transactions, either by specifying a command or for
 //! migrating legacy transactions
 
+use std::collections::HashSet;
+
 use anyhow::Context;
 use indexmap::IndexMap;
 use move_core_types::{ident_str, identifier::Identifier, language_storage::TypeTag};
@@ -13,6 +15,7 @@ use crate::{
     base_types::{ObjectID, ObjectRef, SuiAddress},
     messages::{
         Argument, CallArg, Command, ObjectArg, ProgrammableMoveCall, ProgrammableTransaction,
+        TransactionData,
     },
     move_package::PACKAGE_MODULE_NAME,
     SUI_FRAMEWORK_OBJECT_ID,
@@ -42,6 +45,69 @@ impl ProgrammableTransactionBuilder {
         ProgrammableTransaction { inputs, commands }
     }
 
+    /// Finish the transaction, paying for gas with the fewest of `available_coins` (pairs of
+    /// coin reference and balance) whose balances cover `budget`. Coins are picked largest
+    /// first, ties broken by object ID, so the selection is deterministic. Coins already used
+    /// as inputs of the transaction are never picked. The selected coins are merged into the
+    /// first one during execution. Fails instead of building a transaction that would not
+    /// pass `validity_check`: on a zero `budget`, on duplicate coins in `available_coins`,
+    /// or when covering `budget` takes `max_gas_payment_objects` coins or more.
+    pub fn with_auto_gas(
+        self,
+        sender: SuiAddress,
+        available_coins: &[(ObjectRef, u64)],
+        budget: u64,
+        gas_price: u64,
+        max_gas_payment_objects: u32,
+    ) -> anyhow::Result<TransactionData> {
+        anyhow::ensure!(
+            budget > 0,
+            "Gas budget must be positive to select gas coins"
+        );
+        let mut seen = HashSet::new();
+        if let Some((coin, _)) = available_coins
+            .iter()
+            .find(|(coin, _)| !seen.insert(coin.0))
+        {
+            anyhow::bail!("Gas coin {} is available more than once", coin.0);
+        }
+
+        let mut candidates: Vec<_> = available_coins
+            .iter()
+            .filter(|(coin, _)| !self.inputs.contains_key(&BuilderArg::Object(coin.0)))
+            .collect();
+        candidates.sort_by(|(coin1, balance1), (coin2, balance2)| {
+            balance2.cmp(balance1).then_with(|| coin1.0.cmp(&coin2.0))
+        });
+
+        let mut gas_payment = vec![];
+        let mut total: u128 = 0;
+        for (coin, balance) in candidates {
+            if total >= budget as u128 {
+                break;
+            }
+            gas_payment.push(*coin);
+            total += *balance as u128;
+        }
+        anyhow::ensure!(
+            total >= budget as u128,
+            "Available gas coins hold {total} in total, which does not cover the budget of {budget}"
+        );
+        anyhow::ensure!(
+            gas_payment.len() < max_gas_payment_objects as usize,
+            "Covering the budget of {budget} takes {} gas coins, a payment must use fewer than {max_gas_payment_objects}",
+            gas_payment.len()
+        );
+
+        Ok(TransactionData::new_programmable(
+            sender,
+            gas_payment,
+            self.finish(),
+            budget,
+            gas_price,
+        ))
+    }
+
     pub fn pure_bytes(&mut self, bytes: Vec<u8>, force_separate: bool) -> Argument {
         let arg = if force_separate {
             BuilderArg::ForcedNonUniquePure(self.inputs.len())
@@ -235,6 +301,23 @@ impl ProgrammableTransactionBuilder {
         Ok(())
     }
 
+    /// Transfer each object to its recipient in one transaction, the objects of a recipient
+    /// listed more than once sharing a single command.
+    pub fn transfer_objects_to(
+        &mut self,
+        recipients_and_objects: impl IntoIterator<Item = (SuiAddress, ObjectRef)>,
+    ) -> anyhow::Result<()> {
+        let mut recipient_map: IndexMap<SuiAddress, Vec<Argument>> = IndexMap::new();
+        for (recipient, object_ref) in recipients_and_objects {
+            let obj_arg = self.obj(ObjectArg::ImmOrOwnedObject(object_ref))?;
+            recipient_map.entry(recipient).or_default().push(obj_arg);
+        }
+        for (recipient, obj_args) in recipient_map {
+            self.transfer_args(recipient, obj_args);
+        }
+        Ok(())
+    }
+
     pub fn transfer_sui(&mut self, recipient: SuiAddress, amount: Option<u64>) {
         let rec_arg = self.pure(recipient).unwrap();
         let coin_arg = if let Some(amount) = amount {
@@ -28,7 +28,7 @@ use crate::{
     error::{ExecutionError, SuiError, SuiResult},
     event::Event,
     fp_bail, gas,
-    gas::{GasCostSummary, SuiGasStatus, SuiGasStatusAPI},
+    gas::{CommandGasBreakdown, GasCostSummary, GasProfile, SuiGasStatus, SuiGasStatusAPI},
     messages::{ExecutionStatus, InputObjects, TransactionEffects},
     object::Owner,
     object::{Data, Object},
@@ -48,6 +48,10 @@ pub struct InnerTemporaryStore {
     pub deleted: BTreeMap<ObjectID, (SequenceNumber, DeleteKind)>,
     pub events: TransactionEvents,
     pub max_binary_format_version: u32,
+    /// Computation cost of each programmable transaction command, when the gas model tracks it.
+    pub command_gas_breakdown: Option<CommandGasBreakdown>,
+    /// Computation gas units per Move VM operation, when gas profiling was enabled.
+    pub gas_profile: Option<GasProfile>,
 }
 
 impl InnerTemporaryStore {
@@ -147,6 +151,8 @@ pub struct TemporaryStore<S> {
     /// Ordered sequence of events emitted by execution
     events: Vec<Event>,
     gas_charged: Option<(ObjectID, GasCostSummary)>,
+    command_gas_breakdown: Option<CommandGasBreakdown>,
+    gas_profile: Option<GasProfile>,
     storage_rebate_rate: u64,
     protocol_config: ProtocolConfig,
 }
@@ -173,6 +179,8 @@ impl<S> TemporaryStore<S> {
             deleted: BTreeMap::new(),
             events: Vec::new(),
             gas_charged: None,
+            command_gas_breakdown: None,
+            gas_profile: None,
             storage_rebate_rate: protocol_config.storage_rebate_rate(),
             protocol_config: protocol_config.clone(),
         }
@@ -203,6 +211,8 @@ impl<S> TemporaryStore<S> {
             deleted: BTreeMap::new(),
             events: Vec::new(),
             gas_charged: None,
+            command_gas_breakdown: None,
+            gas_profile: None,
             storage_rebate_rate: protocol_config.storage_rebate_rate(),
             protocol_config: protocol_config.clone(),
         }
@@ -275,6 +285,8 @@ impl<S> TemporaryStore<S> {
             deleted,
             events: TransactionEvents { data: self.events },
             max_binary_format_version: self.protocol_config.move_binary_format_version(),
+            command_gas_breakdown: self.command_gas_breakdown,
+            gas_profile: self.gas_profile,
         }
     }
 
@@ -1006,6 +1018,8 @@ impl<S: ObjectStore> TemporaryStore<S> {
 
             self.write_object(gas_object, WriteKind::Mutate);
             self.gas_charged = Some((gas_object_id, cost_summary.clone()));
+            self.command_gas_breakdown = gas_status.command_gas_breakdown();
+            self.gas_profile = gas_status.gas_profile();
             cost_summary
         } else {
             GasCostSummary::default()
@@ -1366,3 +1366,97 @@ fn check_approx_effects_components_size() {
         "Update APPROX_SIZE_OF_EXECUTION_STATUS constant"
     );
 }
+
+#[test]
+fn test_auto_gas_selection() {
+    let sender = SuiAddress::random_for_testing_only();
+    let max_gas_coins = ProtocolConfig::get_for_max_version().max_gas_payment_objects();
+    let balances = [100u64, 500, 300, 50, 400];
+    let coins: Vec<_> = balances
+        .iter()
+        .map(|balance| (random_object_ref(), *balance))
+        .collect();
+    let budget = 750;
+
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.transfer_sui(sender, Some(1));
+    let data = builder
+        .with_auto_gas(sender, &coins, budget, 1, max_gas_coins)
+        .unwrap();
+    assert_eq!(data.gas_budget(), budget);
+    assert_eq!(data.gas_owner(), sender);
+
+    // The two largest coins are picked, largest first.
+    let selected = data.gas();
+    assert_eq!(selected, &[coins[1].0, coins[4].0]);
+    let covered: u64 = coins
+        .iter()
+        .filter(|(coin, _)| selected.contains(coin))
+        .map(|(_, balance)| balance)
+        .sum();
+    assert!(covered >= budget);
+
+    // No smaller subset of the coins covers the budget.
+    for subset in 0u32..(1 << coins.len()) {
+        if (subset.count_ones() as usize) < selected.len() {
+            let total: u64 = (0..coins.len())
+                .filter(|i| subset & (1 << i) != 0)
+                .map(|i| coins[i].1)
+                .sum();
+            assert!(total < budget);
+        }
+    }
+
+    // A coin used as a transaction input is not used for gas.
+    let mut builder = ProgrammableTransactionBuilder::new();
+    builder.transfer_object(sender, coins[1].0).unwrap();
+    let data = builder
+        .with_auto_gas(sender, &coins, budget, 1, max_gas_coins)
+        .unwrap();
+    assert_eq!(data.gas(), &[coins[4].0, coins[2].0, coins[0].0]);
+
+    let builder = ProgrammableTransactionBuilder::new();
+    assert!(builder
+        .with_auto_gas(
+            sender,
+            &coins,
+            balances.iter().sum::<u64>() + 1,
+            1,
+            max_gas_coins
+        )
+        .is_err());
+}
+
+#[test]
+fn test_auto_gas_selection_fails_validity_check() {
+    let sender = SuiAddress::random_for_testing_only();
+    let max_gas_coins = ProtocolConfig::get_for_max_version().max_gas_payment_objects();
+    let coins: Vec<_> = (0..4).map(|_| (random_object_ref(), 100u64)).collect();
+
+    // Covering the budget takes 3 coins, a payment must use fewer than 3.
+    let builder = ProgrammableTransactionBuilder::new();
+    assert!(builder.with_auto_gas(sender, &coins, 300, 1, 3).is_err());
+    let builder = ProgrammableTransactionBuilder::new();
+    assert_eq!(
+        builder
+            .with_auto_gas(sender, &coins, 200, 1, 3)
+            .unwrap()
+            .gas()
+            .len(),
+        2
+    );
+
+    // A coin available twice could be selected twice.
+    let mut duplicated = coins.clone();
+    duplicated.push(coins[0]);
+    let builder = ProgrammableTransactionBuilder::new();
+    assert!(builder
+        .with_auto_gas(sender, &duplicated, 100, 1, max_gas_coins)
+        .is_err());
+
+    // A zero budget selects no coin at all.
+    let builder = ProgrammableTransactionBuilder::new();
+    assert!(builder
+        .with_auto_gas(sender, &coins, 0, 1, max_gas_coins)
+        .is_err());
+}
@@ -23,7 +23,7 @@ use sui_types::crypto::{
     generate_proof_of_possession, get_account_key_pair, get_key_pair_from_rng, AccountKeyPair,
     KeypairTraits, ToFromBytes,
 };
-use sui_types::error::SuiError;
+use sui_types::error::{SuiError, UserInputError};
 use sui_types::gas::GasCostSummary;
 use sui_types::message_envelope::Message;
 use sui_types::messages::{
@@ -130,7 +130,15 @@ async fn test_transaction_expiration() {
                 .await
         })
         .await;
-    assert!(matches!(result.unwrap_err(), SuiError::TransactionExpired));
+    assert!(matches!(
+        result.unwrap_err(),
+        SuiError::UserInputError {
+            error: UserInputError::TransactionExpired {
+                expiration: 0,
+                current_epoch: 1,
+            }
+        }
+    ));
 
     // Non expired transaction signed without issue
     *data.expiration_mut_for_testing() = TransactionExpiration::Epoch(10);